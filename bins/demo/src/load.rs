@@ -0,0 +1,316 @@
+//! A load generator, recording events from background threads as fast as
+//! asked, to see how the log widget copes.
+
+use eframe::egui;
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+use tracing::{level_filters::LevelFilter, Level};
+
+/// The targets the generated events have.
+const TARGETS: [&str; 3] = ["load::http", "load::db", "load::cache"];
+/// How often the generators wake to catch up with the rate.
+const TICK: Duration = Duration::from_millis(5);
+/// How many frames the render times are kept for.
+const FRAMES: usize = 60;
+
+#[derive(Debug)]
+pub struct LoadGenerator {
+    /// Events per second, across all threads.
+    rate: u32,
+    threads: usize,
+    /// Whether to generate events from tokio tasks rather than threads.
+    #[cfg(feature = "tokio")]
+    tasks: bool,
+    shared: Arc<Shared>,
+    running: Option<Running>,
+    /// How long the log widget took to show, in seconds, for the last frames.
+    widget_times: VecDeque<f32>,
+    /// How long the last frame took, in seconds, if eframe knows.
+    frame_time: Option<f32>,
+}
+
+/// What the generators share with the window.
+#[derive(Debug, Default)]
+struct Shared {
+    rate: AtomicU32,
+    stop: AtomicBool,
+    /// How many events were generated at a level the subscriber records.
+    emitted: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Running {
+    threads: Vec<JoinHandle<()>>,
+    #[cfg(feature = "tokio")]
+    runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl Default for LoadGenerator {
+    fn default() -> Self {
+        LoadGenerator {
+            rate: 1000,
+            threads: 4,
+            #[cfg(feature = "tokio")]
+            tasks: false,
+            shared: Arc::default(),
+            running: None,
+            widget_times: VecDeque::with_capacity(FRAMES),
+            frame_time: None,
+        }
+    }
+}
+
+impl LoadGenerator {
+    /// Note how long showing the log widget took this frame, and how long
+    /// the whole frame took.
+    pub fn frame(&mut self, widget_time: Duration, frame_time: Option<f32>) {
+        if self.widget_times.len() == FRAMES {
+            self.widget_times.pop_front();
+        }
+        self.widget_times.push_back(widget_time.as_secs_f32());
+        self.frame_time = frame_time;
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let running = self.running.is_some();
+        ui.add(
+            egui::Slider::new(&mut self.rate, 10..=20_000)
+                .logarithmic(true)
+                .text("events/s"),
+        );
+        self.shared.rate.store(self.rate, Ordering::Relaxed);
+        ui.scope(|ui| {
+            ui.set_enabled(!running);
+            ui.add(egui::Slider::new(&mut self.threads, 1..=16).text("threads"));
+            #[cfg(feature = "tokio")]
+            ui.checkbox(&mut self.tasks, "as tokio tasks");
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .add(egui::Button::new("Start").enabled(!running))
+                .clicked()
+            {
+                self.start();
+            }
+            if ui.add(egui::Button::new("Stop").enabled(running)).clicked() {
+                self.stop();
+            }
+        });
+
+        ui.separator();
+        let emitted = self.shared.emitted.load(Ordering::Relaxed);
+        let recorded: usize = (TARGETS.iter())
+            .map(|target| tracing_memory::events_for_target(target, <[_]>::len))
+            .sum();
+        ui.label(format!("{} emitted, {} recorded", emitted, recorded))
+            .on_hover_text(
+                "Only events at a level the subscriber records are counted as emitted. \
+                 These differ while events are queued, or once the log is cleared.",
+            );
+        if !self.widget_times.is_empty() {
+            let average = self.widget_times.iter().sum::<f32>() / self.widget_times.len() as f32;
+            let max = self.widget_times.iter().copied().fold(0.0, f32::max);
+            ui.label(format!(
+                "log widget: {:.1} ms average, {:.1} ms max",
+                average * 1e3,
+                max * 1e3,
+            ))
+            .on_hover_text(format!("Over the last {} frames", FRAMES));
+        }
+        if let Some(frame_time) = self.frame_time {
+            ui.label(format!("frame: {:.1} ms", frame_time * 1e3));
+        }
+
+        if running {
+            // keep the counts current
+            ui.ctx().request_repaint();
+        }
+    }
+
+    fn start(&mut self) {
+        self.shared.stop.store(false, Ordering::Relaxed);
+        let pacer = || Pacer::new(Arc::clone(&self.shared), self.threads);
+
+        #[cfg(feature = "tokio")]
+        if self.tasks {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(self.threads)
+                .enable_time()
+                .build()
+                .expect("failed to start the tokio runtime");
+            for worker in 0..self.threads {
+                runtime.spawn(generate_async(pacer(), worker));
+            }
+            self.running = Some(Running {
+                threads: Vec::new(),
+                runtime: Some(runtime),
+            });
+            return;
+        }
+
+        let threads = (0..self.threads)
+            .map(|worker| {
+                let pacer = pacer();
+                let dispatch = tracing::dispatcher::get_default(Clone::clone);
+                std::thread::spawn(move || {
+                    tracing::dispatcher::with_default(&dispatch, || generate(pacer, worker))
+                })
+            })
+            .collect();
+        self.running = Some(Running {
+            threads,
+            #[cfg(feature = "tokio")]
+            runtime: None,
+        });
+    }
+
+    /// Stop generating events, waiting for the generators to finish.
+    pub fn stop(&mut self) {
+        let running = match self.running.take() {
+            Some(running) => running,
+            None => return,
+        };
+        self.shared.stop.store(true, Ordering::Relaxed);
+        for thread in running.threads {
+            let _ = thread.join();
+        }
+        #[cfg(feature = "tokio")]
+        if let Some(runtime) = running.runtime {
+            runtime.shutdown_timeout(Duration::from_secs(1));
+        }
+    }
+}
+
+/// Spreads a generator's share of the rate over time.
+struct Pacer {
+    shared: Arc<Shared>,
+    workers: usize,
+    last: Instant,
+    /// How many events are owed, which may be part of one.
+    owed: f64,
+}
+
+impl Pacer {
+    fn new(shared: Arc<Shared>, workers: usize) -> Self {
+        Pacer {
+            shared,
+            workers,
+            last: Instant::now(),
+            owed: 0.0,
+        }
+    }
+
+    fn stopped(&self) -> bool {
+        self.shared.stop.load(Ordering::Relaxed)
+    }
+
+    /// How many events are due since last asked.
+    fn due(&mut self) -> u64 {
+        let now = Instant::now();
+        let rate = f64::from(self.shared.rate.load(Ordering::Relaxed)) / self.workers as f64;
+        self.owed += now.duration_since(self.last).as_secs_f64() * rate;
+        self.last = now;
+        let due = self.owed.floor();
+        self.owed -= due;
+        due as u64
+    }
+
+    fn emitted(&self, level: Level) {
+        if level <= LevelFilter::current() {
+            self.shared.emitted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn generate(mut pacer: Pacer, worker: usize) {
+    let mut n = 0;
+    while !pacer.stopped() {
+        for _ in 0..pacer.due() {
+            let span = request_span(worker, n);
+            pacer.emitted(span.in_scope(|| emit(n)));
+            n += 1;
+        }
+        std::thread::sleep(TICK);
+    }
+}
+
+#[cfg(feature = "tokio")]
+async fn generate_async(mut pacer: Pacer, worker: usize) {
+    use tracing::Instrument;
+
+    let mut interval = tokio::time::interval(TICK);
+    let mut n = 0;
+    while !pacer.stopped() {
+        interval.tick().await;
+        for _ in 0..pacer.due() {
+            let level = async {
+                tokio::task::yield_now().await;
+                emit(n)
+            };
+            pacer.emitted(level.instrument(request_span(worker, n)).await);
+            n += 1;
+        }
+    }
+}
+
+fn request_span(worker: usize, n: u64) -> tracing::Span {
+    let path = ["/", "/api/items", "/login"][n as usize % 3];
+    tracing::info_span!(target: "load::http", "request", worker, n, path)
+}
+
+/// Emit the `n`th event, returning its level.
+fn emit(n: u64) -> Level {
+    match n % 8 {
+        0 => {
+            let error = LoadError(n);
+            tracing::error!(
+                target: "load::db",
+                error = &error as &(dyn std::error::Error + 'static),
+                "query failed",
+            );
+            Level::ERROR
+        }
+        1 => {
+            tracing::warn!(target: "load::cache", key = n, hit = false, "cache miss");
+            Level::WARN
+        }
+        2 | 3 => {
+            tracing::debug!(
+                target: "load::db",
+                rows = n as i64 % 100 - 10,
+                query = ?("select", n),
+                "query done",
+            );
+            Level::DEBUG
+        }
+        4 => {
+            let value = format!("item-{}", n);
+            tracing::trace!(target: "load::cache", key = n, value = %value);
+            Level::TRACE
+        }
+        _ => {
+            let elapsed_ms = n % 37;
+            tracing::info!(target: "load::http", status = 200u64, elapsed_ms, "request handled");
+            Level::INFO
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LoadError(u64);
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "row {} is locked", self.0)
+    }
+}
+
+impl std::error::Error for LoadError {}
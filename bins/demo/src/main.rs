@@ -1,22 +1,43 @@
+mod load;
+
 use eframe::{egui, epi};
+use std::sync::{Arc, RwLock};
 use tracing_subscriber::prelude::*;
 
 #[derive(Debug, Default)]
 struct App {
     message: String,
+    simulations: u64,
+    requests: u64,
+    on_thread: bool,
+    log_settings: tracing_egui::WidgetSettings,
+    record_filter: Option<Arc<RwLock<tracing_memory::RecordFilter>>>,
+    flush_guard: Option<tracing_memory::FlushGuard>,
+    load: load::LoadGenerator,
 }
 
+const LOG_SETTINGS_KEY: &str = "tracing-egui log";
+
 impl epi::App for App {
-    #[tracing::instrument(skip(ctx, _frame))]
-    fn update(&mut self, ctx: &eframe::egui::CtxRef, _frame: &mut eframe::epi::Frame<'_>) {
+    #[tracing::instrument(skip(ctx, frame))]
+    fn update(&mut self, ctx: &eframe::egui::CtxRef, frame: &mut eframe::epi::Frame<'_>) {
+        let mut widget_time = std::time::Duration::default();
         egui::Window::new("tracing-egui log")
             .resizable(true)
             .collapsible(true)
             .show(ctx, |ui| {
-                ui.add(tracing_egui::Widget {
+                let widget = tracing_egui::Widget {
+                    on_source_click: Some(Box::new(|file, line| {
+                        println!("open {}:{}", file, line);
+                    })),
+                    recording_filter: self.record_filter.clone(),
                     ..Default::default()
-                });
+                };
+                let started = std::time::Instant::now();
+                ui.add(widget.with_settings(&mut self.log_settings));
+                widget_time = started.elapsed();
             });
+        self.load.frame(widget_time, frame.info().cpu_usage);
 
         egui::Window::new("event creator")
             .resizable(true)
@@ -43,29 +64,101 @@ impl epi::App for App {
                         tracing::trace!(message = %self.message);
                     }
                 });
+                if ui.button("simulate traffic").clicked() {
+                    self.simulations += 1;
+                    tracing_memory::synth::spawn(
+                        tracing::dispatcher::get_default(Clone::clone),
+                        tracing_memory::synth::Scenario {
+                            seed: self.simulations,
+                            quiet_period: std::time::Duration::from_millis(250),
+                            ..Default::default()
+                        },
+                    );
+                }
+                if ui
+                    .button("stress test")
+                    .on_hover_text("Record 100k events at once, to scroll and filter through")
+                    .clicked()
+                {
+                    self.simulations += 1;
+                    tracing_memory::synth::spawn(
+                        tracing::dispatcher::get_default(Clone::clone),
+                        tracing_memory::synth::Scenario {
+                            seed: self.simulations,
+                            events: 100_000,
+                            ..Default::default()
+                        },
+                    );
+                }
+            });
+
+        egui::Window::new("span playground")
+            .resizable(true)
+            .collapsible(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("run request").clicked() {
+                        self.requests += 1;
+                        let id = self.requests;
+                        if self.on_thread {
+                            let dispatch = tracing::dispatcher::get_default(Clone::clone);
+                            std::thread::spawn(move || {
+                                tracing::dispatcher::with_default(&dispatch, || handle_request(id))
+                            });
+                        } else {
+                            handle_request(id);
+                        }
+                    }
+                    ui.checkbox(&mut self.on_thread, "on a background thread");
+                });
+                ui.separator();
+                ui.add(tracing_egui::SpanTreeWidget::default());
             });
+
+        egui::Window::new("load generator")
+            .resizable(true)
+            .collapsible(true)
+            .show(ctx, |ui| self.load.ui(ui));
     }
 
     fn setup(
         &mut self,
         _ctx: &egui::CtxRef,
         _frame: &mut epi::Frame<'_>,
-        _storage: Option<&dyn epi::Storage>,
+        storage: Option<&dyn epi::Storage>,
     ) {
+        // eframe only has storage with its `persistence` feature
+        if let Some(storage) = storage {
+            self.log_settings = tracing_egui::WidgetSettings::load(storage, LOG_SETTINGS_KEY);
+        }
+
         if std::env::var_os("RUST_LOG").is_none() {
             std::env::set_var("RUST_LOG", "info");
         }
 
+        let mut memory = tracing_memory::layer();
+        self.record_filter = Some(memory.register_filter_handle());
         tracing_subscriber::registry()
             .with(tracing_subscriber::EnvFilter::from_default_env())
             .with(tracing_subscriber::fmt::layer().pretty())
-            .with(tracing_memory::layer())
+            .with(memory)
             .init();
+        self.flush_guard = Some(tracing_memory::flush_guard());
 
         tracing::warn!("App is starting..");
         log_spam(10);
     }
 
+    fn save(&mut self, storage: &mut dyn epi::Storage) {
+        self.log_settings.save(storage, LOG_SETTINGS_KEY);
+    }
+
+    fn on_exit(&mut self) {
+        self.load.stop();
+        // eframe exits the process without returning from main
+        self.flush_guard.take();
+    }
+
     fn name(&self) -> &str {
         "tracing-utils-demo"
     }
@@ -89,3 +182,29 @@ fn log_spam(spam: u64) {
         tracing::info!(instance, "Log spam requested!");
     }
 }
+
+#[tracing::instrument(fields(items = 3, total = tracing::field::Empty))]
+fn handle_request(id: u64) {
+    tracing::info!("handling request");
+    let total: u64 = (0..3).map(|item| process_item(id, item)).sum();
+    tracing::Span::current().record("total", total);
+    tracing::info!(total, "request handled");
+}
+
+#[tracing::instrument(fields(result = tracing::field::Empty))]
+fn process_item(id: u64, item: u64) -> u64 {
+    let value = lookup(id * 10 + item);
+    tracing::Span::current().record("result", value);
+    value
+}
+
+#[tracing::instrument]
+fn lookup(key: u64) -> u64 {
+    let value = key % 7;
+    if value == 0 {
+        tracing::warn!(key, "cache miss");
+    } else {
+        tracing::info!(key, value, "cache hit");
+    }
+    value
+}
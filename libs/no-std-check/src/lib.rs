@@ -0,0 +1,30 @@
+//! A `#![no_std]` crate using parse-env-filter without its `alloc` feature,
+//! so that building the workspace checks the no-alloc API stays usable there.
+//!
+//! Cargo unifies features across the workspace, so to check that nothing here
+//! needs `alloc`, build this crate on its own:
+//!
+//! ```text
+//! cargo build -p no-std-check
+//! ```
+
+#![no_std]
+#![forbid(unsafe_code)]
+
+use parse_env_filter::{count_filters, filters, validate, Level, ParseError};
+
+/// Check a directive string when loading configuration, and find the most
+/// verbose level it enables, as an embedded user might.
+pub fn load(directives: &str) -> Result<Option<Level>, ParseError> {
+    validate(directives)?;
+    let mut max = None;
+    for filter in filters(directives) {
+        max = max.max(filter?.parsed_level(directives)?);
+    }
+    Ok(max)
+}
+
+/// How many filters a valid directive string holds.
+pub fn len(directives: &str) -> usize {
+    count_filters(directives).unwrap_or(0)
+}
@@ -0,0 +1,76 @@
+//! Build a map of target levels straight from a directive string, without
+//! collecting the parsed filters first.
+//!
+//! ```text
+//! cargo run --example levels -- "warn,my_crate=debug,my_crate[request]=trace"
+//! ```
+
+use parse_env_filter::{parse_with, ParseError, Visitor};
+use std::{
+    collections::HashMap,
+    env,
+    ops::{ControlFlow, Range},
+};
+use tracing_core::LevelFilter;
+
+#[derive(Debug, Default)]
+struct Levels<'a> {
+    levels: HashMap<String, LevelFilter>,
+    target: &'a str,
+    /// Whether the current filter only applies in some spans.
+    in_spans: bool,
+    error: Option<(ParseError, Range<usize>)>,
+}
+
+impl<'a> Visitor<'a> for Levels<'a> {
+    fn begin_filter(&mut self, target: &'a str) -> ControlFlow<()> {
+        self.target = target;
+        self.in_spans = false;
+        ControlFlow::Continue(())
+    }
+
+    fn begin_spans(&mut self) {
+        self.in_spans = true;
+    }
+
+    fn end_filter(&mut self, level: Option<&'a str>) {
+        if self.in_spans {
+            return;
+        }
+        let (target, level) = match level {
+            Some(level) => (self.target, level),
+            // like env_logger, a bare level name sets the default level,
+            None if self.target.parse::<LevelFilter>().is_ok() => ("", self.target),
+            // and a bare target enables everything for it
+            None => (self.target, "trace"),
+        };
+        match level.parse() {
+            Ok(level) => {
+                self.levels.insert(target.to_string(), level);
+            }
+            Err(_) => eprintln!("ignoring bad level {:?} for {:?}", level, target),
+        }
+    }
+
+    fn error(&mut self, error: ParseError, range: Range<usize>) {
+        self.error = Some((error, range));
+    }
+}
+
+fn main() {
+    let directives = env::args()
+        .nth(1)
+        .unwrap_or_else(|| "warn,my_crate=debug,my_crate[request]=trace".into());
+    let mut levels = Levels::default();
+    parse_with(&directives, &mut levels);
+
+    if let Some((error, range)) = levels.error {
+        eprintln!("{}: {:?}", error, &directives[range]);
+        return;
+    }
+    let mut levels: Vec<_> = levels.levels.into_iter().collect();
+    levels.sort();
+    for (target, level) in levels {
+        println!("{:?} = {}", target, level);
+    }
+}
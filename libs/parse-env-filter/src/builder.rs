@@ -0,0 +1,280 @@
+extern crate alloc;
+
+use crate::{
+    lazy::{find_close_quote, is_name, is_syntax},
+    FieldFilter, ParseError, ParseErrorKind, Part,
+};
+use alloc::string::String;
+use core::fmt::Write;
+
+/// Writes a directive string one filter at a time, checking that it parses
+/// back into the same filters.
+///
+/// Each filter is written in order as `target[span{field=value}]=level`, then
+/// ended with [`push`](Self::push). Parts may be left out, but not reordered.
+/// The first invalid part stops the builder, and is returned by
+/// [`finish`](Self::finish) as an error, with the offset where it would be.
+///
+/// ```rust
+/// # use parse_env_filter::DirectiveBuilder;
+/// let directives = DirectiveBuilder::new()
+///     .target("my_crate")
+///     .span("handler")
+///     .field("user", Some("bob"))
+///     .level("debug")
+///     .push()
+///     .level("warn")
+///     .finish();
+/// assert_eq!(directives.unwrap(), "my_crate[handler{user=bob}]=debug,=warn");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct DirectiveBuilder {
+    directives: String,
+    /// The last part written of the current filter.
+    stage: Stage,
+    /// Whether the current filter has been started in `directives`.
+    begun: bool,
+    error: Option<ParseError>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    #[default]
+    Empty,
+    Target,
+    /// A span filter with an empty name, which must be given fields.
+    AnySpan,
+    Span,
+    Field,
+    Level,
+}
+
+impl DirectiveBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the target of a new filter, which may only contain `[A-Za-z0-9_:-]`.
+    pub fn target(mut self, target: &str) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        if self.stage != Stage::Empty {
+            return self.fail(0, ParseErrorKind::BadSyntax, Part::Target);
+        }
+        if let Some((i, c)) = target.char_indices().find(|&(_, c)| !is_name(c)) {
+            let i = self.begin_len() + i;
+            return self.fail(i, ParseErrorKind::InvalidCharacter(c), Part::Target);
+        }
+        if !target.is_empty() {
+            self.begin();
+            self.directives += target;
+        }
+        self.stage = Stage::Target;
+        self
+    }
+
+    /// Add a span filter, which may only contain `[A-Za-z0-9_:-]`.
+    ///
+    /// An empty name matches any span with the fields, so must be followed by
+    /// a [`field`](Self::field), as in `target[{field=value}]`.
+    pub fn span(mut self, name: &str) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        self = self.check_any_span();
+        if self.error.is_some() {
+            return self;
+        }
+        if self.stage >= Stage::Level {
+            let i = self.begin_len();
+            return self.fail(i, ParseErrorKind::BadSyntax, Part::Span);
+        }
+        let separator = match self.stage {
+            Stage::Span => ",",
+            Stage::Field => "},",
+            _ => "[",
+        };
+        if let Some((i, c)) = name.char_indices().find(|&(_, c)| !is_name(c)) {
+            let i = self.begin_len() + separator.len() + i;
+            return self.fail(i, ParseErrorKind::InvalidCharacter(c), Part::Span);
+        }
+        self.begin();
+        self.directives += separator;
+        self.directives += name;
+        self.stage = match name {
+            "" => Stage::AnySpan,
+            _ => Stage::Span,
+        };
+        self
+    }
+
+    /// Add a field filter to the last span filter.
+    ///
+    /// The value is quoted if it needs to be. Values are left as written when
+    /// parsed, with `\` escaping the next character, so one may contain a
+    /// quote only as `\"`, and must not end with an unpaired `\`.
+    pub fn field(mut self, name: &str, value: Option<&str>) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        if !matches!(self.stage, Stage::AnySpan | Stage::Span | Stage::Field) {
+            let i = self.begin_len();
+            return self.fail(i, ParseErrorKind::BadSyntax, Part::Field);
+        }
+        let separator = match self.stage {
+            Stage::Field => ",",
+            _ => "{",
+        };
+        if name.is_empty() {
+            return self.fail(separator.len(), ParseErrorKind::BadSyntax, Part::Field);
+        }
+        let invalid = |c| is_syntax(c) || c == '\\';
+        if let Some((i, c)) = name.char_indices().find(|&(_, c)| invalid(c)) {
+            let i = separator.len() + i;
+            return self.fail(i, ParseErrorKind::InvalidCharacter(c), Part::Field);
+        }
+        let start = self.directives.len();
+        self.directives += separator;
+        let _ = write!(self.directives, "{}", FieldFilter { name, value });
+
+        // a quoted value must end at its closing quote
+        let value_start = start + separator.len() + name.len() + 1;
+        if let (Some(value), Some(quoted)) = (value, self.directives.get(value_start..)) {
+            let end = quoted.strip_prefix('"').map(find_close_quote);
+            if matches!(end, Some(end) if end != Some(value.len())) {
+                self.directives.truncate(start);
+                let (i, c) = match end.flatten() {
+                    Some(i) => (i, '"'),
+                    None => (value.len() - 1, '\\'),
+                };
+                let i = value_start - start + 1 + i;
+                return self.fail(i, ParseErrorKind::InvalidCharacter(c), Part::Field);
+            }
+        }
+        self.stage = Stage::Field;
+        self
+    }
+
+    /// Set the level of the filter, which must not contain the syntax
+    /// characters `[]{}=,"/` or end with whitespace.
+    ///
+    /// This isn't checked to be a [`Level`](crate::Level), but one can be
+    /// given with [`Level::as_str`](crate::Level::as_str).
+    pub fn level(mut self, level: &str) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        if self.stage >= Stage::Level {
+            return self.fail(0, ParseErrorKind::BadSyntax, Part::Level);
+        }
+        self = self.check_any_span();
+        if self.error.is_some() {
+            return self;
+        }
+        let trailing_space = level
+            .char_indices()
+            .last()
+            .filter(|&(_, c)| c.is_whitespace());
+        let invalid = level.char_indices().find(|&(_, c)| is_syntax(c));
+        if let Some((i, c)) = invalid.or(trailing_space) {
+            let i = self.begin_len() + self.close_len() + 1 + i;
+            return self.fail(i, ParseErrorKind::InvalidCharacter(c), Part::Level);
+        }
+        self.begin();
+        self.close();
+        self.directives.push('=');
+        self.directives += level;
+        self.stage = Stage::Level;
+        self
+    }
+
+    /// End the current filter, so the next part starts a new one.
+    ///
+    /// A filter with nothing in it is left out.
+    pub fn push(mut self) -> Self {
+        self = self.check_any_span();
+        if self.error.is_none() {
+            self.close();
+            self.stage = Stage::Empty;
+            self.begun = false;
+        }
+        self
+    }
+
+    /// Finish the directive string with a `/regex` on the current filter.
+    ///
+    /// The regex takes the rest of the directive string, so no filter can
+    /// follow it; it may contain any character.
+    pub fn regex(mut self, regex: &str) -> Result<String, ParseError> {
+        self = self.check_any_span();
+        if self.error.is_none() {
+            self.begin();
+            self.close();
+            self.directives.push('/');
+            self.directives += regex;
+            self.stage = Stage::Empty;
+        }
+        self.finish()
+    }
+
+    /// The directive string, or the first invalid part given.
+    pub fn finish(self) -> Result<String, ParseError> {
+        let builder = self.push();
+        match builder.error {
+            Some(error) => Err(error),
+            None => Ok(builder.directives),
+        }
+    }
+
+    /// Start writing the current filter, if it hasn't been yet.
+    fn begin(&mut self) {
+        if !self.begun {
+            if !self.directives.is_empty() {
+                self.directives.push(',');
+            }
+            self.begun = true;
+        }
+    }
+
+    /// Close the span filters, if any are open.
+    fn close(&mut self) {
+        match self.stage {
+            Stage::Span => self.directives += "]",
+            Stage::Field => self.directives += "}]",
+            _ => {}
+        }
+    }
+
+    /// How much [`begin`](Self::begin) would write.
+    fn begin_len(&self) -> usize {
+        (!self.begun && !self.directives.is_empty()) as usize
+    }
+
+    /// How much [`close`](Self::close) would write.
+    fn close_len(&self) -> usize {
+        match self.stage {
+            Stage::Span => 1,
+            Stage::Field => 2,
+            _ => 0,
+        }
+    }
+
+    /// Stop if the last span filter has an empty name but no fields, which
+    /// would parse back as no span filter at all.
+    fn check_any_span(self) -> Self {
+        match self.stage {
+            Stage::AnySpan if self.error.is_none() => {
+                self.fail(0, ParseErrorKind::BadSyntax, Part::Span)
+            }
+            _ => self,
+        }
+    }
+
+    /// Stop at an error at byte `i` of what the current part would write.
+    fn fail(mut self, i: usize, kind: ParseErrorKind, part: Part) -> Self {
+        let offset = self.directives.len() + i;
+        self.error = Some(ParseError { kind, offset, part });
+        self
+    }
+}
@@ -2,26 +2,114 @@
 
 extern crate alloc;
 
-use crate::{FieldFilter, ParseError};
-use alloc::vec::Vec;
-use core::convert::TryFrom;
+use crate::{lazy::bare_level, FieldFilter, Level, ParseError, ParsedDirective, Visitor};
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::{
+    cmp::Reverse,
+    convert::TryFrom,
+    fmt::{self, Write},
+    ops::{ControlFlow, Range},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Parse a series of filters out of a directive string.
 ///
 /// This is an eager, allocating version of [the root `filters`](crate::filters).
-#[allow(clippy::result_unit_err)]
 pub fn filters(directives: &str) -> Result<Vec<Filter<'_>>, ParseError> {
-    crate::filters(directives)
-        .map(|filter| Filter::try_from(filter?))
-        .collect()
+    let mut builder = Builder::default();
+    crate::parse_with(directives, &mut builder);
+    builder.finish()
+}
+
+/// A [`Visitor`] collecting the eager filters; see [`filters`].
+#[derive(Debug, Default)]
+pub struct Builder<'a> {
+    filters: Vec<Filter<'a>>,
+    target: &'a str,
+    span: Option<Vec<SpanFilter<'a>>>,
+    error: Option<ParseError>,
+}
+
+impl<'a> Builder<'a> {
+    /// The filters visited, or the error parsing them.
+    pub fn finish(self) -> Result<Vec<Filter<'a>>, ParseError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.filters),
+        }
+    }
+
+    fn last_span(&mut self) -> Option<&mut SpanFilter<'a>> {
+        self.span.as_mut()?.last_mut()
+    }
+}
+
+impl<'a> Visitor<'a> for Builder<'a> {
+    fn begin_filter(&mut self, target: &'a str) -> ControlFlow<()> {
+        self.target = target;
+        self.span = None;
+        ControlFlow::Continue(())
+    }
+
+    fn begin_spans(&mut self) {
+        self.span = Some(Vec::new());
+    }
+
+    fn span_filter(&mut self, name: &'a str) {
+        let span = SpanFilter { name, fields: None };
+        self.span.get_or_insert_with(Vec::new).push(span);
+    }
+
+    fn begin_fields(&mut self) {
+        if let Some(span) = self.last_span() {
+            span.fields = Some(Vec::new());
+        }
+    }
+
+    fn field_filter(&mut self, name: &'a str, value: Option<&'a str>) {
+        if let Some(span) = self.last_span() {
+            let field = FieldFilter { name, value };
+            span.fields.get_or_insert_with(Vec::new).push(field);
+        }
+    }
+
+    fn end_filter(&mut self, level: Option<&'a str>) {
+        self.filters.push(Filter {
+            target: self.target,
+            span: self.span.take(),
+            level,
+            regex: None,
+        });
+    }
+
+    fn regex(&mut self, regex: &'a str) {
+        if let Some(filter) = self.filters.last_mut() {
+            filter.regex = Some(regex);
+        }
+    }
+
+    fn error(&mut self, error: ParseError, _: Range<usize>) {
+        self.error = Some(error);
+    }
 }
 
-/// A single event filter, `target[span{field=value}]=level`.
+/// A single event filter, `target[span{field=value}]=level/regex`.
+///
+/// With the `serde` feature, this (de)serializes as a struct of its fields.
+/// Deserializing borrows every string from the input, so it fails for input
+/// strings which contain escapes; use [`OwnedFilter`] for those.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Filter<'a> {
     pub target: &'a str,
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub span: Option<Vec<SpanFilter<'a>>>,
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub level: Option<&'a str>,
+    /// See [the lazy `Filter::regex`](crate::Filter::regex).
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub regex: Option<&'a str>,
 }
 
 impl<'a> TryFrom<crate::Filter<'a>> for Filter<'a> {
@@ -39,14 +127,214 @@ impl<'a> TryFrom<crate::Filter<'a>> for Filter<'a> {
                 })
                 .transpose()?,
             level: filter.level,
+            regex: filter.regex,
+        })
+    }
+}
+
+/// Displays as directive syntax, which parses back to an equal filter as
+/// long as no part but a field value contains a syntax character.
+impl fmt::Display for Filter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_filter(f, self.target, self.span.as_deref(), self.level, self.regex)
+    }
+}
+
+impl Filter<'_> {
+    /// Tell apart a bare level name from a target; see
+    /// [the lazy `Filter::into_directive`](crate::Filter::into_directive).
+    pub fn into_directive(self) -> ParsedDirective<Self> {
+        match bare_level(self.target, self.span.is_some(), self.level) {
+            Some(level) => ParsedDirective::Global(level),
+            None => ParsedDirective::Target(self),
+        }
+    }
+
+    /// Whether this applies to everything: it has no target, and no span
+    /// filter with a name or fields, as in `=info`.
+    ///
+    /// A bare level name, such as `info`, is a target here; see
+    /// [`into_directive`](Self::into_directive).
+    pub fn is_global(&self) -> bool {
+        self.target.is_empty() && !self.constrains_span()
+    }
+
+    /// Whether this has a span filter with a name or fields, so it only
+    /// applies to matching spans and what happens in them, rather than to
+    /// all events with its target.
+    pub fn constrains_span(&self) -> bool {
+        let specificity = self.specificity();
+        specificity.span || specificity.fields != 0
+    }
+
+    /// What this filters on, besides its level and regex, so that a later
+    /// filter with an equal key replaces this one; see [`merge`].
+    ///
+    /// A bare level name has the same key as a filter without a target, so
+    /// `info` and `=warn` have equal keys, and `info=warn` doesn't.
+    pub fn key(&self) -> FilterKey<'_> {
+        match bare_level(self.target, self.span.is_some(), self.level) {
+            Some(_) => FilterKey {
+                target: "",
+                span: None,
+            },
+            None => FilterKey {
+                target: self.target,
+                span: self.span.as_deref().filter(|span| !span.is_empty()),
+            },
+        }
+    }
+
+    /// How specific this is, for picking which of the filters that apply to
+    /// something wins; see [`Specificity`].
+    pub fn specificity(&self) -> Specificity {
+        let spans = || self.span.iter().flatten();
+        Specificity {
+            target_len: Some(self.target.len()).filter(|&len| len != 0),
+            span: spans().any(|span| !span.name.is_empty()),
+            fields: spans()
+                .flat_map(|span| span.fields.iter().flatten())
+                .count(),
+        }
+    }
+}
+
+/// How specific a [`Filter`] is, ordered as tracing-subscriber orders its
+/// directives, so that the most specific filter which applies wins.
+///
+/// A filter with a target is more specific than one without, and a longer
+/// target more specific than a shorter one. For the same target length, a
+/// filter with a named span is more specific, then the one with more field
+/// filters. So `info,my_crate=trace` traces `my_crate`, and
+/// `my_crate[request]=debug` is more specific than `my_crate=trace`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Specificity {
+    // the field order is the comparison order
+    target_len: Option<usize>,
+    span: bool,
+    fields: usize,
+}
+
+/// Sort `filters` from most to least specific, so that the first which
+/// applies to something is the one tracing-subscriber would use.
+///
+/// Of equally specific filters, later ones come first, as when a later
+/// directive for the same target replaces an earlier one.
+pub fn sort_by_specificity(filters: &mut [Filter<'_>]) {
+    filters.reverse();
+    filters.sort_by_key(|filter| Reverse(filter.specificity()));
+}
+
+/// What a [`Filter`] filters on, besides its level and regex; see [`Filter::key`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterKey<'a> {
+    target: &'a str,
+    span: Option<&'a [SpanFilter<'a>]>,
+}
+
+/// Combine two directive strings, where a filter in `overrides` replaces one
+/// in `base` with the same [key](Filter::key).
+///
+/// A replaced filter keeps its place, and the filters new in `overrides`
+/// follow those of `base`. The result is written as filters display, so
+/// whitespace around them is dropped. As only the last filter may have a
+/// `/regex`, the result ends with the regex of `overrides` if it has one,
+/// or else that of `base`.
+///
+/// ```rust
+/// # use parse_env_filter::eager::merge;
+/// assert_eq!(merge("info,hyper=warn", "hyper=trace").unwrap(), "info,hyper=trace");
+/// assert_eq!(merge("info,hyper=warn", "warn,tower").unwrap(), "warn,hyper=warn,tower");
+/// ```
+pub fn merge(base: &str, overrides: &str) -> Result<String, ParseError> {
+    merge_all(&[base, overrides])
+}
+
+/// Combine directive strings like [`merge`], each overriding those before it.
+///
+/// An error's offset is into the directive string which failed to parse.
+pub fn merge_all(sources: &[&str]) -> Result<String, ParseError> {
+    let mut merged: Vec<Filter<'_>> = Vec::new();
+    let mut regex = None;
+    for &source in sources {
+        for mut filter in filters(source)? {
+            regex = filter.regex.take().or(regex);
+            // just a regex, as in `/foo`, is no filter
+            if filter.target.is_empty() && filter.span.is_none() && filter.level.is_none() {
+                continue;
+            }
+            match merged
+                .iter_mut()
+                .find(|merged| merged.key() == filter.key())
+            {
+                Some(merged) => *merged = filter,
+                None => merged.push(filter),
+            }
+        }
+    }
+
+    let mut directives = String::new();
+    for (ix, filter) in merged.iter().enumerate() {
+        if ix != 0 {
+            directives.push(',');
+        }
+        let _ = write!(directives, "{}", filter);
+    }
+    if let Some(regex) = regex {
+        directives.push('/');
+        directives += regex;
+    }
+    Ok(directives)
+}
+
+/// Parse a series of filters out of a directive string, checking their levels.
+///
+/// Like [`filters`], but fails with [`InvalidLevel`](crate::ParseErrorKind::InvalidLevel)
+/// at the first level which isn't a [`Level`].
+pub fn typed_filters(directives: &str) -> Result<Vec<TypedFilter<'_>>, ParseError> {
+    crate::filters(directives)
+        .map(|filter| TypedFilter::check(filter?, directives))
+        .collect()
+}
+
+/// A single event filter with a checked level; see [`typed_filters`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypedFilter<'a> {
+    pub target: &'a str,
+    pub span: Option<Vec<SpanFilter<'a>>>,
+    pub level: Option<Level>,
+    pub regex: Option<&'a str>,
+}
+
+impl<'a> TypedFilter<'a> {
+    /// Collect a filter, checking its level; see [`Filter::parsed_level`].
+    ///
+    /// `directives` is the directive string the filter was parsed from, to
+    /// report errors at their offset in it.
+    ///
+    /// [`Filter::parsed_level`]: crate::Filter::parsed_level
+    pub fn check(filter: crate::Filter<'a>, directives: &str) -> Result<Self, ParseError> {
+        let level = filter.parsed_level(directives);
+        // spans come before the level, so report their errors first
+        let filter = Filter::try_from(filter)?;
+        Ok(TypedFilter {
+            target: filter.target,
+            span: filter.span,
+            level: level?,
+            regex: filter.regex,
         })
     }
 }
 
 /// A single span filter, `[span{field=value}]`.
+///
+/// With the `serde` feature, this deserializes by borrowing from the input;
+/// see [`OwnedSpanFilter`] otherwise.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct SpanFilter<'a> {
     pub name: &'a str,
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub fields: Option<Vec<FieldFilter<'a>>>,
 }
 
@@ -60,3 +348,162 @@ impl<'a> TryFrom<crate::SpanFilter<'a>> for SpanFilter<'a> {
         })
     }
 }
+
+impl fmt::Display for SpanFilter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_span(f, self.name, self.fields.as_deref())
+    }
+}
+
+/// Parse a series of filters out of a directive string into owned filters.
+///
+/// Like [`filters`], but the filters don't borrow from `directives`.
+pub fn parse_owned(directives: &str) -> Result<Vec<OwnedFilter>, ParseError> {
+    Ok(filters(directives)?.into_iter().map(Into::into).collect())
+}
+
+/// An owned [`Filter`]; see [`parse_owned`].
+///
+/// Displays as directive syntax, which parses back to an equal filter as
+/// long as no part but a field value contains a syntax character. To get a
+/// whole directive string, join the filters with `,`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedFilter {
+    pub target: String,
+    pub span: Option<Vec<OwnedSpanFilter>>,
+    pub level: Option<String>,
+    pub regex: Option<String>,
+}
+
+impl From<Filter<'_>> for OwnedFilter {
+    fn from(filter: Filter<'_>) -> Self {
+        OwnedFilter {
+            target: filter.target.into(),
+            span: filter
+                .span
+                .map(|filters| filters.into_iter().map(Into::into).collect()),
+            level: filter.level.map(Into::into),
+            regex: filter.regex.map(Into::into),
+        }
+    }
+}
+
+impl fmt::Display for OwnedFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_filter(
+            f,
+            &self.target,
+            self.span.as_deref(),
+            self.level.as_deref(),
+            self.regex.as_deref(),
+        )
+    }
+}
+
+/// An owned [`SpanFilter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedSpanFilter {
+    pub name: String,
+    pub fields: Option<Vec<OwnedFieldFilter>>,
+}
+
+impl From<SpanFilter<'_>> for OwnedSpanFilter {
+    fn from(filter: SpanFilter<'_>) -> Self {
+        OwnedSpanFilter {
+            name: filter.name.into(),
+            fields: filter
+                .fields
+                .map(|filters| filters.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+impl fmt::Display for OwnedSpanFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_span(f, &self.name, self.fields.as_deref())
+    }
+}
+
+/// An owned [`FieldFilter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OwnedFieldFilter {
+    pub name: String,
+    /// The value, with its quotes stripped and escapes left as written.
+    pub value: Option<String>,
+}
+
+impl OwnedFieldFilter {
+    /// The value with its escapes replaced; see [`unescape`](crate::unescape).
+    pub fn unescaped_value(&self) -> Option<Cow<'_, str>> {
+        self.value.as_deref().map(crate::unescape)
+    }
+}
+
+impl From<FieldFilter<'_>> for OwnedFieldFilter {
+    fn from(filter: FieldFilter<'_>) -> Self {
+        OwnedFieldFilter {
+            name: filter.name.into(),
+            value: filter.value.map(Into::into),
+        }
+    }
+}
+
+/// Quotes the value if it is empty or contains syntax characters or escapes.
+impl fmt::Display for OwnedFieldFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let filter = FieldFilter {
+            name: &self.name,
+            value: self.value.as_deref(),
+        };
+        write!(f, "{}", filter)
+    }
+}
+
+fn write_filter(
+    f: &mut fmt::Formatter<'_>,
+    target: &str,
+    span: Option<&[impl fmt::Display]>,
+    level: Option<&str>,
+    regex: Option<&str>,
+) -> fmt::Result {
+    f.write_str(target)?;
+    if let Some(span) = span {
+        f.write_str("[")?;
+        write_list(f, span)?;
+        f.write_str("]")?;
+    }
+    if let Some(level) = level {
+        write!(f, "={}", level)?;
+    }
+    if let Some(regex) = regex {
+        write!(f, "/{}", regex)?;
+    }
+    Ok(())
+}
+
+fn write_span(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    fields: Option<&[impl fmt::Display]>,
+) -> fmt::Result {
+    f.write_str(name)?;
+    if let Some(fields) = fields {
+        f.write_str("{")?;
+        write_list(f, fields)?;
+        f.write_str("}")?;
+    }
+    Ok(())
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, items: &[impl fmt::Display]) -> fmt::Result {
+    for (ix, item) in items.iter().enumerate() {
+        if ix != 0 {
+            f.write_str(",")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
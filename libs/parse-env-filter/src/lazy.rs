@@ -1,19 +1,75 @@
-use crate::ParseError;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use crate::{Level, ParseError, ParseErrorKind, Part};
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, string::String};
+use core::{fmt, iter::FusedIterator};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Parse a series of filters out of a directive string.
 ///
 /// Note that this is a lazy operation, including validation; parsing/validation
 /// are done simultaneously and on demand in zero-alloc streaming fashion.
 pub fn filters(directives: &str) -> Filters<'_> {
-    Filters { directives }
+    Filters {
+        source: directives,
+        directives,
+    }
+}
+
+/// Count the comma-separated filters in a directive string, without parsing them.
+///
+/// Commas in span and field filters, in quoted values, and in the regex
+/// aren't counted. For a directive string which parses, this is how many
+/// filters [`filters`] yields, so it is handy for sizing a collection up
+/// front. Counting stops at syntax which ends parsing with an error, so
+/// that it is never less than the number yielded either way; use
+/// [`count_filters`](crate::count_filters) to count and check the filters.
+pub fn count_directives(directives: &str) -> usize {
+    max_items(directives.trim_start(), Some((b'[', Syntax::RBrack)), false)
+}
+
+/// Replace each `\` escape in a field filter value with the character it
+/// escapes, borrowing the value if it has no escapes.
+///
+/// A `\` at the very end, which parsing never leaves, is dropped.
+#[cfg(feature = "alloc")]
+pub fn unescape(value: &str) -> Cow<'_, str> {
+    if !value.contains('\\') {
+        return Cow::Borrowed(value);
+    }
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    Cow::Owned(unescaped)
 }
 
 /// Parser-iterator of [Filter]s.
+///
+/// After yielding an `Err`, this only yields `None`, unless made to
+/// [`recover`](Self::recover); the same goes for the nested [`SpanFilters`]
+/// and [`FieldFilters`]. Those of an item yielded
+/// before an error only parse that item's own part of the directives, so
+/// they are unaffected by errors after it.
 #[derive(Debug, Clone)]
 pub struct Filters<'a> {
+    source: &'a str,
     directives: &'a str,
 }
 
+/// Parser-iterator of [Filter]s which goes on past errors; see [`Filters::recover`].
+#[derive(Debug, Clone)]
+pub struct Recover<'a> {
+    filters: Filters<'a>,
+}
+
 /// A single event filter, `target[span{field=value}]=level`.
 ///
 /// Span directives are not parsed/validated until pulled.
@@ -22,11 +78,74 @@ pub struct Filter<'a> {
     pub target: &'a str,
     pub span: Option<SpanFilters<'a>>,
     pub level: Option<&'a str>,
+    /// The env_logger message regex, `/regex`, left unvalidated.
+    ///
+    /// The regex is the whole rest of the directive string, commas included,
+    /// so only the last filter can have one. Like env_logger, consumers will
+    /// likely want to apply it to every filter, not just this one.
+    pub regex: Option<&'a str>,
+}
+
+/// A filter, with a bare level name told apart from a target; see
+/// [`Filter::into_directive`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedDirective<F> {
+    /// A bare level name, such as `info`, which env_logger and
+    /// tracing-subscriber read as the level for every target.
+    Global(Level),
+    /// Any other filter, including an empty target with a level, `=info`.
+    Target(F),
+}
+
+/// The level a filter with just a `target` names, if it is a level name.
+pub(crate) fn bare_level(target: &str, has_span: bool, level: Option<&str>) -> Option<Level> {
+    if has_span || level.is_some() {
+        return None;
+    }
+    Level::parse(target)
+}
+
+impl Filter<'_> {
+    /// Tell apart a bare level name, such as `info`, `TRACE`, or `3`, from
+    /// a target, as env_logger and tracing-subscriber do.
+    ///
+    /// The filter is a level name if it has a target and nothing else but
+    /// maybe a regex, which applies to every filter either way.
+    pub fn into_directive(self) -> ParsedDirective<Self> {
+        match bare_level(self.target, self.span.is_some(), self.level) {
+            Some(level) => ParsedDirective::Global(level),
+            None => ParsedDirective::Target(self),
+        }
+    }
+
+    /// The level, checked to be a [`Level`].
+    ///
+    /// An invalid level is reported at its offset in `directives`, the
+    /// directive string this filter was parsed from, or at offset 0 if the
+    /// level isn't a slice of it, as in a filter built by hand.
+    pub fn parsed_level(&self, directives: &str) -> Result<Option<Level>, ParseError> {
+        self.level
+            .map(|level| {
+                Level::parse(level).ok_or(ParseError {
+                    kind: ParseErrorKind::InvalidLevel,
+                    offset: offset_in(directives, level).unwrap_or(0),
+                    part: Part::Level,
+                })
+            })
+            .transpose()
+    }
+}
+
+/// The offset of `part` in `source`, if it is a slice of it.
+fn offset_in(source: &str, part: &str) -> Option<usize> {
+    let offset = (part.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+    (offset + part.len() <= source.len()).then_some(offset)
 }
 
 /// Parser-iterator of [SpanFilter]s.
 #[derive(Debug, Clone)]
 pub struct SpanFilters<'a> {
+    source: &'a str,
     directives: &'a str,
 }
 
@@ -42,16 +161,64 @@ pub struct SpanFilter<'a> {
 /// Parser-iterator of [FieldFilter]s.
 #[derive(Debug, Clone)]
 pub struct FieldFilters<'a> {
+    source: &'a str,
     directives: &'a str,
 }
 
-/// A single field filter, `{field=value}`.
+/// A single field filter, `{field=value}` or `{field="value"}`.
+///
+/// With the `serde` feature, this deserializes by borrowing from the input;
+/// see [`OwnedFieldFilter`](crate::eager::OwnedFieldFilter) otherwise.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FieldFilter<'a> {
     pub name: &'a str,
+    /// The value, with its quotes stripped if it was quoted.
+    ///
+    /// A quoted value may contain syntax characters. In either form, `\`
+    /// escapes the next character, such as `\,` or `\"`; escapes are left as
+    /// written, so that this borrows from the directives, and
+    /// [`unescaped_value`](Self::unescaped_value) replaces them.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub value: Option<&'a str>,
 }
 
+impl<'a> FieldFilter<'a> {
+    /// The value with its escapes replaced; see [`unescape`].
+    ///
+    /// This only allocates if the value has an escape.
+    #[cfg(feature = "alloc")]
+    pub fn unescaped_value(&self) -> Option<Cow<'a, str>> {
+        self.value.map(unescape)
+    }
+}
+
+/// Displays as directive syntax, quoting the value if it needs it.
+///
+/// A value with an escape is quoted too, so that an unpaired `\` at its
+/// end can't escape the syntax after it.
+impl fmt::Display for FieldFilter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)?;
+        match self.value {
+            None => Ok(()),
+            Some(value) if value.is_empty() || value.contains(|c| is_syntax(c) || c == '\\') => {
+                write!(f, "=\"{}\"", value)
+            }
+            Some(value) => write!(f, "={}", value),
+        }
+    }
+}
+
+pub(crate) fn is_syntax(c: char) -> bool {
+    "[]{}=,\"/".contains(c)
+}
+
+/// Whether `c` may be in a target or span name.
+pub(crate) fn is_name(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_:-".contains(c)
+}
+
 #[repr(u8)]
 #[derive(Clone, Copy)]
 enum Syntax {
@@ -61,6 +228,8 @@ enum Syntax {
     RBrace = b'}',
     Equal = b'=',
     Comma = b',',
+    Quote = b'"',
+    Slash = b'/',
 }
 
 fn find_any_syntax(haystack: &str) -> (usize, Option<Syntax>) {
@@ -75,6 +244,8 @@ fn find_any_syntax(haystack: &str) -> (usize, Option<Syntax>) {
             b'}' => Some((i, RBrace)),
             b'=' => Some((i, Equal)),
             b',' => Some((i, Comma)),
+            b'"' => Some((i, Quote)),
+            b'/' => Some((i, Slash)),
             _ => None,
         })
         .map_or_else(|| (haystack.len(), None), |(i, c)| (i, Some(c)))
@@ -84,9 +255,17 @@ fn find_any_syntax(haystack: &str) -> (usize, Option<Syntax>) {
 macro_rules! switch_syntax {
     ($haystack:expr => |$i:ident| {
         $($($syntax:tt)|+ => $expr:expr),* $(,)?
+    }) => {
+        switch_syntax!(@found find_any_syntax($haystack) => |$i| {
+            $($($syntax)|+ => $expr),*
+        })
+    };
+
+    (@found $found:expr => |$i:ident| {
+        $($($syntax:tt)|+ => $expr:expr),* $(,)?
     }) => {
         #[allow(unused_variables)]
-        match find_any_syntax($haystack) {
+        match $found {
             $(($i, $(switch_syntax!(@syntax $syntax))|+) => $expr,)*
         }
     };
@@ -97,17 +276,174 @@ macro_rules! switch_syntax {
     (@syntax '}') => (Some(Syntax::RBrace));
     (@syntax '=') => (Some(Syntax::Equal));
     (@syntax ',') => (Some(Syntax::Comma));
+    (@syntax '"') => (Some(Syntax::Quote));
+    (@syntax '/') => (Some(Syntax::Slash));
     (@syntax  % ) => (None);
 }
 
-fn find_syntax(haystack: &str, syntax: Syntax) -> Option<usize> {
-    haystack.bytes().position(move |b| b == syntax as u8)
+/// Find the first syntax character in a field filter value, skipping `\`
+/// escapes.
+///
+/// Errors with the position of a `\` which ends the text, escaping nothing.
+fn find_value_syntax(haystack: &str) -> Result<(usize, Option<Syntax>), usize> {
+    let mut i = 0;
+    loop {
+        let (len, syntax) = find_any_syntax(&haystack[i..]);
+        let escape = match haystack[i..i + len].find('\\') {
+            None => return Ok((i + len, syntax)),
+            Some(escape) => i + escape,
+        };
+        // skip the escape and the character it escapes
+        match haystack[escape + 1..].chars().next() {
+            None => return Err(escape),
+            Some(c) => i = escape + 1 + c.len_utf8(),
+        }
+    }
+}
+
+/// Find `syntax` outside of any quoted value or `\` escape.
+///
+/// Errors with the position of the opening quote if a quoted value is not
+/// terminated, or of a `\` which ends the text.
+fn find_syntax(haystack: &str, syntax: Syntax) -> Result<Option<usize>, usize> {
+    let mut i = 0;
+    while let Some(&b) = haystack.as_bytes().get(i) {
+        if b == syntax as u8 {
+            return Ok(Some(i));
+        } else if b == b'"' {
+            i += 1 + find_close_quote(&haystack[i + 1..]).ok_or(i)?;
+        } else if b == b'\\' {
+            i += 1;
+            if i == haystack.len() {
+                return Err(i - 1);
+            }
+        }
+        i += 1;
+    }
+    Ok(None)
+}
+
+/// An upper bound on the number of items parsed from `directives`.
+///
+/// Items are separated by commas, except those in quoted values or in the
+/// nested directives between `nested` and its closing syntax, which are
+/// skipped, those `\` escaped if `escapes`, and a trailing comma. Counting
+/// stops at syntax which always ends parsing, either with an error or, at
+/// the top level, by starting the regex.
+fn max_items(directives: &str, nested: Option<(u8, Syntax)>, escapes: bool) -> usize {
+    if directives.is_empty() {
+        return 0;
+    }
+    let mut items = 1;
+    let mut i = 0;
+    while let Some(&b) = directives.as_bytes().get(i) {
+        // only sliced after ASCII syntax, which is always followed by a char boundary
+        let rest = || &directives[i + 1..];
+        let skip = match (b, nested) {
+            (b',', _) => {
+                // a trailing comma doesn't start another item
+                if !rest().trim_start().is_empty() {
+                    items += 1;
+                }
+                Some(0)
+            }
+            (b'"', _) => find_close_quote(rest()).map(|close| close + 1),
+            (b'\\', _) if escapes => Some(rest().chars().next().map_or(0, char::len_utf8)),
+            (open, Some((nested, close))) if open == nested => find_syntax(rest(), close)
+                .ok()
+                .flatten()
+                .map(|close| close + 1),
+            (b'[' | b']' | b'{' | b'}' | b'/', _) => None,
+            _ => Some(0),
+        };
+        match skip {
+            Some(skip) => i += 1 + skip,
+            None => break,
+        }
+    }
+    items
+}
+
+/// Find the quote closing a quoted value, skipping `\` escapes.
+pub(crate) fn find_close_quote(haystack: &str) -> Option<usize> {
+    let mut escaped = false;
+    haystack.bytes().position(|b| match b {
+        _ if escaped => {
+            escaped = false;
+            false
+        }
+        b'\\' => {
+            escaped = true;
+            false
+        }
+        b'"' => true,
+        _ => false,
+    })
+}
+
+/// Check that the first `len` bytes of `directives`, a target or span name,
+/// only contain `[A-Za-z0-9_:-]`, as tracing-subscriber requires.
+fn check_name(
+    source: &str,
+    directives: &mut &str,
+    len: usize,
+    part: Part,
+) -> Result<(), ParseError> {
+    match directives[..len].char_indices().find(|&(_, c)| !is_name(c)) {
+        Some((i, c)) => fail(
+            source,
+            directives,
+            i,
+            ParseErrorKind::InvalidCharacter(c),
+            part,
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Report an error at byte `i` of `directives`, and fuse the parser.
+fn fail<T>(
+    source: &str,
+    directives: &mut &str,
+    i: usize,
+    kind: ParseErrorKind,
+    part: Part,
+) -> Result<T, ParseError> {
+    // directives is always a slice of source, so this is its offset
+    let offset = directives.as_ptr() as usize - source.as_ptr() as usize + i;
+    *directives = &directives[directives.len()..];
+    Err(ParseError { kind, offset, part })
 }
 
 impl<'a> Filters<'a> {
-    fn err<T>(&mut self) -> Result<T, ParseError> {
-        self.directives = "";
-        Err(ParseError::BadSyntax)
+    /// Go on past a filter which fails to parse, rather than stopping there.
+    ///
+    /// After yielding an `Err`, the returned iterator skips to the comma
+    /// ending the bad filter and parses the next one, so that
+    /// `good=info,[bad,also_good=warn` yields `good=info`, an error, and
+    /// then `also_good=warn`. Commas in a closed span filter or quoted value
+    /// don't end a filter, but those after an unclosed `[` or `"` do. A `/`
+    /// in a bad filter starts the regex, which takes the rest, so nothing is
+    /// parsed after it.
+    ///
+    /// Only this iterator's own errors are skipped; the nested
+    /// [`SpanFilters`] and [`FieldFilters`] of a filter still stop at theirs.
+    pub fn recover(self) -> Recover<'a> {
+        Recover { filters: self }
+    }
+
+    fn err<T>(&mut self, i: usize, part: Part) -> Result<T, ParseError> {
+        fail(
+            self.source,
+            &mut self.directives,
+            i,
+            ParseErrorKind::BadSyntax,
+            part,
+        )
+    }
+
+    fn check_name(&mut self, len: usize) -> Result<(), ParseError> {
+        check_name(self.source, &mut self.directives, len, Part::Target)
     }
 
     fn target(&mut self) -> Result<&'a str, ParseError> {
@@ -115,34 +451,56 @@ impl<'a> Filters<'a> {
             // target]
             // target{
             // target}
+            // target"
             //       👆
-            ']' | '{' | '}' => self.err(),
+            ']' | '{' | '}' | '"' => {
+                self.check_name(i)?;
+                self.err(i, Part::Target)
+            },
 
             // target[
             // target=
-            // target,
-            // target%
+            // target/
             //       👆
-            '[' | '=' | ',' | % => {
+            '[' | '=' | '/' => {
+                self.check_name(i)?;
                 let target = &self.directives[..i];
                 self.directives = &self.directives[i..];
                 Ok(target)
             },
+
+            // target ,
+            // target %
+            //       👆
+            ',' | % => {
+                let target = self.directives[..i].trim_end();
+                self.check_name(target.len())?;
+                self.directives = &self.directives[i..];
+                Ok(target)
+            },
         })
     }
 
     fn span(&mut self) -> Result<Option<SpanFilters<'a>>, ParseError> {
         // at this point, we know directive starts with one of `[=,%`
         if let Some(stripped) = self.directives.strip_prefix('[') {
-            self.directives = stripped;
-            match find_syntax(self.directives, Syntax::RBrack) {
-                None => self.err(),
-                // span]
-                //     👆
-                Some(i) => {
-                    let directives = &self.directives[..i];
-                    self.directives = &self.directives[i + 1..];
-                    Ok(Some(SpanFilters { directives }))
+            match find_syntax(stripped, Syntax::RBrack) {
+                // [span%
+                // 👆
+                Ok(None) => self.err(0, Part::Span),
+                // [span{field="value%
+                // [span{field=value\%
+                //             👆
+                Err(i) => self.err(1 + i, Part::Span),
+                // [span]
+                //      👆
+                Ok(Some(i)) => {
+                    let directives = &stripped[..i];
+                    self.directives = &stripped[i + 1..];
+                    Ok(Some(SpanFilters {
+                        source: self.source,
+                        directives,
+                    }))
                 }
             }
         } else {
@@ -151,14 +509,11 @@ impl<'a> Filters<'a> {
     }
 
     fn level(&mut self) -> Result<Option<&'a str>, ParseError> {
-        // validate we have no junk after span directive
-        if self.directives.is_empty() || self.directives.starts_with(',') {
-            return Ok(None);
-        }
+        // junk after the span directive is reported by comma
         if let Some(stripped) = self.directives.strip_prefix('=') {
             self.directives = stripped;
         } else {
-            return self.err();
+            return Ok(None);
         }
         switch_syntax!(self.directives => |i| {
             // level[
@@ -166,28 +521,45 @@ impl<'a> Filters<'a> {
             // level{
             // level}
             // level=
+            // level"
             //      👆
-            '[' | ']' | '{' | '}' | '=' => self.err(),
+            '[' | ']' | '{' | '}' | '=' | '"' => self.err(i, Part::Level),
 
-            // level,
-            // level%
+            // level/
             //      👆
-            ',' | % => {
+            '/' => {
                 let level = &self.directives[..i];
                 self.directives = &self.directives[i..];
                 Ok(Some(level))
             },
+
+            // level ,
+            // level %
+            //      👆
+            ',' | % => {
+                let level = self.directives[..i].trim_end();
+                self.directives = &self.directives[i..];
+                Ok(Some(level))
+            },
         })
     }
 
+    fn regex(&mut self) -> Option<&'a str> {
+        // the regex is free-form, so it takes the rest of the directives
+        let regex = self.directives.strip_prefix('/')?;
+        self.directives = &self.directives[self.directives.len()..];
+        Some(regex)
+    }
+
     fn comma(&mut self) -> Result<(), ParseError> {
+        self.directives = self.directives.trim_start();
         if let Some(stripped) = self.directives.strip_prefix(',') {
             self.directives = stripped;
             Ok(())
         } else if self.directives.is_empty() {
             Ok(())
         } else {
-            self.err()
+            self.err(0, Part::Level)
         }
     }
 }
@@ -196,48 +568,112 @@ impl<'a> Iterator for Filters<'a> {
     type Item = Result<Filter<'a>, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        // whitespace around a directive is ignored, as it's often written
+        self.directives = self.directives.trim_start();
         if self.directives.is_empty() {
             return None;
         }
 
-        // Reserved syntax
-        if self.directives.contains('"') || self.directives.contains('/') {
-            let _ = self.err::<()>();
-            return Some(Err(ParseError::ReservedSyntax));
-        }
-
         Some((|| {
             let target = self.target()?;
             let span = self.span()?;
             let level = self.level()?;
+            let regex = self.regex();
             self.comma()?;
             Ok(Filter {
                 target,
                 span,
                 level,
+                regex,
             })
         })())
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (
-            0,
-            Some(
-                self.directives
-                    .as_bytes()
-                    .iter()
-                    .filter(|&&b| b == b',')
-                    .count()
-                    + !self.directives.is_empty() as usize,
-            ),
-        )
+        let upper = count_directives(self.directives);
+        (upper.min(1), Some(upper))
+    }
+}
+
+impl FusedIterator for Filters<'_> {}
+
+impl<'a> Recover<'a> {
+    /// The directives not yet parsed.
+    pub(crate) fn rest(&self) -> &'a str {
+        self.filters.directives
+    }
+}
+
+impl<'a> Iterator for Recover<'a> {
+    type Item = Result<Filter<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.filters.directives;
+        let item = self.filters.next()?;
+        if item.is_err() {
+            // the error emptied the directives, so pick up again from the start
+            self.filters.directives = after_filter(start);
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // each error may skip to more items than parsing would have seen
+        (self.filters.size_hint().0, None)
     }
 }
 
+impl FusedIterator for Recover<'_> {}
+
+/// The directives after the comma ending the first filter in `directives`,
+/// or nothing if it is the last filter or has a regex.
+///
+/// Commas in a closed span filter or a closed quoted value, and those `\`
+/// escaped, don't end the filter; an unclosed `[` or `"` is passed over.
+fn after_filter(directives: &str) -> &str {
+    let mut i = 0;
+    while let Some(&b) = directives.as_bytes().get(i) {
+        // only sliced after ASCII syntax, which is always followed by a char boundary
+        let rest = || &directives[i + 1..];
+        let skip = match b {
+            b',' => return rest(),
+            b'/' => break,
+            b'"' => find_close_quote(rest()).map_or(0, |close| close + 1),
+            b'\\' => rest().chars().next().map_or(0, char::len_utf8),
+            b'[' => match find_syntax(rest(), Syntax::RBrack) {
+                Ok(Some(close)) => close + 1,
+                _ => 0,
+            },
+            _ => 0,
+        };
+        i += 1 + skip;
+    }
+    &directives[directives.len()..]
+}
+
 impl<'a> SpanFilters<'a> {
-    fn err<T>(&mut self) -> Result<T, ParseError> {
-        self.directives = "";
-        Err(ParseError::BadSyntax)
+    fn err<T>(&mut self, i: usize, part: Part) -> Result<T, ParseError> {
+        fail(
+            self.source,
+            &mut self.directives,
+            i,
+            ParseErrorKind::BadSyntax,
+            part,
+        )
+    }
+
+    fn reserved<T>(&mut self, i: usize, part: Part) -> Result<T, ParseError> {
+        fail(
+            self.source,
+            &mut self.directives,
+            i,
+            ParseErrorKind::ReservedSyntax,
+            part,
+        )
+    }
+
+    fn check_name(&mut self, len: usize) -> Result<(), ParseError> {
+        check_name(self.source, &mut self.directives, len, Part::Span)
     }
 
     fn name(&mut self) -> Result<&'a str, ParseError> {
@@ -246,14 +682,26 @@ impl<'a> SpanFilters<'a> {
             // span]
             // span}
             // span=
+            // span"
+            //     👆
+            '[' | ']' | '}' | '=' | '"' => {
+                self.check_name(i)?;
+                self.err(i, Part::Span)
+            },
+
+            // span/
             //     👆
-            '[' | ']' | '}' | '=' => self.err(),
+            '/' => {
+                self.check_name(i)?;
+                self.reserved(i, Part::Span)
+            },
 
             // span{
             // span,
             // span%
             //     👆
             '{' | ',' | % => {
+                self.check_name(i)?;
                 let name = &self.directives[..i];
                 self.directives = &self.directives[i..];
                 Ok(name)
@@ -264,15 +712,23 @@ impl<'a> SpanFilters<'a> {
     fn fields(&mut self) -> Result<Option<FieldFilters<'a>>, ParseError> {
         // at this point, we know directive starts with one of `{,%`
         if let Some(stripped) = self.directives.strip_prefix('{') {
-            self.directives = stripped;
-            match find_syntax(self.directives, Syntax::RBrace) {
-                None => self.err(),
-                // field}
-                //      👆
-                Some(i) => {
-                    let directives = &self.directives[..i];
-                    self.directives = &self.directives[i + 1..];
-                    Ok(Some(FieldFilters { directives }))
+            match find_syntax(stripped, Syntax::RBrace) {
+                // {field%
+                // 👆
+                Ok(None) => self.err(0, Part::Field),
+                // {field="value%
+                // {field=value\%
+                //        👆
+                Err(i) => self.err(1 + i, Part::Field),
+                // {field}
+                //       👆
+                Ok(Some(i)) => {
+                    let directives = &stripped[..i];
+                    self.directives = &stripped[i + 1..];
+                    Ok(Some(FieldFilters {
+                        source: self.source,
+                        directives,
+                    }))
                 }
             }
         } else {
@@ -286,8 +742,10 @@ impl<'a> SpanFilters<'a> {
             Ok(())
         } else if self.directives.is_empty() {
             Ok(())
+        } else if self.directives.starts_with('/') {
+            self.reserved(0, Part::Span)
         } else {
-            self.err()
+            self.err(0, Part::Span)
         }
     }
 }
@@ -300,12 +758,6 @@ impl<'a> Iterator for SpanFilters<'a> {
             return None;
         }
 
-        // Reserved syntax
-        if self.directives.contains('"') || self.directives.contains('/') {
-            let _ = self.err::<()>();
-            return Some(Err(ParseError::ReservedSyntax));
-        }
-
         Some((|| {
             let name = self.name()?;
             let fields = self.fields()?;
@@ -315,24 +767,35 @@ impl<'a> Iterator for SpanFilters<'a> {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
+        let nested = Some((b'{', Syntax::RBrace));
         (
-            0,
-            Some(
-                self.directives
-                    .as_bytes()
-                    .iter()
-                    .filter(|&&b| b == b',')
-                    .count()
-                    + !self.directives.is_empty() as usize,
-            ),
+            !self.directives.is_empty() as usize,
+            Some(max_items(self.directives, nested, true)),
         )
     }
 }
 
+impl FusedIterator for SpanFilters<'_> {}
+
 impl<'a> FieldFilters<'a> {
-    fn err<T>(&mut self) -> Result<T, ParseError> {
-        self.directives = "";
-        Err(ParseError::BadSyntax)
+    fn err<T>(&mut self, i: usize) -> Result<T, ParseError> {
+        fail(
+            self.source,
+            &mut self.directives,
+            i,
+            ParseErrorKind::BadSyntax,
+            Part::Field,
+        )
+    }
+
+    fn reserved<T>(&mut self, i: usize) -> Result<T, ParseError> {
+        fail(
+            self.source,
+            &mut self.directives,
+            i,
+            ParseErrorKind::ReservedSyntax,
+            Part::Field,
+        )
     }
 
     fn name(&mut self) -> Result<&'a str, ParseError> {
@@ -341,14 +804,23 @@ impl<'a> FieldFilters<'a> {
             // field]
             // field{
             // field}
+            // field"
+            //      👆
+            '[' | ']' | '{' | '}' | '"' => self.err(i),
+
+            // field/
             //      👆
-            '[' | ']' | '{' | '}' => self.err(),
+            '/' => self.reserved(i),
 
             // field=
             // field,
             // field%
             //      👆
             '=' | ',' | % => {
+                // escapes are only for values
+                if let Some(escape) = self.directives[..i].find('\\') {
+                    return self.err(escape);
+                }
                 let name = &self.directives[..i];
                 self.directives = &self.directives[i..];
                 Ok(name)
@@ -363,14 +835,39 @@ impl<'a> FieldFilters<'a> {
         } else {
             return Ok(None);
         }
-        switch_syntax!(self.directives => |i| {
+        if let Some(stripped) = self.directives.strip_prefix('"') {
+            return match find_close_quote(stripped) {
+                // "value%
+                // 👆
+                None => self.err(0),
+                // "value"
+                //       👆
+                Some(i) => {
+                    let value = &stripped[..i];
+                    self.directives = &stripped[i + 1..];
+                    Ok(Some(value))
+                }
+            };
+        }
+        let found = match find_value_syntax(self.directives) {
+            Ok(found) => found,
+            // value\%
+            //      👆
+            Err(i) => return self.err(i),
+        };
+        switch_syntax!(@found found => |i| {
             // value[
             // value]
             // value{
             // value}
             // value=
+            // value"
             //      👆
-            '[' | ']' | '{' | '}' | '=' => self.err(),
+            '[' | ']' | '{' | '}' | '=' | '"' => self.err(i),
+
+            // value/
+            //      👆
+            '/' => self.reserved(i),
 
             // value,
             // value%
@@ -389,8 +886,10 @@ impl<'a> FieldFilters<'a> {
             Ok(())
         } else if self.directives.is_empty() {
             Ok(())
+        } else if self.directives.starts_with('/') {
+            self.reserved(0)
         } else {
-            self.err()
+            self.err(0)
         }
     }
 }
@@ -403,12 +902,6 @@ impl<'a> Iterator for FieldFilters<'a> {
             return None;
         }
 
-        // Reserved syntax
-        if self.directives.contains('"') || self.directives.contains('/') {
-            let _ = self.err::<()>();
-            return Some(Err(ParseError::ReservedSyntax));
-        }
-
         Some((|| {
             let name = self.name()?;
             let value = self.value()?;
@@ -419,15 +912,10 @@ impl<'a> Iterator for FieldFilters<'a> {
 
     fn size_hint(&self) -> (usize, Option<usize>) {
         (
-            0,
-            Some(
-                self.directives
-                    .as_bytes()
-                    .iter()
-                    .filter(|&&b| b == b',')
-                    .count()
-                    + !self.directives.is_empty() as usize,
-            ),
+            !self.directives.is_empty() as usize,
+            Some(max_items(self.directives, None, true)),
         )
     }
 }
+
+impl FusedIterator for FieldFilters<'_> {}
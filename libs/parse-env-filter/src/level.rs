@@ -0,0 +1,57 @@
+use core::fmt;
+
+/// The level of a filter, from [`Filter::parsed_level`](crate::Filter::parsed_level).
+///
+/// Levels are ordered by verbosity, from `Off` to `Trace`, like
+/// `tracing::level_filters::LevelFilter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Level {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    /// Parse a level name, ignoring case, or a number from `0` (off) to `5` (trace).
+    pub fn parse(level: &str) -> Option<Level> {
+        use Level::*;
+        [Off, Error, Warn, Info, Debug, Trace]
+            .iter()
+            .enumerate()
+            .find(|&(number, name)| {
+                level.eq_ignore_ascii_case(name.as_str())
+                    || level.as_bytes() == [b'0' + number as u8]
+            })
+            .map(|(_, &parsed)| parsed)
+    }
+
+    /// The lowercase name of this level.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Off => "off",
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+/// Parse a level name, ignoring case, or a number from `0` (off) to `5` (trace).
+///
+/// This is [`Level::parse`] as a free function, for checking levels taken
+/// from a [`Filter`](crate::Filter) or elsewhere. With the
+/// `tracing-subscriber` feature, a `Level` converts into a `LevelFilter`.
+pub fn parse_level(level: &str) -> Option<Level> {
+    Level::parse(level)
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -3,33 +3,41 @@
 //! We always support the extended tracing format, that is
 //!
 //! ```text
-//! target[span{field=value}]=level
+//! target[span{field=value}]=level/regex
 //! ```
 //!
 //! with the following validity rules:
 //!
 //! - All fields are optional, and MAY be omitted
 //! - All fields MUST NOT contain the syntax characters `[]{}=,"/`
-//!   - This may be relaxed in the future, to allow e.g. matched brackets in `value` and/or quoting values
+//!   - except for a field filter `value`, which MAY be quoted as `"value"` to contain any of them,
+//!     or escape one with `\`, as in `a\,b`; `\\` is a literal backslash, and `\"` a literal quote
+//!     (the quotes are stripped, but escapes are left as written, to be replaced with `unescape`)
+//! - A `target` or span `name` MUST only contain `[A-Za-z0-9_:-]`, as tracing::EnvFilter requires
+//! - A `/regex`, as env_logger uses to filter messages, takes the whole rest of the directive string,
+//!   so only the last filter MAY have one; it is not validated, and MAY contain any character
 //! - Unlike env_logger/tracing::EnvFilter, we treat a bare level name as a target, not a level directive
-//!   - Adding this behavior back is simple — check if only a target is set and if so try it as a level
+//!   - [`Filter::into_directive`] tells the two apart, reading a filter with only a target that is a level name as a level
 //!
-//! Note that these rules do not trim whitespace; you'll likely want to yourself.
+//! Whitespace around each comma-separated directive is ignored, but not within one.
 //! If you want further verification, you can add it on after the parse step.
 //! This crate is merely intended to pull the directives out of the format, not
-//! to ensure that the directives are otherwise well-formed or meaningful.
+//! to ensure that the directives are otherwise well-formed or meaningful,
+//! except for levels, which [`Filter::parsed_level`] checks if asked.
+//!
+//! ## Cargo features
+//!
+//! - `alloc` (default): the [`eager`] module, which collects filters into `Vec`s,
+//!   and [`DirectiveBuilder`], which writes a directive string.
+//!   Without it, the crate doesn't allocate at all; the lazy [`filters`] iterators,
+//!   the [`parse_with`] visitor, and [`validate`] are always available, for `no_std` targets too.
+//! - `serde`: `Serialize` and `Deserialize` for the eager filters.
+//! - `tracing-subscriber`: the [`subscriber`] module, to build a tracing-subscriber `EnvFilter`.
 //!
 //! ## Features not supported
 //!
 //! - With tracing::EnvFilter, parsing is ad-hoc and can often allow odd edge cases through.
 //!   We instead opt to be strict and require exactly matching the syntax, rather than sloppy acceptance.
-//! - Along the same lines, tracing allows (and sometimes expects) using quotes in field filter values.
-//!   Quotes are currently reserved, such that a quoted syntax that allows syntax characters in filter
-//!   fields can be added in the future.
-//! - env_logger supports a global `/regex` directive to filter messages via a regex. This applies
-//!   separately and to all other earlier directives, and as such doesn't quite fit the filter
-//!   iterator design we've taken. However, we have reserved the `/` character for clarity
-//!   and such that a filter field can potentially be added with this syntax in the future.
 //! - tracing::EnvFilter interprets the value of field=value as a regular expression. We explicitly
 //!   leave that level of interpretation up to the consumer, as this library is just for parsing.
 //!
@@ -37,16 +45,74 @@
 //! [tracing::EnvFilter]: <https://docs.rs/tracing-subscriber/0.2/tracing_subscriber/filter/struct.EnvFilter.html>
 
 #![no_std]
+#![forbid(unsafe_code)]
 
+#[cfg(feature = "alloc")]
+mod builder;
 #[cfg(feature = "alloc")]
 pub mod eager;
 mod lazy;
+mod level;
+#[cfg(feature = "tracing-subscriber")]
+pub mod subscriber;
+mod visit;
+
+use core::fmt;
 
+#[cfg(feature = "alloc")]
+pub use builder::*;
 pub use lazy::*;
+pub use level::*;
+pub use visit::*;
+
+/// An error parsing a directive string, and where it happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// The byte offset into the whole directive string where parsing failed.
+    pub offset: usize,
+    /// The part of a filter being parsed when parsing failed.
+    pub part: Part,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::ReservedSyntax => f.write_str("reserved syntax")?,
+            ParseErrorKind::BadSyntax => f.write_str("invalid syntax")?,
+            ParseErrorKind::InvalidLevel => f.write_str("invalid level")?,
+            ParseErrorKind::InvalidCharacter(c) => write!(f, "invalid character {:?}", c)?,
+        }
+        let part = match self.part {
+            Part::Target => "target",
+            Part::Span => "span filter",
+            Part::Field => "field filter",
+            Part::Level => "level",
+        };
+        write!(f, " in {} at offset {}", part, self.offset)
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[non_exhaustive]
-pub enum ParseError {
+pub enum ParseErrorKind {
     ReservedSyntax,
     BadSyntax,
+    /// A level that is not a [`Level`], from [`Filter::parsed_level`].
+    InvalidLevel,
+    /// A character not allowed in a target or span name, which may only
+    /// contain `[A-Za-z0-9_:-]`; the error's offset is where it starts.
+    InvalidCharacter(char),
+}
+
+/// A part of a filter, `target[span{field=value}]=level`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Part {
+    Target,
+    /// A span filter, or its name.
+    Span,
+    /// A field filter, its name, or its value.
+    Field,
+    Level,
 }
@@ -0,0 +1,205 @@
+//! Conversions into tracing-subscriber's [`EnvFilter`].
+//!
+//! tracing-subscriber can't express everything this crate parses, so the
+//! conversions fail with [`DirectiveError::Unsupported`] for filters with more
+//! than one span filter, a `/regex`, or a field filter that tracing-subscriber
+//! would read differently. Like [`EnvFilter`] itself, a target which is a
+//! level name is ignored, so a bare `info` is a global level.
+
+extern crate alloc;
+
+use crate::{
+    eager::{typed_filters, Filter, TypedFilter},
+    lazy::is_syntax,
+    Level, ParseError,
+};
+use alloc::string::String;
+use core::{convert::TryFrom, fmt, fmt::Write};
+use tracing_subscriber::filter::{Directive, EnvFilter, LevelFilter};
+
+/// Parse a directive string into an [`EnvFilter`].
+///
+/// Empty filters are skipped, and a filter without a level enables every level.
+pub fn to_env_filter(directives: &str) -> Result<EnvFilter, DirectiveError> {
+    build_env_filter(typed_filters(directives)?.into_iter().map(Ok))
+}
+
+/// Build an [`EnvFilter`] from already parsed filters.
+///
+/// Like [`to_env_filter`], but fails with [`DirectiveError::InvalidLevel`]
+/// for a level which isn't a [`Level`].
+pub fn from_filters(filters: &[Filter<'_>]) -> Result<EnvFilter, DirectiveError> {
+    build_env_filter(filters.iter().map(typed))
+}
+
+fn build_env_filter<'a>(
+    filters: impl Iterator<Item = Result<TypedFilter<'a>, DirectiveError>>,
+) -> Result<EnvFilter, DirectiveError> {
+    let mut env_filter = String::new();
+    for filter in filters {
+        let filter = filter?;
+        if is_empty(&filter) {
+            continue;
+        }
+        if !env_filter.is_empty() {
+            env_filter += ",";
+        }
+        write_directive(&mut env_filter, &filter)?;
+    }
+    if env_filter.is_empty() {
+        return Ok(EnvFilter::default());
+    }
+    // adding directives to a default EnvFilter would keep its default directive
+    EnvFilter::try_new(env_filter).map_err(DirectiveError::Subscriber)
+}
+
+impl TryFrom<TypedFilter<'_>> for Directive {
+    type Error = DirectiveError;
+
+    fn try_from(filter: TypedFilter<'_>) -> Result<Self, Self::Error> {
+        let mut directive = String::new();
+        write_directive(&mut directive, &filter)?;
+        directive.parse().map_err(DirectiveError::Subscriber)
+    }
+}
+
+impl TryFrom<Filter<'_>> for Directive {
+    type Error = DirectiveError;
+
+    fn try_from(filter: Filter<'_>) -> Result<Self, Self::Error> {
+        Directive::try_from(&filter)
+    }
+}
+
+impl TryFrom<&Filter<'_>> for Directive {
+    type Error = DirectiveError;
+
+    fn try_from(filter: &Filter<'_>) -> Result<Self, Self::Error> {
+        Directive::try_from(typed(filter)?)
+    }
+}
+
+/// Check the level of `filter`.
+fn typed<'a>(filter: &Filter<'a>) -> Result<TypedFilter<'a>, DirectiveError> {
+    let level = match filter.level {
+        None => None,
+        Some(level) => match Level::parse(level) {
+            Some(level) => Some(level),
+            None => return Err(DirectiveError::InvalidLevel(level.into())),
+        },
+    };
+    Ok(TypedFilter {
+        target: filter.target,
+        span: filter.span.clone(),
+        level,
+        regex: filter.regex,
+    })
+}
+
+/// Write `filter` in the syntax tracing-subscriber parses.
+fn write_directive(directive: &mut String, filter: &TypedFilter<'_>) -> Result<(), DirectiveError> {
+    if filter.regex.is_some() {
+        return Err(DirectiveError::Unsupported("message regex"));
+    }
+    let span = match filter.span.as_deref() {
+        None | Some([]) => None,
+        Some([span]) => Some(span),
+        Some(_) => return Err(DirectiveError::Unsupported("multiple span filters")),
+    };
+    if filter.target.is_empty() && span.is_none() {
+        // the global level, which EnvFilter writes without a `=`
+        let level = filter.level.map_or(LevelFilter::TRACE, Into::into);
+        let _ = write!(directive, "{}", level);
+        return Ok(());
+    }
+
+    *directive += filter.target;
+    if let Some(span) = span {
+        *directive += "[";
+        *directive += span.name;
+        if let Some(fields) = &span.fields {
+            *directive += "{";
+            for (ix, field) in fields.iter().enumerate() {
+                if !is_field_name(field.name) {
+                    return Err(DirectiveError::Unsupported("field name"));
+                }
+                if ix != 0 {
+                    *directive += ",";
+                }
+                *directive += field.name;
+                if let Some(value) = field.value {
+                    if value.is_empty() || value.contains(is_syntax) {
+                        return Err(DirectiveError::Unsupported("field value"));
+                    }
+                    *directive += "=";
+                    *directive += value;
+                }
+            }
+            *directive += "}";
+        }
+        *directive += "]";
+    }
+    if let Some(level) = filter.level {
+        let _ = write!(directive, "={}", LevelFilter::from(level));
+    }
+    Ok(())
+}
+
+impl From<Level> for LevelFilter {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::Off => LevelFilter::OFF,
+            Level::Error => LevelFilter::ERROR,
+            Level::Warn => LevelFilter::WARN,
+            Level::Info => LevelFilter::INFO,
+            Level::Debug => LevelFilter::DEBUG,
+            Level::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+fn is_empty(filter: &TypedFilter<'_>) -> bool {
+    filter.target.is_empty()
+        && filter.span.is_none()
+        && filter.level.is_none()
+        && filter.regex.is_none()
+}
+
+/// tracing-subscriber's field names are `[[:word:]][[[:word:]]\.]*`.
+fn is_field_name(name: &str) -> bool {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    name.starts_with(is_word) && name.chars().all(|c| is_word(c) || c == '.')
+}
+
+/// An error converting filters into tracing-subscriber's filters.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DirectiveError {
+    /// The directive string didn't parse.
+    Parse(ParseError),
+    /// An already parsed filter has a level which isn't a [`Level`].
+    InvalidLevel(String),
+    /// The filter has a part tracing-subscriber can't express.
+    Unsupported(&'static str),
+    /// tracing-subscriber rejected the filter.
+    Subscriber(tracing_subscriber::filter::ParseError),
+}
+
+impl From<ParseError> for DirectiveError {
+    fn from(error: ParseError) -> Self {
+        DirectiveError::Parse(error)
+    }
+}
+
+impl fmt::Display for DirectiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DirectiveError::Parse(error) => error.fmt(f),
+            DirectiveError::InvalidLevel(level) => write!(f, "invalid level {:?}", level),
+            DirectiveError::Unsupported(part) => {
+                write!(f, "{} is not supported by EnvFilter", part)
+            }
+            DirectiveError::Subscriber(error) => error.fmt(f),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{filters, Filter, ParseError};
+use core::ops::{ControlFlow, Range};
+
+/// Callbacks for each part of a directive string, as driven by [`parse_with`].
+///
+/// Callbacks are made in source order. Each filter is begun with
+/// `begin_filter`, then has its span and field filters, and is ended with
+/// `end_filter`, or `error` if parsing fails before it is complete:
+///
+/// ```text
+/// (begin_filter [begin_spans (span_filter [begin_fields field_filter*])*] end_filter)* [regex] [error]
+/// ```
+///
+/// `begin_spans` and `begin_fields` are only made if the filter or span
+/// filter has a `[]` or `{}` section, even if it is empty.
+pub trait Visitor<'a> {
+    /// A filter starts, with its target.
+    ///
+    /// Returning `Break` stops parsing without making any more callbacks.
+    fn begin_filter(&mut self, target: &'a str) -> ControlFlow<()> {
+        let _ = target;
+        ControlFlow::Continue(())
+    }
+
+    /// The filter has a span section, `[..]`.
+    fn begin_spans(&mut self) {}
+
+    /// A span filter, with its name.
+    fn span_filter(&mut self, name: &'a str) {
+        let _ = name;
+    }
+
+    /// The span filter has a field section, `{..}`.
+    fn begin_fields(&mut self) {}
+
+    /// A field filter in the latest span filter.
+    fn field_filter(&mut self, name: &'a str, value: Option<&'a str>) {
+        let _ = (name, value);
+    }
+
+    /// The filter ends, with its level.
+    fn end_filter(&mut self, level: Option<&'a str>) {
+        let _ = level;
+    }
+
+    /// The last filter is followed by a message regex, `/regex`, which is the
+    /// rest of the directive string.
+    fn regex(&mut self, regex: &'a str) {
+        let _ = regex;
+    }
+
+    /// Parsing failed, at the byte `range` of the directive string.
+    ///
+    /// This is the last callback made.
+    fn error(&mut self, error: ParseError, range: Range<usize>);
+}
+
+/// Parse a directive string, calling `visitor` for each part of it.
+///
+/// This drives the lazy [`filters`] parser, so is zero-alloc, and validates
+/// everything up to the first error or `Break`.
+pub fn parse_with<'a, V: Visitor<'a> + ?Sized>(directives: &'a str, visitor: &mut V) {
+    let error = |visitor: &mut V, error: ParseError| {
+        let len = directives[error.offset..]
+            .chars()
+            .next()
+            .map_or(0, char::len_utf8);
+        visitor.error(error, error.offset..error.offset + len);
+    };
+
+    for filter in filters(directives) {
+        let filter = match filter {
+            Ok(filter) => filter,
+            Err(err) => return error(visitor, err),
+        };
+        if visitor.begin_filter(filter.target).is_break() {
+            return;
+        }
+        if let Some(spans) = filter.span {
+            visitor.begin_spans();
+            for span in spans {
+                let span = match span {
+                    Ok(span) => span,
+                    Err(err) => return error(visitor, err),
+                };
+                visitor.span_filter(span.name);
+                if let Some(fields) = span.fields {
+                    visitor.begin_fields();
+                    for field in fields {
+                        match field {
+                            Ok(field) => visitor.field_filter(field.name, field.value),
+                            Err(err) => return error(visitor, err),
+                        }
+                    }
+                }
+            }
+        }
+        visitor.end_filter(filter.level);
+        if let Some(regex) = filter.regex {
+            visitor.regex(regex);
+        }
+    }
+}
+
+/// Check that a directive string parses, without allocating.
+///
+/// The lazy [`filters`] iterator only checks a filter's span and field
+/// filters as they are iterated, so `target[span{field` is accepted until its
+/// spans are; this walks every part of every filter. Run it when loading
+/// configuration to reject a directive string up front.
+pub fn validate(directives: &str) -> Result<(), ParseError> {
+    count_filters(directives).map(drop)
+}
+
+/// Count the filters in a directive string, checking that it parses as
+/// [`validate`] does.
+pub fn count_filters(directives: &str) -> Result<usize, ParseError> {
+    struct Count {
+        filters: usize,
+        error: Option<ParseError>,
+    }
+
+    impl Visitor<'_> for Count {
+        fn end_filter(&mut self, _: Option<&str>) {
+            self.filters += 1;
+        }
+
+        fn error(&mut self, error: ParseError, _: Range<usize>) {
+            self.error = Some(error);
+        }
+    }
+
+    let mut count = Count {
+        filters: 0,
+        error: None,
+    };
+    parse_with(directives, &mut count);
+    match count.error {
+        Some(error) => Err(error),
+        None => Ok(count.filters),
+    }
+}
+
+/// A directive of a directive string, and whether it parses; see [`check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectiveCheck<'a> {
+    /// The directive, without the whitespace around it or the comma after
+    /// it, and with its message regex if it has one.
+    pub directive: &'a str,
+    /// The byte range of `directive` in the directive string.
+    pub range: Range<usize>,
+    /// The first error in the directive, walking every part of it as
+    /// [`validate`] does.
+    pub result: Result<(), ParseError>,
+}
+
+/// Check each directive of a directive string, without allocating.
+///
+/// Unlike [`validate`], this goes on past a directive which fails to parse,
+/// as [`Filters::recover`](crate::Filters::recover) does, so that every bad
+/// directive can be pointed out. Empty directives and trailing commas are
+/// read as [`filters`] reads them: `a,,b,` has three directives, the second
+/// of them empty.
+pub fn check(directives: &str) -> impl Iterator<Item = DirectiveCheck<'_>> {
+    let mut filters = filters(directives).recover();
+    core::iter::from_fn(move || {
+        // the parser skips whitespace before a directive
+        let start = filters.rest().trim_start();
+        let result = filters.next()?.and_then(|filter| walk(&filter));
+        let consumed = &start[..start.len() - filters.rest().len()];
+        let directive = consumed.strip_suffix(',').unwrap_or(consumed).trim_end();
+        let offset = directives.len() - start.len();
+        Some(DirectiveCheck {
+            directive,
+            range: offset..offset + directive.len(),
+            result,
+        })
+    })
+}
+
+/// Parse each span and field filter of `filter`, up to the first error.
+fn walk(filter: &Filter<'_>) -> Result<(), ParseError> {
+    for span in filter.span.clone().into_iter().flatten() {
+        for field in span?.fields.into_iter().flatten() {
+            field?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use parse_env_filter::{
+    eager::{parse_owned, OwnedFieldFilter, OwnedFilter, OwnedSpanFilter},
+    DirectiveBuilder, ParseError, ParseErrorKind, Part,
+};
+
+/// A tiny xorshift generator, so failures reproduce.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    fn text(&mut self, pieces: &[&str], min: usize) -> String {
+        (0..min + self.below(4))
+            .map(|_| pieces[self.below(pieces.len())])
+            .collect()
+    }
+
+    fn maybe<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        match self.below(2) {
+            0 => None,
+            _ => Some(f(self)),
+        }
+    }
+
+    fn filter(&mut self) -> OwnedFilter {
+        let names = ["a", "b", "_", ":", "-", "0"];
+        let span = |rng: &mut Rng| {
+            (0..1 + rng.below(3))
+                .map(|_| {
+                    let fields: Option<Vec<_>> = rng.maybe(|rng| {
+                        (0..1 + rng.below(3))
+                            .map(|_| OwnedFieldFilter {
+                                name: rng.text(&["a", "é", " ", "."], 1),
+                                value: rng.maybe(|rng| {
+                                    let pieces = [
+                                        "a", " ", ",", "[]", "{}", "=", "/", r#"\""#, r"\\", r"\,",
+                                    ];
+                                    rng.text(&pieces, 0)
+                                }),
+                            })
+                            .collect()
+                    });
+                    OwnedSpanFilter {
+                        // only a span filter with fields may have an empty name
+                        name: rng.text(&names, fields.is_none() as usize),
+                        fields,
+                    }
+                })
+                .collect()
+        };
+        OwnedFilter {
+            target: self.text(&names, 0),
+            span: self.maybe(span),
+            level: self.maybe(|rng| rng.text(&["a", "é", " a", "5"], 0)),
+            regex: None,
+        }
+    }
+}
+
+/// Build `filters`, with the regex of the last one.
+fn build(filters: &[OwnedFilter]) -> Result<String, ParseError> {
+    let mut builder = DirectiveBuilder::new();
+    for (ix, filter) in filters.iter().enumerate() {
+        if ix != 0 {
+            builder = builder.push();
+        }
+        builder = builder.target(&filter.target);
+        for span in filter.span.iter().flatten() {
+            builder = builder.span(&span.name);
+            for field in span.fields.iter().flatten() {
+                builder = builder.field(&field.name, field.value.as_deref());
+            }
+        }
+        if let Some(level) = &filter.level {
+            builder = builder.level(level);
+        }
+    }
+    match filters.last().and_then(|filter| filter.regex.as_deref()) {
+        Some(regex) => builder.regex(regex),
+        None => builder.finish(),
+    }
+}
+
+#[test]
+fn built_directives_parse_back() {
+    let mut rng = Rng(0xb01d);
+    for _ in 0..2000 {
+        let mut filters: Vec<_> = (0..rng.below(4)).map(|_| rng.filter()).collect();
+        if let Some(last) = filters.last_mut() {
+            last.regex = rng.maybe(|rng| rng.text(&["a", ".*", ",", "/", " ", "\""], 0));
+        }
+        let directives = build(&filters).unwrap();
+
+        // filters with nothing in them are left out
+        filters.retain(|filter| {
+            !filter.target.is_empty()
+                || filter.span.is_some()
+                || filter.level.is_some()
+                || filter.regex.is_some()
+        });
+        assert_eq!(parse_owned(&directives), Ok(filters), "{:?}", directives);
+    }
+}
+
+#[test]
+fn invalid_parts_are_errors() {
+    let error = |kind, offset, part| Err(ParseError { kind, offset, part });
+    let invalid = |c, offset, part| error(ParseErrorKind::InvalidCharacter(c), offset, part);
+
+    let builder = || DirectiveBuilder::new().target("a").push();
+    assert_eq!(
+        builder().target("b c").finish(),
+        invalid(' ', 3, Part::Target)
+    );
+    assert_eq!(builder().span("é").finish(), invalid('é', 3, Part::Span));
+    assert_eq!(
+        builder().span("s").field("x=y", None).finish(),
+        invalid('=', 6, Part::Field)
+    );
+    assert_eq!(
+        builder().span("s").field("x", Some(r#"a"b"#)).finish(),
+        invalid('"', 9, Part::Field)
+    );
+    assert_eq!(
+        builder().span("s").field("x", Some(r"a,\")).finish(),
+        invalid('\\', 10, Part::Field)
+    );
+    assert_eq!(
+        builder().span("s").field("x", Some(r"a\")).finish(),
+        invalid('\\', 9, Part::Field)
+    );
+    assert_eq!(
+        builder().span("s").field(r"x\,y", None).finish(),
+        invalid('\\', 6, Part::Field)
+    );
+    assert_eq!(
+        builder().level("info/").finish(),
+        invalid('/', 7, Part::Level)
+    );
+    assert_eq!(
+        builder().level("info ").finish(),
+        invalid(' ', 7, Part::Level)
+    );
+
+    // parts must be in order, and only spans with fields can have empty names
+    let bad = |offset, part| error(ParseErrorKind::BadSyntax, offset, part);
+    assert_eq!(
+        builder().level("info").target("b").finish(),
+        bad(7, Part::Target)
+    );
+    assert_eq!(builder().field("x", None).finish(), bad(2, Part::Field));
+    assert_eq!(
+        builder().level("info").span("s").finish(),
+        bad(7, Part::Span)
+    );
+    assert_eq!(
+        builder().span("s").field("", None).finish(),
+        bad(5, Part::Field)
+    );
+    assert_eq!(builder().span("").finish(), bad(3, Part::Span));
+    assert_eq!(builder().span("").span("s").finish(), bad(3, Part::Span));
+    assert_eq!(
+        builder().span("").level("info").finish(),
+        bad(3, Part::Span)
+    );
+    assert_eq!(builder().span("").regex("x"), bad(3, Part::Span));
+
+    // the first error is kept
+    assert_eq!(
+        builder().target("é").level("/").regex("x"),
+        invalid('é', 2, Part::Target)
+    );
+}
+
+#[test]
+fn span_fields_without_a_span_name() {
+    let built = DirectiveBuilder::new()
+        .target("a")
+        .span("")
+        .field("user", Some("bob"))
+        .span("")
+        .field("id", None)
+        .level("debug")
+        .finish();
+    assert_eq!(built.unwrap(), "a[{user=bob},{id}]=debug");
+}
+
+#[test]
+fn values_are_quoted_when_needed() {
+    let built = DirectiveBuilder::new()
+        .span("s")
+        .field("a", Some("x y"))
+        .field("b", Some(""))
+        .field("c", Some(r#"1,\"2\""#))
+        .span("t")
+        .regex("x,y");
+    assert_eq!(built.unwrap(), r#"[s{a=x y,b="",c="1,\"2\""},t]/x,y"#);
+}
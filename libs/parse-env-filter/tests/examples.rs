@@ -1,7 +1,24 @@
 use parse_env_filter::{
-    eager::{filters, Filter, SpanFilter},
-    FieldFilter, ParseError,
+    eager::{filters, typed_filters, Filter, SpanFilter, TypedFilter},
+    parse_level, unescape, FieldFilter, Level, ParseError, ParseErrorKind, ParsedDirective, Part,
 };
+use std::borrow::Cow;
+
+fn bad_syntax(offset: usize, part: Part) -> ParseError {
+    ParseError {
+        kind: ParseErrorKind::BadSyntax,
+        offset,
+        part,
+    }
+}
+
+fn reserved(offset: usize, part: Part) -> ParseError {
+    ParseError {
+        kind: ParseErrorKind::ReservedSyntax,
+        offset,
+        part,
+    }
+}
 
 #[test]
 fn tracing_examples() {
@@ -16,7 +33,8 @@ fn tracing_examples() {
                     value: Some("value")
                 }])
             }]),
-            level: Some("level")
+            level: Some("level"),
+            regex: None,
         }]
     );
 
@@ -26,6 +44,7 @@ fn tracing_examples() {
             target: "tokio::net",
             span: None,
             level: Some("info"),
+            regex: None,
         }]
     );
 
@@ -37,7 +56,8 @@ fn tracing_examples() {
                 name: "span_a",
                 fields: None
             }]),
-            level: Some("trace")
+            level: Some("trace"),
+            regex: None,
         }]
     );
 
@@ -52,13 +72,156 @@ fn tracing_examples() {
                     value: Some("bob")
                 }])
             }]),
-            level: None
+            level: None,
+            regex: None,
         }]
     );
 
     assert_eq!(
-        filters(r#"[span_b{name="bob"}]"#),
-        Err(ParseError::ReservedSyntax)
+        filters(r#"[span_b{name="bob"}]"#).unwrap(),
+        vec![Filter {
+            target: "",
+            span: Some(vec![SpanFilter {
+                name: "span_b",
+                fields: Some(vec![FieldFilter {
+                    name: "name",
+                    value: Some("bob")
+                }])
+            }]),
+            level: None,
+            regex: None,
+        }]
+    );
+}
+
+#[test]
+fn quoted_values() {
+    assert_eq!(
+        filters(r#"[span{a="x,}]{=/",b=y}]=info"#).unwrap(),
+        vec![Filter {
+            target: "",
+            span: Some(vec![SpanFilter {
+                name: "span",
+                fields: Some(vec![
+                    FieldFilter {
+                        name: "a",
+                        value: Some("x,}]{=/")
+                    },
+                    FieldFilter {
+                        name: "b",
+                        value: Some("y")
+                    }
+                ])
+            }]),
+            level: Some("info"),
+            regex: None,
+        }]
+    );
+
+    assert_eq!(
+        filters(r#"[span{a="say \"hi\""}]"#).unwrap(),
+        vec![Filter {
+            target: "",
+            span: Some(vec![SpanFilter {
+                name: "span",
+                fields: Some(vec![FieldFilter {
+                    name: "a",
+                    value: Some(r#"say \"hi\""#)
+                }])
+            }]),
+            level: None,
+            regex: None,
+        }]
+    );
+
+    // the value is a slice of the directives, without the quotes
+    let directives = r#"[span_b{name="bob, jr"}]"#;
+    let parsed = filters(directives).unwrap();
+    let fields = parsed[0].span.as_ref().unwrap()[0].fields.as_ref().unwrap();
+    let value = fields[0].value.unwrap();
+    assert_eq!(value, "bob, jr");
+    assert_eq!(value.as_ptr(), directives[14..].as_ptr());
+
+    assert_eq!(filters(r#"[span{a="x}]"#), Err(bad_syntax(8, Part::Span)));
+    assert_eq!(
+        filters(r#"[span{a="x"y}]"#),
+        Err(bad_syntax(11, Part::Field))
+    );
+    assert_eq!(filters(r#"tar"get"#), Err(bad_syntax(3, Part::Target)));
+
+    let mut lazy = parse_env_filter::filters(r#"[span{a="x}],b"#);
+    assert!(lazy.next().unwrap().is_err());
+    assert!(lazy.next().is_none());
+}
+
+/// The value of the only field filter in `directives`.
+fn field_value(directives: &str) -> &str {
+    let parsed = filters(directives).unwrap();
+    let fields = parsed[0].span.as_ref().unwrap()[0].fields.as_ref().unwrap();
+    fields[0].value.unwrap()
+}
+
+#[test]
+fn escaped_values() {
+    assert_eq!(
+        filters(r"[span{path=a\,b,c=d}]").unwrap(),
+        vec![Filter {
+            target: "",
+            span: Some(vec![SpanFilter {
+                name: "span",
+                fields: Some(vec![
+                    FieldFilter {
+                        name: "path",
+                        value: Some(r"a\,b")
+                    },
+                    FieldFilter {
+                        name: "c",
+                        value: Some("d")
+                    }
+                ])
+            }]),
+            level: None,
+            regex: None,
+        }]
+    );
+
+    for (directives, unescaped) in [
+        (r"[s{a=x\=y}]", "x=y"),
+        (r"[s{a=x\,y}]", "x,y"),
+        (r"[s{a=x\]y}]", "x]y"),
+        (r"[s{a=x\}y}]", "x}y"),
+        (r"[s{a=x\\y}]", r"x\y"),
+        (r#"[s{a=x\[\{\"\/y}]"#, r#"x[{"/y"#),
+        (r"[s{a=x\\\,y}]", r"x\,y"),
+        (r"[s{a=\é}]", "é"),
+        (r#"[s{a="say \"hi\""}]"#, r#"say "hi""#),
+    ] {
+        let value = FieldFilter {
+            name: "a",
+            value: Some(field_value(directives)),
+        };
+        assert_eq!(
+            value.unescaped_value().unwrap(),
+            unescaped,
+            "{}",
+            directives
+        );
+    }
+
+    // escapes are left as written, so the value borrows unless unescaped
+    let directives = r"[s{a=x\,y}]";
+    let value = field_value(directives);
+    assert_eq!(value.as_ptr(), directives[5..].as_ptr());
+    assert!(matches!(unescape("x,y"), Cow::Borrowed("x,y")));
+    assert!(matches!(unescape(r"x\,y"), Cow::Owned(value) if value == "x,y"));
+
+    // an escape at the end of the text escapes nothing
+    assert_eq!(filters(r"[s{a=b\"), Err(bad_syntax(6, Part::Span)));
+    assert_eq!(filters(r"[s{a=b\}]"), Err(bad_syntax(2, Part::Field)));
+    assert_eq!(filters(r"[s{a\,b=c}]"), Err(bad_syntax(4, Part::Field)));
+    assert_eq!(
+        filters(r"[s\]]"),
+        Err(invalid_character('\\', 2, Part::Span))
     );
 }
 
@@ -69,7 +232,8 @@ fn envlogger_examples() {
         vec![Filter {
             target: "hello",
             span: None,
-            level: None
+            level: None,
+            regex: None,
         }]
     );
 
@@ -78,7 +242,8 @@ fn envlogger_examples() {
         vec![Filter {
             target: "trace",
             span: None,
-            level: None
+            level: None,
+            regex: None,
         }]
     );
 
@@ -87,7 +252,8 @@ fn envlogger_examples() {
         vec![Filter {
             target: "TRACE",
             span: None,
-            level: None
+            level: None,
+            regex: None,
         }]
     );
 
@@ -96,7 +262,8 @@ fn envlogger_examples() {
         vec![Filter {
             target: "info",
             span: None,
-            level: None
+            level: None,
+            regex: None,
         }]
     );
 
@@ -105,7 +272,8 @@ fn envlogger_examples() {
         vec![Filter {
             target: "INFO",
             span: None,
-            level: None
+            level: None,
+            regex: None,
         }]
     );
 
@@ -114,7 +282,8 @@ fn envlogger_examples() {
         vec![Filter {
             target: "hello",
             span: None,
-            level: Some("debug")
+            level: Some("debug"),
+            regex: None,
         }]
     );
 
@@ -123,7 +292,8 @@ fn envlogger_examples() {
         vec![Filter {
             target: "hello",
             span: None,
-            level: Some("DEBUG")
+            level: Some("DEBUG"),
+            regex: None,
         }]
     );
 
@@ -133,12 +303,14 @@ fn envlogger_examples() {
             Filter {
                 target: "hello",
                 span: None,
-                level: None
+                level: None,
+                regex: None,
             },
             Filter {
                 target: "std::option",
                 span: None,
-                level: None
+                level: None,
+                regex: None,
             }
         ]
     );
@@ -149,12 +321,14 @@ fn envlogger_examples() {
             Filter {
                 target: "error",
                 span: None,
-                level: None
+                level: None,
+                regex: None,
             },
             Filter {
                 target: "hello",
                 span: None,
-                level: Some("warn")
+                level: Some("warn"),
+                regex: None,
             }
         ]
     );
@@ -164,7 +338,8 @@ fn envlogger_examples() {
         vec![Filter {
             target: "off",
             span: None,
-            level: None
+            level: None,
+            regex: None,
         }]
     );
 
@@ -173,30 +348,142 @@ fn envlogger_examples() {
         vec![Filter {
             target: "OFF",
             span: None,
-            level: None
+            level: None,
+            regex: None,
         }]
     );
+
+    assert_eq!(
+        typed_filters("hello=DEBUG").unwrap(),
+        vec![TypedFilter {
+            target: "hello",
+            span: None,
+            level: Some(Level::Debug),
+            regex: None,
+        }]
+    );
+
+    assert_eq!(
+        typed_filters("hello=verbose"),
+        Err(ParseError {
+            kind: ParseErrorKind::InvalidLevel,
+            offset: 6,
+            part: Part::Level,
+        })
+    );
+}
+
+#[test]
+fn levels() {
+    #[rustfmt::skip]
+    let levels = [
+        ("off", Some(Level::Off)), ("0", Some(Level::Off)),
+        ("ERROR", Some(Level::Error)), ("1", Some(Level::Error)),
+        ("Warn", Some(Level::Warn)), ("2", Some(Level::Warn)),
+        ("info", Some(Level::Info)), ("3", Some(Level::Info)),
+        ("debug", Some(Level::Debug)), ("4", Some(Level::Debug)),
+        ("trace", Some(Level::Trace)), ("5", Some(Level::Trace)),
+        ("OFF", Some(Level::Off)), ("Error", Some(Level::Error)), ("WARN", Some(Level::Warn)),
+        ("INFO", Some(Level::Info)), ("DeBuG", Some(Level::Debug)), ("TRACE", Some(Level::Trace)),
+        ("", None), ("6", None), ("05", None), ("-1", None), ("warning", None), ("inf", None),
+        (" info", None), ("info ", None),
+    ];
+    for &(name, level) in &levels {
+        assert_eq!(Level::parse(name), level, "{:?}", name);
+        assert_eq!(parse_level(name), level, "{:?}", name);
+    }
+    assert!(Level::Off < Level::Error && Level::Debug < Level::Trace);
+
+    // without a level there is nothing to check, and syntax errors come first
+    assert_eq!(typed_filters("hello").unwrap()[0].level, None);
+    assert_eq!(typed_filters("a[b{]=lol"), Err(bad_syntax(3, Part::Field)));
+}
+
+#[test]
+fn bare_levels() {
+    let directive = |directive| filters(directive).unwrap().remove(0).into_directive();
+    for &(name, level) in &[
+        ("info", Level::Info),
+        ("TRACE", Level::Trace),
+        ("off", Level::Off),
+        ("2", Level::Warn),
+    ] {
+        assert_eq!(directive(name), ParsedDirective::Global(level), "{}", name);
+    }
+    // the regex applies to every filter, so doesn't make it a target
+    assert_eq!(directive("warn/foo"), ParsedDirective::Global(Level::Warn));
+
+    for &target in &["hello", "info=warn", "info[span]", "=info", "inf", "info[]"] {
+        let filter = filters(target).unwrap().remove(0);
+        let expected = ParsedDirective::Target(filter.clone());
+        assert_eq!(filter.into_directive(), expected, "{}", target);
+    }
 }
 
 #[test]
 fn envlogger_regex() {
-    assert_eq!(filters("hello/foo"), Err(ParseError::ReservedSyntax));
-    assert_eq!(filters("info/f.o"), Err(ParseError::ReservedSyntax));
+    assert_eq!(
+        filters("hello/foo").unwrap(),
+        vec![Filter {
+            target: "hello",
+            span: None,
+            level: None,
+            regex: Some("foo"),
+        }]
+    );
 
     assert_eq!(
-        filters("hello=debug/foo*foo"),
-        Err(ParseError::ReservedSyntax)
+        filters("hello=debug/foo*foo").unwrap(),
+        vec![Filter {
+            target: "hello",
+            span: None,
+            level: Some("debug"),
+            regex: Some("foo*foo"),
+        }]
+    );
+
+    // the regex is the rest of the directives, even past a comma
+    assert_eq!(
+        filters("error,hello=warn/[0-9]scopes,x").unwrap(),
+        vec![
+            Filter {
+                target: "error",
+                span: None,
+                level: None,
+                regex: None,
+            },
+            Filter {
+                target: "hello",
+                span: None,
+                level: Some("warn"),
+                regex: Some("[0-9]scopes,x"),
+            }
+        ]
     );
 
     assert_eq!(
-        filters("error,hello=warn/[0-9]scopes"),
-        Err(ParseError::ReservedSyntax)
+        filters("a[b{c=d}]/").unwrap(),
+        vec![Filter {
+            target: "a",
+            span: Some(vec![SpanFilter {
+                name: "b",
+                fields: Some(vec![FieldFilter {
+                    name: "c",
+                    value: Some("d"),
+                }]),
+            }]),
+            level: None,
+            regex: Some(""),
+        }]
     );
+
+    // within a span filter, `/` is still reserved
+    assert_eq!(filters("[a/b]/c"), Err(reserved(2, Part::Span)));
 }
 
 #[test]
 fn negative_examples() {
-    assert_eq!(filters("[a[a]"), Err(ParseError::BadSyntax));
+    assert_eq!(filters("[a[a]"), Err(bad_syntax(2, Part::Span)));
     /* tracing::EnvFilter gives:
     Filter {
         target: "",
@@ -205,10 +492,11 @@ fn negative_examples() {
             fields: None,
         }]),
         level: None,
+        regex: None,
     }
     */
 
-    assert_eq!(filters("[[]"), Err(ParseError::BadSyntax));
+    assert_eq!(filters("[[]"), Err(bad_syntax(1, Part::Span)));
     /* tracing::EnvFilter gives:
     Filter {
         target: "",
@@ -217,10 +505,11 @@ fn negative_examples() {
             fields: None,
         }]),
         level: None,
+        regex: None,
     }
     */
 
-    assert_eq!(filters("[=]"), Err(ParseError::BadSyntax));
+    assert_eq!(filters("[=]"), Err(bad_syntax(1, Part::Span)));
     /* tracing::EnvFilter gives:
     Filter {
         target: "",
@@ -229,10 +518,11 @@ fn negative_examples() {
             fields: None,
         }]),
         level: None,
+        regex: None,
     }
     */
 
-    assert_eq!(filters("[}]"), Err(ParseError::BadSyntax));
+    assert_eq!(filters("[}]"), Err(bad_syntax(1, Part::Span)));
     /* tracing::EnvFilter gives:
     Filter {
         target: "",
@@ -241,6 +531,7 @@ fn negative_examples() {
             fields: None,
         }]),
         level: None,
+        regex: None,
     }
     */
 }
@@ -252,7 +543,132 @@ fn unique_examples() {
         vec![Filter {
             target: "",
             span: None,
-            level: Some("warn")
+            level: Some("warn"),
+            regex: None,
         }]
     );
 }
+
+fn invalid_character(c: char, offset: usize, part: Part) -> ParseError {
+    ParseError {
+        kind: ParseErrorKind::InvalidCharacter(c),
+        offset,
+        part,
+    }
+}
+
+#[test]
+fn names_are_checked() {
+    assert_eq!(
+        filters("foo bar=info"),
+        Err(invalid_character(' ', 3, Part::Target))
+    );
+    assert_eq!(
+        filters("a=info,b.c"),
+        Err(invalid_character('.', 8, Part::Target))
+    );
+    assert_eq!(
+        filters("a[b c]"),
+        Err(invalid_character(' ', 3, Part::Span))
+    );
+    // the first problem is reported, even before a syntax error
+    assert_eq!(
+        filters("a b}"),
+        Err(invalid_character(' ', 1, Part::Target))
+    );
+    assert_eq!(
+        filters("ü=info"),
+        Err(invalid_character('ü', 0, Part::Target))
+    );
+
+    // fields, levels, and regexes are not names
+    assert_eq!(
+        filters("my-crate::a_1[span-2{f.x=y z}]=hi there/a b").unwrap(),
+        vec![Filter {
+            target: "my-crate::a_1",
+            span: Some(vec![SpanFilter {
+                name: "span-2",
+                fields: Some(vec![FieldFilter {
+                    name: "f.x",
+                    value: Some("y z"),
+                }]),
+            }]),
+            level: Some("hi there"),
+            regex: Some("a b"),
+        }]
+    );
+}
+
+#[test]
+fn whitespace_around_directives() {
+    let expected = vec![
+        Filter {
+            target: "a",
+            span: None,
+            level: Some("info"),
+            regex: None,
+        },
+        Filter {
+            target: "b",
+            span: None,
+            level: Some("warn"),
+            regex: None,
+        },
+    ];
+    assert_eq!(filters("a=info, b=warn").unwrap(), expected);
+    assert_eq!(filters("  a=info,b=warn \n").unwrap(), expected);
+    assert_eq!(filters("a=info\t,\tb=warn").unwrap(), expected);
+    assert_eq!(filters("a=info , b=warn, ").unwrap(), expected);
+
+    assert_eq!(
+        filters(" a , b[c] ").unwrap(),
+        vec![
+            Filter {
+                target: "a",
+                span: None,
+                level: None,
+                regex: None,
+            },
+            Filter {
+                target: "b",
+                span: Some(vec![SpanFilter {
+                    name: "c",
+                    fields: None,
+                }]),
+                level: None,
+                regex: None,
+            },
+        ]
+    );
+    assert_eq!(filters(" \t ").unwrap(), vec![]);
+
+    // offsets are still into the untrimmed string
+    assert_eq!(
+        filters("a=info,\t b c"),
+        Err(invalid_character(' ', 10, Part::Target))
+    );
+    assert_eq!(filters(" a[b] c"), Err(bad_syntax(6, Part::Level)));
+    assert_eq!(
+        filters("a =info"),
+        Err(invalid_character(' ', 1, Part::Target))
+    );
+}
+
+#[test]
+fn error_messages() {
+    let error = filters("[a/b]").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "reserved syntax in span filter at offset 2"
+    );
+    let error = filters("[span{a=\"x\"y}]").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid syntax in field filter at offset 11"
+    );
+    let error = filters("a,b c").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid character ' ' in target at offset 3"
+    );
+}
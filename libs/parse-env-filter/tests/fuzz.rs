@@ -0,0 +1,153 @@
+//! Randomized checks of the parser's invariants over adversarial input:
+//! it never panics, never yields after an error, keeps within its size
+//! hints, and the lazy and eager parsers agree.
+
+use parse_env_filter::{count_directives, count_filters, eager, filters, validate, ParseError};
+
+/// Fragments of directives, heavy on syntax so that most inputs are broken.
+const PIECES: &[&str] = &[
+    "a", "b_c", "d::e", "f-g", " ", "[", "]", "{", "}", "=", ",", "\"", "\\", "/", "info", "5",
+    "x.y", "é", "\t",
+];
+
+/// A xorshift generator, so that failures reproduce without any dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+
+    fn directives(&mut self) -> String {
+        let len = self.next() % 16;
+        (0..len)
+            .map(|_| PIECES[self.next() % PIECES.len()])
+            .collect()
+    }
+}
+
+/// Collect `iter`, converting each item before pulling the next, so that
+/// errors come in source order, and checking its invariants along the way.
+fn collect_checked<T, U, I>(
+    mut iter: I,
+    mut convert: impl FnMut(T) -> Result<U, ParseError>,
+) -> Result<Vec<U>, ParseError>
+where
+    I: Iterator<Item = Result<T, ParseError>> + Clone,
+{
+    let mut items = Vec::new();
+    loop {
+        let (lower, upper) = iter.size_hint();
+        let remaining = iter.clone().count();
+        assert!(lower <= remaining, "{} > {}", lower, remaining);
+        assert!(remaining <= upper.unwrap(), "{} > {:?}", remaining, upper);
+        match iter.next() {
+            None => {
+                assert!(iter.next().is_none());
+                return Ok(items);
+            }
+            Some(Ok(item)) => items.push(convert(item)?),
+            Some(Err(error)) => {
+                assert!(iter.next().is_none());
+                assert!(iter.next().is_none());
+                return Err(error);
+            }
+        }
+    }
+}
+
+/// Parse `directives` lazily, walking every span and field filter.
+fn lazy(directives: &str) -> Result<Vec<eager::Filter<'_>>, ParseError> {
+    collect_checked(filters(directives), |filter| {
+        let span = filter.span.map(|spans| {
+            collect_checked(spans, |span| {
+                let fields = span.fields.map(|fields| collect_checked(fields, Ok));
+                Ok(eager::SpanFilter {
+                    name: span.name,
+                    fields: fields.transpose()?,
+                })
+            })
+        });
+        Ok(eager::Filter {
+            target: filter.target,
+            span: span.transpose()?,
+            level: filter.level,
+            regex: filter.regex,
+        })
+    })
+}
+
+#[test]
+fn lazy_and_eager_agree_on_random_input() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    for _ in 0..20_000 {
+        let directives = rng.directives();
+        let lazy = lazy(&directives);
+        assert_eq!(lazy, eager::filters(&directives), "{:?}", directives);
+        let count = lazy.as_ref().map(Vec::len).map_err(Clone::clone);
+        assert_eq!(count, count_filters(&directives), "{:?}", directives);
+        assert_eq!(validate(&directives), count.map(drop), "{:?}", directives);
+        if let Ok(count) = count {
+            assert_eq!(count_directives(&directives), count, "{:?}", directives);
+        }
+    }
+}
+
+#[test]
+fn count_directives_skips_nested_commas() {
+    assert_eq!(count_directives("a[b,c]=info,d"), 2);
+    assert_eq!(count_directives(r#"a[b{c="d,e",f}]=info, g,h=warn"#), 3);
+    assert_eq!(count_directives(""), 0);
+    assert_eq!(count_directives(" , "), 1);
+    assert_eq!(count_directives("a,,b"), 3);
+    assert_eq!(count_directives("a, "), 1);
+    assert_eq!(count_directives("a=info/b,c"), 1);
+}
+
+#[test]
+fn size_hints_skip_nested_commas() {
+    let hint = |directives| filters(directives).size_hint();
+    assert_eq!(hint(""), (0, Some(0)));
+    assert_eq!(hint("  "), (0, Some(0)));
+    assert_eq!(hint("a"), (1, Some(1)));
+    assert_eq!(hint("a[b,c{d,e}],f"), (1, Some(2)));
+    assert_eq!(hint(r#"a[b{c="],{"}],d"#), (1, Some(2)));
+    // the regex takes the rest, commas included
+    assert_eq!(hint("a=info/x,y,z"), (1, Some(1)));
+    // parsing stops at broken syntax
+    assert_eq!(hint("a[b,c"), (1, Some(1)));
+    assert_eq!(hint("a}b,c"), (1, Some(1)));
+
+    let spans = filters("a[b{c,d},e]")
+        .next()
+        .unwrap()
+        .unwrap()
+        .span
+        .unwrap();
+    assert_eq!(spans.size_hint(), (1, Some(2)));
+    let fields = spans.clone().next().unwrap().unwrap().fields.unwrap();
+    assert_eq!(fields.size_hint(), (1, Some(2)));
+}
+
+/// The parts of a lazy filter which can be compared.
+fn parts(
+    filter: Result<parse_env_filter::Filter<'_>, ParseError>,
+) -> Result<(&str, Option<&str>, Option<&str>), ParseError> {
+    filter.map(|filter| (filter.target, filter.level, filter.regex))
+}
+
+#[test]
+fn recovering_agrees_until_the_first_error() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..20_000 {
+        let directives = rng.directives();
+        let parsed: Vec<_> = filters(&directives).map(parts).collect();
+        let recovered: Vec<_> = filters(&directives).recover().map(parts).collect();
+        assert!(recovered.starts_with(&parsed), "{:?}", directives);
+        let errors = recovered.iter().filter(|filter| filter.is_err()).count();
+        assert!(errors <= directives.len(), "{:?}", directives);
+    }
+}
@@ -0,0 +1,79 @@
+use parse_env_filter::{
+    eager::{filters, merge, merge_all},
+    ParseError, ParseErrorKind, Part,
+};
+
+#[test]
+fn later_filters_replace_earlier_ones() {
+    assert_eq!(
+        merge("info,hyper=warn", "hyper=trace").unwrap(),
+        "info,hyper=trace"
+    );
+    assert_eq!(
+        merge("hyper=warn,info", "debug,tower=off").unwrap(),
+        "hyper=warn,debug,tower=off"
+    );
+    // bare level names and `=level` are both global
+    assert_eq!(merge("info", "=warn").unwrap(), "=warn");
+    assert_eq!(merge("info=warn", "warn").unwrap(), "info=warn,warn");
+    // spans and fields are part of what is filtered on
+    assert_eq!(
+        merge(
+            "app[req{user=bob}]=info,app=warn",
+            "app[req{user=alice}]=debug,app[req{user=bob}]"
+        )
+        .unwrap(),
+        "app[req{user=bob}],app=warn,app[req{user=alice}]=debug"
+    );
+    // duplicates within one string are merged too
+    assert_eq!(merge("a=info,b,a=trace", "").unwrap(), "a=trace,b");
+}
+
+#[test]
+fn merging_with_nothing_is_the_identity() {
+    for directives in [
+        "",
+        "info",
+        "info,hyper=warn",
+        r#"a[s{x="a,b"}]=debug/re,gex"#,
+    ] {
+        assert_eq!(merge(directives, "").unwrap(), directives);
+        assert_eq!(merge("", directives).unwrap(), directives);
+    }
+    // apart from whitespace around filters
+    assert_eq!(merge(" info , hyper=warn ", "").unwrap(), "info,hyper=warn");
+}
+
+#[test]
+fn the_last_regex_wins() {
+    assert_eq!(merge("info/a", "hyper=warn").unwrap(), "info,hyper=warn/a");
+    assert_eq!(
+        merge("info/a", "hyper=warn/b").unwrap(),
+        "info,hyper=warn/b"
+    );
+    assert_eq!(merge("info", "/b").unwrap(), "info/b");
+}
+
+#[test]
+fn many_sources() {
+    let merged = merge_all(&["info,hyper=off", "hyper=warn,app=debug", "app=trace"]).unwrap();
+    assert_eq!(merged, "info,hyper=warn,app=trace");
+    assert_eq!(merge_all(&[]).unwrap(), "");
+    assert_eq!(
+        filters(&merged).unwrap().len(),
+        3,
+        "the merged string parses"
+    );
+}
+
+#[test]
+fn errors_are_reported_in_their_source() {
+    assert_eq!(
+        merge("info", "a[b").unwrap_err(),
+        ParseError {
+            kind: ParseErrorKind::BadSyntax,
+            offset: 1,
+            part: Part::Span,
+        }
+    );
+}
@@ -0,0 +1,152 @@
+//! Uses only what's available without the `alloc` feature, so this is built
+//! and run by `cargo test --no-default-features` as well.
+
+use core::ops::{ControlFlow, Range};
+use parse_env_filter::{
+    check, count_filters, filters, parse_with, validate, DirectiveCheck, Filter, Level, ParseError,
+    ParseErrorKind, ParsedDirective, Part, Visitor,
+};
+
+#[test]
+fn lazy_filters_without_alloc() {
+    let directives = "a[b{c=d}]=info,e=5/x";
+    let mut filters = filters(directives);
+
+    let filter = filters.next().unwrap().unwrap();
+    assert_eq!(filter.target, "a");
+    assert_eq!(filter.parsed_level(directives), Ok(Some(Level::Info)));
+    let mut spans = filter.span.unwrap();
+    let span = spans.next().unwrap().unwrap();
+    assert_eq!(span.name, "b");
+    let field = span.fields.unwrap().next().unwrap().unwrap();
+    assert_eq!((field.name, field.value), ("c", Some("d")));
+    assert!(spans.next().is_none());
+
+    let filter = filters.next().unwrap().unwrap();
+    assert_eq!(filter.target, "e");
+    assert_eq!(filter.parsed_level(directives), Ok(Some(Level::Trace)));
+    assert_eq!(filter.regex, Some("x"));
+    assert!(filters.next().is_none());
+
+    let mut bare = parse_env_filter::filters("debug,hello");
+    let global = bare.next().unwrap().unwrap().into_directive();
+    assert!(matches!(global, ParsedDirective::Global(Level::Debug)));
+    let target = bare.next().unwrap().unwrap().into_directive();
+    assert!(matches!(target, ParsedDirective::Target(filter) if filter.target == "hello"));
+}
+
+#[test]
+fn lazy_filters_built_by_hand() {
+    let filter = Filter {
+        target: "a",
+        span: None,
+        level: Some("loud"),
+        regex: None,
+    };
+    let invalid = |offset| ParseError {
+        kind: ParseErrorKind::InvalidLevel,
+        offset,
+        part: Part::Level,
+    };
+    assert_eq!(filter.parsed_level("a=loud"), Err(invalid(0)));
+
+    let directives = "a=info,b=loud";
+    let parsed = filters(directives).nth(1).unwrap().unwrap();
+    let filter = Filter {
+        target: "c",
+        ..parsed
+    };
+    assert_eq!(filter.parsed_level(directives), Err(invalid(9)));
+}
+
+#[test]
+fn visit_without_alloc() {
+    #[derive(Default)]
+    struct Count {
+        filters: usize,
+        error: Option<(Part, Range<usize>)>,
+    }
+
+    impl<'a> Visitor<'a> for Count {
+        fn begin_filter(&mut self, _: &'a str) -> ControlFlow<()> {
+            self.filters += 1;
+            ControlFlow::Continue(())
+        }
+
+        fn error(&mut self, error: ParseError, range: Range<usize>) {
+            self.error = Some((error.part, range));
+        }
+    }
+
+    let mut count = Count::default();
+    parse_with("a=info, b , c[d]=warn,e}", &mut count);
+    assert_eq!(count.filters, 3);
+    assert_eq!(count.error, Some((Part::Target, 23..24)));
+}
+
+#[test]
+fn validate_walks_spans_and_fields() {
+    // the filter itself is fine until its spans are iterated
+    let unterminated = "a[span{field]";
+    assert!(filters(unterminated).all(|filter| filter.is_ok()));
+    let error = validate(unterminated).unwrap_err();
+    assert_eq!(error.part, Part::Field);
+    assert_eq!(count_filters(unterminated), Err(error));
+
+    assert_eq!(validate("a[b{c=d}]=info,e=5/x"), Ok(()));
+    assert_eq!(count_filters("a[b{c=d}]=info,e=5/x"), Ok(2));
+    assert_eq!(count_filters("  "), Ok(0));
+    assert_eq!(count_filters(""), Ok(0));
+    assert!(validate("a,b[c]}").is_err());
+}
+
+#[test]
+fn check_each_directive() {
+    let directives = "a=info, b[c{d]=warn, hyper=[oops , e";
+    let mut checks = check(directives);
+    let check_of = |range: Range<usize>, result| DirectiveCheck {
+        directive: &directives[range.clone()],
+        range,
+        result,
+    };
+    let bad_syntax = |offset, part| {
+        Err(ParseError {
+            kind: ParseErrorKind::BadSyntax,
+            offset,
+            part,
+        })
+    };
+
+    assert_eq!(checks.next(), Some(check_of(0..6, Ok(()))));
+    // a bad field filter is found by walking the spans
+    let fields = checks.next().unwrap();
+    assert_eq!(fields.directive, "b[c{d]=warn");
+    assert_eq!(fields, check_of(8..19, bad_syntax(11, Part::Field)));
+    // and the directives after a bad one are still checked
+    let hyper = checks.next().unwrap();
+    assert_eq!(hyper.directive, "hyper=[oops");
+    assert_eq!(hyper, check_of(21..32, bad_syntax(27, Part::Level)));
+    assert_eq!(checks.next(), Some(check_of(35..36, Ok(()))));
+    assert_eq!(checks.next(), None);
+}
+
+#[test]
+fn check_reads_commas_like_the_parser() {
+    let ranges = |directives| -> [Option<Range<usize>>; 4] {
+        let mut checks = check(directives).map(|check| {
+            assert_eq!(check.result, Ok(()));
+            check.range
+        });
+        [checks.next(), checks.next(), checks.next(), checks.next()]
+    };
+
+    assert_eq!(ranges(""), [None, None, None, None]);
+    // an empty directive is where its comma is
+    assert_eq!(ranges(" , "), [Some(1..1), None, None, None]);
+    assert_eq!(ranges("a,,b,"), [Some(0..1), Some(2..2), Some(3..4), None]);
+    assert_eq!(
+        ranges("a[b,c], d=info/x,y "),
+        [Some(0..6), Some(8..18), None, None]
+    );
+    assert_eq!(filters("a,,b,").count(), 3);
+}
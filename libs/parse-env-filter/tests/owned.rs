@@ -0,0 +1,104 @@
+use parse_env_filter::eager::{
+    filters, parse_owned, OwnedFieldFilter, OwnedFilter, OwnedSpanFilter,
+};
+
+const EXAMPLES: &[&str] = &[
+    "target[span{field=value}]=level",
+    "tokio::net=info",
+    "my_crate[span_a]=trace",
+    "[span_b{name=bob}]",
+    r#"[span_b{name="bob"}]"#,
+    r#"[span{a="x,}]{=/",b=y}]=info"#,
+    r#"[span{a="say \"hi\""}]"#,
+    r#"[span{a=""}]"#,
+    r"[span{a=x\,y,b=\\}]",
+    "hello",
+    "trace",
+    "hello=DEBUG",
+    "hello,std::option",
+    "error,hello=warn",
+    "off",
+    "a[]",
+    "a[b{}]=",
+    "=warn",
+    "[{x}]",
+    "hello=debug/foo*foo",
+    "error,hello=warn/[0-9]scopes,x",
+    "hello/foo",
+    "a[b{c=d}]/",
+];
+
+fn directives(filters: &[impl ToString]) -> String {
+    let filters: Vec<_> = filters.iter().map(ToString::to_string).collect();
+    filters.join(",")
+}
+
+#[test]
+fn owned_filters_match_borrowed() {
+    assert_eq!(
+        parse_owned("target[span{field=value}]=level").unwrap(),
+        vec![OwnedFilter {
+            target: "target".into(),
+            span: Some(vec![OwnedSpanFilter {
+                name: "span".into(),
+                fields: Some(vec![OwnedFieldFilter {
+                    name: "field".into(),
+                    value: Some("value".into()),
+                }]),
+            }]),
+            level: Some("level".into()),
+            regex: None,
+        }]
+    );
+
+    for example in EXAMPLES {
+        let owned: Vec<OwnedFilter> = filters(example)
+            .unwrap()
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        assert_eq!(parse_owned(example).unwrap(), owned, "{}", example);
+    }
+}
+
+#[test]
+fn owned_filters_round_trip() {
+    for example in EXAMPLES {
+        let parsed = parse_owned(example).unwrap();
+        let displayed = directives(&parsed);
+        assert_eq!(parse_owned(&displayed).unwrap(), parsed, "{}", example);
+    }
+
+    // only values are quoted, and only when they need it
+    let displayed = directives(&parse_owned(r#"a[b{c="d",e="f,g"}]=info"#).unwrap());
+    assert_eq!(displayed, r#"a[b{c=d,e="f,g"}]=info"#);
+}
+
+#[test]
+fn borrowed_filters_round_trip() {
+    for example in EXAMPLES {
+        let parsed = filters(example).unwrap();
+        let displayed = directives(&parsed);
+        assert_eq!(filters(&displayed).unwrap(), parsed, "{}", example);
+        assert_eq!(displayed, directives(&parse_owned(example).unwrap()));
+    }
+
+    let displayed = directives(&filters(r#"=warn,[span{a="",b=c}]"#).unwrap());
+    assert_eq!(displayed, r#"=warn,[span{a="",b=c}]"#);
+}
+
+#[test]
+fn edited_filters_display() {
+    let mut parsed = parse_owned("hello=warn,world").unwrap();
+    parsed[0].level = Some("debug".into());
+    parsed[1].span = Some(vec![OwnedSpanFilter {
+        name: "request".into(),
+        fields: Some(vec![OwnedFieldFilter {
+            name: "path".into(),
+            value: Some("/api".into()),
+        }]),
+    }]);
+    let displayed = directives(&parsed);
+    assert_eq!(displayed, r#"hello=debug,world[request{path="/api"}]"#);
+    assert_eq!(parse_owned(&displayed).unwrap(), parsed);
+}
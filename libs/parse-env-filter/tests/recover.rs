@@ -0,0 +1,106 @@
+use parse_env_filter::{filters, ParseError, ParseErrorKind, Part};
+
+fn bad_syntax(offset: usize, part: Part) -> ParseError {
+    ParseError {
+        kind: ParseErrorKind::BadSyntax,
+        offset,
+        part,
+    }
+}
+
+/// The target and level of each filter `recover` yields, or its error.
+fn recovered(directives: &str) -> Vec<Result<(&str, Option<&str>), ParseError>> {
+    filters(directives)
+        .recover()
+        .map(|filter| filter.map(|filter| (filter.target, filter.level)))
+        .collect()
+}
+
+#[test]
+fn recovers_at_the_next_filter() {
+    assert_eq!(
+        recovered("good=info,[bad,also_good=warn"),
+        [
+            Ok(("good", Some("info"))),
+            Err(bad_syntax(10, Part::Span)),
+            Ok(("also_good", Some("warn"))),
+        ]
+    );
+    // without recovering, the first error is the last item
+    let stopped: Vec<_> = filters("good=info,[bad,also_good=warn").collect();
+    assert_eq!(stopped.len(), 2);
+}
+
+#[test]
+fn recovers_across_several_bad_filters() {
+    assert_eq!(
+        recovered("a}b, c=info, d=info], f[g=warn, h=nope]x, i"),
+        [
+            Err(bad_syntax(1, Part::Target)),
+            Ok(("c", Some("info"))),
+            Err(bad_syntax(19, Part::Level)),
+            // the span filter is closed, so its comma doesn't end the filter
+            Err(bad_syntax(39, Part::Level)),
+            Ok(("i", None)),
+        ]
+    );
+    assert_eq!(
+        recovered("[a,[b,c"),
+        [
+            Err(bad_syntax(0, Part::Span)),
+            Err(bad_syntax(3, Part::Span)),
+            Ok(("c", None)),
+        ]
+    );
+    assert_eq!(
+        recovered(",}"),
+        [Ok(("", None)), Err(bad_syntax(1, Part::Target))]
+    );
+}
+
+#[test]
+fn nested_commas_dont_end_a_bad_filter() {
+    // the span filter is closed, so its comma is passed over
+    assert_eq!(
+        recovered("a[b,c]}=info,d=warn"),
+        [Err(bad_syntax(6, Part::Level)), Ok(("d", Some("warn")))]
+    );
+    assert_eq!(
+        recovered(r#"a[b{c="x,y"}] junk,d"#),
+        [Err(bad_syntax(14, Part::Level)), Ok(("d", None))]
+    );
+    // as is one in an unclosed span filter's closed quoted value
+    assert_eq!(
+        recovered(r#"a[b{c="x,y"}, d"#),
+        [Err(bad_syntax(1, Part::Span)), Ok(("d", None))]
+    );
+    assert_eq!(
+        recovered(r#"a"b,c"#),
+        [Err(bad_syntax(1, Part::Target)), Ok(("c", None))]
+    );
+}
+
+#[test]
+fn a_regex_in_a_bad_filter_takes_the_rest() {
+    assert_eq!(recovered("a}=info/x,b"), [Err(bad_syntax(1, Part::Target))]);
+    // a good filter's regex is kept as usual
+    let regexes: Vec<_> = filters("a},b=info/x,y")
+        .recover()
+        .map(|filter| filter.ok().and_then(|filter| filter.regex))
+        .collect();
+    assert_eq!(regexes, [None, Some("x,y")]);
+}
+
+#[test]
+fn valid_directives_parse_the_same() {
+    for directives in &["", "a", "a[b{c=d,e}]=info, f=warn/x,y", " a , b "] {
+        let recovered: Vec<_> = filters(directives)
+            .recover()
+            .map(|filter| filter.map(|filter| (filter.target, filter.level, filter.regex)))
+            .collect();
+        let parsed: Vec<_> = filters(directives)
+            .map(|filter| filter.map(|filter| (filter.target, filter.level, filter.regex)))
+            .collect();
+        assert_eq!(recovered, parsed, "{:?}", directives);
+    }
+}
@@ -0,0 +1,76 @@
+use parse_env_filter::{
+    eager::{filters, Filter, OwnedFilter, SpanFilter},
+    FieldFilter,
+};
+
+const JSON: &str = r#"[
+    { "target": "hello", "span": null, "level": "warn", "regex": null },
+    {
+        "target": "",
+        "span": [{ "name": "span", "fields": [{ "name": "a", "value": "x,y" }] }],
+        "level": null,
+        "regex": "foo"
+    }
+]"#;
+
+#[test]
+fn deserialize_borrowed() {
+    let parsed: Vec<Filter<'_>> = serde_json::from_str(JSON).unwrap();
+    assert_eq!(
+        parsed,
+        vec![
+            Filter {
+                target: "hello",
+                span: None,
+                level: Some("warn"),
+                regex: None,
+            },
+            Filter {
+                target: "",
+                span: Some(vec![SpanFilter {
+                    name: "span",
+                    fields: Some(vec![FieldFilter {
+                        name: "a",
+                        value: Some("x,y"),
+                    }]),
+                }]),
+                level: None,
+                regex: Some("foo"),
+            },
+        ]
+    );
+    assert_eq!(
+        parsed,
+        filters(r#"hello=warn,[span{a="x,y"}]/foo"#).unwrap()
+    );
+
+    // borrowing can't unescape strings
+    assert!(serde_json::from_str::<Filter<'_>>(r#"{ "target": "a\"b" }"#).is_err());
+}
+
+#[test]
+fn deserialize_owned() {
+    let parsed: Vec<OwnedFilter> = serde_json::from_str(JSON).unwrap();
+    let borrowed: Vec<Filter<'_>> = serde_json::from_str(JSON).unwrap();
+    let borrowed: Vec<OwnedFilter> = borrowed.into_iter().map(Into::into).collect();
+    assert_eq!(parsed, borrowed);
+
+    let escaped: OwnedFilter =
+        serde_json::from_str(r#"{ "target": "a\"b", "span": null, "level": null, "regex": null }"#)
+            .unwrap();
+    assert_eq!(escaped.target, "a\"b");
+}
+
+#[test]
+fn serialize_round_trip() {
+    let parsed = filters("a[b{c=d}]=info,e/f").unwrap();
+    let json = serde_json::to_string(&parsed).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"target":"a","span":[{"name":"b","fields":[{"name":"c","value":"d"}]}],"level":"info","regex":null},{"target":"e","span":null,"level":null,"regex":"f"}]"#
+    );
+    assert_eq!(
+        serde_json::from_str::<Vec<Filter<'_>>>(&json).unwrap(),
+        parsed
+    );
+}
@@ -0,0 +1,80 @@
+use parse_env_filter::eager::{filters, sort_by_specificity, Filter};
+
+/// `directives`, sorted by specificity and displayed.
+fn sorted(directives: &str) -> Vec<String> {
+    let mut parsed = filters(directives).unwrap();
+    sort_by_specificity(&mut parsed);
+    parsed.iter().map(ToString::to_string).collect()
+}
+
+fn filter(directive: &str) -> Filter<'_> {
+    filters(directive).unwrap().remove(0)
+}
+
+#[test]
+fn longer_targets_first() {
+    assert_eq!(
+        sorted("foo=trace,a=trace,foo::bar::baz=trace,foo::bar=trace"),
+        [
+            "foo::bar::baz=trace",
+            "foo::bar=trace",
+            "foo=trace",
+            "a=trace"
+        ],
+    );
+    assert_eq!(sorted("info,my_crate=trace"), ["my_crate=trace", "info"]);
+    assert_eq!(sorted("=info,my_crate=trace"), ["my_crate=trace", "=info"]);
+}
+
+#[test]
+fn spans_then_fields_break_ties() {
+    assert_eq!(
+        sorted("foo=trace,bar[span]=trace"),
+        ["bar[span]=trace", "foo=trace"],
+    );
+    assert_eq!(
+        sorted("foo[{a}]=trace,foo=trace,foo[{a,b}]=trace,foo[span]=trace"),
+        [
+            "foo[span]=trace",
+            "foo[{a,b}]=trace",
+            "foo[{a}]=trace",
+            "foo=trace"
+        ],
+    );
+    // the target still comes first
+    assert_eq!(
+        sorted("[request{id=1}]=debug,my_crate=info"),
+        ["my_crate=info", "[request{id=1}]=debug"],
+    );
+    assert_eq!(
+        sorted("[request]=debug,=warn"),
+        ["[request]=debug", "=warn"]
+    );
+}
+
+#[test]
+fn later_filters_win_ties() {
+    assert_eq!(
+        sorted("a=info,b=warn,a=debug"),
+        ["a=debug", "b=warn", "a=info"]
+    );
+    assert_eq!(filter("a=info").specificity(), filter("b").specificity());
+}
+
+#[test]
+fn classification() {
+    for global in ["=info", "[]=warn", "[{}]", "="] {
+        let parsed = filters(global).unwrap();
+        assert!(parsed.iter().all(Filter::is_global), "{}", global);
+    }
+    for not_global in ["info", "a=info", "[span]", "[{x}]"] {
+        assert!(!filter(not_global).is_global(), "{}", not_global);
+    }
+
+    for spans in ["[request]=debug", "a[{x=1}]", "a[,b]"] {
+        assert!(filter(spans).constrains_span(), "{}", spans);
+    }
+    for events in ["a=debug", "a[]", "=info"] {
+        assert!(!filter(events).constrains_span(), "{}", events);
+    }
+}
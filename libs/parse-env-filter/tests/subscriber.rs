@@ -0,0 +1,170 @@
+use parse_env_filter::{
+    eager::{filters, sort_by_specificity},
+    subscriber::{from_filters, to_env_filter, DirectiveError},
+};
+use std::convert::TryFrom;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use tracing::{subscriber::Subscriber, Event};
+use tracing_subscriber::{
+    filter::{Directive, EnvFilter},
+    layer::{Context, Layer},
+    prelude::*,
+};
+
+struct Count(Arc<AtomicUsize>);
+
+impl<S: Subscriber> Layer<S> for Count {
+    fn on_event(&self, _: &Event<'_>, _: Context<'_, S>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Which of a few sample events `filter` lets through.
+fn enabled(filter: EnvFilter) -> Vec<bool> {
+    let count = Arc::new(AtomicUsize::new(0));
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(Count(count.clone()));
+    let mut enabled = Vec::new();
+    let mut check = |emit: &dyn Fn()| {
+        let before = count.load(Ordering::Relaxed);
+        emit();
+        enabled.push(count.load(Ordering::Relaxed) > before);
+    };
+    tracing::subscriber::with_default(subscriber, || {
+        check(&|| tracing::info!(target: "app", "app info"));
+        check(&|| tracing::debug!(target: "app", "app debug"));
+        check(&|| tracing::warn!(target: "app::db", "db warn"));
+        check(&|| tracing::trace!(target: "app::db", "db trace"));
+        check(&|| tracing::error!(target: "hyper", "hyper error"));
+        check(&|| tracing::info!(target: "hyper", "hyper info"));
+        check(&|| {
+            tracing::info_span!(target: "hyper", "conn", id = 3)
+                .in_scope(|| tracing::debug!(target: "hyper", "conn debug"))
+        });
+    });
+    enabled
+}
+
+#[test]
+fn decisions_match_env_filter() {
+    let examples = [
+        "info",
+        "3",
+        "app=debug",
+        "app=4,app::db=error",
+        "warn,app::db=trace",
+        "hyper=error,app=TRACE",
+        "off,hyper[conn]=debug",
+        "hyper[conn{id=3}]=debug",
+        "hyper[conn{id=4}]=debug",
+        "app",
+    ];
+    for example in &examples {
+        let parsed = to_env_filter(example).unwrap();
+        assert_eq!(
+            enabled(parsed),
+            enabled(EnvFilter::new(example)),
+            "{}",
+            example
+        );
+    }
+
+    #[rustfmt::skip]
+    assert_eq!(
+        enabled(to_env_filter("warn,app::db=trace").unwrap()),
+        [false, false, true, true, true, false, false],
+    );
+}
+
+#[test]
+fn empty_target_is_global() {
+    let global = enabled(to_env_filter("=debug").unwrap());
+    assert_eq!(global, enabled(EnvFilter::new("debug")));
+    assert_eq!(global, [true, true, true, false, true, true, true]);
+
+    // empty filters are skipped, as EnvFilter treats an empty string
+    let empty = enabled(to_env_filter(" , app=info,").unwrap());
+    assert_eq!(empty, enabled(EnvFilter::new("app=info")));
+}
+
+#[test]
+fn unsupported_filters() {
+    let unsupported = |directives| match to_env_filter(directives) {
+        Err(DirectiveError::Unsupported(part)) => part,
+        other => panic!("{}: {:?}", directives, other),
+    };
+    assert_eq!(unsupported("app=info/foo"), "message regex");
+    assert_eq!(unsupported("app[a,b]"), "multiple span filters");
+    assert_eq!(unsupported("app[a{b-c}]"), "field name");
+    assert_eq!(unsupported(r#"app[a{b="c,d"}]"#), "field value");
+
+    assert!(matches!(
+        to_env_filter("app=loud"),
+        Err(DirectiveError::Parse(_))
+    ));
+}
+
+#[test]
+fn parsed_filters_match_env_filter() {
+    let examples = [
+        "hello",
+        "trace",
+        "app=info,hyper=warn",
+        "hyper[conn]",
+        "hyper[conn{id=3}]=DEBUG",
+        "app::db=5,off",
+        "tokio::net=info,[conn{id}]=trace",
+    ];
+    for example in &examples {
+        let parsed = filters(example).unwrap();
+        assert_eq!(
+            enabled(from_filters(&parsed).unwrap()),
+            enabled(EnvFilter::new(example)),
+            "{}",
+            example
+        );
+
+        let mut env_filter = EnvFilter::new("off");
+        for filter in parsed {
+            env_filter = env_filter.add_directive(Directive::try_from(filter).unwrap());
+        }
+        assert_eq!(
+            enabled(env_filter),
+            enabled(EnvFilter::new(format!("off,{}", example))),
+            "{}",
+            example
+        );
+    }
+
+    let parsed = filters("app=loud").unwrap();
+    assert!(matches!(
+        from_filters(&parsed),
+        Err(DirectiveError::InvalidLevel(level)) if level == "loud"
+    ));
+}
+
+#[test]
+fn specificity_matches_directive_order() {
+    let directives =
+        "a=info,hyper[conn]=debug,app::db=trace,=warn,hyper[{id}]=trace,app=error,hyper=info";
+    let mut parsed = filters(directives).unwrap();
+    let display = |filters: &[_]| -> Vec<_> {
+        filters
+            .iter()
+            .map(|filter| Directive::try_from(filter).unwrap().to_string())
+            .collect()
+    };
+
+    let mut sorted: Vec<_> = parsed
+        .iter()
+        .map(|filter| Directive::try_from(filter).unwrap())
+        .collect();
+    sorted.sort();
+    let sorted: Vec<_> = sorted.iter().map(ToString::to_string).collect();
+    sort_by_specificity(&mut parsed);
+    assert_eq!(display(&parsed), sorted);
+}
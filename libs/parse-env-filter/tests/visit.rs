@@ -0,0 +1,365 @@
+use parse_env_filter::{eager, parse_with, ParseError, ParseErrorKind, Part, Visitor};
+use std::{
+    convert::TryFrom,
+    ops::{ControlFlow, Range},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Call {
+    Begin(String),
+    Spans,
+    Span(String),
+    Fields,
+    Field(String, Option<String>),
+    End(Option<String>),
+    Regex(String),
+    Error(ParseError, Range<usize>),
+}
+
+use Call::*;
+
+/// Records the callbacks made, and the source offsets of what they were given.
+struct Record<'a> {
+    source: &'a str,
+    calls: Vec<Call>,
+    offsets: Vec<usize>,
+    /// Break after beginning this many filters.
+    stop_after: Option<usize>,
+}
+
+impl<'a> Record<'a> {
+    fn offset(&mut self, s: &'a str) -> String {
+        self.offsets
+            .push(s.as_ptr() as usize - self.source.as_ptr() as usize);
+        s.to_string()
+    }
+}
+
+impl<'a> Visitor<'a> for Record<'a> {
+    fn begin_filter(&mut self, target: &'a str) -> ControlFlow<()> {
+        let target = self.offset(target);
+        self.calls.push(Begin(target));
+        let begun = self.calls.iter().filter(|call| matches!(call, Begin(_)));
+        match self.stop_after {
+            Some(n) if begun.count() == n => ControlFlow::Break(()),
+            _ => ControlFlow::Continue(()),
+        }
+    }
+
+    fn begin_spans(&mut self) {
+        self.calls.push(Spans);
+    }
+
+    fn span_filter(&mut self, name: &'a str) {
+        let name = self.offset(name);
+        self.calls.push(Span(name));
+    }
+
+    fn begin_fields(&mut self) {
+        self.calls.push(Fields);
+    }
+
+    fn field_filter(&mut self, name: &'a str, value: Option<&'a str>) {
+        let name = self.offset(name);
+        let value = value.map(|value| self.offset(value));
+        self.calls.push(Field(name, value));
+    }
+
+    fn end_filter(&mut self, level: Option<&'a str>) {
+        let level = level.map(|level| self.offset(level));
+        self.calls.push(End(level));
+    }
+
+    fn regex(&mut self, regex: &'a str) {
+        let regex = self.offset(regex);
+        self.calls.push(Regex(regex));
+    }
+
+    fn error(&mut self, error: ParseError, range: Range<usize>) {
+        self.offsets.push(range.start);
+        self.calls.push(Error(error, range));
+    }
+}
+
+fn record(directives: &str, stop_after: Option<usize>) -> Record<'_> {
+    let mut record = Record {
+        source: directives,
+        calls: Vec::new(),
+        offsets: Vec::new(),
+        stop_after,
+    };
+    parse_with(directives, &mut record);
+    record
+}
+
+/// Check that `calls` follow the grammar documented on `Visitor`.
+fn assert_balanced(directives: &str, calls: &[Call]) {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum State {
+        Outside,
+        Filter,
+        Spans,
+        Span,
+        Fields,
+    }
+
+    let mut state = State::Outside;
+    for (i, call) in calls.iter().enumerate() {
+        state = match (state, call) {
+            (_, Error(..)) => {
+                assert_eq!(i, calls.len() - 1, "{:?}: error is not last", directives);
+                State::Outside
+            }
+            (State::Outside, Begin(_)) => State::Filter,
+            (State::Outside, Regex(_)) => {
+                assert!(
+                    i > 0 && matches!(calls[i - 1], End(_)),
+                    "{:?}: regex outside filter",
+                    directives
+                );
+                assert_eq!(i, calls.len() - 1, "{:?}: regex is not last", directives);
+                State::Outside
+            }
+            (State::Filter, Spans) => State::Spans,
+            (State::Spans, Span(_)) | (State::Span, Span(_)) | (State::Fields, Span(_)) => {
+                State::Span
+            }
+            (State::Span, Fields) | (State::Fields, Field(..)) => State::Fields,
+            (State::Outside, _) | (_, Begin(_)) => {
+                panic!("{:?}: unbalanced {:?} in {:?}", directives, call, calls)
+            }
+            (_, End(_)) => State::Outside,
+            _ => panic!("{:?}: misplaced {:?} in {:?}", directives, call, calls),
+        };
+    }
+    // an error ends any filter it interrupts
+    assert_eq!(
+        state,
+        State::Outside,
+        "{:?}: unended filter in {:?}",
+        directives,
+        calls
+    );
+}
+
+/// The eager filters, built without going through `parse_with`.
+fn eager_from_lazy(directives: &str) -> Result<Vec<eager::Filter<'_>>, ParseError> {
+    parse_env_filter::filters(directives)
+        .map(|filter| eager::Filter::try_from(filter?))
+        .collect()
+}
+
+/// A tiny xorshift generator, so failures reproduce.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+
+    fn pick(&mut self, chars: &str) -> char {
+        let chars: Vec<char> = chars.chars().collect();
+        chars[self.below(chars.len())]
+    }
+
+    fn name(&mut self, chars: &str) -> String {
+        (0..1 + self.below(3)).map(|_| self.pick(chars)).collect()
+    }
+
+    fn space(&mut self) -> &'static str {
+        ["", "", " ", "\t", " \t "][self.below(5)]
+    }
+
+    /// A valid directive string, and the callbacks it should make.
+    fn directives(&mut self) -> (String, Vec<Call>) {
+        let (mut text, mut calls) = (String::new(), Vec::new());
+        let count = 1 + self.below(3);
+        for i in 0..count {
+            if i > 0 {
+                text.push(',');
+            }
+            text += self.space();
+            let (start_text, start_calls) = (text.len(), calls.len());
+
+            let target = if self.below(3) == 0 {
+                String::new()
+            } else {
+                self.name("ab:_-")
+            };
+            text += &target;
+            calls.push(Begin(target));
+            if self.below(2) == 0 {
+                text.push('[');
+                calls.push(Spans);
+                for j in 0..self.below(3) {
+                    if j > 0 {
+                        text.push(',');
+                    }
+                    self.span(&mut text, &mut calls);
+                }
+                text.push(']');
+            }
+            let level = if self.below(2) == 0 {
+                None
+            } else {
+                let level = self.name("ab:_é");
+                text += &format!("={}", level);
+                Some(level)
+            };
+            calls.push(End(level));
+
+            if i == count - 1 {
+                if self.below(4) == 0 {
+                    // the regex takes any trailing whitespace with it
+                    let regex: String = (0..self.below(6))
+                        .map(|_| self.pick("a.*,[]{}=/\""))
+                        .collect();
+                    text += &format!("/{}", regex);
+                    calls.push(Regex(regex));
+                    continue;
+                } else if text.len() == start_text {
+                    // a trailing empty filter is not a filter
+                    calls.truncate(start_calls);
+                }
+            }
+            text += self.space();
+        }
+        (text, calls)
+    }
+
+    fn span(&mut self, text: &mut String, calls: &mut Vec<Call>) {
+        let name = self.name("ab:_-");
+        *text += &name;
+        calls.push(Span(name));
+        if self.below(2) == 0 {
+            text.push('{');
+            calls.push(Fields);
+            for k in 0..self.below(3) {
+                if k > 0 {
+                    text.push(',');
+                }
+                let name = self.name("ab:_é");
+                *text += &name;
+                let value = match self.below(3) {
+                    0 => None,
+                    1 => {
+                        let value = self.name("ab:_é");
+                        *text += &format!("={}", value);
+                        Some(value)
+                    }
+                    _ => {
+                        let value: String = (0..self.below(6))
+                            .map(|_| match self.pick("a,[]{}=/ \"") {
+                                '"' => r#"\""#.to_string(),
+                                c => c.to_string(),
+                            })
+                            .collect();
+                        *text += &format!(r#"="{}""#, value);
+                        Some(value)
+                    }
+                };
+                calls.push(Field(name, value));
+            }
+            text.push('}');
+        }
+    }
+}
+
+#[test]
+fn valid_directives_are_visited_in_order() {
+    let mut rng = Rng(0x5eed);
+    for _ in 0..2000 {
+        let (directives, expected) = rng.directives();
+        let record = record(&directives, None);
+        assert_eq!(record.calls, expected, "{:?}", directives);
+        assert_balanced(&directives, &record.calls);
+        assert!(
+            record.offsets.windows(2).all(|w| w[0] <= w[1]),
+            "{:?}: out of order",
+            directives
+        );
+    }
+}
+
+#[test]
+fn arbitrary_directives_are_balanced_and_ordered() {
+    let mut rng = Rng(0xf00d);
+    for _ in 0..5000 {
+        let directives: String = (0..rng.below(14))
+            .map(|_| rng.pick("ab[]{}=,\"/\\ "))
+            .collect();
+        let record = record(&directives, None);
+        assert_balanced(&directives, &record.calls);
+        assert!(
+            record.offsets.windows(2).all(|w| w[0] <= w[1]),
+            "{:?}: out of order in {:?}",
+            directives,
+            record.calls
+        );
+        assert_eq!(
+            eager::filters(&directives),
+            eager_from_lazy(&directives),
+            "{:?}",
+            directives
+        );
+    }
+}
+
+#[test]
+fn errors_are_last_with_their_range() {
+    let error = |kind, offset, part, range| Error(ParseError { kind, offset, part }, range);
+
+    assert_eq!(
+        record("a=info,[b/]", None).calls,
+        [
+            Begin("a".into()),
+            End(Some("info".into())),
+            Begin("".into()),
+            Spans,
+            error(ParseErrorKind::ReservedSyntax, 9, Part::Span, 9..10),
+        ]
+    );
+    assert_eq!(
+        record("a[b{c,d[}]", None).calls,
+        [
+            Begin("a".into()),
+            Spans,
+            Span("b".into()),
+            Fields,
+            Field("c".into(), None),
+            error(ParseErrorKind::BadSyntax, 7, Part::Field, 7..8),
+        ]
+    );
+    assert_eq!(
+        record("é[b", None).calls,
+        [error(
+            ParseErrorKind::InvalidCharacter('é'),
+            0,
+            Part::Target,
+            0..2
+        )]
+    );
+    assert_eq!(
+        record("a[bé]", None).calls,
+        [
+            Begin("a".into()),
+            Spans,
+            error(ParseErrorKind::InvalidCharacter('é'), 3, Part::Span, 3..5),
+        ]
+    );
+}
+
+#[test]
+fn break_stops_parsing() {
+    assert_eq!(
+        record("a=info,b[c]=warn,d", Some(2)).calls,
+        [
+            Begin("a".into()),
+            End(Some("info".into())),
+            Begin("b".into())
+        ]
+    );
+}
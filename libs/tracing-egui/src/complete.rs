@@ -0,0 +1,316 @@
+//! Completions for the filter box, from what is typed before the cursor.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    sync::Arc,
+};
+use tracing_memory::Event;
+
+/// What the cursor is in the middle of typing, and where that starts and ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Context<'a> {
+    pub position: Position<'a>,
+    /// The text of the name being typed, up to the cursor.
+    pub prefix: &'a str,
+    /// The byte range of the whole name being typed, which a completion replaces.
+    pub range: Range<usize>,
+}
+
+/// A place in a directive where a name can be completed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Position<'a> {
+    Target,
+    /// A span name, in a directive with this target.
+    Span {
+        target: &'a str,
+    },
+    /// A field name, in a span filter with this name.
+    Field {
+        target: &'a str,
+        span: &'a str,
+    },
+    Level,
+}
+
+const SYNTAX: &[char] = &['[', ']', '{', '}', '=', ',', '"', '/'];
+
+/// Work out what is being typed at byte offset `cursor` of `text`.
+///
+/// Only the text before the cursor is parsed, and text that could not be
+/// completed into a valid directive gives `None`.
+pub(crate) fn context(text: &str, cursor: usize) -> Option<Context<'_>> {
+    #[derive(Clone, Copy)]
+    enum State {
+        Target,
+        Span,
+        Field,
+        Value { quoted: bool },
+        AfterValue,
+        AfterFields,
+        AfterSpans,
+        Level,
+    }
+
+    let before = text.get(..cursor)?;
+    let mut state = State::Target;
+    let (mut start, mut target, mut span) = (0, "", "");
+    let mut chars = before.char_indices();
+    while let Some((i, c)) = chars.next() {
+        state = match (state, c) {
+            (State::Target, '[') => {
+                target = &before[start..i];
+                State::Span
+            }
+            (State::Target, '=') => State::Level,
+            (State::Span, '{') => {
+                span = &before[start..i];
+                State::Field
+            }
+            (State::Span, ']') | (State::AfterFields, ']') => State::AfterSpans,
+            (State::Span, ',') | (State::AfterFields, ',') => State::Span,
+            (State::Field, '=') => State::Value { quoted: false },
+            (State::Field, '}')
+            | (State::Value { quoted: false }, '}')
+            | (State::AfterValue, '}') => State::AfterFields,
+            (State::Field, ',')
+            | (State::Value { quoted: false }, ',')
+            | (State::AfterValue, ',') => State::Field,
+            (State::Value { quoted: false }, '"') if before[start..i].is_empty() => {
+                State::Value { quoted: true }
+            }
+            (State::Value { quoted: true }, '\\') => {
+                chars.next();
+                state
+            }
+            (State::Value { quoted: true }, '"') => State::AfterValue,
+            (State::Value { quoted: true }, _) => state,
+            (State::AfterSpans, '=') => State::Level,
+            (State::Target, ',') | (State::AfterSpans, ',') | (State::Level, ',') => {
+                target = "";
+                State::Target
+            }
+            (_, c) if SYNTAX.contains(&c) => return None,
+            (State::AfterValue, _) | (State::AfterFields, _) | (State::AfterSpans, _) => {
+                return None
+            }
+            // still typing the same name, so it starts where it did
+            _ => continue,
+        };
+        start = i + c.len_utf8();
+    }
+
+    let position = match state {
+        State::Target => Position::Target,
+        State::Span => Position::Span { target },
+        State::Field => Position::Field { target, span },
+        State::Level => Position::Level,
+        _ => return None,
+    };
+    let end = text[cursor..]
+        .find(SYNTAX)
+        .map_or(text.len(), |len| cursor + len);
+    Some(Context {
+        position,
+        prefix: &before[start..],
+        range: start..end,
+    })
+}
+
+/// Replace `range` of `text` with `completion`.
+pub(crate) fn apply(text: &str, range: Range<usize>, completion: &str) -> String {
+    [&text[..range.start], completion, &text[range.end..]].concat()
+}
+
+/// The targets, span names, and field names seen in recorded events.
+#[derive(Debug, Default)]
+pub(crate) struct Known<'a> {
+    targets: BTreeSet<&'a str>,
+    /// For each span name, the targets it was seen under and its fields.
+    spans: BTreeMap<&'a str, (BTreeSet<&'a str>, BTreeSet<&'a str>)>,
+    /// For each target, the fields of events with it.
+    event_fields: BTreeMap<&'a str, BTreeSet<&'a str>>,
+}
+
+const LEVELS: [&str; 6] = ["trace", "debug", "info", "warn", "error", "off"];
+
+impl<'a> Known<'a> {
+    pub fn new(events: &'a [Arc<Event>]) -> Self {
+        let mut known = Known::default();
+        for event in events {
+            let target = event.effective_target();
+            known.targets.insert(target);
+            let fields = event.fields().map(|(name, _)| name);
+            known.event_fields.entry(target).or_default().extend(fields);
+
+            for span in std::iter::successors(event.span(), |span| span.parent()) {
+                known.targets.insert(span.meta().target());
+                let (targets, fields) = known.spans.entry(span.meta().name()).or_default();
+                targets.insert(span.meta().target());
+                fields.extend(span.fields().map(|(name, _)| name));
+            }
+        }
+        known
+    }
+
+    /// The names that could complete `context`, in order.
+    ///
+    /// Span and field names are only those seen under the target typed so
+    /// far, matching like the filter does: by containing the typed text.
+    pub fn candidates(&self, context: &Context<'_>) -> Vec<&'a str> {
+        let prefix = context.prefix;
+        let completes = |name: &&str| name.starts_with(prefix) && *name != prefix;
+        let under = |targets: &BTreeSet<&str>, target| targets.iter().any(|t| t.contains(target));
+
+        match context.position {
+            Position::Target => self.targets.iter().copied().filter(completes).collect(),
+            Position::Span { target } => self
+                .spans
+                .iter()
+                .filter(|(_, (targets, _))| under(targets, target))
+                .map(|(&name, _)| name)
+                .filter(completes)
+                .collect(),
+            Position::Field { target, span } => {
+                let mut fields = BTreeSet::new();
+                for (name, (targets, span_fields)) in &self.spans {
+                    if name.contains(span) && under(targets, target) {
+                        fields.extend(span_fields);
+                    }
+                }
+                // an unnamed span filter also matches the event's own fields
+                if span.is_empty() {
+                    for (event_target, event_fields) in &self.event_fields {
+                        if event_target.contains(target) {
+                            fields.extend(event_fields);
+                        }
+                    }
+                }
+                fields.into_iter().filter(completes).collect()
+            }
+            Position::Level => LEVELS
+                .iter()
+                .copied()
+                .filter(|level| {
+                    level.len() > prefix.len() && level[..prefix.len()].eq_ignore_ascii_case(prefix)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_memory::{EventStore, Layer};
+    use tracing_subscriber::prelude::*;
+
+    /// `text` without the `|` marking the cursor, and the cursor's offset.
+    fn split_cursor(text: &str) -> (String, usize) {
+        (text.replacen('|', "", 1), text.find('|').unwrap())
+    }
+
+    fn at<'a>(position: Position<'a>, prefix: &'a str, range: Range<usize>) -> Option<Context<'a>> {
+        Some(Context {
+            position,
+            prefix,
+            range,
+        })
+    }
+
+    #[test]
+    fn positions() {
+        use Position::*;
+        #[rustfmt::skip]
+        let cases = [
+            ("|", at(Target, "", 0..0)),
+            ("my_cra|", at(Target, "my_cra", 0..6)),
+            ("my_cra|te=info", at(Target, "my_cra", 0..8)),
+            ("a,my_|", at(Target, "my_", 2..5)),
+            ("my_crate[|", at(Span { target: "my_crate" }, "", 9..9)),
+            ("my_crate[req|]", at(Span { target: "my_crate" }, "req", 9..12)),
+            ("my_crate[a{x=1},b|", at(Span { target: "my_crate" }, "b", 16..17)),
+            ("[a,|", at(Span { target: "" }, "", 3..3)),
+            ("app[request{|", at(Field { target: "app", span: "request" }, "", 12..12)),
+            ("app[request{us|er}]", at(Field { target: "app", span: "request" }, "us", 12..16)),
+            ("app[request{x=1,y|", at(Field { target: "app", span: "request" }, "y", 16..17)),
+            ("app[request{x=\"a,b\",y|", at(Field { target: "app", span: "request" }, "y", 20..21)),
+            ("app[{|", at(Field { target: "app", span: "" }, "", 5..5)),
+            ("app=|", at(Level, "", 4..4)),
+            ("app[span]=de|", at(Level, "de", 10..12)),
+            ("app=debug,|", at(Target, "", 10..10)),
+            ("app[span{x=1}],ot|", at(Target, "ot", 15..17)),
+        ];
+
+        for (marked, expected) in &cases {
+            let (text, cursor) = split_cursor(marked);
+            assert_eq!(context(&text, cursor), *expected, "{}", marked);
+        }
+    }
+
+    #[test]
+    fn no_position() {
+        for marked in &[
+            "app[span{x=|",
+            "app[span{x=\"a|",
+            "app[span]|",
+            "app[span{x=1}|",
+            "app[span{x=\"a\"|",
+            "app]|",
+            "app[a[|",
+            "app/|",
+            "app[span{x=1}]x|",
+        ] {
+            let (text, cursor) = split_cursor(marked);
+            assert_eq!(context(&text, cursor), None, "{}", marked);
+        }
+    }
+
+    #[test]
+    fn apply_replaces_the_whole_name() {
+        let text = "my_cra=info";
+        let context = context(text, 3).unwrap();
+        assert_eq!(apply(text, context.range, "my_crate"), "my_crate=info");
+    }
+
+    fn events() -> Vec<Arc<Event>> {
+        let store = Arc::new(EventStore::new());
+        let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let request = tracing::info_span!(target: "app::http", "request", user = "bob");
+            let query =
+                tracing::info_span!(target: "app::db", parent: &request, "query", table = "users");
+            query.in_scope(|| tracing::info!(target: "app::db", rows = 1, "done"));
+            let connect = tracing::info_span!(target: "lib", "connect", host = "localhost");
+            connect.in_scope(|| tracing::info!(target: "lib", "connected"));
+        });
+        store.with_events(|events| events.clone())
+    }
+
+    #[test]
+    fn candidates_are_scoped() {
+        let events = events();
+        let known = Known::new(&events);
+        #[rustfmt::skip]
+        let cases: &[(&str, &[&str])] = &[
+            ("|", &["app::db", "app::http", "lib"]),
+            ("app|", &["app::db", "app::http"]),
+            ("app::db|", &[]),
+            ("app[|", &["query", "request"]),
+            ("app::db[|", &["query"]),
+            ("lib[|", &["connect"]),
+            ("app[request{|", &["user"]),
+            ("app[quer{|", &["table"]),
+            ("app[{|", &["message", "rows", "table", "user"]),
+            ("lib[{|", &["host", "message"]),
+            ("app=|", &["trace", "debug", "info", "warn", "error", "off"]),
+            ("app=W|", &["warn"]),
+        ];
+
+        for (marked, expected) in cases {
+            let (text, cursor) = split_cursor(marked);
+            let context = context(&text, cursor).unwrap();
+            assert_eq!(known.candidates(&context), *expected, "{}", marked);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use tracing_memory::{Event, FormatOptions, TimeFormat};
+
+/// Format an event as plain text, as the widget's copy and save actions do,
+/// with the full local date and time.
+///
+/// The first line holds the timestamp, level, thread, target, and message; the
+/// event's other fields and then its spans (innermost first, with their
+/// fields) follow on indented lines. This is [`tracing_memory::format_event`]
+/// with everything included, ending in a newline.
+pub fn format_event(event: &Event) -> String {
+    format_event_at(event, TimeFormat::Local("%F %T%.3f".into()))
+}
+
+/// Format an event as [`format_event`] does, with its time in `timestamp`.
+pub(crate) fn format_event_at(event: &Event, timestamp: TimeFormat) -> String {
+    let options = FormatOptions {
+        timestamp,
+        ..FormatOptions::default()
+    };
+    let mut text = tracing_memory::format_event(event, &options);
+    text.push('\n');
+    text
+}
+
+/// What the widget's header for an event shows of it, besides its timestamp.
+pub(crate) const SUMMARY: FormatOptions = FormatOptions {
+    timestamp: TimeFormat::Hidden,
+    level: true,
+    thread: false,
+    target: false,
+    fields: false,
+    spans: false,
+    _non_exhaustive_but_allow_fru: (),
+};
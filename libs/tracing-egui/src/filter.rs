@@ -1,211 +1,1206 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
-use once_cell::sync::Lazy;
+use parse_env_filter::ParseError;
 use regex::Regex;
-use tracing::{metadata::LevelFilter, Level};
-use tracing_memory::Event;
+use tracing::metadata::LevelFilter;
+use tracing_memory::{Event, Field};
 
 type SStr = smartstring::SmartString<smartstring::LazyCompact>;
 type SVec<T, const N: usize> = smallvec::SmallVec<[T; N]>;
 
-#[derive(Debug, Default)]
-pub(crate) struct EventFilter {
+/// A filter over recorded events, in `target[span{field=value}]=level` syntax.
+///
+/// Forms which could be read more than one way are interpreted like
+/// `tracing_subscriber::EnvFilter` does:
+///
+/// | directive     | meaning                                  |
+/// |---------------|------------------------------------------|
+/// | (empty)       | every event                              |
+/// | `warn`        | every event at `WARN` or above           |
+/// | `Warn`, `2`   | the same; level names ignore case        |
+/// | `=warn`       | the same, spelled with an empty target   |
+/// | `off`         | no events                                |
+/// | `info=warn`   | events with target `info` at `WARN`+     |
+/// | `info[span]`  | events in `span` with target `info`      |
+/// | `hello`       | events with target `hello`, any level    |
+///
+/// A directive may list several span filters, `[a{x=1},b{y=2}]`, each with
+/// several field filters; an event must match all of them. A span filter
+/// matches a span of the event that is under the directive's target and has
+/// all of the listed fields. A span filter without a name, `[{x=1}]`, looks
+/// for each field on the event and then on its spans, nearest first, and only
+/// the nearest field by that name is matched: `[{id=1}]` doesn't match an
+/// event with `id=2` in a span with `id=1`. A field name starting with `@`,
+/// `[{@id=1}]`, must be on the event itself; this is only allowed without a
+/// span name.
+///
+/// Targets and span names match if they contain the text given for them, so
+/// `app` also matches `my_app::db`. An event's target is its
+/// [effective target](tracing_memory::Event::effective_target), so with the
+/// `log-compat` feature, records bridged from `log` match by their own target.
+///
+/// Field names must be given in full. Field values are regular expressions
+/// searched for in the field's `Debug` text, so `status=5\d\d` matches a
+/// status of `503`, and plain text still matches by containing it; use `^`
+/// and `$` to match the whole value. A value may also be wrapped in slashes,
+/// which are left out, so that a pattern with `/` in it reads as one:
+/// `[{path="/api\/v[12]/"}]`. Such a value must be quoted, as unquoted values
+/// can't contain `/` or brackets. Targets and span names can't be patterns,
+/// as the `/` would start the message regex.
+///
+/// A directive starting with `!` hides the events it would show, whatever
+/// the other directives say: `info,!hyper` shows events at `INFO` or above
+/// except those with target `hyper`, and `!hyper=warn` hides only those of
+/// them at `WARN` or above. With only such directives, every other event
+/// is shown.
+///
+/// Like env_logger, the directives may end with `/regex`, a regular
+/// expression which an event's message must also contain to be included.
+///
+/// A filter can also be built without writing out its directives, with
+/// [`EventFilter::builder`].
+///
+/// Changes from earlier versions: a bare level name used to be treated as
+/// an empty directive matching every event, `off` used to be a target, and
+/// a level name followed by `=level` or `[span]` used to lose its target.
+#[derive(Debug, Default, Clone)]
+pub struct EventFilter {
     directives: SVec<Directive, 2>,
+    message: Option<Regex>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Directive {
     target: Option<SStr>,
-    span: Option<SStr>,
-    field: Option<FieldDirective>,
+    spans: SVec<SpanDirective, 1>,
     level: LevelFilter,
+    /// Whether the events this applies to are hidden rather than shown.
+    negated: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+struct SpanDirective {
+    name: Option<SStr>,
+    fields: SVec<FieldDirective, 1>,
+}
+
+#[derive(Debug, Clone)]
 struct FieldDirective {
     name: SStr,
-    value: Option<SStr>,
+    /// Whether the field must be on the event, rather than on it or its spans.
+    on_event: bool,
+    value: Option<Regex>,
+}
+
+/// Builds an [`EventFilter`] one directive at a time, rather than parsing
+/// it from directive text.
+///
+/// Each directive is given its parts, in any order, and ended with
+/// [`push`](Self::push). Field filters are added to the span filter added
+/// last, or to a new span filter without a name if there is none. A directive
+/// without a level includes events at every level, as in the text syntax.
+///
+/// ```rust
+/// # use tracing::metadata::LevelFilter;
+/// # use tracing_egui::EventFilter;
+/// let filter = EventFilter::builder()
+///     .target("my_crate")
+///     .span("handler")
+///     .field("user", Some("bob"))
+///     .level(LevelFilter::DEBUG)
+///     .push()
+///     .level(LevelFilter::WARN)
+///     .finish();
+/// // the same as `"my_crate[handler{user=bob}]=debug,warn".parse()`
+/// assert!(filter.is_ok());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct EventFilterBuilder {
+    filter: EventFilter,
+    /// The directive being built, once any part of it has been given.
+    directive: Option<Directive>,
+    /// The first pattern given which is not a valid regular expression.
+    error: Option<regex::Error>,
 }
 
 impl EventFilter {
+    /// Build a filter from its parts; see [`EventFilterBuilder`].
+    pub fn builder() -> EventFilterBuilder {
+        EventFilterBuilder::default()
+    }
+
+    /// Whether the filter includes `event`, to be shown.
     pub fn includes(&self, event: &Event) -> bool {
+        if let Some(message) = &self.message {
+            match event.message() {
+                Some(field) if message.is_match(&field.to_string()) => {}
+                _ => return false,
+            }
+        }
         if self.directives.is_empty() {
             return true;
         }
 
-        let mut included = false;
-
-        for directive in &self.directives {
-            let mut this_directive_applies = true;
+        let level = event.meta().level();
+        let (negated, shown): (SVec<&Directive, 2>, SVec<&Directive, 2>) = self
+            .directives
+            .iter()
+            .partition(|directive| directive.negated);
+        let mut included = shown.is_empty();
 
-            if let Some(target_directive) = &directive.target {
-                this_directive_applies &= event
-                    .meta()
-                    .target()
-                    .matches(target_directive.as_str())
-                    .any(|_| true);
+        for directive in shown {
+            if directive.applies_to(event) {
+                included = *level <= directive.level;
             }
+        }
 
-            // FIXME: should require being in `target` (if provided)
-            if let Some(span_directive) = &directive.span {
-                this_directive_applies &= std::iter::successors(event.span(), |span| span.parent())
-                    .filter(|span| {
-                        span.meta()
-                            .name()
-                            .matches(span_directive.as_str())
-                            .any(|_| true)
-                    })
-                    .any(|_| true);
-            }
+        included
+            && !(negated.iter())
+                .any(|directive| directive.applies_to(event) && *level <= directive.level)
+    }
+}
+
+/// The filter parsed from the filter box, kept between frames so that it is
+/// only parsed, and its patterns compiled, when the text changes.
+#[derive(Debug, Clone)]
+pub(crate) struct FilterCache {
+    /// The text the filter was parsed from.
+    text: String,
+    parsed: Result<EventFilter, FilterParseError>,
+}
+
+impl Default for FilterCache {
+    fn default() -> Self {
+        // what the empty text parses to
+        FilterCache {
+            text: String::new(),
+            parsed: Ok(EventFilter::default()),
+        }
+    }
+}
+
+impl FilterCache {
+    /// The filter `text` parses to, parsing it only if it changed.
+    pub fn parse(&mut self, text: &str) -> Result<&EventFilter, &FilterParseError> {
+        self.parse_with(text, str::parse)
+    }
+
+    fn parse_with(
+        &mut self,
+        text: &str,
+        parse: impl FnOnce(&str) -> Result<EventFilter, FilterParseError>,
+    ) -> Result<&EventFilter, &FilterParseError> {
+        if text != self.text {
+            self.parsed = parse(text);
+            self.text.clear();
+            self.text.push_str(text);
+        }
+        self.parsed.as_ref()
+    }
+}
+
+impl EventFilterBuilder {
+    /// Set the target of the directive, which matches events with a target
+    /// containing it.
+    pub fn target(mut self, target: &str) -> Self {
+        self.directive().target = non_empty(target);
+        self
+    }
+
+    /// Add a span filter to the directive, which matches events in a span
+    /// with a name containing `name`, or any span if `name` is empty.
+    pub fn span(mut self, name: &str) -> Self {
+        self.directive().spans.push(SpanDirective {
+            name: non_empty(name),
+            fields: SVec::new(),
+        });
+        self
+    }
+
+    /// Add a field filter to the last span filter.
+    ///
+    /// The value is a regular expression searched for in the field's `Debug`
+    /// text, as in the text syntax, but without quotes or slashes around it.
+    pub fn field(mut self, name: &str, value: Option<&str>) -> Self {
+        let value = value.and_then(|value| self.pattern(value));
+        self.add_field(FieldDirective {
+            name: name.into(),
+            on_event: false,
+            value,
+        })
+    }
+
+    /// Add a field filter which the event itself must match, like `@name`
+    /// in the text syntax.
+    ///
+    /// It is added to the last span filter if that has no name, or else to a
+    /// new span filter without a name.
+    pub fn event_field(mut self, name: &str, value: Option<&str>) -> Self {
+        let value = value.and_then(|value| self.pattern(value));
+        self.add_field(FieldDirective {
+            name: name.into(),
+            on_event: true,
+            value,
+        })
+    }
+
+    /// Set the most verbose level of events the directive includes.
+    pub fn level(mut self, level: LevelFilter) -> Self {
+        self.directive().level = level;
+        self
+    }
+
+    /// Make the directive hide the events it would show, like `!` in the
+    /// text syntax.
+    pub fn negate(mut self) -> Self {
+        self.directive().negated = true;
+        self
+    }
+
+    /// End the directive, so the next part starts a new one.
+    ///
+    /// A directive with nothing in it is left out.
+    pub fn push(mut self) -> Self {
+        self.filter.directives.extend(self.directive.take());
+        self
+    }
+
+    /// Set a regular expression which an event's message must also contain.
+    pub fn message(mut self, pattern: &str) -> Self {
+        self.filter.message = self.pattern(pattern);
+        self
+    }
+
+    /// The filter, or the first pattern given which is not a valid regular
+    /// expression.
+    pub fn finish(self) -> Result<EventFilter, regex::Error> {
+        let builder = self.push();
+        match builder.error {
+            Some(error) => Err(error),
+            None => Ok(builder.filter),
+        }
+    }
 
-            for field_directive in &directive.field {
-                // FIXME: should require being in `span` (if provided)
-                // FIXME: `value` should be treated as a regex
-                this_directive_applies &= event
-                    .fields()
-                    .chain(
-                        std::iter::successors(event.span(), |span| span.parent())
-                            .flat_map(|span| span.fields()),
-                    )
-                    .filter(|(name, _value)| {
-                        name.matches(field_directive.name.as_str()).any(|_| true)
-                    })
-                    .filter(|(_name, value)| {
-                        if let Some(value_directive) = &field_directive.value {
-                            // FIXME: avoid format! call where possible (i.e. primitive, str fields)
-                            value
-                                .with_debug(|field| {
-                                    let field = format!("{:?}", field);
-                                    field.matches(value_directive.as_str()).any(|_| true)
-                                })
-                                .any(std::convert::identity)
-                        } else {
-                            true
-                        }
-                    })
-                    .any(|_| true);
+    fn directive(&mut self) -> &mut Directive {
+        self.directive.get_or_insert_with(|| Directive {
+            target: None,
+            spans: SVec::new(),
+            // Setting the target without the level enables every level for that target
+            level: LevelFilter::TRACE,
+            negated: false,
+        })
+    }
+
+    fn add_field(mut self, field: FieldDirective) -> Self {
+        let spans = &mut self.directive().spans;
+        match spans.last_mut() {
+            Some(span) if !(field.on_event && span.name.is_some()) => span.fields.push(field),
+            _ => spans.push(SpanDirective {
+                name: None,
+                fields: smallvec::smallvec![field],
+            }),
+        }
+        self
+    }
+
+    /// `pattern` compiled, keeping the first error.
+    fn pattern(&mut self, pattern: &str) -> Option<Regex> {
+        match Regex::new(pattern) {
+            Ok(regex) => Some(regex),
+            Err(error) => {
+                self.error.get_or_insert(error);
+                None
             }
+        }
+    }
+}
+
+impl Directive {
+    fn applies_to(&self, event: &Event) -> bool {
+        let target = self.target.as_deref();
+        matches_directive(event.effective_target(), target)
+            && self.spans.iter().all(|span| span.applies_to(event, target))
+    }
+}
+
+impl SpanDirective {
+    /// Whether a span of `event` has this name and fields and is under `target`.
+    ///
+    /// Without a name, each field is the nearest by its name, on the event
+    /// itself or on one of its spans.
+    fn applies_to(&self, event: &Event, target: Option<&str>) -> bool {
+        let spans = || {
+            std::iter::successors(event.span(), |span| span.parent())
+                .filter(move |span| matches_directive(span.meta().target(), target))
+        };
+        match &self.name {
+            Some(name) => spans()
+                .filter(|span| matches_directive(span.meta().name(), Some(name)))
+                .any(|span| self.has_fields(|field| span.field(&field.name))),
+            None => self.has_fields(|field| {
+                let on_event = event.field(&field.name);
+                if field.on_event {
+                    return on_event;
+                }
+                on_event.or_else(|| spans().find_map(|span| span.field(&field.name)))
+            }),
+        }
+    }
+
+    fn has_fields<'a>(&self, field: impl Fn(&FieldDirective) -> Option<&'a Field>) -> bool {
+        self.fields
+            .iter()
+            .all(|directive| matches!(field(directive), Some(value) if directive.applies_to(value)))
+    }
+}
+
+impl FieldDirective {
+    fn applies_to(&self, value: &Field) -> bool {
+        match &self.value {
+            // FIXME: avoid format! call where possible (i.e. primitive, str fields)
+            Some(pattern) => value
+                .with_debug(|field| pattern.is_match(&format!("{:?}", field)))
+                .any(std::convert::identity),
+            None => true,
+        }
+    }
+}
 
-            if this_directive_applies {
-                included = *event.meta().level() <= directive.level;
+/// Targets and span names match a directive by containing it.
+fn matches_directive(haystack: &str, directive: Option<&str>) -> bool {
+    match directive {
+        Some(directive) => haystack.contains(directive),
+        None => true,
+    }
+}
+
+/// Why a filter could not be parsed.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum FilterParseError {
+    /// The syntax is malformed, e.g. an unclosed span or field section.
+    Syntax(ParseError),
+    /// A level is not a level name or number.
+    BadLevel { level: SStr, offset: usize },
+    /// A field filter has no field name, which it needs to match anything.
+    EmptyFieldName { offset: usize },
+    /// A field filter in a named span filter is marked with `@` as being on the event.
+    EventFieldInSpan { offset: usize },
+    /// A field filter's value or the message regex is not a valid regular expression.
+    BadPattern { offset: usize, error: regex::Error },
+}
+
+impl fmt::Display for FilterParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterParseError::Syntax(err) => err.fmt(f),
+            FilterParseError::BadLevel { level, offset } => write!(
+                f,
+                "{:?} at offset {} is not a level; \
+                 expected one of trace, debug, info, warn, error, or off",
+                level, offset,
+            ),
+            FilterParseError::EmptyFieldName { offset } => {
+                write!(f, "missing field name at offset {}", offset)
+            }
+            FilterParseError::EventFieldInSpan { offset } => write!(
+                f,
+                "event field at offset {} is in a named span; use `[{{@field}}]`",
+                offset,
+            ),
+            FilterParseError::BadPattern { offset, error } => {
+                write!(f, "bad pattern at offset {}: {}", offset, error)
             }
         }
+    }
+}
 
-        included
+impl FilterParseError {
+    /// The byte offset into the filter text where the error is.
+    fn offset(&self) -> usize {
+        match self {
+            FilterParseError::Syntax(err) => err.offset,
+            FilterParseError::BadLevel { offset, .. }
+            | FilterParseError::EmptyFieldName { offset }
+            | FilterParseError::EventFieldInSpan { offset }
+            | FilterParseError::BadPattern { offset, .. } => *offset,
+        }
+    }
+
+    /// A line for each directive of `text`, the filter text this error is
+    /// from, which fails to parse, like
+    /// ``directive 2 (`hyper=[oops`): invalid syntax in level at offset 12``.
+    ///
+    /// Parsing stops at the first error, but every directive with bad syntax
+    /// is listed, as well as the one this error is in.
+    pub fn directive_errors(&self, text: &str) -> Vec<String> {
+        let (blanked, _) = blank_negations(text);
+        let checks = parse_env_filter::check(&blanked).enumerate();
+        let errors = checks.filter_map(|(ix, check)| {
+            let error = match (check.result, self) {
+                (Err(err), _) => err.to_string(),
+                (Ok(()), FilterParseError::Syntax(_)) => return None,
+                (Ok(()), error) if check.range.contains(&self.offset()) => error.to_string(),
+                (Ok(()), _) => return None,
+            };
+            Some(format!(
+                "directive {} (`{}`): {}",
+                ix + 1,
+                &text[check.range],
+                error
+            ))
+        });
+        errors.collect()
     }
+}
 
-    pub fn excludes(&self, event: &Event) -> bool {
-        !self.includes(event)
+impl From<ParseError> for FilterParseError {
+    fn from(err: ParseError) -> Self {
+        FilterParseError::Syntax(err)
     }
 }
 
 impl FromStr for EventFilter {
-    type Err = (); // TODO: nicer error message
+    type Err = FilterParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Adapted from <tracing_subscriber@54780fb::EnvFilter>::try_new
-        // https://github.com/tokio-rs/tracing/blob/54780fb/tracing-subscriber/src/filter/env/mod.rs#L166-L171
-        if s.is_empty() {
-            return Ok(EventFilter::default());
+        let (blanked, negations) = blank_negations(s);
+        let s = blanked.as_str();
+        let filters = parse_env_filter::eager::filters(s)?;
+        // the regex is always last, so report the directives' errors first
+        let message = filters.last().and_then(|filter| filter.regex);
+        let mut builder = EventFilter::builder();
+        for filter in filters {
+            // the `!` was blanked out right before the directive's target
+            let target = offset(s, filter.target);
+            let start = s[..target].trim_end().len();
+            let negated = negations.iter().any(|&at| (start..target).contains(&at));
+            builder = builder.parsed(s, filter, negated)?.push();
+        }
+        if let Some(regex) = message {
+            let message = Regex::new(regex).map_err(|error| FilterParseError::BadPattern {
+                offset: offset(s, regex),
+                error,
+            })?;
+            builder.filter.message = Some(message);
         }
-        let directives = s.split(',').map(|s| s.parse()).collect::<Result<_, _>>()?;
-        Ok(EventFilter { directives })
+        // the patterns were compiled here, to report where the bad ones are
+        Ok(builder.push().filter)
     }
 }
 
-impl FromStr for Directive {
-    type Err = (); // TODO: actual error messages
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Adapted from <tracing_subscriber@54780fb::filter::Directive as FromStr>::from_str
-        // https://github.com/tokio-rs/tracing/blob/54780fb/tracing-subscriber/src/filter/env/directive.rs#L177-L266
-        // target[span{field=value}]=level
+impl EventFilterBuilder {
+    /// Add the directive parsed as `filter` from `source`.
+    fn parsed(
+        self,
+        source: &str,
+        filter: parse_env_filter::eager::Filter<'_>,
+        negated: bool,
+    ) -> Result<Self, FilterParseError> {
+        let builder = if negated { self.negate() } else { self };
+        // A bare level name is a global directive, not a target
+        let is_bare = filter.span.is_none() && filter.level.is_none();
+        if let Some(level) = Some(filter.target)
+            .filter(|target| is_bare && !target.is_empty())
+            .and_then(level_filter)
+        {
+            return Ok(builder.level(level));
+        }
+
+        let level = match filter.level {
+            None | Some("") => LevelFilter::TRACE,
+            Some(level) => level_filter(level).ok_or_else(|| FilterParseError::BadLevel {
+                level: level.into(),
+                offset: offset(source, level),
+            })?,
+        };
+        // the level is always set, so that an empty directive is kept
+        let mut builder = builder.target(filter.target).level(level);
+        for span in filter.span.into_iter().flatten() {
+            builder = builder.parsed_span(source, span)?;
+        }
+        Ok(builder)
+    }
+
+    fn parsed_span(
+        mut self,
+        source: &str,
+        filter: parse_env_filter::eager::SpanFilter<'_>,
+    ) -> Result<Self, FilterParseError> {
+        self = self.span(filter.name);
+        for field in filter.fields.into_iter().flatten() {
+            let (name, on_event) = match field.name.strip_prefix('@') {
+                Some(name) if filter.name.is_empty() => (name, true),
+                Some(_) => {
+                    let offset = offset(source, field.name);
+                    return Err(FilterParseError::EventFieldInSpan { offset });
+                }
+                None => (field.name, false),
+            };
+            if name.is_empty() {
+                let offset = offset(source, field.name);
+                return Err(FilterParseError::EmptyFieldName { offset });
+            }
+            let value = field.value.map(|value| {
+                let pattern = (value.strip_prefix('/'))
+                    .and_then(|value| value.strip_suffix('/'))
+                    .unwrap_or(value);
+                Regex::new(pattern).map_err(|error| FilterParseError::BadPattern {
+                    offset: offset(source, value),
+                    error,
+                })
+            });
+            self = self.add_field(FieldDirective {
+                name: name.into(),
+                on_event,
+                value: value.transpose()?,
+            });
+        }
+        Ok(self)
+    }
+}
+
+/// Blank out the `!` negating a directive, which the parser doesn't know,
+/// returning the directives without them and where they were.
+///
+/// Only a `!` starting a directive negates it, not one in a span, a quoted
+/// value, or the message regex.
+fn blank_negations(directives: &str) -> (String, SVec<usize, 2>) {
+    let mut blanked = String::with_capacity(directives.len());
+    let mut negations = SVec::new();
+    let mut depth = 0_usize;
+    let mut quoted = false;
+    let mut escaped = false;
+    // whether only whitespace was seen since the directive started
+    let mut starting = true;
+    for (i, c) in directives.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if quoted {
+            quoted = c != '"';
+        } else {
+            match c {
+                '!' if starting && depth == 0 => {
+                    negations.push(i);
+                    blanked.push(' ');
+                    starting = false;
+                    continue;
+                }
+                '"' => quoted = true,
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth = depth.saturating_sub(1),
+                '/' if depth == 0 => {
+                    blanked.push_str(&directives[i..]);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        starting = depth == 0 && !quoted && (c == ',' || (starting && c.is_whitespace()));
+        blanked.push(c);
+    }
+    (blanked, negations)
+}
+
+/// Parse a level like the recording filter does, with [`parse_env_filter::parse_level`].
+fn level_filter(level: &str) -> Option<LevelFilter> {
+    use parse_env_filter::Level;
+    Some(match parse_env_filter::parse_level(level)? {
+        Level::Off => LevelFilter::OFF,
+        Level::Error => LevelFilter::ERROR,
+        Level::Warn => LevelFilter::WARN,
+        Level::Info => LevelFilter::INFO,
+        Level::Debug => LevelFilter::DEBUG,
+        Level::Trace => LevelFilter::TRACE,
+    })
+}
+
+/// The offset of `part` in `source`, which it is a slice of.
+fn offset(source: &str, part: &str) -> usize {
+    part.as_ptr() as usize - source.as_ptr() as usize
+}
+
+fn non_empty(s: &str) -> Option<SStr> {
+    Some(s).filter(|s| !s.is_empty()).map(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parse_env_filter::{ParseErrorKind, Part};
+    use std::sync::Arc;
+    use tracing_memory::{EventStore, Layer};
+    use tracing_subscriber::prelude::*;
+
+    /// Each ambiguous directive form, and the `(target, level)` it means.
+    const INTERPRETATIONS: &[(&str, Option<&str>, LevelFilter)] = &[
+        ("warn", None, LevelFilter::WARN),
+        ("Warn", None, LevelFilter::WARN),
+        ("2", None, LevelFilter::WARN),
+        ("=warn", None, LevelFilter::WARN),
+        ("off", None, LevelFilter::OFF),
+        ("OFF", None, LevelFilter::OFF),
+        ("info=warn", Some("info"), LevelFilter::WARN),
+        ("hello", Some("hello"), LevelFilter::TRACE),
+        ("hello=", Some("hello"), LevelFilter::TRACE),
+    ];
+
+    #[test]
+    fn ambiguous_forms() {
+        for &(directive, target, level) in INTERPRETATIONS {
+            let parsed: EventFilter = directive.parse().unwrap();
+            assert_eq!(parsed.directives.len(), 1, "{}", directive);
+            assert_eq!(
+                parsed.directives[0].target.as_deref(),
+                target,
+                "{}",
+                directive
+            );
+            assert_eq!(parsed.directives[0].level, level, "{}", directive);
+        }
+        assert!("".parse::<EventFilter>().unwrap().directives.is_empty());
+    }
+
+    #[test]
+    fn bad_level() {
+        let error = "hello=lol".parse::<EventFilter>().unwrap_err();
+        let expected = FilterParseError::BadLevel {
+            level: "lol".into(),
+            offset: 6,
+        };
+        assert_eq!(error, expected);
+    }
+
+    /// A directive's target, span names, and level.
+    type Summary<'a> = (Option<&'a str>, Vec<Option<&'a str>>, LevelFilter);
+
+    fn summarize(filter: &EventFilter) -> Vec<Summary<'_>> {
+        filter
+            .directives
+            .iter()
+            .map(|directive| {
+                let spans = directive.spans.iter().map(|span| span.name.as_deref());
+                (
+                    directive.target.as_deref(),
+                    spans.collect(),
+                    directive.level,
+                )
+            })
+            .collect()
+    }
+
+    /// The valid examples from parse-env-filter's tests, as directives.
+    #[test]
+    fn parse_env_filter_examples() {
+        use LevelFilter as L;
         #[rustfmt::skip]
-        static DIRECTIVE_RE: Lazy<Regex> = Lazy::new(|| {
-            Regex::new(r#"(?x)
-                ^
-                (?P<target>[\w:-]+)? # target
-                (?:                  # [span{field=value}]
-                    \[(?P<span>[^\]]*)\]
-                )?
-                (?:                  # =level
-                    =(?P<level>(?i:trace|debug|info|warn|error|off|[0-5]))?
-                    #          ^^^.
-                    #              `note: we match log level names case-insensitively
-                )?
-                $
-            "#).unwrap()
-        });
+        let examples: &[(&str, &[Summary<'_>])] = &[
+            ("tokio::net=info", &[(Some("tokio::net"), vec![], L::INFO)]),
+            ("my_crate[span_a]=trace", &[(Some("my_crate"), vec![Some("span_a")], L::TRACE)]),
+            ("[span_b{name=bob}]", &[(None, vec![Some("span_b")], L::TRACE)]),
+            ("hello", &[(Some("hello"), vec![], L::TRACE)]),
+            ("trace", &[(None, vec![], L::TRACE)]),
+            ("TRACE", &[(None, vec![], L::TRACE)]),
+            ("info", &[(None, vec![], L::INFO)]),
+            ("INFO", &[(None, vec![], L::INFO)]),
+            ("hello=debug", &[(Some("hello"), vec![], L::DEBUG)]),
+            ("hello=DEBUG", &[(Some("hello"), vec![], L::DEBUG)]),
+            ("hello,std::option", &[(Some("hello"), vec![], L::TRACE), (Some("std::option"), vec![], L::TRACE)]),
+            ("error,hello=warn", &[(None, vec![], L::ERROR), (Some("hello"), vec![], L::WARN)]),
+            ("off", &[(None, vec![], L::OFF)]),
+            ("OFF", &[(None, vec![], L::OFF)]),
+            ("=warn", &[(None, vec![], L::WARN)]),
+        ];
+
+        for (directives, expected) in examples {
+            let filter: EventFilter = directives.parse().unwrap();
+            assert_eq!(summarize(&filter), *expected, "{}", directives);
+        }
+    }
+
+    /// The invalid examples from parse-env-filter's tests, which fail the same way.
+    #[test]
+    fn parse_env_filter_negative_examples() {
+        use ParseErrorKind::{BadSyntax, ReservedSyntax};
         #[rustfmt::skip]
-        static SPAN_PART_RE: Lazy<Regex> =
-            Lazy::new(|| Regex::new(r#"(?x)
-                (?P<name>[^\]\{]+)? # span
-                (?:                 # {field=value}
-                    \{(?P<fields>[^\}]*)\}
-                )?
-            "#).unwrap());
+        let examples = [
+            ("[a/b]", ReservedSyntax, 2, Part::Span),
+            ("[a[a]", BadSyntax, 2, Part::Span),
+            ("[[]", BadSyntax, 1, Part::Span),
+            ("[=]", BadSyntax, 1, Part::Span),
+            ("[}]", BadSyntax, 1, Part::Span),
+        ];
+
+        for &(directives, kind, offset, part) in &examples {
+            let error = directives.parse::<EventFilter>().unwrap_err();
+            let expected = FilterParseError::Syntax(ParseError { kind, offset, part });
+            assert_eq!(error, expected, "{}", directives);
+        }
+    }
+
+    #[test]
+    fn error_variants() {
+        use FilterParseError::*;
+        let bad_level = |level: &str, offset| BadLevel {
+            level: level.into(),
+            offset,
+        };
+        let syntax = |offset, part| {
+            Syntax(ParseError {
+                kind: ParseErrorKind::BadSyntax,
+                offset,
+                part,
+            })
+        };
         #[rustfmt::skip]
-        static FIELD_PART_RE: Lazy<Regex> = Lazy::new(|| {
-            Regex::new(r#"(?x)
-                (?P<name>[^=]+) # field
-                (?:             # =value
-                    =(?P<value>[^,]+)
-                )?
-            "#).unwrap()
+        let examples = [
+            ("=lol", bad_level("lol", 1)),
+            ("a=info,b=lol", bad_level("lol", 9)),
+            // parses, but `level` is not a level
+            ("target[span{field=value}]=level", bad_level("level", 26)),
+            ("app[span{=x}]", EmptyFieldName { offset: 9 }),
+            ("app[{x,,y}]=info", EmptyFieldName { offset: 7 }),
+            ("app[{@}]", EmptyFieldName { offset: 5 }),
+            ("app[span{@x=1}]", EventFieldInSpan { offset: 9 }),
+            ("app[span", syntax(3, Part::Span)),
+            ("app[span{x=1]", syntax(8, Part::Field)),
+        ];
+
+        for (directives, expected) in &examples {
+            let error = directives.parse::<EventFilter>().unwrap_err();
+            assert_eq!(error, *expected, "{}", directives);
+        }
+
+        assert_eq!(
+            "=lol".parse::<EventFilter>().unwrap_err().to_string(),
+            r#""lol" at offset 1 is not a level; expected one of trace, debug, info, warn, error, or off"#,
+        );
+        assert!(matches!(
+            "app[{x=(}]".parse::<EventFilter>().unwrap_err(),
+            BadPattern { offset: 7, .. },
+        ));
+    }
+
+    #[test]
+    fn errors_name_their_directive() {
+        let errors = |text: &str| {
+            let error = text.parse::<EventFilter>().unwrap_err();
+            error.directive_errors(text)
+        };
+
+        assert_eq!(
+            errors("info, hyper=[oops, app=debug"),
+            ["directive 2 (`hyper=[oops`): invalid syntax in level at offset 12"],
+        );
+        // every directive with bad syntax is listed, not only the first
+        assert_eq!(
+            errors("a}, b=info, c[d{e]=warn, f"),
+            [
+                "directive 1 (`a}`): invalid syntax in target at offset 1",
+                "directive 3 (`c[d{e]=warn`): invalid syntax in field filter at offset 15",
+            ],
+        );
+        // and an error found after parsing, in the directive it is in
+        assert_eq!(
+            errors("info, !hyper=lol, app"),
+            [
+                r#"directive 2 (`hyper=lol`): "lol" at offset 13 is not a level; expected one of trace, debug, info, warn, error, or off"#
+            ],
+        );
+        let pattern = errors("app[{x=(}], b");
+        assert_eq!(pattern.len(), 1);
+        assert!(pattern[0].starts_with("directive 1 (`app[{x=(}]`): bad pattern at offset 7"));
+    }
+
+    /// Record the events emitted by `f` into a fresh store.
+    fn record(f: impl FnOnce()) -> Vec<Arc<Event>> {
+        let store = Arc::new(EventStore::new());
+        let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, f);
+        store.with_events(|events| events.clone())
+    }
+
+    #[test]
+    fn multiple_spans_and_fields() {
+        let filter: EventFilter = "my_crate[a{x=1},b{y=2}]=debug".parse().unwrap();
+        let spans = &filter.directives[0].spans;
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].name.as_deref(), Some("a"));
+        assert_eq!(spans[0].fields[0].name, "x");
+        assert_eq!(spans[1].name.as_deref(), Some("b"));
+        assert_eq!(
+            spans[1].fields[0].value.as_ref().map(Regex::as_str),
+            Some("2")
+        );
+
+        let events = record(|| {
+            let a = tracing::info_span!(target: "my_crate", "a", x = 1);
+            let b = tracing::info_span!(target: "my_crate", parent: &a, "b", y = 2);
+            let c = tracing::info_span!(target: "my_crate", parent: &a, "c", y = 2);
+            b.in_scope(|| tracing::debug!(target: "my_crate", "in a and b"));
+            b.in_scope(|| tracing::trace!(target: "my_crate", "too verbose"));
+            c.in_scope(|| tracing::debug!(target: "my_crate", "not in b"));
+            a.in_scope(|| tracing::debug!(target: "my_crate", "only in a"));
+            b.in_scope(|| tracing::debug!(target: "other_crate", "wrong target"));
         });
+        let included: Vec<_> = events.iter().map(|event| filter.includes(event)).collect();
+        assert_eq!(included, [true, false, false, false, false]);
+    }
 
-        let caps = DIRECTIVE_RE.captures(s).ok_or(())?;
+    #[test]
+    fn fields_are_scoped_to_their_span() {
+        let filter: EventFilter = "app[request{user=bob}]".parse().unwrap();
+        let events = record(|| {
+            let request = tracing::info_span!(target: "app", "request", user = "bob");
+            request.in_scope(|| tracing::info!(target: "app", "in request"));
 
-        if let Some(level) = caps
-            .name("global_level")
-            .and_then(|s| s.as_str().parse().ok())
-        {
-            return Ok(Directive {
-                target: None,
-                span: None,
-                field: None,
-                level,
+            let request = tracing::info_span!(target: "app", "request", user = "alice");
+            let query =
+                tracing::info_span!(target: "app", parent: &request, "db_query", user = "bob");
+            query.in_scope(|| tracing::info!(target: "app", "user only on db_query"));
+
+            let request = tracing::info_span!(target: "app", "request");
+            request.in_scope(|| tracing::info!(target: "app", user = "bob", "user only on event"));
+
+            let request = tracing::info_span!(target: "lib", "request", user = "bob");
+            request.in_scope(|| tracing::info!(target: "app", "request under another target"));
+        });
+        let included: Vec<_> = events.iter().map(|event| filter.includes(event)).collect();
+        assert_eq!(included, [true, false, false, false]);
+    }
+
+    #[test]
+    fn nested_spans_with_the_same_field() {
+        let events = record(|| {
+            let request = tracing::info_span!(target: "app", "request", user = "bob");
+            let query =
+                tracing::info_span!(target: "app", parent: &request, "db_query", user = "alice");
+            query.in_scope(|| tracing::info!(target: "app", "in both"));
+            request.in_scope(|| tracing::info!(target: "app", "in request"));
+            tracing::info!(target: "app", user = "bob", "in neither");
+        });
+        let included = |filter: &str| -> Vec<_> {
+            let filter: EventFilter = filter.parse().unwrap();
+            events.iter().map(|event| filter.includes(event)).collect()
+        };
+
+        assert_eq!(included("app[request{user=bob}]"), [true, true, false]);
+        assert_eq!(included("app[db_query{user=bob}]"), [false, false, false]);
+        assert_eq!(included("app[db_query{user=alice}]"), [true, false, false]);
+        // without fields, only the span name matters
+        assert_eq!(included("app[request]"), [true, true, false]);
+        assert_eq!(included("app[db_query]"), [true, false, false]);
+    }
+
+    #[test]
+    fn span_filters_are_scoped_to_their_target() {
+        let events = record(|| {
+            let first = tracing::info_span!(target: "first", "work");
+            let second = tracing::info_span!(target: "second", "work");
+            first.in_scope(|| tracing::info!(target: "first", "first in first"));
+            second.in_scope(|| tracing::info!(target: "second", "second in second"));
+            first.in_scope(|| tracing::info!(target: "second", "second in first"));
+            tracing::info!(target: "first", "first outside");
+        });
+        let included = |filter: &str| -> Vec<_> {
+            let filter: EventFilter = filter.parse().unwrap();
+            events.iter().map(|event| filter.includes(event)).collect()
+        };
+
+        assert_eq!(included("first[work]"), [true, false, false, false]);
+        assert_eq!(included("second[work]"), [false, true, false, false]);
+        // with only a target or only a span, the other is unconstrained
+        assert_eq!(included("first"), [true, false, false, true]);
+        assert_eq!(included("[work]"), [true, true, true, false]);
+    }
+
+    #[test]
+    fn message_regex() {
+        let events = record(|| {
+            tracing::info!(target: "app", "request 200");
+            tracing::info!(target: "app", "request 503");
+            tracing::debug!(target: "app", "request 504");
+            tracing::info!(target: "lib", "request 500");
+            tracing::info!(target: "app", status = 500);
+        });
+        let included = |filter: &str| -> Vec<_> {
+            let filter: EventFilter = filter.parse().unwrap();
+            events.iter().map(|event| filter.includes(event)).collect()
+        };
+
+        assert_eq!(included("/5\\d\\d"), [false, true, true, true, false]);
+        assert_eq!(
+            included("info,lib=off/5.."),
+            [false, true, false, false, false]
+        );
+        // the regex may contain syntax characters
+        assert_eq!(
+            included("app/^request (2|5)0[03]$"),
+            [true, true, false, false, false]
+        );
+        assert!(matches!(
+            "app/(".parse::<EventFilter>().unwrap_err(),
+            FilterParseError::BadPattern { offset: 4, .. },
+        ));
+    }
+
+    #[test]
+    fn unnamed_span_filters_match_event_fields() {
+        let filter: EventFilter = "app[{user=bob}]".parse().unwrap();
+        let events = record(|| {
+            tracing::info!(target: "app", user = "bob", "on event");
+            let request = tracing::info_span!(target: "app", "request", user = "bob");
+            request.in_scope(|| tracing::info!(target: "app", "on span"));
+            tracing::info!(target: "app", user = "alice", "someone else");
+            tracing::info!(target: "app", username = "bob", "other field");
+        });
+        let included: Vec<_> = events.iter().map(|event| filter.includes(event)).collect();
+        assert_eq!(included, [true, true, false, false]);
+    }
+
+    #[test]
+    fn nearest_field_shadows_those_of_ancestors() {
+        let events = record(|| {
+            let outer = tracing::info_span!(target: "app", "outer", id = 1, user = "bob");
+            let inner = tracing::info_span!(target: "app", parent: &outer, "inner", id = 2);
+            inner.in_scope(|| tracing::info!(target: "app", id = 3, "on all three"));
+            inner.in_scope(|| tracing::info!(target: "app", "on both spans"));
+            outer.in_scope(|| tracing::info!(target: "app", "on outer"));
+            outer.in_scope(|| tracing::info!(target: "app", id = 3, "on event and outer"));
+        });
+        let included = |filter: &str| -> Vec<_> {
+            let filter: EventFilter = filter.parse().unwrap();
+            events.iter().map(|event| filter.includes(event)).collect()
+        };
+
+        assert_eq!(included("app[{id=3}]"), [true, false, false, true]);
+        assert_eq!(included("app[{id=2}]"), [false, true, false, false]);
+        assert_eq!(included("app[{id=1}]"), [false, false, true, false]);
+        // each field is the nearest by its own name
+        assert_eq!(
+            included("app[{id=2,user=bob}]"),
+            [false, true, false, false]
+        );
+        // named spans still see their own fields
+        assert_eq!(included("app[outer{id=1}]"), [true, true, true, true]);
+        // `@` fields must be on the event
+        assert_eq!(included("app[{@id}]"), [true, false, false, true]);
+        assert_eq!(included("app[{@id=3}]"), [true, false, false, true]);
+        assert_eq!(included("app[{@user}]"), [false; 4]);
+        assert_eq!(
+            included("app[{@id=3,user=bob}]"),
+            [true, false, false, true]
+        );
+    }
+
+    #[test]
+    fn built_filters_match_parsed_ones() {
+        let events = record(|| {
+            let request = tracing::info_span!(target: "app", "request", user = "bob", id = 1);
+            request.in_scope(|| {
+                tracing::debug!(target: "app::db", id = 2, "query 1");
+                tracing::trace!(target: "app::db", "query 2");
+                tracing::warn!(target: "hyper", "slow");
             });
-        }
+            tracing::info!(target: "app", user = "alice", "request 3");
+            tracing::error!(target: "hyper", "failed 500");
+            tracing::info!(target: "other", "started");
+        });
+        let check = |text: &str, built: EventFilterBuilder| {
+            let parsed: EventFilter = text.parse().unwrap();
+            let built = built.finish().unwrap();
+            let parsed: Vec<_> = events.iter().map(|event| parsed.includes(event)).collect();
+            let built: Vec<_> = events.iter().map(|event| built.includes(event)).collect();
+            assert_eq!(built, parsed, "{}", text);
+        };
+        let builder = EventFilter::builder;
 
-        let target = caps.name("target").and_then(|c| {
-            let s = c.as_str();
-            if s.parse::<Level>().is_ok() {
-                None
-            } else {
-                Some(s.into())
+        check("", builder());
+        check("warn", builder().level(LevelFilter::WARN));
+        check(
+            "app[request{user=bob}]=debug",
+            builder()
+                .target("app")
+                .span("request")
+                .field("user", Some("bob"))
+                .level(LevelFilter::DEBUG),
+        );
+        check(
+            "info,!hyper=error",
+            builder()
+                .level(LevelFilter::INFO)
+                .push()
+                .target("hyper")
+                .level(LevelFilter::ERROR)
+                .negate(),
+        );
+        check(
+            "[request,{id=2}]",
+            builder().span("request").span("").field("id", Some("2")),
+        );
+        // fields without a span go in an unnamed one
+        check("app[{user}]", builder().target("app").field("user", None));
+        check(
+            "[{@id=^2$}],other",
+            builder()
+                .event_field("id", Some("^2$"))
+                .push()
+                .target("other"),
+        );
+        check(
+            "[request{user=bob},{@id}]",
+            builder()
+                .span("request")
+                .field("user", Some("bob"))
+                .event_field("id", None),
+        );
+        check(
+            "app,hyper/\\d+$",
+            builder()
+                .target("app")
+                .push()
+                .target("hyper")
+                .message(r"\d+$"),
+        );
+        // an empty directive is kept, as parsing keeps it
+        check(
+            "app=warn,,",
+            builder()
+                .target("app")
+                .level(LevelFilter::WARN)
+                .push()
+                .level(LevelFilter::TRACE),
+        );
+    }
+
+    #[test]
+    fn builder_reports_bad_patterns() {
+        let result = EventFilter::builder()
+            .span("")
+            .field("x", Some("("))
+            .message(")")
+            .finish();
+        let error = result.unwrap_err();
+        assert!(error.to_string().contains("unclosed group"), "{}", error);
+        assert!(EventFilter::builder().message("(").finish().is_err());
+        // nothing given is the empty filter
+        let empty = EventFilter::builder().push().push().finish().unwrap();
+        assert!(empty.directives.is_empty());
+    }
+
+    #[test]
+    fn field_values_are_patterns() {
+        let events = record(|| {
+            for status in &[200u64, 404, 500, 503, 1500] {
+                tracing::info!(status, "response");
             }
+            tracing::info!(status = "5xx", "not a number");
         });
+        let statuses = |filter: &str| -> Vec<_> {
+            let filter: EventFilter = filter.parse().unwrap();
+            let events = events.iter().filter(|event| filter.includes(event));
+            events
+                .map(|event| event.field("status").unwrap().to_string())
+                .collect()
+        };
 
-        let (span, field) = caps
-            .name("span")
-            .map(|cap| {
-                let caps = SPAN_PART_RE.captures(cap.as_str()).ok_or(())?;
-                let span = caps.name("name").map(|c| c.as_str().into());
-                let field = caps
-                    .name("fields")
-                    .map(|cap| {
-                        let caps = FIELD_PART_RE.captures(cap.as_str()).ok_or(())?;
-                        let name = caps.name("name").unwrap().as_str().into();
-                        let value = caps.name("value").map(|c| c.as_str().into());
-                        Ok(FieldDirective { name, value })
-                    })
-                    .transpose()?;
-                Ok((span, field))
-            })
-            .transpose()?
-            .unwrap_or((None, None));
+        assert_eq!(statuses(r"[{status=5\d\d}]"), ["500", "503", "1500"]);
+        assert_eq!(statuses(r"[{status=^5\d\d$}]"), ["500", "503"]);
+        // plain text still matches by containing it, in the value's `Debug` text
+        assert_eq!(statuses(r#"[{status=5xx}]"#), ["5xx"]);
+        assert_eq!(statuses(r#"[{status="^\"5"}]"#), ["5xx"]);
+    }
 
-        let level = caps
-            .name("level")
-            .map(|l| l.as_str().parse().map_err(drop))
-            .transpose()?
-            // Setting the target without the level enables every level for that target
-            .unwrap_or(LevelFilter::TRACE);
+    #[test]
+    fn slash_wrapped_field_values() {
+        let events = record(|| {
+            for path in &["/api/v1/items", "/api/v3/items", "/login"] {
+                tracing::info!(path, "request");
+            }
+        });
+        let paths = |filter: &str| -> Vec<_> {
+            let filter: EventFilter = filter.parse().unwrap();
+            let events = events.iter().filter(|event| filter.includes(event));
+            events
+                .map(|event| event.field("path").unwrap().to_string())
+                .collect()
+        };
 
-        Ok(Directive {
-            target,
-            span,
-            field,
-            level,
-        })
+        assert_eq!(paths(r#"[{path="/api\/v[12]/"}]"#), ["/api/v1/items"]);
+        // unquoted values can't have slashes or brackets in them
+        assert!(r"[{path=/api\/v[12]/}]".parse::<EventFilter>().is_err());
+        assert_eq!(paths(r#"[{path="/^\"\/login/"}]"#), ["/login"]);
+        // only slashes on both ends are left out
+        assert_eq!(
+            paths(r#"[{path="/api"}]"#),
+            ["/api/v1/items", "/api/v3/items"]
+        );
+        assert!(matches!(
+            r#"[{path="/(/"}]"#.parse::<EventFilter>().unwrap_err(),
+            FilterParseError::BadPattern { offset: 8, .. },
+        ));
+    }
+
+    #[test]
+    fn negated_directives() {
+        let events = record(|| {
+            tracing::info!(target: "app", "app info");
+            tracing::debug!(target: "app", "app debug");
+            tracing::info!(target: "hyper", "hyper info");
+            tracing::warn!(target: "hyper", "hyper warn");
+            let request = tracing::info_span!(target: "app", "request", user = "bob");
+            request.in_scope(|| tracing::info!(target: "app", "bob's request"));
+        });
+        let included = |filter: &str| -> Vec<_> {
+            let filter: EventFilter = filter.parse().unwrap();
+            events.iter().map(|event| filter.includes(event)).collect()
+        };
+
+        assert_eq!(included("!hyper"), [true, true, false, false, true]);
+        assert_eq!(included("info,!hyper"), [true, false, false, false, true]);
+        assert_eq!(included("!hyper=warn"), [true, true, true, false, true]);
+        // negations win whatever the order
+        assert_eq!(
+            included("!hyper, hyper"),
+            [false, false, false, false, false]
+        );
+        assert_eq!(
+            included("!app[request{user=bob}]"),
+            [true, true, true, true, false]
+        );
+        assert_eq!(included("!app,!hyper"), [false, false, false, false, false]);
+        assert_eq!(included("!app/hyper"), [false, false, true, true, false]);
+
+        let filter: EventFilter = "!hyper".parse().unwrap();
+        assert_eq!(filter.directives[0].target.as_deref(), Some("hyper"));
+        // a `!` elsewhere is not a negation
+        assert!("app!".parse::<EventFilter>().is_err());
+        let filter: EventFilter = r#"app[{x="!a,!b"}]/!c"#.parse().unwrap();
+        assert!(!filter.directives[0].negated);
+        assert_eq!(filter.message.as_ref().map(Regex::as_str), Some("!c"));
+    }
+
+    #[test]
+    fn filter_cache_parses_on_change() {
+        let mut cache = FilterCache::default();
+        let mut parsed = Vec::new();
+        let mut parse = |cache: &mut FilterCache, text: &str| {
+            let result = cache.parse_with(text, |text| {
+                parsed.push(text.to_owned());
+                text.parse()
+            });
+            result.is_ok()
+        };
+
+        assert!(parse(&mut cache, ""));
+        assert!(parse(&mut cache, "app"));
+        assert!(parse(&mut cache, "app"));
+        assert!(parse(&mut cache, "app,lib"));
+        assert!(!parse(&mut cache, "app["));
+        assert!(!parse(&mut cache, "app["));
+        assert!(parse(&mut cache, "app"));
+        assert_eq!(parsed, ["app", "app,lib", "app[", "app"]);
+        assert_eq!(cache.parse("app,lib").unwrap().directives.len(), 2);
     }
 }
@@ -0,0 +1,217 @@
+use std::{collections::BTreeMap, sync::Arc};
+use tracing::Level;
+use tracing_memory::Event;
+
+/// How the [`Widget`](crate::Widget)'s [tree](crate::Layout::Tree) layout
+/// groups events, under a collapsing header per group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GroupBy {
+    /// By [effective target](Event::effective_target), in order of name.
+    Target,
+    /// By level, from `ERROR` to `TRACE`.
+    Level,
+    /// By the name of the outermost span each event was recorded in, with
+    /// the events outside of any span last.
+    RootSpan,
+}
+
+impl GroupBy {
+    /// The name of this grouping, for choosing it.
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            GroupBy::Target => "Target",
+            GroupBy::Level => "Level",
+            GroupBy::RootSpan => "Root span",
+        }
+    }
+}
+
+/// Events in one group.
+#[derive(Debug, Clone)]
+pub(crate) struct Group {
+    pub(crate) name: String,
+    /// The indices of the events, oldest first.
+    pub(crate) events: Vec<usize>,
+    /// The most severe level of the events.
+    pub(crate) max_level: Level,
+}
+
+/// The shown events in groups, kept between frames so that they are only
+/// regrouped when the events or which of them are shown change.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct GroupCache {
+    /// The grouping, the number of events and the sequence number of the
+    /// last one, and a hash of what decides which events are shown.
+    key: Option<(GroupBy, usize, u64, u64)>,
+    groups: Vec<Group>,
+}
+
+impl GroupCache {
+    /// The events for which `include` returns true, grouped by `group_by`.
+    ///
+    /// `shown` should change whenever `include` could return something else.
+    pub(crate) fn groups(
+        &mut self,
+        group_by: GroupBy,
+        events: &[Arc<Event>],
+        shown: u64,
+        include: impl FnMut(usize, &Event) -> bool,
+    ) -> &[Group] {
+        let last = events.last().map_or(0, |event| event.seq());
+        let key = Some((group_by, events.len(), last, shown));
+        if self.key != key {
+            self.key = key;
+            self.groups = group(group_by, events, include);
+        }
+        &self.groups
+    }
+}
+
+/// Group the events for which `include` returns true by `group_by`.
+pub(crate) fn group(
+    group_by: GroupBy,
+    events: &[Arc<Event>],
+    mut include: impl FnMut(usize, &Event) -> bool,
+) -> Vec<Group> {
+    // ordered by level (more verbose levels compare greater), or by whether
+    // the events are outside of any span and then by name
+    let mut groups: BTreeMap<(Level, bool, String), Group> = BTreeMap::new();
+    for (event_ix, event) in events.iter().enumerate() {
+        if !include(event_ix, event) {
+            continue;
+        }
+        let level = *event.meta().level();
+        let (order, name) = match group_by {
+            GroupBy::Target => {
+                let target = event.effective_target();
+                ((Level::TRACE, false, target.into()), target.into())
+            }
+            GroupBy::Level => ((level, false, String::new()), level.to_string()),
+            GroupBy::RootSpan => {
+                let root = std::iter::successors(event.span(), |span| span.parent()).last();
+                match root {
+                    Some(root) => {
+                        let name = root.meta().name();
+                        ((Level::TRACE, false, name.into()), name.into())
+                    }
+                    None => ((Level::TRACE, true, String::new()), "(no span)".into()),
+                }
+            }
+        };
+        let group = groups.entry(order).or_insert_with(|| Group {
+            name,
+            events: Vec::new(),
+            max_level: level,
+        });
+        group.events.push(event_ix);
+        group.max_level = group.max_level.min(level);
+    }
+    groups.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_memory::EventStore;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn events_are_grouped_by_target_level_and_root_span() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "db", "outside");
+            tracing::info_span!("request").in_scope(|| {
+                tracing::debug!(target: "app", "in request");
+                tracing::info_span!("query").in_scope(|| tracing::error!(target: "db", "failed"));
+            });
+            tracing::info_span!("job").in_scope(|| tracing::warn!(target: "app", "in job"));
+        });
+        let events = store.with_events(|events| events.clone());
+
+        let summary = |groups: Vec<Group>| -> Vec<(String, Vec<usize>, Level)> {
+            (groups.into_iter())
+                .map(|group| (group.name, group.events, group.max_level))
+                .collect()
+        };
+        let all = |_, _: &Event| true;
+        assert_eq!(
+            summary(group(GroupBy::Target, &events, all)),
+            [
+                ("app".into(), vec![1, 3], Level::WARN),
+                ("db".into(), vec![0, 2], Level::ERROR),
+            ]
+        );
+        assert_eq!(
+            summary(group(GroupBy::Level, &events, all)),
+            [
+                ("ERROR".into(), vec![2], Level::ERROR),
+                ("WARN".into(), vec![3], Level::WARN),
+                ("INFO".into(), vec![0], Level::INFO),
+                ("DEBUG".into(), vec![1], Level::DEBUG),
+            ]
+        );
+        assert_eq!(
+            summary(group(GroupBy::RootSpan, &events, all)),
+            [
+                ("job".into(), vec![3], Level::WARN),
+                ("request".into(), vec![1, 2], Level::ERROR),
+                ("(no span)".into(), vec![0], Level::INFO),
+            ]
+        );
+
+        // only included events are grouped, and only regrouped when needed
+        let mut cache = GroupCache::default();
+        let calls = std::cell::Cell::new(0);
+        let mut groups = |shown| {
+            let include = |event_ix, _: &Event| {
+                calls.set(calls.get() + 1);
+                event_ix != 2
+            };
+            summary(
+                cache
+                    .groups(GroupBy::Target, &events, shown, include)
+                    .to_vec(),
+            )
+        };
+        let first = groups(0);
+        assert_eq!(first[1], ("db".into(), vec![0], Level::INFO));
+        assert_eq!(groups(0), first);
+        assert_eq!(calls.get(), 4);
+        groups(1);
+        assert_eq!(calls.get(), 8);
+    }
+
+    #[test]
+    fn events_read_again_are_not_regrouped() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app", "first");
+            tracing::info!(target: "db", "second");
+        });
+        // compressed events are decompressed into new `Arc`s on every read
+        store.set_compression(Some(tracing_memory::Compression {
+            older_than: None,
+            keep_newest: Some(0),
+            chunk_len: 1,
+            cached_chunks: 0,
+            ..Default::default()
+        }));
+        let (events, again) = (store.snapshot(), store.snapshot());
+        assert!(!Arc::ptr_eq(&events[1], &again[1]));
+
+        let mut cache = GroupCache::default();
+        let calls = std::cell::Cell::new(0);
+        let include = |_, _: &Event| {
+            calls.set(calls.get() + 1);
+            true
+        };
+        cache.groups(GroupBy::Target, &events, 0, include);
+        cache.groups(GroupBy::Target, &again, 0, include);
+        assert_eq!(calls.get(), 2);
+    }
+}
@@ -1,6 +1,23 @@
+mod complete;
+mod export;
 mod filter;
+mod group;
+mod rows;
+mod search;
+mod settings;
+mod span_tree;
+mod time;
+mod time_range;
 mod widget;
 
+pub use crate::{
+    export::format_event,
+    filter::{EventFilter, EventFilterBuilder, FilterParseError},
+    group::GroupBy,
+    settings::{WidgetSettings, WithSettings},
+    span_tree::SpanTreeWidget,
+    time::TimestampFormat,
+    widget::{DetailPane, EventSource, HeaderText, Layout, LevelColors, Style, Widget},
+};
 #[doc(no_inline)]
 pub use tracing_memory::layer as memory_layer;
-pub use widget::Widget;
@@ -0,0 +1,168 @@
+use std::{collections::HashMap, ops::Range};
+
+/// The heights of the rows of the [tree](crate::Layout::Tree) layout, kept
+/// between frames so that only the rows in view need to be laid out.
+///
+/// Collapsed rows all take the same height, so only the heights of expanded
+/// rows are kept, by [sequence number](tracing_memory::Event::seq). Each
+/// height includes the spacing below the row.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RowHeights {
+    /// The height of a collapsed row, once one has been laid out.
+    collapsed: Option<f32>,
+    expanded: HashMap<u64, f32>,
+}
+
+impl RowHeights {
+    /// The height of the row of the event with sequence number `seq`, or
+    /// `estimate` if no collapsed row has been laid out yet.
+    pub(crate) fn height(&self, seq: u64, estimate: f32) -> f32 {
+        match self.expanded.get(&seq) {
+            Some(&height) => height,
+            None => self.collapsed.unwrap_or(estimate),
+        }
+    }
+
+    /// Note the `height` of the row of the event with sequence number `seq`,
+    /// as just laid out.
+    pub(crate) fn measured(&mut self, seq: u64, height: f32, expanded: bool) {
+        if expanded {
+            self.expanded.insert(seq, height);
+        } else {
+            self.expanded.remove(&seq);
+            self.collapsed = Some(height);
+        }
+    }
+}
+
+/// The rows to lay out of those with `heights`, stacked from 0, to fill the
+/// view from `top` to `bottom`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct VisibleRows {
+    pub(crate) range: Range<usize>,
+    /// The height of the rows before `range`, to leave space for.
+    pub(crate) before: f32,
+    /// The height of the rows after `range`, to leave space for.
+    pub(crate) after: f32,
+}
+
+/// The rows of those with `heights` that are at least partly between `top`
+/// and `bottom`.
+///
+/// This is a pass over the heights alone, so laying out only these rows
+/// takes time in proportion to how many fit in the view rather than to how
+/// many there are.
+pub(crate) fn visible_rows(heights: &[f32], top: f32, bottom: f32) -> VisibleRows {
+    let (mut start, mut before) = (0, 0.0);
+    while start < heights.len() && before + heights[start] <= top {
+        before += heights[start];
+        start += 1;
+    }
+    let (mut end, mut filled) = (start, before);
+    while end < heights.len() && filled < bottom {
+        filled += heights[end];
+        end += 1;
+    }
+    VisibleRows {
+        range: start..end,
+        before,
+        after: heights[end..].iter().sum(),
+    }
+}
+
+/// Like [`visible_rows`], for `count` rows that are all `height` tall, without
+/// a pass over their heights.
+///
+/// The range starts at an even row, so that striped rows keep their stripes
+/// as they are scrolled.
+pub(crate) fn uniform_rows(count: usize, height: f32, top: f32, bottom: f32) -> VisibleRows {
+    let start = ((top / height) as usize).min(count) & !1;
+    let end = ((bottom / height).ceil() as usize).clamp(start, count);
+    VisibleRows {
+        range: start..end,
+        before: start as f32 * height,
+        after: (count - end) as f32 * height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_rows_in_view_are_visible() {
+        let rows = |range, before, after| VisibleRows {
+            range,
+            before,
+            after,
+        };
+        let uniform = [10.0; 1000];
+        assert_eq!(visible_rows(&uniform, 0.0, 95.0), rows(0..10, 0.0, 9900.0));
+        assert_eq!(
+            visible_rows(&uniform, 100.0, 200.0),
+            rows(10..20, 100.0, 9800.0)
+        );
+        // partly visible rows at either end are laid out too
+        assert_eq!(
+            visible_rows(&uniform, 105.0, 201.0),
+            rows(10..21, 100.0, 9790.0)
+        );
+        // scrolled past the end, as when events were cleared
+        assert_eq!(
+            visible_rows(&uniform, 20_000.0, 20_100.0),
+            rows(1000..1000, 10_000.0, 0.0)
+        );
+        assert_eq!(visible_rows(&[], 0.0, 100.0), rows(0..0, 0.0, 0.0));
+
+        let mut expanded = [10.0; 100];
+        expanded[1] = 200.0;
+        assert_eq!(visible_rows(&expanded, 0.0, 100.0), rows(0..2, 0.0, 980.0));
+        assert_eq!(
+            visible_rows(&expanded, 150.0, 250.0),
+            rows(1..6, 10.0, 940.0)
+        );
+    }
+
+    #[test]
+    fn uniform_rows_are_found_without_their_heights() {
+        let uniform = [10.0; 1000];
+        for &(top, bottom) in &[
+            (0.0, 95.0),
+            (100.0, 200.0),
+            (120.0, 201.0),
+            (20_000.0, 20_100.0),
+        ] {
+            assert_eq!(
+                uniform_rows(uniform.len(), 10.0, top, bottom),
+                visible_rows(&uniform, top, bottom)
+            );
+        }
+        // from the even row before, to keep the stripes in place
+        assert_eq!(
+            uniform_rows(1000, 10.0, 110.0, 200.0),
+            VisibleRows {
+                range: 10..20,
+                before: 100.0,
+                after: 9800.0,
+            }
+        );
+        assert_eq!(uniform_rows(0, 10.0, 0.0, 100.0).range, 0..0);
+    }
+
+    #[test]
+    fn only_expanded_heights_are_kept() {
+        let mut heights = RowHeights::default();
+        assert_eq!(heights.height(1, 20.0), 20.0);
+        heights.measured(1, 22.0, false);
+        heights.measured(2, 100.0, true);
+        assert_eq!(
+            (heights.height(1, 20.0), heights.height(3, 20.0)),
+            (22.0, 22.0)
+        );
+        assert_eq!(heights.height(2, 20.0), 100.0);
+
+        heights.measured(2, 22.0, false);
+        assert_eq!(heights.height(2, 20.0), 22.0);
+        assert!(heights.expanded.is_empty());
+    }
+}
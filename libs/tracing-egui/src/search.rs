@@ -0,0 +1,184 @@
+use std::sync::Arc;
+use tracing_memory::Event;
+
+/// Where a search matched an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Match {
+    None,
+    /// In the message, shown in the event's header.
+    Message,
+    /// In the target or another field.
+    Other,
+}
+
+/// Which events match a search, kept between frames so that each event is
+/// only formatted and searched once per search string.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SearchCache {
+    /// The lowercased search string the matches are for.
+    query: String,
+    matches: Vec<Match>,
+    /// The sequence number of the last event searched, to notice the events
+    /// changing.
+    last: u64,
+}
+
+impl SearchCache {
+    /// Where `query` matches each of `events`, ignoring case.
+    pub fn matches(&mut self, query: &str, events: &[Arc<Event>]) -> &[Match] {
+        let query = query.to_lowercase();
+        let searched = match self.matches.len() {
+            0 => None,
+            len => events.get(len - 1),
+        };
+        // events are only ever appended, unless they were cleared or removed
+        if query != self.query || searched.map(|event| event.seq()) != Some(self.last) {
+            self.query = query;
+            self.matches.clear();
+        }
+
+        let query = &self.query;
+        let new = events[self.matches.len()..]
+            .iter()
+            .map(|event| search(query, event));
+        self.matches.extend(new);
+        self.last = events.last().map_or(0, |event| event.seq());
+        &self.matches
+    }
+}
+
+/// Search the rendered message, target, and field values of `event`.
+fn search(query: &str, event: &Event) -> Match {
+    let contains = |text: String| text.to_lowercase().contains(query);
+    if let Some(message) = event.message() {
+        if contains(message.to_string()) {
+            return Match::Message;
+        }
+    }
+    let in_fields = event
+        .fields()
+        .filter(|&(name, _)| name != "message")
+        .any(|(_, value)| {
+            let mut found = value.with_debug(|value| contains(format!("{:?}", value)));
+            found.any(std::convert::identity)
+        });
+    if in_fields || contains(event.effective_target().into()) {
+        Match::Other
+    } else {
+        Match::None
+    }
+}
+
+/// Split `text` into the parts that match `query`, ignoring case, and those
+/// that don't, in order. Overlapping matches are merged into one part.
+///
+/// Each part is paired with whether it matched. An empty query matches nothing.
+pub(crate) fn highlights<'a>(text: &'a str, query: &str) -> Vec<(&'a str, bool)> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let mut parts = Vec::new();
+    if query.is_empty() {
+        parts.push((text, false));
+        return parts;
+    }
+
+    // the end of the match being extended, and where the unmatched text starts
+    let mut matched: Option<(usize, usize)> = None;
+    let mut plain = 0;
+    for (i, _) in text.char_indices() {
+        if let Some((start, end)) = matched {
+            if end <= i {
+                parts.push((&text[start..end], true));
+                matched = None;
+                plain = end;
+            }
+        }
+        if let Some(len) = match_len(&text[i..], &query) {
+            matched = match matched {
+                Some((start, end)) => Some((start, end.max(i + len))),
+                None => {
+                    if plain < i {
+                        parts.push((&text[plain..i], false));
+                    }
+                    Some((i, i + len))
+                }
+            };
+        }
+    }
+    if let Some((start, end)) = matched {
+        parts.push((&text[start..end], true));
+        plain = end;
+    }
+    if plain < text.len() {
+        parts.push((&text[plain..], false));
+    }
+    parts
+}
+
+/// How many bytes at the start of `text` match the lowercased `query`.
+fn match_len(text: &str, query: &[char]) -> Option<usize> {
+    let mut query = query.iter();
+    for (i, c) in text.char_indices() {
+        for lower in c.to_lowercase() {
+            if query.next() != Some(&lower) {
+                return None;
+            }
+        }
+        if query.len() == 0 {
+            return Some(i + c.len_utf8());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_memory::{EventStore, Layer};
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn searches_messages_targets_and_fields() {
+        let store = Arc::new(EventStore::new());
+        let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app::net", "Connection TIMEOUT");
+            tracing::info!(target: "app::net", error = ?"timeout", "failed");
+            tracing::info!(target: "timeouts", "retrying");
+            tracing::info!(target: "app", "unrelated");
+        });
+        let events = store.with_events(|events| events.clone());
+
+        let mut cache = SearchCache::default();
+        let expected = [Match::Message, Match::Other, Match::Other, Match::None];
+        assert_eq!(cache.matches("Timeout", &events), expected);
+        assert_eq!(cache.matches("timeout", &events[..2]), &expected[..2]);
+        assert_eq!(cache.matches("timeout", &events), expected);
+        assert_eq!(cache.matches("unrelated", &events)[3], Match::Message);
+        assert_eq!(cache.matches("unrelated", &events[1..])[2], Match::Message);
+    }
+
+    #[test]
+    fn highlights_matches_ignoring_case() {
+        assert_eq!(
+            highlights("connection timeout", ""),
+            [("connection timeout", false)]
+        );
+        assert_eq!(highlights("", "timeout"), []);
+        assert_eq!(
+            highlights("Timeout after timeout", "timeout"),
+            [("Timeout", true), (" after ", false), ("timeout", true)],
+        );
+        assert_eq!(
+            highlights("no match here", "timeout"),
+            [("no match here", false)],
+        );
+        // overlapping matches are merged
+        assert_eq!(
+            highlights("xaaaay", "aaa"),
+            [("x", false), ("aaaa", true), ("y", false)]
+        );
+        // adjacent matches are kept apart
+        assert_eq!(highlights("abab", "ab"), [("ab", true), ("ab", true)]);
+        assert_eq!(highlights("GRÜẞE", "grüß"), [("GRÜẞ", true), ("E", false)]);
+    }
+}
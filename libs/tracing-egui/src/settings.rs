@@ -0,0 +1,181 @@
+use crate::{widget::Widget, GroupBy, TimestampFormat};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The parts of a [`Widget`]'s state worth keeping when the app restarts:
+/// the filter, search, and view toggles.
+///
+/// Show a widget with [`Widget::with_settings`] to keep its state here rather
+/// than only in egui's memory. With the `persistence` feature, settings can
+/// be [loaded](Self::load) and [saved](Self::save) through an [`epi::Storage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct WidgetSettings {
+    /// The filter directives, which are shown as invalid rather than
+    /// applied if they don't parse.
+    pub filters: String,
+    pub search: String,
+    /// Show events not matching the search as well.
+    pub show_unmatched: bool,
+    /// Which levels are shown, from `ERROR` to `TRACE`.
+    pub levels: [bool; 5],
+    /// Keep the newest events in view.
+    pub follow: bool,
+    /// The timestamp format chosen in the widget, if one has been.
+    pub timestamp: Option<TimestampFormat>,
+    /// The grouping chosen in the widget, if one has been, which may be no grouping.
+    pub group_by: Option<Option<GroupBy>>,
+    #[doc(hidden)]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub _non_exhaustive_but_allow_fru: (),
+}
+
+impl Default for WidgetSettings {
+    fn default() -> Self {
+        WidgetSettings {
+            filters: String::new(),
+            search: String::new(),
+            show_unmatched: false,
+            levels: [true; 5],
+            follow: false,
+            timestamp: None,
+            group_by: None,
+            _non_exhaustive_but_allow_fru: (),
+        }
+    }
+}
+
+#[cfg(feature = "persistence")]
+impl WidgetSettings {
+    /// The settings [saved](Self::save) under `key`, or the defaults if there
+    /// are none or they can't be read.
+    pub fn load(storage: &dyn epi::Storage, key: &str) -> Self {
+        let saved = storage.get_string(key);
+        saved
+            .and_then(|saved| serde_json::from_str(&saved).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save these settings under `key`, to [load](Self::load) them later.
+    pub fn save(&self, storage: &mut dyn epi::Storage, key: &str) {
+        let saved = serde_json::to_string(self).expect("settings always serialize");
+        storage.set_string(key, saved);
+    }
+}
+
+/// A [`Widget`] keeping its state in [`WidgetSettings`]; see
+/// [`Widget::with_settings`].
+#[derive(Debug)]
+pub struct WithSettings<'a> {
+    widget: Widget,
+    settings: &'a mut WidgetSettings,
+}
+
+impl Widget {
+    /// Show this widget with its filter, search, and view toggles read from
+    /// and written back to `settings`, which the caller keeps.
+    ///
+    /// The rest of the widget's state, like a selected event or a paused
+    /// snapshot, is still kept in egui's memory.
+    pub fn with_settings(self, settings: &mut WidgetSettings) -> WithSettings<'_> {
+        WithSettings {
+            widget: self,
+            settings,
+        }
+    }
+}
+
+impl egui::Widget for WithSettings<'_> {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        self.widget.show(ui, Some(self.settings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tracing_memory::EventStore;
+
+    fn show(settings: &mut WidgetSettings, ctx: &mut egui::CtxRef, store: &Arc<EventStore>) {
+        ctx.begin_frame(Default::default());
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let widget = Widget {
+                store: Some(store.clone()),
+                ..Widget::default()
+            };
+            ui.add(widget.with_settings(settings));
+        });
+        let _ = ctx.end_frame();
+    }
+
+    #[test]
+    fn settings_outlive_the_widget_state() {
+        let store = Arc::new(EventStore::new());
+        let mut settings = WidgetSettings {
+            filters: "app[request{id=".into(),
+            levels: [true, true, true, false, false],
+            ..WidgetSettings::default()
+        };
+
+        // an invalid filter is shown as such, and kept as it was
+        let mut ctx = egui::CtxRef::default();
+        show(&mut settings, &mut ctx, &store);
+        assert_eq!(settings.filters, "app[request{id=");
+        assert_eq!(settings.levels, [true, true, true, false, false]);
+
+        // a new context starts from the settings, not its own memory
+        settings.filters = "app=debug".into();
+        let mut ctx = egui::CtxRef::default();
+        show(&mut settings, &mut ctx, &store);
+        assert_eq!(settings.filters, "app=debug");
+        assert_eq!(settings.levels, [true, true, true, false, false]);
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn settings_round_trip_through_storage() {
+        #[derive(Default)]
+        struct Storage(std::collections::HashMap<String, String>);
+
+        impl epi::Storage for Storage {
+            fn get_string(&self, key: &str) -> Option<String> {
+                self.0.get(key).cloned()
+            }
+
+            fn set_string(&mut self, key: &str, value: String) {
+                self.0.insert(key.into(), value);
+            }
+
+            fn flush(&mut self) {}
+        }
+
+        let mut storage = Storage::default();
+        assert_eq!(
+            WidgetSettings::load(&storage, "log"),
+            WidgetSettings::default()
+        );
+
+        let settings = WidgetSettings {
+            filters: "app=debug,[{id=\"a,b\"}]".into(),
+            search: "timeout".into(),
+            levels: [true, true, false, false, true],
+            timestamp: Some(TimestampFormat::Utc("%F %T".into())),
+            group_by: Some(Some(GroupBy::RootSpan)),
+            ..WidgetSettings::default()
+        };
+        settings.save(&mut storage, "log");
+        assert_eq!(WidgetSettings::load(&storage, "log"), settings);
+        assert_eq!(
+            WidgetSettings::load(&storage, "other"),
+            WidgetSettings::default()
+        );
+
+        // settings that can't be read are replaced by the defaults
+        storage.0.insert("log".into(), "{\"levels\": 5}".into());
+        assert_eq!(
+            WidgetSettings::load(&storage, "log"),
+            WidgetSettings::default()
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{
+    time,
+    widget::{show_fields, show_span_unset, LevelColors, LiveStore, Rendering},
+};
+use std::{collections::HashMap, sync::Arc};
+use tracing_memory::{distinct_spans, Event, EventStore, Span, SpanKey};
+
+/// An egui widget showing the recorded spans as a tree, grouped by root span,
+/// with each event under the span it was recorded in.
+///
+/// Spans show the fields most recently [recorded][tracing::Span::record] on
+/// them, rather than those of any one event's snapshot.
+#[derive(Debug)]
+pub struct SpanTreeWidget {
+    /// Color events by their level, with `colors`.
+    pub level_colors: bool,
+    pub colors: LevelColors,
+    /// The store to show spans from, or the global store if `None`.
+    pub store: Option<Arc<EventStore>>,
+    #[doc(hidden)]
+    pub _non_exhaustive_but_allow_fru: (),
+}
+
+impl Default for SpanTreeWidget {
+    fn default() -> Self {
+        SpanTreeWidget {
+            level_colors: true,
+            colors: LevelColors::default(),
+            store: None,
+            _non_exhaustive_but_allow_fru: (),
+        }
+    }
+}
+
+impl egui::Widget for SpanTreeWidget {
+    fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        let SpanTreeWidget {
+            level_colors,
+            colors,
+            store,
+            _non_exhaustive_but_allow_fru: (),
+        } = self;
+
+        let colors = Some(colors).filter(|_| level_colors);
+        let events = LiveStore(store.as_deref()).snapshot();
+        let rendering = Rendering::start();
+        let tree = Tree::new(&events, |_, _| true);
+        let response = ui
+            .vertical(|ui| {
+                egui::ScrollArea::auto_sized()
+                    .id_source("tracing-egui::SpanTree")
+                    .show(ui, |ui| show_tree(ui, &tree, &events, colors));
+            })
+            .response;
+        drop(rendering);
+        response
+    }
+}
+
+/// The spans and events of a snapshot, arranged by where they are shown.
+pub(crate) struct Tree<'a> {
+    /// The spans without a parent.
+    roots: Vec<&'a Span>,
+    /// The spans directly inside each span.
+    children: HashMap<SpanKey, Vec<&'a Span>>,
+    /// The index of each event recorded directly inside each span.
+    events: HashMap<SpanKey, Vec<usize>>,
+    /// The index of each event recorded outside of any span.
+    pub(crate) outside: Vec<usize>,
+}
+
+impl<'a> Tree<'a> {
+    /// Arrange the `events` for which `include` returns true, given each
+    /// event's index, by the spans they were recorded in.
+    pub(crate) fn new(
+        events: &'a [Arc<Event>],
+        mut include: impl FnMut(usize, &Event) -> bool,
+    ) -> Self {
+        let mut tree = Tree {
+            roots: Vec::new(),
+            children: HashMap::new(),
+            events: HashMap::new(),
+            outside: Vec::new(),
+        };
+        let mut included = Vec::new();
+        for (event_ix, event) in events.iter().enumerate() {
+            if !include(event_ix, event) {
+                continue;
+            }
+            included.push(&**event);
+            match event.span() {
+                Some(span) => tree.events.entry(span.key()).or_default().push(event_ix),
+                None => tree.outside.push(event_ix),
+            }
+        }
+        for span in distinct_spans(included) {
+            match span.parent() {
+                Some(parent) => tree.children.entry(parent.key()).or_default().push(span),
+                None => tree.roots.push(span),
+            }
+        }
+        tree
+    }
+
+    /// Show each root span as a collapsing header, holding its fields, child
+    /// spans, and events, each shown by `show_event` given its index.
+    ///
+    /// Events outside of any span are left for the caller to show.
+    pub(crate) fn show_spans(
+        &self,
+        ui: &mut egui::Ui,
+        newest_first: bool,
+        show_event: &mut dyn FnMut(&mut egui::Ui, usize),
+    ) {
+        for span in ordered(&self.roots, newest_first) {
+            self.show_span(ui, span, newest_first, show_event);
+        }
+    }
+
+    fn show_span(
+        &self,
+        ui: &mut egui::Ui,
+        span: &Span,
+        newest_first: bool,
+        show_event: &mut dyn FnMut(&mut egui::Ui, usize),
+    ) {
+        let key = span.key();
+        let latest = span.latest();
+        let closed = if span.is_closed() { " (closed)" } else { "" };
+        egui::CollapsingHeader::new(format_args!(
+            "{}::{}{}",
+            span.meta().target(),
+            span.meta().name(),
+            closed,
+        ))
+        // by span rather than position; nested in the parent header's id
+        .id_source(key)
+        .text_style(egui::TextStyle::Monospace)
+        .show(ui, |ui| {
+            let latest = latest.as_ref().unwrap_or(span);
+            show_fields(latest.fields())(ui);
+            show_span_unset(ui, latest);
+            let children = self.children.get(&key).map_or(&[][..], Vec::as_slice);
+            for child in ordered(children, newest_first) {
+                self.show_span(ui, child, newest_first, show_event);
+            }
+            let events = self.events.get(&key).map_or(&[][..], Vec::as_slice);
+            for &event_ix in ordered(events, newest_first) {
+                show_event(ui, event_ix);
+            }
+        });
+    }
+}
+
+/// The `items` in order, or newest first.
+fn ordered<T>(items: &[T], newest_first: bool) -> Box<dyn Iterator<Item = &T> + '_> {
+    match newest_first {
+        true => Box::new(items.iter().rev()),
+        false => Box::new(items.iter()),
+    }
+}
+
+fn show_tree(
+    ui: &mut egui::Ui,
+    tree: &Tree<'_>,
+    events: &[Arc<Event>],
+    colors: Option<LevelColors>,
+) {
+    if tree.roots.is_empty() {
+        ui.label("No spans recorded.");
+    }
+    tree.show_spans(ui, false, &mut |ui, event_ix| {
+        show_event_line(ui, &events[event_ix], colors)
+    });
+    if !tree.outside.is_empty() {
+        egui::CollapsingHeader::new(format_args!(
+            "outside of any span ({} events)",
+            tree.outside.len()
+        ))
+        .id_source("outside")
+        .show(ui, |ui| {
+            for &event_ix in &tree.outside {
+                show_event_line(ui, &events[event_ix], colors);
+            }
+        });
+    }
+}
+
+/// Show an event on one line, with its fields after the message.
+fn show_event_line(ui: &mut egui::Ui, event: &Event, colors: Option<LevelColors>) {
+    let mut line = format!(
+        "[{}] [{}]",
+        time::local(event.time()).format("%H:%M:%S%.3f"),
+        event.meta().level(),
+    );
+    if let Some(message) = event.message() {
+        line += &format!(" {}", message);
+    }
+    for (name, value) in event.fields().filter(|&(name, _)| name != "message") {
+        line += &format!(" {}={}", name, value);
+    }
+    match colors.and_then(|colors| colors.get(event.meta().level())) {
+        Some(color) => ui.colored_label(color, line),
+        None => ui.label(line),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn events_are_shown_under_their_span() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside");
+            let root = tracing::info_span!("root", n = tracing::field::Empty);
+            root.in_scope(|| {
+                tracing::info_span!("child").in_scope(|| tracing::info!("in child"));
+                root.record("n", 1);
+                tracing::info!("in root");
+            });
+            tracing::info_span!("other").in_scope(|| tracing::info!("in other"));
+        });
+
+        let events = store.with_events(|events| events.clone());
+        let tree = Tree::new(&events, |_, _| true);
+        let names = |spans: &[&Span]| -> Vec<&str> {
+            spans.iter().map(|span| span.meta().name()).collect()
+        };
+        let messages = |event_ixs: &[usize]| -> Vec<String> {
+            event_ixs
+                .iter()
+                .map(|&event_ix| events[event_ix].message().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(names(&tree.roots), ["root", "other"]);
+        let root = tree.roots[0].key();
+        assert_eq!(names(&tree.children[&root]), ["child"]);
+        assert_eq!(messages(&tree.events[&root]), ["in root"]);
+        let child = tree.children[&root][0].key();
+        assert_eq!(messages(&tree.events[&child]), ["in child"]);
+        assert_eq!(messages(&tree.outside), ["outside"]);
+
+        // only spans holding an included event are kept
+        let only_child = Tree::new(&events, |event_ix, _| event_ix == 1);
+        assert_eq!(names(&only_child.roots), ["root"]);
+        assert_eq!(names(&only_child.children[&root]), ["child"]);
+        assert!(!only_child.events.contains_key(&root));
+        assert_eq!(messages(&only_child.events[&child]), ["in child"]);
+        assert!(only_child.outside.is_empty());
+
+        // and rendering it doesn't touch the store
+        let mut ctx = egui::CtxRef::default();
+        ctx.begin_frame(Default::default());
+        egui::CentralPanel::default().show(&ctx, |ui| {
+            ui.add(SpanTreeWidget {
+                store: Some(store.clone()),
+                ..SpanTreeWidget::default()
+            });
+        });
+        let _ = ctx.end_frame();
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::{DateTime, Local};
+use std::{
+    borrow::Cow,
+    time::{Duration, SystemTime},
+};
+use tracing_memory::{Event, TimeFormat};
+
+/// How the [`Widget`](crate::Widget) shows when each event happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimestampFormat {
+    /// The local time, with a [chrono format string], like `%H:%M:%S%.3f`.
+    ///
+    /// [chrono format string]: https://docs.rs/chrono/0.4/chrono/format/strftime/index.html
+    Absolute(Cow<'static, str>),
+    /// The time in UTC, with a [chrono format string], like `%FT%T%.3fZ`.
+    ///
+    /// [chrono format string]: https://docs.rs/chrono/0.4/chrono/format/strftime/index.html
+    Utc(Cow<'static, str>),
+    /// The time since tracing-memory started keeping time, like `+12.345s`;
+    /// see [`Event::elapsed_since_start`].
+    Elapsed,
+    /// The time since the previous shown event, like `Δ3ms`. The first shown
+    /// event is measured from when tracing-memory started keeping time.
+    Delta,
+    /// No timestamp, in the log or when copied or saved.
+    Hidden,
+}
+
+impl Default for TimestampFormat {
+    fn default() -> Self {
+        TimestampFormat::Absolute(Cow::Borrowed("%H:%M:%S%.3f"))
+    }
+}
+
+impl TimestampFormat {
+    /// How tracing-memory is to format the timestamp of `event`, which was
+    /// shown after `previous`.
+    pub(crate) fn time_format(&self, previous: Option<&Event>) -> TimeFormat {
+        match self {
+            TimestampFormat::Absolute(format) => TimeFormat::Local(format.clone()),
+            TimestampFormat::Utc(format) => TimeFormat::Utc(format.clone()),
+            TimestampFormat::Elapsed => TimeFormat::Elapsed,
+            TimestampFormat::Delta => {
+                TimeFormat::Delta(previous.map_or(Duration::ZERO, Event::elapsed_since_start))
+            }
+            TimestampFormat::Hidden => TimeFormat::Hidden,
+        }
+    }
+
+    /// What to call this format when choosing one.
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            TimestampFormat::Absolute(_) => "Time of day",
+            TimestampFormat::Utc(_) => "Time of day (UTC)",
+            TimestampFormat::Elapsed => "Since start",
+            TimestampFormat::Delta => "Since previous",
+            TimestampFormat::Hidden => "Hidden",
+        }
+    }
+}
+
+/// `time` in the local timezone, to show it.
+///
+/// Event times are [`SystemTime`]s, so that tracing-memory can be built
+/// without chrono; they are only converted where they are formatted.
+pub(crate) fn local(time: SystemTime) -> DateTime<Local> {
+    DateTime::from(time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_times() {
+        use chrono::{TimeZone, Utc};
+        let time = Utc.timestamp_nanos(1_628_946_309_042_000_000);
+        let system = SystemTime::UNIX_EPOCH + Duration::from_nanos(1_628_946_309_042_000_000);
+        assert_eq!(local(system), time);
+    }
+}
@@ -0,0 +1,190 @@
+use std::time::{Duration, SystemTime};
+
+/// Which events the [`Widget`](crate::Widget) shows by when they were fired.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TimeRange {
+    /// Those from the last `within`, or all of them if `None`, up to the
+    /// newest as new ones arrive.
+    Live { within: Option<Duration> },
+    /// Those up to `to`, from `from` or all of them if `None`, fixed as new
+    /// events arrive.
+    Fixed {
+        from: Option<SystemTime>,
+        to: SystemTime,
+    },
+}
+
+impl Default for TimeRange {
+    fn default() -> Self {
+        TimeRange::Live { within: None }
+    }
+}
+
+impl TimeRange {
+    /// The times between which events are shown, as of `now`.
+    pub(crate) fn bounds(self, now: SystemTime) -> TimeBounds {
+        match self {
+            TimeRange::Live { within } => TimeBounds {
+                from: within.and_then(|within| now.checked_sub(within)),
+                to: None,
+            },
+            TimeRange::Fixed { from, to } => TimeBounds { from, to: Some(to) },
+        }
+    }
+
+    pub(crate) fn is_live(self) -> bool {
+        matches!(self, TimeRange::Live { .. })
+    }
+
+    /// This range fixed at or set live from `now`, keeping the same times
+    /// shown for the moment.
+    pub(crate) fn toggle_live(self, now: SystemTime) -> Self {
+        match self {
+            TimeRange::Live { within } => TimeRange::Fixed {
+                from: within.and_then(|within| now.checked_sub(within)),
+                to: now,
+            },
+            TimeRange::Fixed { from, .. } => TimeRange::Live {
+                within: from.map(|from| now.duration_since(from).unwrap_or_default()),
+            },
+        }
+    }
+}
+
+/// The times between which events are shown, from [`TimeRange::bounds`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct TimeBounds {
+    /// The earliest time shown, if any is too early.
+    pub(crate) from: Option<SystemTime>,
+    /// The latest time shown, if any is too late.
+    pub(crate) to: Option<SystemTime>,
+}
+
+impl TimeBounds {
+    pub(crate) fn includes(&self, time: SystemTime) -> bool {
+        self.from.iter().all(|&from| from <= time) && self.to.iter().all(|&to| time <= to)
+    }
+}
+
+/// How long before `now` `time` was, in seconds, or 0 if it is later.
+pub(crate) fn seconds_ago(now: SystemTime, time: SystemTime) -> f64 {
+    now.duration_since(time).unwrap_or_default().as_secs_f64()
+}
+
+/// The time `seconds` before `now`, or `now` if `seconds` isn't positive.
+pub(crate) fn time_ago(now: SystemTime, seconds: f64) -> SystemTime {
+    let ago = Duration::from_secs_f64(seconds.max(0.0));
+    now.checked_sub(ago).unwrap_or(now)
+}
+
+/// How many of `times` fall into each of `bins` equal spans of time from
+/// `start` to `end`. Times outside of those are not counted.
+pub(crate) fn histogram(
+    times: impl Iterator<Item = SystemTime>,
+    start: SystemTime,
+    end: SystemTime,
+    bins: usize,
+) -> Vec<usize> {
+    let mut counts = vec![0; bins];
+    let span = end.duration_since(start).unwrap_or_default().as_secs_f64();
+    if bins == 0 || span <= 0.0 {
+        return counts;
+    }
+    for time in times {
+        let since = match time.duration_since(start) {
+            Ok(since) => since.as_secs_f64(),
+            Err(_) => continue,
+        };
+        if since <= span {
+            let bin = (since / span * bins as f64) as usize;
+            counts[bin.min(bins - 1)] += 1;
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn live_ranges_track_now_and_fixed_ones_dont() {
+        let now = SystemTime::UNIX_EPOCH + 100 * SECOND;
+        let all = TimeRange::default();
+        assert_eq!(all.bounds(now), TimeBounds::default());
+        assert!(all.bounds(now).includes(SystemTime::UNIX_EPOCH));
+
+        let last_30s = TimeRange::Live {
+            within: Some(30 * SECOND),
+        };
+        let bounds = last_30s.bounds(now);
+        assert!(!bounds.includes(now - 31 * SECOND));
+        assert!(bounds.includes(now - 30 * SECOND));
+        assert!(bounds.includes(now + SECOND));
+
+        let fixed = last_30s.toggle_live(now);
+        assert_eq!(
+            fixed,
+            TimeRange::Fixed {
+                from: Some(now - 30 * SECOND),
+                to: now,
+            }
+        );
+        assert!(!fixed.is_live());
+        let later = now + 10 * SECOND;
+        assert_eq!(fixed.bounds(later), bounds_between(now - 30 * SECOND, now));
+        assert!(!fixed.bounds(later).includes(now + SECOND));
+
+        // back to live, from the same earliest time
+        let live = fixed.toggle_live(later);
+        assert_eq!(
+            live,
+            TimeRange::Live {
+                within: Some(40 * SECOND),
+            }
+        );
+        assert_eq!(all.toggle_live(now).toggle_live(later), all);
+    }
+
+    fn bounds_between(from: SystemTime, to: SystemTime) -> TimeBounds {
+        TimeBounds {
+            from: Some(from),
+            to: Some(to),
+        }
+    }
+
+    #[test]
+    fn seconds_relative_to_now() {
+        let now = SystemTime::UNIX_EPOCH + 100 * SECOND;
+        assert_eq!(seconds_ago(now, now - 30 * SECOND), 30.0);
+        assert_eq!(seconds_ago(now, now + SECOND), 0.0);
+        assert_eq!(time_ago(now, 30.0), now - 30 * SECOND);
+        assert_eq!(time_ago(now, -1.0), now);
+    }
+
+    #[test]
+    fn events_are_counted_per_bin() {
+        let start = SystemTime::UNIX_EPOCH + 100 * SECOND;
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let times = [
+            at(0),
+            at(100),
+            at(900),
+            at(1500),
+            at(2999),
+            at(3000),
+            at(3001),
+        ];
+        let counts = histogram(times.iter().copied(), start, at(3000), 3);
+        assert_eq!(counts, [3, 1, 2]);
+        let before = start - SECOND;
+        assert_eq!(
+            histogram([before].iter().copied(), start, at(3000), 3),
+            [0; 3]
+        );
+        assert_eq!(histogram(times.iter().copied(), start, start, 3), [0; 3]);
+        assert!(histogram(times.iter().copied(), start, at(3000), 0).is_empty());
+    }
+}
@@ -1,9 +1,199 @@
-use crate::filter::EventFilter;
-use tracing_memory::{with_events, Event, Field};
+use crate::{
+    complete::{self, Known},
+    export::{format_event_at, SUMMARY},
+    filter::{EventFilter, FilterCache},
+    group::{Group, GroupBy, GroupCache},
+    rows::{uniform_rows, visible_rows, RowHeights},
+    search::{self, Match, SearchCache},
+    settings::WidgetSettings,
+    span_tree::Tree,
+    time::{self, TimestampFormat},
+    time_range::{histogram, seconds_ago, time_ago, TimeBounds, TimeRange},
+};
+use std::{
+    cell::Cell,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::RangeInclusive,
+    sync::{Arc, PoisonError, RwLock},
+    time::{Duration, SystemTime},
+};
+use tracing::Level;
+use tracing_memory::{
+    clear_events, dropped_by_filter, is_recording, set_recording, snapshot, with_events_since,
+    Event, EventSnapshot, EventStore, Field, RecordFilter, Span, SpanKey, TimeFormat,
+};
+
+/// Called with the file and line of a clicked source location.
+type OnSourceClick = dyn Fn(&str, u32);
+
+/// Called with an event to get its one-line summary.
+type HeaderFormatter = dyn Fn(&Event) -> HeaderText;
+
+/// An event's one-line summary, as returned by a `header_formatter`, with
+/// the styling it is shown with.
+///
+/// Styling left unset is taken from the widget's [`Style`], e.g. the color of
+/// the event's level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderText {
+    text: String,
+    color: Option<egui::Color32>,
+    text_style: Option<egui::TextStyle>,
+}
+
+impl HeaderText {
+    pub fn new(text: impl ToString) -> Self {
+        HeaderText {
+            text: text.to_string(),
+            color: None,
+            text_style: None,
+        }
+    }
+
+    /// Show the text in `color`, in place of the color of the event's level.
+    /// Events matching the search are still shown in the search color.
+    pub fn color(mut self, color: egui::Color32) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Show the text in `text_style`, in place of the one the style picks.
+    pub fn text_style(mut self, text_style: egui::TextStyle) -> Self {
+        self.text_style = Some(text_style);
+        self
+    }
+
+    pub fn monospace(self) -> Self {
+        self.text_style(egui::TextStyle::Monospace)
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl From<String> for HeaderText {
+    fn from(text: String) -> Self {
+        HeaderText::new(text)
+    }
+}
+
+impl From<&str> for HeaderText {
+    fn from(text: &str) -> Self {
+        HeaderText::new(text)
+    }
+}
+
+/// How the [`Widget`] lays out the events it shows.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// A collapsing header per event, expanding to its details.
+    #[default]
+    Tree,
+    /// A table with a row per event; clicking a row's message shows its
+    /// details below the table.
+    Table,
+}
+
+/// Where the [`Widget`] shows the details of the selected event.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DetailPane {
+    /// Nowhere, besides the details the [table](Layout::Table) shows below itself.
+    #[default]
+    Hidden,
+    /// Below the events.
+    Bottom,
+    /// To the right of the events.
+    Right,
+}
+
+/// Which events the [`Widget`] shows.
+#[derive(Debug, Default, Clone)]
+pub enum EventSource {
+    /// Those recorded in the widget's `store`, as they are recorded.
+    #[default]
+    Live,
+    /// These events, e.g. a subset of another snapshot or those of an
+    /// earlier run. Nothing is paused or cleared, as they never change.
+    Snapshot(EventSnapshot),
+}
 
-#[derive(Debug)]
 pub struct Widget {
     pub filter: bool,
+    /// Show a box searching event messages, targets, and field values.
+    /// Matches are highlighted in event details; other events are hidden
+    /// unless "Show non-matching" is checked.
+    pub search: bool,
+    /// Color events by their level, with the style's `colors`.
+    pub level_colors: bool,
+    pub style: Style,
+    /// Called with an event to get its one-line summary, in place of its
+    /// level and message, for its header in the [tree](Layout::Tree) layout
+    /// or its message cell in the [table](Layout::Table). The timestamp and
+    /// repeat count are still added, and styling the summary leaves unset
+    /// taken from the `style`, e.g. coloring it by the event's level.
+    pub header_formatter: Option<Box<HeaderFormatter>>,
+    pub level_toggles: bool,
+    /// Show a strip at the top counting the events at each level that the
+    /// other filters let through, where clicking a count shows or hides the
+    /// level, with buttons jumping between the shown errors.
+    pub level_counts: bool,
+    /// Show a button copying the shown events to the clipboard as text,
+    /// and a "Save log" button if `on_save` is set. Right-clicking an event
+    /// then copies just that event, formatted the same way.
+    pub allow_export: bool,
+    /// Called with the shown events as text when "Save log" is clicked,
+    /// e.g. to write them to a file.
+    pub on_save: Option<Box<dyn FnOnce(String)>>,
+    /// Show a button clearing all recorded events from the store.
+    pub clear_button: bool,
+    /// Make each event's source location clickable, calling `on_source_click`.
+    pub source_links: bool,
+    /// Called with the file and line of an event's source location when it
+    /// is clicked, e.g. to open it in an editor.
+    pub on_source_click: Option<Box<OnSourceClick>>,
+    /// Show a button pausing the log on the events shown so far, a toggle
+    /// keeping the newest events in view, and one [pausing recording](tracing_memory::set_recording).
+    pub live_controls: bool,
+    pub layout: Layout,
+    /// Show the event selected by clicking its header or row in a pane,
+    /// with its whole metadata, span chain, and each field's value as text
+    /// that can be selected. Escape clears the selection.
+    pub detail_pane: DetailPane,
+    /// How event timestamps are shown, and copied or saved.
+    pub timestamp: TimestampFormat,
+    /// Show a box choosing how event timestamps are shown, overriding `timestamp`.
+    pub timestamp_choice: bool,
+    /// Show the time column in the [table](Layout::Table) layout.
+    pub show_time: bool,
+    /// Show the target column in the [table](Layout::Table) layout.
+    pub show_target: bool,
+    /// Group events under a collapsing header for each span they were
+    /// recorded in, nested like the spans, in the [tree](Layout::Tree) layout.
+    pub group_by_span: bool,
+    /// Group events under a collapsing header for each target, level, or
+    /// root span, in the [tree](Layout::Tree) layout. Each header shows how
+    /// many events it holds, colored by the most severe of them. This takes
+    /// the place of `group_by_span`.
+    pub group_by: Option<GroupBy>,
+    /// Show a box choosing how events are grouped, overriding `group_by`.
+    pub group_choice: bool,
+    /// Show a control restricting the shown events to those fired in a range
+    /// of time before now, under a count of the events fired each second.
+    /// In live mode the range keeps up with the newest events.
+    pub time_range: bool,
+    /// The store to show events from, or the global store if `None`.
+    pub store: Option<Arc<EventStore>>,
+    /// Show the events of `store` live, or those of a snapshot instead, in
+    /// which case there are no live controls or "Clear" button.
+    pub source: EventSource,
+    /// Only show, filter, and count the newest this many events, if set.
+    pub max_events: Option<usize>,
+    /// Show a box editing the filter on which events are recorded at all,
+    /// from [`Layer::register_filter_handle`](tracing_memory::Layer::register_filter_handle),
+    /// with how many events it kept out of `store`.
+    pub recording_filter: Option<Arc<RwLock<RecordFilter>>>,
     #[doc(hidden)]
     pub _non_exhaustive_but_allow_fru: (),
 }
@@ -12,6 +202,181 @@ impl Default for Widget {
     fn default() -> Self {
         Self {
             filter: true,
+            search: true,
+            level_colors: true,
+            style: Style::default(),
+            header_formatter: None,
+            level_toggles: true,
+            level_counts: true,
+            allow_export: true,
+            on_save: None,
+            clear_button: true,
+            source_links: true,
+            on_source_click: None,
+            live_controls: true,
+            layout: Layout::Tree,
+            detail_pane: DetailPane::Hidden,
+            timestamp: TimestampFormat::default(),
+            timestamp_choice: true,
+            show_time: true,
+            show_target: true,
+            group_by_span: false,
+            group_by: None,
+            group_choice: true,
+            time_range: false,
+            store: None,
+            source: EventSource::Live,
+            max_events: None,
+            recording_filter: None,
+            _non_exhaustive_but_allow_fru: (),
+        }
+    }
+}
+
+impl fmt::Debug for Widget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Widget")
+            .field("filter", &self.filter)
+            .field("search", &self.search)
+            .field("level_colors", &self.level_colors)
+            .field("style", &self.style)
+            .field(
+                "header_formatter",
+                &self.header_formatter.as_ref().map(|_| ".."),
+            )
+            .field("level_toggles", &self.level_toggles)
+            .field("level_counts", &self.level_counts)
+            .field("allow_export", &self.allow_export)
+            .field("on_save", &self.on_save.as_ref().map(|_| ".."))
+            .field("clear_button", &self.clear_button)
+            .field("source_links", &self.source_links)
+            .field(
+                "on_source_click",
+                &self.on_source_click.as_ref().map(|_| ".."),
+            )
+            .field("live_controls", &self.live_controls)
+            .field("layout", &self.layout)
+            .field("detail_pane", &self.detail_pane)
+            .field("timestamp", &self.timestamp)
+            .field("timestamp_choice", &self.timestamp_choice)
+            .field("show_time", &self.show_time)
+            .field("show_target", &self.show_target)
+            .field("group_by_span", &self.group_by_span)
+            .field("group_by", &self.group_by)
+            .field("group_choice", &self.group_choice)
+            .field("time_range", &self.time_range)
+            .field("store", &self.store)
+            .field("source", &self.source)
+            .field("max_events", &self.max_events)
+            .field("recording_filter", &self.recording_filter)
+            .finish()
+    }
+}
+
+// The presets deliberately spell out every option rather than using FRU,
+// so that adding an option fails to compile until each preset chooses its value.
+impl Widget {
+    /// Everything on: for developers digging through their own app's logs.
+    pub fn developer() -> Self {
+        Self {
+            filter: true,
+            search: true,
+            level_colors: true,
+            style: Style::default(),
+            header_formatter: None,
+            level_toggles: true,
+            level_counts: true,
+            allow_export: true,
+            on_save: None,
+            clear_button: true,
+            source_links: true,
+            on_source_click: None,
+            live_controls: true,
+            layout: Layout::Tree,
+            detail_pane: DetailPane::Right,
+            timestamp: TimestampFormat::default(),
+            timestamp_choice: true,
+            show_time: true,
+            show_target: true,
+            group_by_span: false,
+            group_by: None,
+            group_choice: true,
+            time_range: true,
+            store: None,
+            source: EventSource::Live,
+            max_events: None,
+            recording_filter: None,
+            _non_exhaustive_but_allow_fru: (),
+        }
+    }
+
+    /// A read-only feel: for end users collecting logs to send to support.
+    pub fn support() -> Self {
+        Self {
+            filter: true,
+            search: true,
+            level_colors: true,
+            style: Style::default(),
+            header_formatter: None,
+            level_toggles: true,
+            level_counts: true,
+            allow_export: true,
+            on_save: None,
+            clear_button: false,
+            source_links: false,
+            on_source_click: None,
+            live_controls: true,
+            layout: Layout::Table,
+            detail_pane: DetailPane::Hidden,
+            timestamp: TimestampFormat::default(),
+            timestamp_choice: true,
+            show_time: true,
+            show_target: true,
+            group_by_span: false,
+            group_by: None,
+            group_choice: true,
+            time_range: false,
+            store: None,
+            source: EventSource::Live,
+            max_events: None,
+            recording_filter: None,
+            _non_exhaustive_but_allow_fru: (),
+        }
+    }
+
+    /// As little chrome as possible: for embedding a small log view.
+    ///
+    /// There is no toolbar at all, only a table of the newest 200 events
+    /// with their time and message.
+    pub fn minimal() -> Self {
+        Self {
+            filter: false,
+            search: false,
+            level_colors: true,
+            style: Style::default(),
+            header_formatter: None,
+            level_toggles: false,
+            level_counts: false,
+            allow_export: false,
+            on_save: None,
+            clear_button: false,
+            source_links: false,
+            on_source_click: None,
+            live_controls: false,
+            layout: Layout::Table,
+            detail_pane: DetailPane::Hidden,
+            timestamp: TimestampFormat::default(),
+            timestamp_choice: false,
+            show_time: true,
+            show_target: false,
+            group_by_span: false,
+            group_by: None,
+            group_choice: false,
+            time_range: false,
+            store: None,
+            source: EventSource::Live,
+            max_events: Some(200),
+            recording_filter: None,
             _non_exhaustive_but_allow_fru: (),
         }
     }
@@ -20,143 +385,2641 @@ impl Default for Widget {
 #[derive(Debug, Default, Clone)]
 struct State {
     filters: String,
+    search: String,
+    /// Whether events not matching the search are shown too.
+    show_unmatched: bool,
+    levels: ShownLevels,
+    /// Whether completions for the filter were shown last frame.
+    completing: bool,
+    /// The highlighted completion.
+    completion: usize,
+    /// The events shown while paused.
+    paused: Option<EventSnapshot>,
+    /// Whether to keep the scroll on the newest events, which are at the top.
+    /// Scrolling away from them turns this off.
+    follow: bool,
+    /// How far down the events were scrolled last frame.
+    scrolled: f32,
+    /// The [sequence number](Event::seq) of the event whose details are
+    /// shown below the table or in the detail pane.
+    selected: Option<u64>,
+    /// The timestamp format chosen in the widget, if one has been.
+    timestamp: Option<TimestampFormat>,
+    /// The span to show only the events from, if one was picked.
+    span: Option<OnlySpan>,
+    /// The grouping chosen in the widget, if one has been.
+    group_by: Option<Option<GroupBy>>,
+    /// The recording filter being edited, once it has been shown.
+    recording_filter: Option<String>,
+    /// The [sequence number](Event::seq) of the error last jumped to.
+    current_error: Option<u64>,
+    /// Until when the error jumped to is highlighted, in [egui's time](egui::InputState::time).
+    highlight_until: f64,
+    /// When the shown events were fired.
+    time_range: TimeRange,
+    /// Whether the live store was ever seen with events, so that having none
+    /// means they were cleared, rather than that none are recorded.
+    seen_events: bool,
+}
+
+/// What was clicked while showing the events, to act on once they are shown.
+#[derive(Default)]
+struct Picked {
+    /// A span to show only the events from, by right-clicking it.
+    span: Cell<Option<OnlySpan>>,
+    /// The [sequence number](Event::seq) of an event whose header was clicked,
+    /// to select it for the detail pane.
+    event: Cell<Option<u64>>,
+}
+
+/// A span picked to show only the events from, by right-clicking it.
+#[derive(Debug, Clone, Copy)]
+struct OnlySpan {
+    key: SpanKey,
+    name: &'static str,
+}
+
+impl State {
+    /// A hash of what decides which events are shown, besides the events
+    /// and the `times` they are shown between.
+    fn shown_hash(&self, times: TimeBounds) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        times.hash(&mut hasher);
+        self.filters.hash(&mut hasher);
+        self.levels.0.hash(&mut hasher);
+        self.span.map(|only| only.key).hash(&mut hasher);
+        self.search.hash(&mut hasher);
+        self.show_unmatched.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn load(&mut self, settings: &WidgetSettings) {
+        self.filters.clone_from(&settings.filters);
+        self.search.clone_from(&settings.search);
+        self.show_unmatched = settings.show_unmatched;
+        self.levels = ShownLevels(settings.levels);
+        self.follow = settings.follow;
+        self.timestamp.clone_from(&settings.timestamp);
+        self.group_by = settings.group_by;
+    }
+
+    fn save(&self, settings: &mut WidgetSettings) {
+        settings.filters.clone_from(&self.filters);
+        settings.search.clone_from(&self.search);
+        settings.show_unmatched = self.show_unmatched;
+        settings.levels = self.levels.0;
+        settings.follow = self.follow;
+        settings.timestamp.clone_from(&self.timestamp);
+        settings.group_by = self.group_by;
+    }
+}
+
+const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+
+/// Which levels are shown, in the same order as [`LEVELS`].
+#[derive(Debug, Clone, Copy)]
+struct ShownLevels([bool; 5]);
+
+impl Default for ShownLevels {
+    fn default() -> Self {
+        ShownLevels([true; 5])
+    }
+}
+
+impl ShownLevels {
+    fn includes(&self, level: &Level) -> bool {
+        self.0[level_index(level)]
+    }
+}
+
+/// The position of `level` in [`LEVELS`].
+fn level_index(level: &Level) -> usize {
+    LEVELS.iter().position(|l| l == level).unwrap()
+}
+
+/// The color of events at each level, or `None` for the usual text color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelColors {
+    pub error: Option<egui::Color32>,
+    pub warn: Option<egui::Color32>,
+    pub info: Option<egui::Color32>,
+    pub debug: Option<egui::Color32>,
+    pub trace: Option<egui::Color32>,
+}
+
+/// Errors are red and warnings yellow, while debug and trace events are
+/// dimmed, leaving info events in the usual text color.
+impl Default for LevelColors {
+    fn default() -> Self {
+        let dimmed = egui::Color32::from_gray(0x80);
+        LevelColors {
+            error: Some(egui::Color32::from_rgb(0xff, 0x00, 0x33)),
+            warn: Some(egui::Color32::from_rgb(0xff, 0xcc, 0x00)),
+            info: None,
+            debug: Some(dimmed),
+            trace: Some(dimmed),
+        }
+    }
+}
+
+impl LevelColors {
+    /// The color of events at `level`.
+    pub fn get(&self, level: &Level) -> Option<egui::Color32> {
+        match *level {
+            Level::ERROR => self.error,
+            Level::WARN => self.warn,
+            Level::INFO => self.info,
+            Level::DEBUG => self.debug,
+            _ => self.trace,
+        }
+    }
+}
+
+impl LevelColors {
+    /// Colors which read well on the background of `visuals`: the
+    /// [default](Self::default) ones on a dark theme, and darker ones on a
+    /// light theme.
+    pub fn for_visuals(visuals: &egui::Visuals) -> Self {
+        if visuals.dark_mode {
+            return LevelColors::default();
+        }
+        LevelColors {
+            error: Some(egui::Color32::from_rgb(0xcc, 0x00, 0x22)),
+            warn: Some(egui::Color32::from_rgb(0xb3, 0x6b, 0x00)),
+            info: None,
+            debug: Some(egui::Color32::from_gray(0x80)),
+            trace: Some(egui::Color32::from_gray(0x80)),
+        }
+    }
+}
+
+/// How the [`Widget`] looks.
+///
+/// Colors left as `None` are picked to suit the [visuals](egui::Visuals)
+/// the widget is shown with, so they read on dark and light themes alike.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    /// The color of events at each level, when `level_colors` is on.
+    pub colors: Option<LevelColors>,
+    /// The color of the mark by a filter box when its filter is valid.
+    pub valid: Option<egui::Color32>,
+    /// The color of the mark by a filter box when its filter is invalid.
+    pub invalid: Option<egui::Color32>,
+    /// The color of events whose message matches the search.
+    pub search: Option<egui::Color32>,
+    /// Show event headers in monospace, in the [tree](Layout::Tree) layout.
+    pub monospace_headers: bool,
+    /// Shade every other row of the [table](Layout::Table).
+    pub striped: bool,
+    #[doc(hidden)]
+    pub _non_exhaustive_but_allow_fru: (),
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            colors: None,
+            valid: None,
+            invalid: None,
+            search: None,
+            monospace_headers: false,
+            striped: true,
+            _non_exhaustive_but_allow_fru: (),
+        }
+    }
+}
+
+impl Style {
+    /// The style's colors for `visuals`, and the rest of it, as it is used
+    /// to show events.
+    fn look<'a>(&self, visuals: &egui::Visuals, header: Option<&'a HeaderFormatter>) -> Look<'a> {
+        let dark = visuals.dark_mode;
+        let pick = |color: Option<egui::Color32>, on_dark, on_light| {
+            color.unwrap_or(if dark { on_dark } else { on_light })
+        };
+        Look {
+            colors: Some(
+                self.colors
+                    .unwrap_or_else(|| LevelColors::for_visuals(visuals)),
+            ),
+            valid: pick(
+                self.valid,
+                egui::Color32::from_rgb(0x00, 0xff, 0x33),
+                egui::Color32::from_rgb(0x00, 0x99, 0x22),
+            ),
+            invalid: pick(
+                self.invalid,
+                egui::Color32::from_rgb(0xff, 0x00, 0x33),
+                egui::Color32::from_rgb(0xcc, 0x00, 0x22),
+            ),
+            search: pick(
+                self.search,
+                egui::Color32::from_rgb(0x33, 0xcc, 0xff),
+                egui::Color32::from_rgb(0x00, 0x66, 0xcc),
+            ),
+            monospace: self.monospace_headers,
+            striped: self.striped,
+            header,
+        }
+    }
+}
+
+/// A [`Style`] resolved for the visuals the events are shown with.
+#[derive(Clone, Copy)]
+struct Look<'a> {
+    /// The color of events at each level, if they are colored.
+    colors: Option<LevelColors>,
+    valid: egui::Color32,
+    invalid: egui::Color32,
+    search: egui::Color32,
+    monospace: bool,
+    striped: bool,
+    header: Option<&'a HeaderFormatter>,
+}
+
+impl Look<'_> {
+    /// The one-line summary of `event`, without its timestamp and repeats,
+    /// styled as its header in the [tree](Layout::Tree) layout.
+    fn summary(&self, event: &Event) -> HeaderText {
+        let mut summary = match self.header {
+            Some(format) => format(event),
+            None => HeaderText::new(tracing_memory::format_event(event, &SUMMARY)),
+        };
+        let level = event.meta().level();
+        summary.color = summary
+            .color
+            .or_else(|| self.colors.and_then(|colors| colors.get(level)));
+        if self.monospace {
+            summary.text_style = summary.text_style.or(Some(egui::TextStyle::Monospace));
+        }
+        summary
+    }
 }
 
 impl egui::Widget for Widget {
     fn ui(self, ui: &mut egui::Ui) -> egui::Response {
+        self.show(ui, None)
+    }
+}
+
+impl Widget {
+    /// Show the widget, with its settings kept in `settings` if given, or
+    /// else in egui's memory with the rest of its state.
+    pub(crate) fn show(
+        self,
+        ui: &mut egui::Ui,
+        settings: Option<&mut WidgetSettings>,
+    ) -> egui::Response {
+        let Widget {
+            filter: show_filter,
+            search: show_search,
+            level_colors,
+            style,
+            header_formatter,
+            level_toggles,
+            level_counts,
+            allow_export,
+            clear_button,
+            source_links,
+            on_source_click,
+            live_controls,
+            layout,
+            detail_pane,
+            timestamp: default_timestamp,
+            timestamp_choice,
+            show_time,
+            show_target,
+            group_by_span,
+            group_by: default_group_by,
+            group_choice,
+            time_range,
+            on_save,
+            store,
+            source,
+            max_events,
+            recording_filter,
+            _non_exhaustive_but_allow_fru: (),
+        } = self;
+        let live = LiveStore(store.as_deref());
+        let on_source_click = on_source_click.as_deref().filter(|_| source_links);
+        let mut look = style.look(ui.visuals(), header_formatter.as_deref());
+        if !level_colors {
+            look.colors = None;
+        }
+
         let id = ui.make_persistent_id("tracing-egui::LogPanel");
         let mut state = ui.memory().id_data_temp.get_or_default::<State>(id).clone();
+        if let Some(settings) = &settings {
+            state.load(settings);
+        }
+        let search_id = id.with("search");
+        let mut search_cache = std::mem::take(
+            ui.memory()
+                .id_data_temp
+                .get_mut_or_default::<SearchCache>(search_id),
+        );
+        let filter_id = id.with("filter");
+        let mut filter_cache = std::mem::take(
+            ui.memory()
+                .id_data_temp
+                .get_mut_or_default::<FilterCache>(filter_id),
+        );
+        let unfiltered = EventFilter::default();
+        let groups_id = id.with("groups");
+        let mut group_cache = std::mem::take(
+            ui.memory()
+                .id_data_temp
+                .get_mut_or_default::<GroupCache>(groups_id),
+        );
+        let rows_id = id.with("rows");
+        let mut row_heights = std::mem::take(
+            ui.memory()
+                .id_data_temp
+                .get_mut_or_default::<RowHeights>(rows_id),
+        );
+        let tally_id = id.with("tally");
+        let mut tally_cache = std::mem::take(
+            ui.memory()
+                .id_data_temp
+                .get_mut_or_default::<TallyCache>(tally_id),
+        );
+
+        // Render from a snapshot, and only touch the live store again once done.
+        let given = match source {
+            EventSource::Live => None,
+            EventSource::Snapshot(events) => Some(events),
+        };
+        let is_live = given.is_none();
+        let clear_button = clear_button && is_live;
+        let live_controls = live_controls && is_live;
+        let paused = state.paused.clone().filter(|_| is_live);
+        let snapshot = match (given, &paused) {
+            (Some(given), _) => given,
+            (None, Some(paused)) => paused.clone(),
+            (None, None) => live.snapshot(),
+        };
+        let events = snapshot.as_slice();
+        let events = match max_events {
+            Some(max) => &events[events.len().saturating_sub(max)..],
+            None => events,
+        };
+        if is_live && !snapshot.is_empty() {
+            state.seen_events = true;
+        } else if is_live && !state.seen_events {
+            static ONCE: std::sync::Once = std::sync::Once::new();
+            ONCE.call_once(|| {
+                tracing::warn!(
+                    "tracing-egui is running but sees no recorded events. \
+                     Is the tracing-memory layer installed?"
+                );
+            });
+        }
+        let arrived = paused
+            .as_ref()
+            .map(|paused| live.len().saturating_sub(paused.len()));
+        let dropped = recording_filter.as_ref().map(|_| live.dropped_by_filter());
+        let recording = is_recording();
+        let mut record_filter = None;
+        let mut actions = Vec::new();
 
+        let rendering = Rendering::start();
         let inner = ui.allocate_ui(ui.available_size(), |ui| {
-            let filter = if self.filter {
+            // the counts are only known once the filters below are read
+            let strip = if level_counts {
+                let size = egui::vec2(ui.available_width(), ui.spacing().interact_size.y);
+                Some(ui.allocate_exact_size(size, egui::Sense::hover()).0)
+            } else {
+                None
+            };
+
+            let filter = if show_filter {
                 ui.horizontal(|ui| {
                     ui.label("Filter:");
-                    ui.add(
-                        egui::TextEdit::singleline(&mut state.filters)
-                            .hint_text("target[span{field=value}]=level")
+                    filter_box(ui, id, &mut state, events);
+                    egui::reset_button(ui, &mut state.filters);
+                    match filter_cache.parse(&state.filters) {
+                        Ok(_) => {
+                            ui.colored_label(look.valid, "✔")
+                                .on_hover_text("Valid filter!");
+                        }
+                        Err(err) => {
+                            ui.colored_label(look.invalid, "⚠").on_hover_ui(|ui| {
+                                ui.label(format!("Invalid filter: {}", err));
+                                for line in err.directive_errors(&state.filters) {
+                                    ui.monospace(line);
+                                }
+                            });
+                        }
+                    }
+                });
+                // parsed above, once the text was edited
+                filter_cache.parse(&state.filters).unwrap_or(&unfiltered)
+            } else {
+                &unfiltered
+            };
+
+            if let (Some(handle), Some(dropped)) = (&recording_filter, dropped) {
+                ui.horizontal(|ui| {
+                    ui.label("Recording filter:");
+                    let text = state.recording_filter.get_or_insert_with(|| {
+                        let filter = handle.read().unwrap_or_else(PoisonError::into_inner);
+                        filter.to_string()
+                    });
+                    let response = ui.add(
+                        egui::TextEdit::singleline(text)
+                            .id(id.with("recording filter"))
+                            .hint_text("target=level")
                             .text_style(egui::TextStyle::Monospace),
                     );
-                    egui::reset_button(ui, &mut state.filters);
-                    match state.filters.parse() {
+                    match RecordFilter::parse(text) {
                         Ok(filter) => {
-                            ui.colored_label(egui::Color32::from_rgb(0x00, 0xff, 0x33), "✔")
+                            ui.colored_label(look.valid, "✔")
                                 .on_hover_text("Valid filter!");
-                            filter
+                            if response.changed() {
+                                record_filter = Some(filter);
+                            }
                         }
-                        Err(_err) => {
-                            ui.colored_label(egui::Color32::from_rgb(0xff, 0x00, 0x33), "⚠")
-                                .on_hover_text("Invalid filter!");
-                            EventFilter::default()
+                        Err(err) => {
+                            ui.colored_label(look.invalid, "⚠")
+                                .on_hover_text(format!("Invalid filter: {}", err));
                         }
                     }
-                })
-                .inner
+                    ui.add(egui::Label::new(format!("{} not recorded", dropped)).weak())
+                        .on_hover_text("Events kept out of the log by the recording filter");
+                });
+            }
+
+            if show_search {
+                ui.horizontal(|ui| {
+                    ui.label("Search:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut state.search)
+                            .hint_text("text in messages, targets, or fields"),
+                    );
+                    egui::reset_button(ui, &mut state.search);
+                    ui.checkbox(&mut state.show_unmatched, "Show non-matching");
+                });
+            }
+            let search = state.search.clone();
+
+            if level_toggles {
+                ui.horizontal(|ui| {
+                    for (level, shown) in LEVELS.iter().zip(&mut state.levels.0) {
+                        ui.checkbox(shown, level);
+                    }
+                });
+            }
+            let levels = if level_toggles || level_counts {
+                state.levels
             } else {
-                EventFilter::default()
+                ShownLevels::default()
             };
 
-            egui::ScrollArea::auto_sized()
-                .always_show_scroll(true)
-                .show(ui, show_log(filter));
-        });
+            if timestamp_choice {
+                ui.horizontal(|ui| {
+                    ui.label("Time:");
+                    let current = state.timestamp.as_ref().unwrap_or(&default_timestamp);
+                    let mut chosen = current.clone();
+                    egui::ComboBox::from_id_source(id.with("timestamp"))
+                        .selected_text(current.label())
+                        .show_ui(ui, |ui| {
+                            let time_format = match &default_timestamp {
+                                TimestampFormat::Absolute(format)
+                                | TimestampFormat::Utc(format) => format.clone(),
+                                _ => "%H:%M:%S%.3f".into(),
+                            };
+                            let formats = [
+                                TimestampFormat::Absolute(time_format.clone()),
+                                TimestampFormat::Utc(time_format),
+                                TimestampFormat::Elapsed,
+                                TimestampFormat::Delta,
+                                TimestampFormat::Hidden,
+                            ];
+                            for format in formats {
+                                let label = format.label();
+                                ui.selectable_value(&mut chosen, format, label);
+                            }
+                        });
+                    if chosen != *current {
+                        state.timestamp = Some(chosen);
+                    }
+                });
+            }
+            let timestamp = state.timestamp.clone().unwrap_or(default_timestamp);
 
-        ui.memory().id_data_temp.insert(id, state);
-        inner.response
-    }
-}
+            if group_choice && layout == Layout::Tree {
+                ui.horizontal(|ui| {
+                    ui.label("Group by:");
+                    let label =
+                        |group_by: Option<GroupBy>| group_by.map_or("Nothing", GroupBy::label);
+                    let mut chosen = state.group_by.unwrap_or(default_group_by);
+                    egui::ComboBox::from_id_source(id.with("group by"))
+                        .selected_text(label(chosen))
+                        .show_ui(ui, |ui| {
+                            let choices = [
+                                None,
+                                Some(GroupBy::Target),
+                                Some(GroupBy::Level),
+                                Some(GroupBy::RootSpan),
+                            ];
+                            for choice in choices {
+                                ui.selectable_value(&mut chosen, choice, label(choice));
+                            }
+                        });
+                    if chosen != state.group_by.unwrap_or(default_group_by) {
+                        state.group_by = Some(chosen);
+                    }
+                });
+            }
+            let group_by = state.group_by.unwrap_or(default_group_by);
 
-fn show_log(filter: EventFilter) -> impl FnOnce(&mut egui::Ui) {
-    move |ui: &mut egui::Ui| {
-        with_events(|events| {
-            if events.is_empty() {
-                ui.label("No events recorded.");
-                static ONCE: std::sync::Once = std::sync::Once::new();
-                ONCE.call_once(|| {
-                    tracing::warn!(
-                        "tracing-egui is running but sees no recorded events. \
-                         Is the tracing-memory layer installed?"
-                    );
+            if let Some(only) = state.span {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Only events from span {}", only.name));
+                    let clear = ui.button("✖").on_hover_text("Show events from every span");
+                    if clear.clicked() {
+                        state.span = None;
+                    }
+                });
+            }
+
+            let wall_now = SystemTime::now();
+            if time_range {
+                time_range_control(ui, &mut state.time_range, events, wall_now);
+            }
+
+            let mut shown = Shown {
+                filter,
+                levels,
+                span: state.span.map(|only| only.key),
+                times: match time_range {
+                    true => state.time_range.bounds(wall_now),
+                    false => TimeBounds::default(),
+                },
+                search: &search,
+                keep_unmatched: state.show_unmatched,
+                matches: match search.as_str() {
+                    "" => None,
+                    search => Some(search_cache.matches(search, events)),
+                },
+                rows: Vec::new(),
+                timestamp: &timestamp,
+                jump: None,
+                selected: None,
+            };
+            let shown_hash = state.shown_hash(shown.times);
+            let Tally {
+                rows,
+                tallied,
+                counts,
+                errors,
+            } = tally_cache.tally(shown_hash, events, &shown);
+            shown.rows = rows;
+            let grouping = match group_by {
+                Some(group_by) => {
+                    let include = |event_ix, _: &Event| shown.is_row(event_ix);
+                    Grouping::Groups(group_cache.groups(group_by, events, shown_hash, include))
+                }
+                None if group_by_span => Grouping::Spans,
+                None => Grouping::Flat(&mut row_heights),
+            };
+
+            let now = ui.input().time;
+            let mut scroll = false;
+            if let Some(rect) = strip {
+                let row = egui::Layout::left_to_right().with_cross_align(egui::Align::Center);
+                let mut strip = ui.child_ui(rect, row);
+                for (level_ix, level) in LEVELS.iter().enumerate() {
+                    let color = look.colors.and_then(|colors| colors.get(level));
+                    let shown = state.levels.0[level_ix];
+                    let count = strip.scope(|ui| {
+                        ui.visuals_mut().override_text_color = color;
+                        ui.selectable_label(shown, format!("{} {}", counts[level_ix], level))
+                    });
+                    let hover = match shown {
+                        true => "Hide these events",
+                        false => "Show these events",
+                    };
+                    if count.inner.on_hover_text(hover).clicked() {
+                        // the events shown were already picked, so show the change next frame
+                        state.levels.0[level_ix] = !shown;
+                        ui.ctx().request_repaint();
+                    }
+                }
+
+                strip.separator();
+                let seq = |event_ix: usize| events[event_ix].seq();
+                let jumps = [
+                    ("⏷", "Jump to the next older error", false),
+                    ("⏶", "Jump to the next newer error", true),
+                ];
+                for (text, hover, newer) in jumps {
+                    let to = error_to_jump_to(events, &errors, state.current_error, newer);
+                    let button = egui::Button::new(text).enabled(to.is_some());
+                    if strip.add(button).on_hover_text(hover).clicked() {
+                        let to = seq(to.unwrap());
+                        state.current_error = Some(to);
+                        state.highlight_until = now + HIGHLIGHT_SECONDS;
+                        state.follow = false;
+                        if layout == Layout::Table || detail_pane != DetailPane::Hidden {
+                            state.selected = Some(to);
+                        }
+                        scroll = true;
+                    }
+                }
+                let current = (errors.iter())
+                    .position(|&event_ix| Some(seq(event_ix)) == state.current_error);
+                match current {
+                    Some(current) => {
+                        strip.label(format!("Error {} of {}", current + 1, errors.len()))
+                    }
+                    None => strip.label(format!("{} errors", errors.len())),
+                };
+            }
+            let highlight = now < state.highlight_until;
+            if highlight {
+                // keep repainting until the highlight is over
+                ui.ctx().request_repaint();
+            }
+            shown.jump = state.current_error.map(|seq| Jump {
+                seq,
+                scroll,
+                highlight,
+            });
+
+            if allow_export || clear_button {
+                ui.horizontal(|ui| {
+                    if allow_export {
+                        if ui.button("Copy all (filtered)").clicked() {
+                            ui.output().copied_text = export_log(events, &shown);
+                        }
+                        if let Some(on_save) = on_save {
+                            if ui.button("Save log").clicked() {
+                                on_save(export_log(events, &shown));
+                            }
+                        }
+                    }
+                    if clear_button {
+                        let clear = egui::Button::new("Clear").enabled(!events.is_empty());
+                        if ui.add(clear).clicked() {
+                            actions.push(Action::Clear);
+                        }
+                    }
+                });
+            }
+
+            if live_controls {
+                ui.horizontal(|ui| {
+                    let pause = if paused.is_some() {
+                        "▶ Resume"
+                    } else {
+                        "⏸ Pause"
+                    };
+                    if ui.button(pause).clicked() {
+                        state.paused = match state.paused {
+                            Some(_) => None,
+                            None => Some(snapshot.clone()),
+                        };
+                    }
+                    if let Some(arrived) = arrived.filter(|&arrived| arrived != 0) {
+                        ui.colored_label(look.search, format!("{} new", arrived))
+                            .on_hover_text("Events recorded since pausing");
+                    }
+                    ui.checkbox(&mut state.follow, "Follow newest");
+                    let mut record = recording;
+                    if ui
+                        .checkbox(&mut record, "Record")
+                        .on_hover_text("Record new events")
+                        .changed()
+                    {
+                        actions.push(Action::Record(record));
+                    }
                 });
             }
 
-            for (event_ix, event) in events.iter().enumerate().rev() {
-                if filter.excludes(event) {
-                    continue;
+            // the selection may be stale, from before clearing or switching layouts
+            let selected = state
+                .selected
+                .and_then(|seq| events.iter().find(|event| event.seq() == seq));
+            let detail = selected
+                .map(Arc::as_ref)
+                .filter(|_| detail_pane != DetailPane::Hidden);
+            shown.selected = detail.map(|event| event.seq());
+
+            // The newest events are at the top, where new ones push the rest
+            // down, so following only needs to snap back there when turned on.
+            let snap_to_newest = state.follow && state.scrolled > 0.0;
+            let follow = |scroll_area: egui::ScrollArea| match snap_to_newest {
+                true => scroll_area.scroll_offset(0.0),
+                false => scroll_area,
+            };
+            let picked = Picked::default();
+            let show_events = |ui: &mut egui::Ui| match layout {
+                Layout::Tree => {
+                    let scroll_area = egui::ScrollArea::auto_sized().always_show_scroll(true);
+                    follow(scroll_area).show_viewport(ui, |ui, viewport| {
+                        show_log(
+                            events,
+                            &shown,
+                            look,
+                            allow_export,
+                            on_source_click,
+                            &picked,
+                            grouping,
+                        )(ui, viewport);
+                        viewport.min.y
+                    })
                 }
-                match event.field("message") {
-                    Some(message) => egui::CollapsingHeader::new(format_args!(
-                        "[{}] [{}] {}",
-                        event.timestamp().format("%H:%M:%S%.3f"),
-                        event.meta().level(),
-                        display_field(message),
-                    )),
-                    None => egui::CollapsingHeader::new(format_args!(
-                        "[{}] [{}]",
-                        event.timestamp().format("%H:%M:%S%.3f"),
-                        event.meta().level(),
-                    )),
+                Layout::Table => {
+                    // without a detail pane, the table shows the selected event below itself
+                    let below = selected.filter(|_| detail_pane == DetailPane::Hidden);
+                    let height = match below {
+                        Some(_) => ui.available_height() * 0.6,
+                        None => f32::INFINITY,
+                    };
+                    let columns = Columns {
+                        time: show_time && timestamp != TimestampFormat::Hidden,
+                        target: show_target,
+                        look,
+                        copy: allow_export,
+                    };
+                    let scroll_area = egui::ScrollArea::from_max_height(height);
+                    let scrolled = follow(scroll_area.always_show_scroll(true)).show_viewport(
+                        ui,
+                        |ui, viewport| {
+                            let selected = &mut state.selected;
+                            show_table(id, events, &shown, columns, selected)(ui, viewport);
+                            viewport.min.y
+                        },
+                    );
+                    if let Some(event) = below {
+                        ui.separator();
+                        egui::ScrollArea::auto_sized()
+                            .id_source("selected")
+                            .show(ui, show_event(event, &search, on_source_click, &picked));
+                    }
+                    scrolled
+                }
+            };
+            let show_pane = |ui: &mut egui::Ui, event: &Event| {
+                egui::ScrollArea::auto_sized()
+                    .id_source("detail")
+                    .show(ui, show_detail(event, on_source_click));
+            };
+            let scrolled = match (detail, detail_pane) {
+                (Some(event), DetailPane::Right) => ui.columns(2, |columns| {
+                    let scrolled = show_events(&mut columns[0]);
+                    show_pane(&mut columns[1], event);
+                    scrolled
+                }),
+                (Some(event), _) => {
+                    let size = egui::vec2(ui.available_width(), ui.available_height() * 0.6);
+                    let scrolled = ui.allocate_ui(size, show_events).inner;
+                    ui.separator();
+                    show_pane(ui, event);
+                    scrolled
                 }
-                .id_source(ui.make_persistent_id(event_ix))
-                .show(ui, show_event(event));
+                (None, _) => show_events(ui),
+            };
+            if scrolled > 0.0 {
+                state.follow = false;
+            }
+            if let Some(only) = picked.span.get() {
+                state.span = Some(only);
+            }
+            let clicked = picked
+                .event
+                .get()
+                .filter(|_| detail_pane != DetailPane::Hidden);
+            if let Some(seq) = clicked {
+                // selecting a different event replaces the selection
+                state.selected = match state.selected == Some(seq) {
+                    true => None,
+                    false => Some(seq),
+                };
             }
+            if state.selected.is_some() && ui.input().key_pressed(egui::Key::Escape) {
+                state.selected = None;
+            }
+            state.scrolled = scrolled;
+            tally_cache.keep(Tally {
+                rows: shown.rows,
+                tallied,
+                counts,
+                errors,
+            });
         });
+        drop(rendering);
+        ui.memory().id_data_temp.insert(search_id, search_cache);
+        ui.memory().id_data_temp.insert(groups_id, group_cache);
+        ui.memory().id_data_temp.insert(filter_id, filter_cache);
+        ui.memory().id_data_temp.insert(rows_id, row_heights);
+        ui.memory().id_data_temp.insert(tally_id, tally_cache);
+
+        if !actions.is_empty() {
+            // show the result right away, rather than on the next input
+            ui.ctx().request_repaint();
+        }
+        for action in actions {
+            live.apply(action);
+        }
+        if let (Some(handle), Some(filter)) = (&recording_filter, record_filter) {
+            *handle.write().unwrap_or_else(PoisonError::into_inner) = filter;
+        }
+
+        if let Some(settings) = settings {
+            state.save(settings);
+        }
+        ui.memory().id_data_temp.insert(id, state);
+        inner.response
     }
 }
 
-fn show_event(event: &Event) -> impl '_ + FnOnce(&mut egui::Ui) {
-    move |ui: &mut egui::Ui| {
-        egui::CollapsingHeader::new(format_args!(
-            "{} {}",
-            event.meta().target(),
-            event.meta().name(),
-        ))
-        .id_source(ui.make_persistent_id(0usize))
-        .text_style(egui::TextStyle::Monospace)
-        .show(ui, show_fields(event.fields()));
+/// How tall the count of events over time is, above the time range control.
+const HISTOGRAM_HEIGHT: f32 = 24.0;
 
-        for (span_ix, span) in std::iter::successors(event.span(), |span| span.parent()).enumerate()
-        {
-            egui::CollapsingHeader::new(format_args!(
-                "{}::{}",
-                span.meta().target(),
-                span.meta().name(),
-            ))
-            .id_source(ui.make_persistent_id(span_ix + 1))
-            .text_style(egui::TextStyle::Monospace)
-            .show(ui, show_fields(span.fields()));
+/// The control choosing the time `range` of the shown events, as times
+/// before `now`, under a count of `events` fired each second, or each few
+/// seconds if there are too many seconds to fit.
+fn time_range_control(
+    ui: &mut egui::Ui,
+    range: &mut TimeRange,
+    events: &[Arc<Event>],
+    now: SystemTime,
+) {
+    // in seconds before now, as the range is chosen
+    let span = match events.first() {
+        Some(oldest) => seconds_ago(now, oldest.time()).max(1.0),
+        None => return,
+    };
+
+    let size = egui::vec2(ui.available_width(), HISTOGRAM_HEIGHT);
+    let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let bins = (span.ceil() as usize).min(rect.width() as usize / 3).max(1);
+    let counts = histogram(
+        events.iter().map(|event| event.time()),
+        time_ago(now, span),
+        now,
+        bins,
+    );
+    let x = |seconds: f64| rect.right() - (seconds / span) as f32 * rect.width();
+    let bounds = range.bounds(now);
+    let shown = egui::Rect::from_min_max(
+        egui::pos2(
+            bounds
+                .from
+                .map_or(rect.left(), |from| x(seconds_ago(now, from))),
+            rect.top(),
+        ),
+        egui::pos2(
+            bounds.to.map_or(rect.right(), |to| x(seconds_ago(now, to))),
+            rect.bottom(),
+        ),
+    );
+    let painter = ui.painter();
+    painter.rect_filled(shown, 0.0, ui.visuals().selection.bg_fill);
+    let most = counts.iter().copied().max().unwrap_or(0).max(1);
+    let width = rect.width() / bins as f32;
+    let color = ui.visuals().widgets.noninteractive.fg_stroke.color;
+    for (bin, &count) in counts.iter().enumerate() {
+        if count > 0 {
+            let left = rect.left() + bin as f32 * width;
+            let height = rect.height() * count as f32 / most as f32;
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(left, rect.bottom() - height),
+                egui::pos2(left + width, rect.bottom()),
+            );
+            painter.rect_filled(bar, 0.0, color);
         }
     }
+    response.on_hover_text(format!(
+        "Events fired each {:.0}s over the last {:.0}s, up to {} at once",
+        span / bins as f64,
+        span,
+        most,
+    ));
+
+    ui.horizontal(|ui| {
+        let mut live = range.is_live();
+        let toggle = ui.checkbox(&mut live, "Live");
+        if toggle
+            .on_hover_text("Keep up with the newest events")
+            .changed()
+        {
+            *range = range.toggle_live(now);
+        }
+        match range {
+            TimeRange::Live { within } => {
+                ui.label("From");
+                let mut from = within.map_or(span, |within| within.as_secs_f64());
+                if ui.add(drag_ago(&mut from, 0.0..=span)).changed() {
+                    // reaching the oldest event shows all of them, even older ones later
+                    *within = match from < span {
+                        true => Some(Duration::from_secs_f64(from)),
+                        false => None,
+                    };
+                }
+            }
+            TimeRange::Fixed { from, to } => {
+                let mut from_ago = from.map_or(span, |from| seconds_ago(now, from));
+                let mut to_ago = seconds_ago(now, *to);
+                ui.label("From");
+                if ui
+                    .add(drag_ago(&mut from_ago, to_ago.min(span)..=span))
+                    .changed()
+                {
+                    *from = match from_ago < span {
+                        true => Some(time_ago(now, from_ago)),
+                        false => None,
+                    };
+                }
+                ui.label("to");
+                if ui.add(drag_ago(&mut to_ago, 0.0..=from_ago)).changed() {
+                    *to = time_ago(now, to_ago);
+                }
+            }
+        }
+        egui::reset_button(ui, range);
+    });
 }
 
-fn show_fields<'a, 'b>(
-    fields: impl Iterator<Item = (&'a str, &'b Field)>,
-) -> impl FnOnce(&mut egui::Ui) {
-    move |ui: &mut egui::Ui| {
-        for (name, value) in fields {
-            value
-                .with_debug(|value| ui.add(egui::Label::new(format_args!("{}: {:?}", name, value))))
-                .for_each(drop)
+/// A value dragged to choose a time as `seconds` before now, within `range`.
+fn drag_ago(seconds: &mut f64, range: RangeInclusive<f64>) -> egui::DragValue<'_> {
+    egui::DragValue::new(seconds)
+        .clamp_range(range)
+        .speed(0.1)
+        .max_decimals(1)
+        .suffix("s ago")
+}
+
+/// The filter text box, with a popup completing what is being typed from the
+/// names in `events`.
+///
+/// Up and down pick a completion, and tab, enter, or a click accept it.
+fn filter_box(ui: &mut egui::Ui, id: egui::Id, state: &mut State, events: &[Arc<Event>]) {
+    let response = ui.add(
+        egui::TextEdit::singleline(&mut state.filters)
+            .id(id.with("filter"))
+            .hint_text("target[span{field=value}]=level")
+            .text_style(egui::TextStyle::Monospace)
+            // keep tab for accepting a completion
+            .lock_focus(state.completing),
+    );
+    let popup_id = id.with("completions");
+
+    // enter or clicking a completion accepts it, but also takes focus away
+    let entered = response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+    let cursor = egui::TextEdit::<String>::cursor(ui, response.id).map(|cursor| {
+        let index = cursor.primary.ccursor.index;
+        let text = &state.filters;
+        text.char_indices()
+            .nth(index)
+            .map_or(text.len(), |(i, _)| i)
+    });
+    let was_completing = state.completing;
+    let context = cursor
+        .filter(|_| response.has_focus() || (response.lost_focus() && was_completing))
+        .and_then(|cursor| complete::context(&state.filters, cursor));
+    let (candidates, range) = match context {
+        Some(context) => (Known::new(events).candidates(&context), context.range),
+        None => (Vec::new(), 0..0),
+    };
+
+    state.completing = !candidates.is_empty();
+    if !state.completing {
+        state.completion = 0;
+        if ui.memory().is_popup_open(popup_id) {
+            ui.memory().close_popup();
         }
+        return;
     }
-}
 
-fn display_field(field: &Field) -> impl '_ + std::fmt::Display {
-    struct DisplayField<'a>(&'a Field);
-    impl std::fmt::Display for DisplayField<'_> {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            let mut first = true;
-            self.0
-                .with_debug(move |field| {
-                    if !first {
-                        f.write_str(", ")?;
-                    }
-                    first = false;
-                    field.fmt(f)?;
-                    Ok(())
-                })
-                .collect()
+    let input = ui.input();
+    let (down, up, tab) = (
+        input.key_pressed(egui::Key::ArrowDown),
+        input.key_pressed(egui::Key::ArrowUp),
+        input.key_pressed(egui::Key::Tab),
+    );
+    let count = candidates.len();
+    state.completion = match (down, up) {
+        (true, false) => (state.completion + 1) % count,
+        (false, true) => (state.completion + count - 1) % count,
+        _ => state.completion.min(count - 1),
+    };
+
+    let mut accepted = (tab || entered).then(|| candidates[state.completion]);
+    ui.memory().open_popup(popup_id);
+    egui::popup::popup_below_widget(ui, popup_id, &response, |ui| {
+        for (ix, &candidate) in candidates.iter().enumerate() {
+            let label = egui::SelectableLabel::new(ix == state.completion, candidate);
+            if ui.add(label).clicked() {
+                accepted = Some(candidate);
+            }
         }
+    });
+
+    if let Some(candidate) = accepted {
+        state.filters = complete::apply(&state.filters, range, candidate);
+        state.completion = 0;
+        // egui can't move the cursor, but forgetting it puts it at the end
+        ui.memory().id_data.remove(&response.id);
+        ui.memory().request_focus(response.id);
     }
+}
+
+/// The store shown by the widget, which is only touched outside of rendering.
+#[derive(Clone, Copy)]
+pub(crate) struct LiveStore<'a>(pub(crate) Option<&'a EventStore>);
+
+/// A change requested while rendering, applied once the frame is rendered.
+enum Action {
+    Clear,
+    /// Recording is global, so this applies whatever the store.
+    Record(bool),
+}
 
-    DisplayField(field)
+thread_local! {
+    static RENDERING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Marks this thread as rendering from a snapshot until dropped.
+pub(crate) struct Rendering;
+
+impl Rendering {
+    pub(crate) fn start() -> Self {
+        RENDERING.with(|rendering| rendering.set(true));
+        Rendering
+    }
+}
+
+impl Drop for Rendering {
+    fn drop(&mut self) {
+        RENDERING.with(|rendering| rendering.set(false));
+    }
+}
+
+impl LiveStore<'_> {
+    pub(crate) fn snapshot(self) -> EventSnapshot {
+        self.debug_assert_not_rendering();
+        match self.0 {
+            Some(store) => store.snapshot(),
+            None => snapshot(),
+        }
+    }
+
+    fn len(self) -> usize {
+        self.debug_assert_not_rendering();
+        let len = |events: &[Arc<Event>]| events.len();
+        match self.0 {
+            Some(store) => store.with_events_since(0, len),
+            None => with_events_since(0, len),
+        }
+    }
+
+    fn dropped_by_filter(self) -> u64 {
+        self.debug_assert_not_rendering();
+        match self.0 {
+            Some(store) => store.dropped_by_filter(),
+            None => dropped_by_filter(),
+        }
+    }
+
+    fn apply(self, action: Action) {
+        self.debug_assert_not_rendering();
+        match action {
+            Action::Clear => match self.0 {
+                Some(store) => store.clear_events(),
+                None => clear_events(),
+            },
+            Action::Record(recording) => set_recording(recording),
+        }
+    }
+
+    fn debug_assert_not_rendering(self) {
+        debug_assert!(
+            !RENDERING.with(Cell::get),
+            "the live event store was touched while rendering a snapshot of it"
+        );
+    }
+}
+
+/// Which events are shown: those passing the filter, levels, span, and search.
+struct Shown<'a> {
+    filter: &'a EventFilter,
+    levels: ShownLevels,
+    /// The span to show only the events from, if any.
+    span: Option<SpanKey>,
+    /// When the shown events were fired.
+    times: TimeBounds,
+    /// The text searched for, which is highlighted in event details.
+    search: &'a str,
+    /// Whether events the search didn't match are shown too.
+    keep_unmatched: bool,
+    /// Where the search matched each event, if searching.
+    matches: Option<&'a [Match]>,
+    /// The indices of the shown events, oldest first, from [`tally`].
+    rows: Vec<usize>,
+    /// How the shown events are timestamped.
+    timestamp: &'a TimestampFormat,
+    /// The error last jumped to, if any.
+    jump: Option<Jump>,
+    /// The [sequence number](Event::seq) of the event shown in the detail
+    /// pane, if any, whose header is outlined.
+    selected: Option<u64>,
+}
+
+impl Shown<'_> {
+    /// Whether the event would be shown if its level were.
+    fn includes_at_any_level(&self, event_ix: usize, event: &Event) -> bool {
+        self.filter.includes(event)
+            && self.times.includes(event.time())
+            && self.in_span(event)
+            && (self.keep_unmatched || self.search_match(event_ix) != Match::None)
+    }
+
+    fn in_span(&self, event: &Event) -> bool {
+        match self.span {
+            Some(key) => std::iter::successors(event.span(), |span| span.parent())
+                .any(|span| span.key() == key),
+            None => true,
+        }
+    }
+
+    /// Whether the event at `event_ix` is shown, going by [`rows`](Self::rows).
+    fn is_row(&self, event_ix: usize) -> bool {
+        self.rows.binary_search(&event_ix).is_ok()
+    }
+
+    fn search_match(&self, event_ix: usize) -> Match {
+        match self.matches {
+            Some(matches) => matches[event_ix],
+            None => Match::Other,
+        }
+    }
+
+    /// How the shown event at `event_ix` is timestamped, going by the event
+    /// shown before it.
+    fn time_format(&self, events: &[Arc<Event>], event_ix: usize) -> TimeFormat {
+        let previous = match self.rows.binary_search(&event_ix) {
+            Ok(row) | Err(row) => row.checked_sub(1).map(|row| &*events[self.rows[row]]),
+        };
+        self.timestamp.time_format(previous)
+    }
+
+    /// The timestamp of the shown event at `event_ix`, if it is shown with one.
+    fn timestamp(&self, events: &[Arc<Event>], event_ix: usize) -> Option<String> {
+        self.time_format(events, event_ix).format(&events[event_ix])
+    }
+}
+
+/// What a pass over the events finds out about those shown.
+///
+/// This is the only pass deciding which events are shown; the rest go by its
+/// `rows`. It is [cached](TallyCache) between frames, and only goes over the
+/// events recorded since the last frame while what is shown stays the same.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Tally {
+    /// The indices of the shown events, oldest first.
+    rows: Vec<usize>,
+    /// How many of the events were tallied.
+    tallied: usize,
+    /// How many events would be shown at each level, in the same order as
+    /// [`LEVELS`], if that level were.
+    counts: [usize; 5],
+    /// The indices of the shown `ERROR` events, oldest first.
+    errors: Vec<usize>,
+}
+
+fn tally(events: &[Arc<Event>], shown: &Shown<'_>) -> Tally {
+    let mut tally = Tally::default();
+    tally.extend(events, shown);
+    tally
+}
+
+impl Tally {
+    /// Tally those of `events` after the ones already tallied.
+    fn extend(&mut self, events: &[Arc<Event>], shown: &Shown<'_>) {
+        for (event_ix, event) in events.iter().enumerate().skip(self.tallied) {
+            let level = event.meta().level();
+            if !shown.includes_at_any_level(event_ix, event) {
+                continue;
+            }
+            self.counts[level_index(level)] += 1;
+            if !shown.levels.includes(level) {
+                continue;
+            }
+            if *level == Level::ERROR {
+                self.errors.push(event_ix);
+            }
+            self.rows.push(event_ix);
+        }
+        self.tallied = events.len();
+    }
+}
+
+/// The [`Tally`] of the events, kept between frames so that only the events
+/// recorded since are tallied, until what decides which are shown changes.
+#[derive(Debug, Default, Clone)]
+struct TallyCache {
+    /// The [`State::shown_hash`] the tally is for.
+    key: Option<u64>,
+    tally: Tally,
+    /// The sequence number of the last event tallied, to notice the events
+    /// changing.
+    last: u64,
+}
+
+impl TallyCache {
+    /// The tally of `events`, as shown by `shown`, where `shown_hash` changes
+    /// whenever `shown` could show others.
+    ///
+    /// The tally is taken out of the cache, to be [kept](Self::keep) once done with.
+    fn tally(&mut self, shown_hash: u64, events: &[Arc<Event>], shown: &Shown<'_>) -> Tally {
+        let tallied = match self.tally.tallied {
+            0 => None,
+            len => events.get(len - 1),
+        };
+        let key = Some(shown_hash);
+        // events are only ever appended, unless they were cleared or removed
+        if self.key != key || tallied.map(|event| event.seq()) != Some(self.last) {
+            self.key = key;
+            self.tally = Tally::default();
+        }
+        self.tally.extend(events, shown);
+        self.last = events.last().map_or(0, |event| event.seq());
+        std::mem::take(&mut self.tally)
+    }
+
+    /// Keep `tally`, as taken by [`tally`](Self::tally), for the next frame.
+    fn keep(&mut self, tally: Tally) {
+        self.tally = tally;
+    }
+}
+
+/// The shown error to jump to from the one with sequence number `current`:
+/// the next newer or older one, or the oldest or newest if there is none.
+fn error_to_jump_to(
+    events: &[Arc<Event>],
+    errors: &[usize],
+    current: Option<u64>,
+    newer: bool,
+) -> Option<usize> {
+    let seq = |event_ix: &&usize| events[**event_ix].seq();
+    match (current, newer) {
+        (None, true) => errors.first(),
+        (None, false) => errors.last(),
+        (Some(current), true) => errors.iter().find(|event_ix| seq(event_ix) > current),
+        (Some(current), false) => errors.iter().rev().find(|event_ix| seq(event_ix) < current),
+    }
+    .copied()
+}
+
+/// How long an error jumped to stays highlighted.
+const HIGHLIGHT_SECONDS: f64 = 1.5;
+
+/// The error last jumped to.
+#[derive(Clone, Copy)]
+struct Jump {
+    /// Its [sequence number](Event::seq).
+    seq: u64,
+    /// Whether to scroll to it, which is only done right after jumping.
+    scroll: bool,
+    /// Whether to highlight it, which is done for a moment after jumping.
+    highlight: bool,
+}
+
+impl Jump {
+    /// Scroll to and highlight `response`, as that of the event jumped to.
+    fn mark(self, ui: &egui::Ui, response: &egui::Response) {
+        if self.scroll {
+            response.scroll_to_me(egui::Align::Center);
+        }
+        if self.highlight {
+            let rect = response.rect.expand(2.0);
+            ui.painter().rect_stroke(rect, 2.0, (1.5, HIGHLIGHT_COLOR));
+        }
+    }
+}
+
+/// The background of text matching the search, in event details.
+const HIGHLIGHT_COLOR: egui::Color32 = egui::Color32::from_rgb(0xff, 0xee, 0x55);
+
+/// The shown events, formatted as text.
+fn export_log(events: &[Arc<Event>], shown: &Shown<'_>) -> String {
+    (shown.rows.iter())
+        .map(|&event_ix| format_event_at(&events[event_ix], shown.time_format(events, event_ix)))
+        .collect()
+}
+
+/// Show the shown events, newest first, in a scroll area's `viewport`.
+///
+/// Ungrouped, only the rows in the viewport are laid out, going by the
+/// heights of the others as last laid out, so that a frame takes time in
+/// proportion to how many events fit in view rather than to how many there
+/// are: O(visible) rather than O(n), besides a pass over the rows' heights.
+/// Grouped or nested under their spans, the events are all laid out.
+fn show_log<'a>(
+    events: &'a [Arc<Event>],
+    shown: &'a Shown<'a>,
+    look: Look<'a>,
+    allow_copy: bool,
+    on_source_click: Option<&'a OnSourceClick>,
+    picked: &'a Picked,
+    grouping: Grouping<'a>,
+) -> impl 'a + FnOnce(&mut egui::Ui, egui::Rect) {
+    move |ui: &mut egui::Ui, viewport: egui::Rect| {
+        if events.is_empty() {
+            ui.label("No events recorded.");
+        } else if shown.rows.is_empty() {
+            ui.label("No events match the filters.");
+        }
+
+        let mut show = |ui: &mut egui::Ui, event_ix: usize| {
+            let event = &events[event_ix];
+            let time = match shown.timestamp(events, event_ix) {
+                Some(timestamp) => format!("[{}] ", timestamp),
+                None => String::new(),
+            };
+            let summary = look.summary(event);
+            let mut header = egui::CollapsingHeader::new(format_args!(
+                "{}{}{}",
+                time,
+                summary.text,
+                Repeats(event),
+            ))
+            // by event rather than position, so headers keep their state when
+            // pausing or clearing moves events around, and by sequence number
+            // rather than address, which compressed events don't keep
+            .id_source(ui.make_persistent_id(event.seq()));
+            if let Some(text_style) = summary.text_style {
+                header = header.text_style(text_style);
+            }
+            let color = if shown.search_match(event_ix) == Match::Message {
+                Some(look.search)
+            } else {
+                summary.color
+            };
+            let response = show_colored(
+                ui,
+                header,
+                color,
+                show_event(event, shown.search, on_source_click, picked),
+            );
+            if allow_copy && response.header_response.secondary_clicked() {
+                ui.output().copied_text =
+                    format_event_at(event, shown.time_format(events, event_ix));
+            }
+            if let Some(jump) = shown.jump.filter(|jump| jump.seq == event.seq()) {
+                jump.mark(ui, &response.header_response);
+            }
+            if response.header_response.clicked() {
+                picked.event.set(Some(event.seq()));
+            }
+            if shown.selected == Some(event.seq()) {
+                let stroke = ui.visuals().selection.stroke;
+                ui.painter()
+                    .rect_stroke(response.header_response.rect, 2.0, stroke);
+            }
+            response
+        };
+
+        match grouping {
+            Grouping::Flat(heights) => {
+                let rows = || shown.rows.iter().rev().copied();
+                let estimate = ui.spacing().interact_size.y + ui.spacing().item_spacing.y;
+                let row_heights: Vec<f32> = rows()
+                    .map(|event_ix| heights.height(events[event_ix].seq(), estimate))
+                    .collect();
+                let mut top = viewport.min.y;
+                if let Some(jump) = shown.jump.filter(|jump| jump.scroll) {
+                    let jumped = rows().position(|event_ix| events[event_ix].seq() == jump.seq);
+                    if let Some(row) = jumped {
+                        // lay out from the row jumped to, so that it can be scrolled to
+                        top = row_heights[..row].iter().sum();
+                    }
+                }
+                let visible = visible_rows(&row_heights, top, top + viewport.height());
+
+                ui.add_space(visible.before);
+                let spacing = ui.spacing().item_spacing.y;
+                for event_ix in rows().skip(visible.range.start).take(visible.range.len()) {
+                    let response = show(ui, event_ix);
+                    let rect = match &response.body_response {
+                        Some(body) => response.header_response.rect.union(body.rect),
+                        None => response.header_response.rect,
+                    };
+                    let expanded = response.body_response.is_some();
+                    heights.measured(events[event_ix].seq(), rect.height() + spacing, expanded);
+                }
+                ui.add_space(visible.after);
+            }
+            Grouping::Spans => {
+                let tree = Tree::new(events, |event_ix, _| shown.is_row(event_ix));
+                let mut show = |ui: &mut egui::Ui, event_ix| {
+                    show(ui, event_ix);
+                };
+                tree.show_spans(ui, true, &mut show);
+                for &event_ix in tree.outside.iter().rev() {
+                    show(ui, event_ix);
+                }
+            }
+            Grouping::Groups(groups) => {
+                for group in groups {
+                    let header = egui::CollapsingHeader::new(format_args!(
+                        "{} ({})",
+                        group.name,
+                        group.events.len()
+                    ))
+                    .id_source(ui.make_persistent_id(("group", &group.name)));
+                    let color = look.colors.and_then(|colors| colors.get(&group.max_level));
+                    let response = show_colored(ui, header, color, |ui| {
+                        for &event_ix in group.events.iter().rev() {
+                            show(ui, event_ix);
+                        }
+                    });
+                    // egui can't open the group, so point at it instead
+                    if let Some(jump) = shown.jump {
+                        let in_group = (group.events.iter())
+                            .any(|&event_ix| events[event_ix].seq() == jump.seq);
+                        if in_group && response.body_returned.is_none() {
+                            jump.mark(ui, &response.header_response);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// How [`show_log`] groups the shown events.
+enum Grouping<'a> {
+    /// Not at all, laying out only the rows in view by their heights.
+    Flat(&'a mut RowHeights),
+    /// Nested under their spans.
+    Spans,
+    Groups(&'a [Group]),
+}
+
+/// Which columns [`show_table`] shows, besides the level and message, and how.
+#[derive(Clone, Copy)]
+struct Columns<'a> {
+    time: bool,
+    target: bool,
+    look: Look<'a>,
+    /// Whether right-clicking a row copies its event.
+    copy: bool,
+}
+
+/// Show the shown events as a table, newest first, in a scroll area's `viewport`.
+///
+/// Each row is taken to be one line tall, so only the rows in the viewport
+/// are laid out, without going over the others at all.
+fn show_table<'a>(
+    id: egui::Id,
+    events: &'a [Arc<Event>],
+    shown: &'a Shown<'a>,
+    columns: Columns<'a>,
+    selected: &'a mut Option<u64>,
+) -> impl 'a + FnOnce(&mut egui::Ui, egui::Rect) {
+    move |ui: &mut egui::Ui, viewport: egui::Rect| {
+        if events.is_empty() {
+            ui.label("No events recorded.");
+        } else if shown.rows.is_empty() {
+            ui.label("No events match the filters.");
+        }
+
+        let rows = || shown.rows.iter().rev().copied();
+        // as laid out by the grid, with its default row height and spacing
+        let height = ui.spacing().interact_size.y + ui.spacing().item_spacing.y;
+        let mut top = viewport.min.y;
+        if let Some(jump) = shown.jump.filter(|jump| jump.scroll) {
+            let jumped = rows().position(|event_ix| events[event_ix].seq() == jump.seq);
+            if let Some(row) = jumped {
+                // lay out from the row jumped to, so that it can be scrolled to
+                top = row as f32 * height;
+            }
+        }
+        let visible = uniform_rows(shown.rows.len(), height, top, top + viewport.height());
+
+        ui.add_space(visible.before);
+        egui::Grid::new(id.with("table"))
+            .striped(columns.look.striped)
+            .show(ui, |ui| {
+                for event_ix in rows().skip(visible.range.start).take(visible.range.len()) {
+                    let event = &events[event_ix];
+                    if columns.time {
+                        ui.monospace(shown.timestamp(events, event_ix).unwrap_or_default());
+                    }
+                    let level = event.meta().level();
+                    match columns.look.colors.and_then(|colors| colors.get(level)) {
+                        Some(color) => ui.colored_label(color, level.to_string()),
+                        None => ui.label(level.to_string()),
+                    };
+                    if columns.target {
+                        ui.monospace(event.effective_target());
+                    }
+
+                    let message = match (columns.look.header, event.message()) {
+                        (Some(format), _) => format(event),
+                        (None, Some(message)) => HeaderText::new(message),
+                        (None, None) => HeaderText::new(""),
+                    };
+                    let is_selected = *selected == Some(event.seq());
+                    let mut label = egui::SelectableLabel::new(
+                        is_selected,
+                        format_args!("{}{}", message.text, Repeats(event)),
+                    );
+                    if let Some(text_style) = message.text_style {
+                        label = label.text_style(text_style);
+                    }
+                    let response = ui
+                        .scope(|ui| {
+                            if shown.search_match(event_ix) == Match::Message {
+                                ui.visuals_mut().override_text_color = Some(columns.look.search);
+                            } else if message.color.is_some() {
+                                ui.visuals_mut().override_text_color = message.color;
+                            }
+                            ui.add(label)
+                        })
+                        .inner;
+                    if columns.copy && response.secondary_clicked() {
+                        ui.output().copied_text =
+                            format_event_at(event, shown.time_format(events, event_ix));
+                    }
+                    if let Some(jump) = shown.jump.filter(|jump| jump.seq == event.seq()) {
+                        jump.mark(ui, &response);
+                    }
+                    if response.clicked() {
+                        *selected = match is_selected {
+                            true => None,
+                            false => Some(event.seq()),
+                        };
+                    }
+                    ui.end_row();
+                }
+            });
+        ui.add_space(visible.after);
+    }
+}
+
+/// Shows how often a coalesced event was repeated, as ` ×N`, if it was.
+struct Repeats<'a>(&'a Event);
+
+impl fmt::Display for Repeats<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.repeat_count() {
+            1 => Ok(()),
+            count => write!(f, " ×{}", count),
+        }
+    }
+}
+
+/// Show a collapsing header with its title in `color`, leaving its body unaffected.
+fn show_colored<R>(
+    ui: &mut egui::Ui,
+    header: egui::CollapsingHeader,
+    color: Option<egui::Color32>,
+    add_body: impl FnOnce(&mut egui::Ui) -> R,
+) -> egui::CollapsingResponse<R> {
+    ui.scope(|ui| {
+        let body_color = ui.visuals().override_text_color;
+        if color.is_some() {
+            ui.visuals_mut().override_text_color = color;
+        }
+        header.show(ui, |ui| {
+            ui.visuals_mut().override_text_color = body_color;
+            add_body(ui)
+        })
+    })
+    .inner
+}
+
+fn show_event<'a>(
+    event: &'a Event,
+    search: &'a str,
+    on_source_click: Option<&'a OnSourceClick>,
+    picked: &'a Picked,
+) -> impl 'a + FnOnce(&mut egui::Ui) {
+    move |ui: &mut egui::Ui| {
+        ui.add(egui::Label::new(format_args!(
+            "on thread {}",
+            event.thread()
+        )));
+        if event.repeat_count() > 1 {
+            ui.add(egui::Label::new(format_args!(
+                "repeated {} times, last at {}",
+                event.repeat_count(),
+                time::local(event.last_time()).format("%H:%M:%S%.3f"),
+            )));
+        }
+        egui::CollapsingHeader::new(format_args!(
+            "{} {}",
+            event.effective_target(),
+            event.meta().name(),
+        ))
+        .id_source(ui.make_persistent_id(0usize))
+        .text_style(egui::TextStyle::Monospace)
+        .show(ui, |ui| {
+            show_source(ui, event, on_source_click);
+            show_fields_matching(event.fields(), search)(ui);
+            show_unset(ui, event.unset_fields());
+        });
+
+        for (span_ix, span) in std::iter::successors(event.span(), |span| span.parent()).enumerate()
+        {
+            let response = egui::CollapsingHeader::new(format_args!(
+                "{}::{}",
+                span.meta().target(),
+                span.meta().name(),
+            ))
+            .id_source(ui.make_persistent_id(span_ix + 1))
+            .text_style(egui::TextStyle::Monospace)
+            .show(ui, |ui| {
+                show_fields(span.fields())(ui);
+                show_span_unset(ui, span);
+                if let Some(latest) = span.latest() {
+                    egui::CollapsingHeader::new("(updated later — view latest)")
+                        .id_source(ui.make_persistent_id("latest"))
+                        .show(ui, |ui| {
+                            show_fields(latest.fields())(ui);
+                            show_span_unset(ui, &latest);
+                        });
+                }
+            });
+            let header = (response.header_response)
+                .on_hover_text("Right-click to show only events from this span");
+            if header.secondary_clicked() {
+                picked.span.set(Some(OnlySpan {
+                    key: span.key(),
+                    name: span.meta().name(),
+                }));
+            }
+        }
+    }
+}
+
+/// The detail pane for `event`: its whole metadata, then its fields and those
+/// of each span it is in, innermost first.
+fn show_detail<'a>(
+    event: &'a Event,
+    on_source_click: Option<&'a OnSourceClick>,
+) -> impl 'a + FnOnce(&mut egui::Ui) {
+    move |ui: &mut egui::Ui| {
+        let meta = event.meta();
+        ui.strong(format!("{} {}", meta.level(), meta.name()));
+        ui.monospace(format!("target: {}", event.effective_target()));
+        show_source(ui, event, on_source_click);
+        ui.add(egui::Label::new(format_args!(
+            "on thread {}",
+            event.thread()
+        )));
+        ui.add(egui::Label::new(format_args!(
+            "at {}",
+            time::local(event.time()).format("%Y-%m-%d %H:%M:%S%.6f"),
+        )));
+        if event.repeat_count() > 1 {
+            ui.add(egui::Label::new(format_args!(
+                "repeated {} times, last at {}",
+                event.repeat_count(),
+                time::local(event.last_time()).format("%H:%M:%S%.3f"),
+            )));
+        }
+        show_field_text(ui, event.fields());
+        show_unset(ui, event.unset_fields());
+
+        for span in std::iter::successors(event.span(), |span| span.parent()) {
+            ui.separator();
+            ui.monospace(format!(
+                "in {}::{}",
+                span.meta().target(),
+                span.meta().name(),
+            ));
+            show_field_text(ui, span.fields());
+            show_span_unset(ui, span);
+        }
+    }
+}
+
+/// Show each field's whole value as monospace text that can be selected and
+/// copied, with a value per line for a field recorded more than once.
+fn show_field_text<'a, 'b>(ui: &mut egui::Ui, fields: impl Iterator<Item = (&'a str, &'b Field)>) {
+    for (name, field) in fields {
+        ui.label(format!("{}:", name));
+        match field {
+            Field::Multiple(_) => {
+                for (value_ix, value) in field.iter().enumerate() {
+                    ui.horizontal_top(|ui| {
+                        ui.monospace(format!("[{}]", value_ix));
+                        value_text(ui, value);
+                    });
+                }
+            }
+            value => value_text(ui, value),
+        }
+    }
+}
+
+/// A read-only text box holding `value`, which keeps the look of a text box
+/// so its text can be selected.
+///
+/// An error with sources gets a text box per source, each indented below the
+/// one it is the source of.
+fn value_text(ui: &mut egui::Ui, value: &Field) {
+    if let Field::ErrorChain(_) = value {
+        let links = value.error_chain().into_iter().flatten();
+        ui.vertical(|ui| {
+            for (depth, link) in links.enumerate() {
+                ui.horizontal_top(|ui| {
+                    ui.add_space(depth as f32 * ui.spacing().indent);
+                    text_box(ui, link.to_owned());
+                });
+            }
+        });
+        return;
+    }
+    let response = text_box(ui, value.to_string());
+    if value.is_truncated() {
+        response.on_hover_text("Truncated when recorded; the rest wasn't kept");
+    }
+}
+
+fn text_box(ui: &mut egui::Ui, mut text: String) -> egui::Response {
+    // edits are lost, as the text is formatted anew each frame
+    let edit = egui::TextEdit::multiline(&mut text)
+        .text_style(egui::TextStyle::Monospace)
+        .desired_rows(1)
+        .desired_width(f32::INFINITY);
+    ui.add(edit)
+}
+
+/// Show the module and `file:line` of `event`, looking through `log` records,
+/// leaving out what it lacks.
+fn show_source(ui: &mut egui::Ui, event: &Event, on_source_click: Option<&OnSourceClick>) {
+    if let Some(module_path) = event.effective_module_path() {
+        ui.add(egui::Label::new(format_args!("module: {}", module_path)));
+    }
+    // `log` records may come without one
+    let (file, line) = match (event.effective_file(), event.effective_line()) {
+        (Some(file), Some(line)) => (file, line),
+        _ => return,
+    };
+    let location = egui::Label::new(format_args!("at {}:{}", file, line)).monospace();
+    let response = match on_source_click {
+        Some(on_source_click) => {
+            let link = location
+                .text_color(ui.visuals().hyperlink_color)
+                .sense(egui::Sense::click());
+            let response = ui
+                .add(link)
+                .on_hover_text("Open source; right-click to copy");
+            if response.clicked() {
+                on_source_click(file, line);
+            }
+            response
+        }
+        None => ui.add(location).on_hover_text("Right-click to copy"),
+    };
+    if response.interact(egui::Sense::click()).secondary_clicked() {
+        ui.output().copied_text = format!("{}:{}", file, line);
+    }
+}
+
+pub(crate) fn show_fields<'a, 'b>(
+    fields: impl Iterator<Item = (&'a str, &'b Field)>,
+) -> impl FnOnce(&mut egui::Ui) {
+    move |ui: &mut egui::Ui| {
+        for (name, value) in fields {
+            let response = ui.add(egui::Label::new(format_args!("{}: {}", name, value)));
+            copy_value(ui, response, value);
+        }
+    }
+}
+
+/// Let right-clicking a field copy its value, unless the value was
+/// truncated when recorded, so there is no whole value to copy.
+fn copy_value(ui: &egui::Ui, response: egui::Response, value: &Field) {
+    if value.is_truncated() {
+        response.on_hover_text("Truncated when recorded; the rest wasn't kept");
+    } else if response.interact(egui::Sense::click()).secondary_clicked() {
+        ui.output().copied_text = value.to_string();
+    }
+}
+
+/// Show the names of fields declared but not recorded, dimmed.
+pub(crate) fn show_unset<'a>(ui: &mut egui::Ui, names: impl Iterator<Item = &'a str>) {
+    for name in names {
+        ui.add(egui::Label::new(format_args!("{}: <unset>", name)).weak());
+    }
+}
+
+/// Like [`show_unset`] for `span`'s fields, unless it was archived without
+/// them, when that is noted instead.
+pub(crate) fn show_span_unset(ui: &mut egui::Ui, span: &Span) {
+    if span.fields_unavailable() {
+        ui.add(egui::Label::new("(fields not captured)").weak())
+            .on_hover_text("The span was created before it could be recorded");
+    } else {
+        show_unset(ui, span.unset_fields());
+    }
+}
+
+/// Like [`show_fields`], highlighting where `search` matches the values.
+fn show_fields_matching<'a, 'b, 'c>(
+    fields: impl 'c + Iterator<Item = (&'a str, &'b Field)>,
+    search: &'c str,
+) -> impl 'c + FnOnce(&mut egui::Ui) {
+    move |ui: &mut egui::Ui| {
+        for (name, field) in fields {
+            let value = field.to_string();
+            let parts = search::highlights(&value, search);
+            if parts.iter().all(|&(_, matched)| !matched) {
+                let response = ui.add(egui::Label::new(format_args!("{}: {}", name, value)));
+                copy_value(ui, response, field);
+                continue;
+            }
+            let response = ui.horizontal_wrapped(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                ui.label(format!("{}: ", name));
+                for (part, matched) in parts {
+                    let label = egui::Label::new(part);
+                    ui.add(match matched {
+                        true => label
+                            .background_color(HIGHLIGHT_COLOR)
+                            .text_color(egui::Color32::BLACK),
+                        false => label,
+                    });
+                }
+            });
+            copy_value(ui, response.response, field);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn levels_map_to_their_colors() {
+        let colors = LevelColors::default();
+        let dimmed = Some(egui::Color32::from_gray(0x80));
+        #[rustfmt::skip]
+        let expected = [
+            (Level::ERROR, Some(egui::Color32::from_rgb(0xff, 0x00, 0x33))),
+            (Level::WARN, Some(egui::Color32::from_rgb(0xff, 0xcc, 0x00))),
+            (Level::INFO, None),
+            (Level::DEBUG, dimmed),
+            (Level::TRACE, dimmed),
+        ];
+        for (level, color) in &expected {
+            assert_eq!(colors.get(level), *color, "{}", level);
+        }
+
+        let themed = LevelColors {
+            info: Some(egui::Color32::GREEN),
+            trace: None,
+            ..colors
+        };
+        assert_eq!(themed.get(&Level::INFO), Some(egui::Color32::GREEN));
+        assert_eq!(themed.get(&Level::DEBUG), dimmed);
+        assert_eq!(themed.get(&Level::TRACE), None);
+    }
+
+    #[test]
+    fn style_suits_the_visuals() {
+        let (dark, light) = (egui::Visuals::dark(), egui::Visuals::light());
+        let on_dark = Style::default().look(&dark, None);
+        let on_light = Style::default().look(&light, None);
+        assert_eq!(on_dark.colors, Some(LevelColors::default()));
+        assert_eq!(on_light.colors, Some(LevelColors::for_visuals(&light)));
+        assert_ne!(on_dark.colors, on_light.colors);
+        assert_ne!(on_dark.valid, on_light.valid);
+        assert_ne!(on_dark.search, on_light.search);
+
+        // chosen colors are kept whatever the visuals
+        let chosen = Style {
+            colors: Some(LevelColors {
+                info: Some(egui::Color32::GREEN),
+                ..LevelColors::default()
+            }),
+            invalid: Some(egui::Color32::BLUE),
+            ..Style::default()
+        };
+        let look = chosen.look(&light, None);
+        let info = look.colors.and_then(|colors| colors.get(&Level::INFO));
+        assert_eq!(info, Some(egui::Color32::GREEN));
+        assert_eq!(look.invalid, egui::Color32::BLUE);
+        assert_eq!(look.valid, on_light.valid);
+    }
+
+    #[test]
+    fn header_formatter_replaces_the_summary() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app", "hello");
+            tracing::warn!(target: "app", status = 503);
+        });
+        let events = store.with_events(|events| events.clone());
+
+        let visuals = egui::Visuals::dark();
+        let warn = LevelColors::for_visuals(&visuals).get(&Level::WARN);
+        let look = Style::default().look(&visuals, None);
+        assert_eq!(look.summary(&events[0]).text(), "[INFO] hello");
+        assert_eq!(look.summary(&events[1]).text(), "[WARN]");
+        assert_eq!(look.summary(&events[1]).color, warn);
+
+        let format: Box<HeaderFormatter> = Box::new(|event: &Event| {
+            let status = event.field_u64("status").unwrap_or(200);
+            let text = HeaderText::new(format!("{} {}", event.effective_target(), status));
+            if status >= 500 {
+                text.color(egui::Color32::RED).monospace()
+            } else {
+                text
+            }
+        });
+        let look = Style::default().look(&visuals, Some(&*format));
+        let ok = look.summary(&events[0]);
+        assert_eq!(ok.text(), "app 200");
+        assert_eq!(
+            ok.color,
+            LevelColors::for_visuals(&visuals).get(&Level::INFO)
+        );
+        assert_eq!(ok.text_style, None);
+        let failed = look.summary(&events[1]);
+        assert_eq!(failed.text(), "app 503");
+        assert_eq!(failed.color, Some(egui::Color32::RED));
+        assert_eq!(failed.text_style, Some(egui::TextStyle::Monospace));
+    }
+
+    #[test]
+    fn tab_accepts_a_completion() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app::http", "request");
+        });
+
+        let mut ctx = egui::CtxRef::default();
+        let mut id = None;
+        let tab = egui::Event::Key {
+            key: egui::Key::Tab,
+            pressed: true,
+            modifiers: Default::default(),
+        };
+        for events in [vec![], vec![egui::Event::Text("ap".into())], vec![tab]] {
+            if let Some(id) = id {
+                ctx.memory().request_focus(egui::Id::with(id, "filter"));
+            }
+            ctx.begin_frame(egui::RawInput {
+                events,
+                ..Default::default()
+            });
+            egui::CentralPanel::default().show(&ctx, |ui| {
+                id = Some(ui.make_persistent_id("tracing-egui::LogPanel"));
+                ui.add(Widget {
+                    store: Some(store.clone()),
+                    ..Widget::default()
+                });
+            });
+            let _ = ctx.end_frame();
+        }
+
+        let state = ctx
+            .memory()
+            .id_data_temp
+            .get::<State>(&id.unwrap())
+            .cloned();
+        assert_eq!(state.unwrap().filters, "app::http");
+    }
+
+    #[test]
+    fn hiding_every_level_hides_every_event() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!("error");
+            tracing::debug!("debug");
+        });
+        let events = store.with_events(|events| events.clone());
+        let unfiltered = EventFilter::default();
+        let shown = |levels| Shown {
+            filter: &unfiltered,
+            levels: ShownLevels(levels),
+            span: None,
+            times: TimeBounds::default(),
+            search: "",
+            keep_unmatched: false,
+            matches: None,
+            rows: Vec::new(),
+            timestamp: &TimestampFormat::Hidden,
+            jump: None,
+            selected: None,
+        };
+
+        let rows = |levels| tally(&events, &shown(levels)).rows;
+        assert_eq!(rows([true; 5]), [0, 1]);
+        assert!(rows([false, true, true, false, true]).is_empty());
+        assert!(rows([false; 5]).is_empty());
+        assert_eq!(rows([false, false, false, true, false]), [1]);
+    }
+
+    #[test]
+    fn a_given_snapshot_is_shown_in_place_of_the_store() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app", "first run");
+            tracing::info!(target: "other", "first run");
+            tracing::warn!(target: "app", "second run");
+            tracing::info!(target: "app", "second run");
+        });
+        // as if only the second run were loaded
+        let second: EventSnapshot = store
+            .snapshot()
+            .iter()
+            .filter(|event| event.field_str("message") == Some("second run"))
+            .cloned()
+            .collect();
+        store.clear_events();
+        let app = "app".parse().unwrap();
+        let shown = |levels| Shown {
+            filter: &app,
+            levels: ShownLevels(levels),
+            span: None,
+            times: TimeBounds::default(),
+            search: "",
+            keep_unmatched: false,
+            matches: None,
+            rows: Vec::new(),
+            timestamp: &TimestampFormat::Hidden,
+            jump: None,
+            selected: None,
+        };
+        let rows = |levels| tally(&second, &shown(levels)).rows;
+        assert_eq!(rows([true; 5]), [0, 1]);
+        assert_eq!(rows([false, true, false, false, false]), [0]);
+
+        // rendered from the snapshot, though the store has been cleared since
+        let mut ctx = egui::CtxRef::default();
+        ctx.begin_frame(Default::default());
+        egui::CentralPanel::default().show(&ctx, |ui| {
+            ui.add(Widget {
+                store: Some(store.clone()),
+                source: EventSource::Snapshot(second),
+                ..Widget::default()
+            });
+        });
+        let _ = ctx.end_frame();
+    }
+
+    #[test]
+    fn levels_are_counted_and_errors_jumped_between() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::error!(target: "app", "first");
+            tracing::info!(target: "app", "info");
+            tracing::error!(target: "app", "second");
+            tracing::debug!(target: "app", "debug");
+            tracing::error!(target: "other", "third");
+        });
+        let events = store.with_events(|events| events.clone());
+        let shown = Shown {
+            filter: &"app".parse().unwrap(),
+            levels: ShownLevels([true, true, false, true, true]),
+            span: None,
+            times: TimeBounds::default(),
+            search: "",
+            keep_unmatched: false,
+            matches: None,
+            rows: Vec::new(),
+            timestamp: &TimestampFormat::Delta,
+            jump: None,
+            selected: None,
+        };
+        let Tally {
+            rows,
+            tallied,
+            counts,
+            errors,
+        } = tally(&events, &shown);
+
+        // hidden levels are still counted, but not other filtered out events
+        assert_eq!(rows, [0, 2, 3]);
+        assert_eq!(tallied, 5);
+        assert_eq!(counts, [2, 0, 1, 1, 0]);
+        assert_eq!(errors, [0, 2]);
+
+        // timed since the event shown before
+        let shown = Shown { rows, ..shown };
+        let since = |event_ix: usize| TimeFormat::Delta(events[event_ix].elapsed_since_start());
+        let first = TimeFormat::Delta(Duration::ZERO);
+        assert_eq!(shown.time_format(&events, 0), first);
+        assert_eq!(shown.time_format(&events, 2), since(0));
+        assert_eq!(shown.time_format(&events, 3), since(2));
+
+        let seq = |event_ix: usize| events[event_ix].seq();
+        let jump = |current, newer| error_to_jump_to(&events, &errors, current, newer);
+        assert_eq!(jump(None, false), Some(2));
+        assert_eq!(jump(None, true), Some(0));
+        assert_eq!(jump(Some(seq(2)), false), Some(0));
+        assert_eq!(jump(Some(seq(0)), false), None);
+        assert_eq!(jump(Some(seq(0)), true), Some(2));
+        assert_eq!(jump(Some(seq(2)), true), None);
+        // from an error no longer shown, to the one next to where it was
+        assert_eq!(jump(Some(seq(1)), true), Some(2));
+    }
+
+    #[test]
+    fn only_new_events_are_tallied() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let app = "app".parse().unwrap();
+        let shown = Shown {
+            filter: &app,
+            levels: ShownLevels([true, true, true, false, false]),
+            span: None,
+            times: TimeBounds::default(),
+            search: "",
+            keep_unmatched: false,
+            matches: None,
+            rows: Vec::new(),
+            timestamp: &TimestampFormat::Hidden,
+            jump: None,
+            selected: None,
+        };
+        let mut cache = TallyCache::default();
+        let mut cached = |shown_hash: u64, events: &[Arc<Event>]| {
+            let tally = cache.tally(shown_hash, events, &shown);
+            cache.keep(tally.clone());
+            tally
+        };
+
+        tracing::error!(target: "app", "first");
+        tracing::info!(target: "other", "other");
+        let events = store.snapshot();
+        assert_eq!(cached(0, &events).rows, [0]);
+
+        tracing::debug!(target: "app", "debug");
+        tracing::warn!(target: "app", "second");
+        let events = store.snapshot();
+        let tally = cached(0, &events);
+        assert_eq!(tally, self::tally(&events, &shown));
+        assert_eq!(tally.rows, [0, 3]);
+        assert_eq!(tally.counts, [1, 1, 0, 1, 0]);
+
+        // retallied from the start, for other filters or other events
+        let tally = cached(1, &events);
+        assert_eq!(tally, self::tally(&events, &shown));
+        store.clear_events();
+        tracing::info!(target: "app", "after clearing");
+        let events = store.snapshot();
+        assert_eq!(cached(1, &events).rows, [0]);
+    }
+
+    #[test]
+    fn only_events_in_the_time_range_are_shown() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("earlier");
+            std::thread::sleep(Duration::from_millis(5));
+            tracing::info!("later");
+        });
+        let events = store.with_events(|events| events.clone());
+        let unfiltered = EventFilter::default();
+        let shown = |times| Shown {
+            filter: &unfiltered,
+            levels: ShownLevels::default(),
+            span: None,
+            times,
+            search: "",
+            keep_unmatched: false,
+            matches: None,
+            rows: Vec::new(),
+            timestamp: &TimestampFormat::Hidden,
+            jump: None,
+            selected: None,
+        };
+        let rows = |times| tally(&events, &shown(times)).rows;
+
+        let later = events[1].time();
+        let live = TimeRange::Live {
+            within: Some(Duration::from_millis(1)),
+        };
+        assert_eq!(rows(live.bounds(later)), [1]);
+        let fixed = TimeRange::Fixed {
+            from: None,
+            to: events[0].time(),
+        };
+        assert_eq!(rows(fixed.bounds(later)), [0]);
+        assert_eq!(rows(TimeRange::default().bounds(later)), [0, 1]);
+        // the groups are recomputed as the range moves
+        let state = State::default();
+        assert_ne!(
+            state.shown_hash(live.bounds(later)),
+            state.shown_hash(live.bounds(later + Duration::from_secs(1)))
+        );
+    }
+
+    #[test]
+    fn picking_a_span_shows_only_its_events() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("request").in_scope(|| {
+                tracing::info!("in request");
+                tracing::info_span!("lookup").in_scope(|| tracing::info!("in lookup"));
+            });
+            tracing::info_span!("request").in_scope(|| tracing::info!("in another request"));
+        });
+        let events = store.with_events(|events| events.clone());
+        let shown = Shown {
+            filter: &EventFilter::default(),
+            levels: ShownLevels::default(),
+            span: Some(events[0].span().unwrap().key()),
+            times: TimeBounds::default(),
+            search: "",
+            keep_unmatched: false,
+            matches: None,
+            rows: Vec::new(),
+            timestamp: &TimestampFormat::Hidden,
+            jump: None,
+            selected: None,
+        };
+
+        let Tally { rows, .. } = tally(&events, &shown);
+        assert_eq!(rows, [0, 1]);
+    }
+
+    #[test]
+    fn pausing_keeps_the_shown_events() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut ctx = egui::CtxRef::default();
+        let mut id = None;
+        let mut frame = |ctx: &mut egui::CtxRef| {
+            ctx.begin_frame(Default::default());
+            egui::CentralPanel::default().show(ctx, |ui| {
+                id = Some(ui.make_persistent_id("tracing-egui::LogPanel"));
+                ui.add(Widget {
+                    store: Some(store.clone()),
+                    ..Widget::default()
+                });
+            });
+            let _ = ctx.end_frame();
+            id.unwrap()
+        };
+
+        tracing::info!("before pausing");
+        let id = frame(&mut ctx);
+        // as if the pause button were clicked
+        let paused = store.snapshot();
+        ctx.memory()
+            .id_data_temp
+            .get_mut::<State>(&id)
+            .unwrap()
+            .paused = Some(paused);
+
+        tracing::info!("while paused");
+        frame(&mut ctx);
+        let state = ctx.memory().id_data_temp.get::<State>(&id).cloned();
+        assert_eq!(state.unwrap().paused.map(|paused| paused.len()), Some(1));
+        assert_eq!(store.with_events(|events| events.len()), 2);
+    }
+
+    #[test]
+    fn scrolling_away_stops_following() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            for ix in 0..100 {
+                tracing::info!(ix, "event");
+            }
+        });
+
+        let mut ctx = egui::CtxRef::default();
+        let mut id = None;
+        let mut frame = |ctx: &mut egui::CtxRef, input: egui::RawInput| {
+            ctx.begin_frame(egui::RawInput {
+                screen_rect: Some(egui::Rect::from_min_size(
+                    egui::Pos2::ZERO,
+                    egui::vec2(400.0, 300.0),
+                )),
+                ..input
+            });
+            egui::CentralPanel::default().show(ctx, |ui| {
+                id = Some(ui.make_persistent_id("tracing-egui::LogPanel"));
+                ui.add(Widget {
+                    store: Some(store.clone()),
+                    ..Widget::default()
+                });
+            });
+            let _ = ctx.end_frame();
+            id.unwrap()
+        };
+        let following = |ctx: &egui::CtxRef, id| {
+            let state = ctx.memory().id_data_temp.get::<State>(&id).cloned();
+            state.unwrap().follow
+        };
+
+        let id = frame(&mut ctx, Default::default());
+        // as if the follow checkbox were checked
+        ctx.memory()
+            .id_data_temp
+            .get_mut::<State>(&id)
+            .unwrap()
+            .follow = true;
+        frame(&mut ctx, Default::default());
+        assert!(following(&ctx, id));
+
+        let scroll = egui::RawInput {
+            events: vec![egui::Event::PointerMoved(egui::pos2(200.0, 250.0))],
+            scroll_delta: egui::vec2(0.0, -100.0),
+            ..Default::default()
+        };
+        frame(&mut ctx, scroll);
+        frame(&mut ctx, Default::default());
+        assert!(!following(&ctx, id));
+
+        // following again snaps back to the newest events
+        ctx.memory()
+            .id_data_temp
+            .get_mut::<State>(&id)
+            .unwrap()
+            .follow = true;
+        frame(&mut ctx, Default::default());
+        frame(&mut ctx, Default::default());
+        assert!(following(&ctx, id));
+    }
+
+    #[test]
+    fn switching_layouts_keeps_a_stale_selection_harmless() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut ctx = egui::CtxRef::default();
+        let frame = |ctx: &mut egui::CtxRef, layout| {
+            let mut id = None;
+            ctx.begin_frame(Default::default());
+            egui::CentralPanel::default().show(ctx, |ui| {
+                id = Some(ui.make_persistent_id("tracing-egui::LogPanel"));
+                ui.add(Widget {
+                    layout,
+                    store: Some(store.clone()),
+                    ..Widget::default()
+                });
+            });
+            let _ = ctx.end_frame();
+            id.unwrap()
+        };
+
+        tracing::info!("selected");
+        let id = frame(&mut ctx, Layout::Table);
+        let seq = store.with_events(|events| events[0].seq());
+        ctx.memory()
+            .id_data_temp
+            .get_mut::<State>(&id)
+            .unwrap()
+            .selected = Some(seq);
+        frame(&mut ctx, Layout::Table);
+        frame(&mut ctx, Layout::Tree);
+
+        store.clear_events();
+        tracing::info!("after clearing");
+        frame(&mut ctx, Layout::Table);
+        frame(&mut ctx, Layout::Tree);
+        frame(&mut ctx, Layout::Table);
+        let state = ctx.memory().id_data_temp.get::<State>(&id).cloned();
+        assert_eq!(state.unwrap().selected, Some(seq));
+    }
+
+    #[test]
+    fn escape_clears_the_detail_pane() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut ctx = egui::CtxRef::default();
+        let frame = |ctx: &mut egui::CtxRef, input, detail_pane| {
+            let mut id = None;
+            ctx.begin_frame(input);
+            egui::CentralPanel::default().show(ctx, |ui| {
+                id = Some(ui.make_persistent_id("tracing-egui::LogPanel"));
+                ui.add(Widget {
+                    detail_pane,
+                    store: Some(store.clone()),
+                    ..Widget::default()
+                });
+            });
+            let _ = ctx.end_frame();
+            id.unwrap()
+        };
+        let selected = |ctx: &egui::CtxRef, id| {
+            let state = ctx.memory().id_data_temp.get::<State>(&id).cloned();
+            state.unwrap().selected
+        };
+
+        tracing::info_span!("request", path = "/api").in_scope(|| {
+            tracing::info!(lines = "one\ntwo", "selected");
+        });
+        let id = frame(&mut ctx, Default::default(), DetailPane::Right);
+        let seq = store.with_events(|events| events[0].seq());
+        ctx.memory()
+            .id_data_temp
+            .get_mut::<State>(&id)
+            .unwrap()
+            .selected = Some(seq);
+        for &pane in &[DetailPane::Right, DetailPane::Bottom] {
+            frame(&mut ctx, Default::default(), pane);
+            assert_eq!(selected(&ctx, id), Some(seq));
+        }
+
+        let escape = egui::RawInput {
+            events: vec![egui::Event::Key {
+                key: egui::Key::Escape,
+                pressed: true,
+                modifiers: Default::default(),
+            }],
+            ..Default::default()
+        };
+        frame(&mut ctx, escape, DetailPane::Bottom);
+        assert_eq!(selected(&ctx, id), None);
+    }
+
+    /// The text drawn by a frame showing `widget` given `input`, with where
+    /// each was drawn.
+    fn frame(
+        ctx: &mut egui::CtxRef,
+        input: egui::RawInput,
+        widget: Widget,
+    ) -> Vec<(String, egui::Rect)> {
+        fn collect(shape: &egui::Shape, texts: &mut Vec<(String, egui::Rect)>) {
+            match shape {
+                egui::Shape::Vec(shapes) => shapes.iter().for_each(|shape| collect(shape, texts)),
+                egui::Shape::Text { pos, galley, .. } => {
+                    let rect = egui::Rect::from_min_size(*pos, galley.size);
+                    texts.push((galley.text.clone(), rect));
+                }
+                _ => {}
+            }
+        }
+
+        ctx.begin_frame(input);
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.add(widget);
+        });
+        let (_, shapes) = ctx.end_frame();
+        let mut texts = Vec::new();
+        for egui::epaint::ClippedShape(_, shape) in &shapes {
+            collect(shape, &mut texts);
+        }
+        texts
+    }
+
+    /// The text drawn by one frame showing `widget`.
+    fn texts(widget: Widget) -> Vec<String> {
+        let mut ctx = egui::CtxRef::default();
+        let texts = frame(&mut ctx, Default::default(), widget);
+        texts.into_iter().map(|(text, _)| text).collect()
+    }
+
+    #[test]
+    fn presets_show_their_toolbars() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "app", "hello");
+        });
+        let toolbar = [
+            "Filter:",
+            "Search:",
+            "Time:",
+            "Group by:",
+            "Copy all (filtered)",
+            "Clear",
+            "⏸ Pause",
+            "Follow newest",
+        ];
+        let shown = |widget: Widget| {
+            let texts = texts(Widget {
+                store: Some(store.clone()),
+                ..widget
+            });
+            assert!(texts.iter().any(|text| text.contains("hello")));
+            let drawn = |element: &&str| texts.iter().any(|text| text == element);
+            toolbar.iter().copied().filter(drawn).collect::<Vec<_>>()
+        };
+
+        assert_eq!(shown(Widget::developer()), toolbar);
+        assert_eq!(
+            shown(Widget::support()),
+            [
+                "Filter:",
+                "Search:",
+                "Time:",
+                "Copy all (filtered)",
+                "⏸ Pause",
+                "Follow newest",
+            ]
+        );
+        assert!(shown(Widget::minimal()).is_empty());
+    }
+
+    #[test]
+    fn only_the_newest_events_are_shown() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("first");
+            tracing::info!("second");
+            tracing::info!("third");
+        });
+
+        let texts = texts(Widget {
+            store: Some(store.clone()),
+            max_events: Some(2),
+            ..Widget::minimal()
+        });
+        let shown = |message| texts.iter().any(|text| text.contains(message));
+        assert!(!shown("first"));
+        assert!(shown("second") && shown("third"));
+    }
+
+    #[test]
+    fn clearing_takes_effect_after_the_frame() {
+        let store = Arc::new(EventStore::new());
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_memory::Layer::with_store(store.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("before clearing");
+        });
+        let widget = || Widget {
+            store: Some(store.clone()),
+            ..Widget::default()
+        };
+        let shown = |texts: &[(String, egui::Rect)]| {
+            (texts.iter()).any(|(text, _)| text.contains("before clearing"))
+        };
+
+        let mut ctx = egui::CtxRef::default();
+        let texts = frame(&mut ctx, Default::default(), widget());
+        let clear = texts.iter().find(|(text, _)| text == "Clear").unwrap().1;
+        let pos = clear.center();
+        let hover = egui::RawInput {
+            events: vec![egui::Event::PointerMoved(pos)],
+            ..Default::default()
+        };
+        frame(&mut ctx, hover, widget());
+
+        let click = |pressed| egui::Event::PointerButton {
+            pos,
+            button: egui::PointerButton::Primary,
+            pressed,
+            modifiers: Default::default(),
+        };
+        let click = egui::RawInput {
+            events: vec![click(true), click(false)],
+            ..Default::default()
+        };
+        // the frame clearing the store still shows what it held
+        assert!(shown(&frame(&mut ctx, click, widget())));
+        assert_eq!(store.with_events(|events| events.len()), 0);
+        assert!(!shown(&frame(&mut ctx, Default::default(), widget())));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "touched while rendering")]
+    fn touching_the_store_while_rendering_panics() {
+        let store = EventStore::new();
+        let _rendering = Rendering::start();
+        let _ = LiveStore(Some(&store)).snapshot();
+    }
 }
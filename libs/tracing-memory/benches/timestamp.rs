@@ -0,0 +1,19 @@
+use chrono::{DateTime, Local, Utc};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::time::SystemTime;
+
+/// What recording an event cost before the calibrated clock, against it.
+fn timestamp(c: &mut Criterion) {
+    let mut group = c.benchmark_group("timestamp");
+    group.bench_function("SystemTime::now + Local", |b| {
+        b.iter(|| black_box(DateTime::<Utc>::from(SystemTime::now()).with_timezone(&Local)))
+    });
+    group.bench_function("Local::now", |b| b.iter(|| black_box(Local::now())));
+    group.bench_function("clock::now", |b| {
+        b.iter(|| black_box(tracing_memory::__timestamp_now()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, timestamp);
+criterion_main!(benches);
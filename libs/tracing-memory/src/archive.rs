@@ -1,17 +1,71 @@
 type SmartString = smartstring::SmartString<smartstring::LazyCompact>;
+type SVec<T, const N: usize> = smallvec::SmallVec<[T; N]>;
 
+use crate::clock;
+#[cfg(feature = "chrono")]
 use chrono::prelude::*;
 use indexmap::IndexMap;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
 use replace_with::replace_with_or_abort;
-use std::sync::Arc;
+use std::{
+    collections::HashSet,
+    convert::TryFrom,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, Ordering},
+        Arc,
+    },
+    thread::{self, ThreadId},
+    time::{Duration, SystemTime},
+};
 
 /// A record of a tracing [event](https://docs.rs/tracing/0.1/tracing/index.html#events).
 #[derive(Debug, Clone)]
 pub struct Event {
     pub(crate) meta: &'static tracing::Metadata<'static>,
-    pub(crate) timestamp: NaiveDateTime,
+    pub(crate) timestamp: i64,
+    /// The monotonic time since the clock started.
+    pub(crate) elapsed: Duration,
+    pub(crate) thread: Arc<ThreadInfo>,
     pub(crate) fields: FieldMap,
     pub(crate) span: Option<Arc<Span>>,
+    pub(crate) span_id: Option<u64>,
+    pub(crate) repeats: Repeats,
+    pub(crate) seq: u64,
+    #[cfg(feature = "log-compat")]
+    pub(crate) log: Option<Box<LogMeta>>,
+}
+
+/// Where a `log` record bridged by tracing-log really came from.
+///
+/// tracing-log fires every record from one callsite per level, with target
+/// `log`, and passes the record's own metadata as `log.*` fields instead.
+#[cfg(feature = "log-compat")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LogMeta {
+    pub(crate) target: SmartString,
+    pub(crate) module_path: Option<SmartString>,
+    pub(crate) file: Option<SmartString>,
+    pub(crate) line: Option<u32>,
+}
+
+/// How often an [`Event`] was recorded by a layer that
+/// [coalesces repeats](crate::Layer::coalesce_repeats).
+#[derive(Debug)]
+pub(crate) struct Repeats {
+    pub(crate) count: AtomicU32,
+    /// The timestamp of the latest repeat.
+    pub(crate) last: AtomicI64,
+}
+
+/// The thread an [`Event`] was recorded on.
+///
+/// Displays as the thread's name, or its id if it has no name.
+#[derive(Debug, Clone)]
+pub struct ThreadInfo {
+    pub(crate) id: ThreadId,
+    pub(crate) name: Option<SmartString>,
 }
 
 /// A record of a tracing [span](https://docs.rs/tracing/0.1/tracing/index.html#spans).
@@ -20,20 +74,211 @@ pub struct Span {
     pub(crate) meta: &'static tracing::Metadata<'static>,
     pub(crate) fields: FieldMap,
     pub(crate) parent: Option<Arc<Span>>,
+    pub(crate) instance: Arc<SpanInstance>,
+    pub(crate) generation: u64,
+    /// Whether the span was only archived after it was created, so the
+    /// values recorded on it before then are missing.
+    pub(crate) fields_unavailable: bool,
 }
 
-type FieldMap = IndexMap<&'static str, Field, ahash::RandomState>;
+/// Identifies an archived [`Span`] across its snapshots; see [`Span::key`].
+///
+/// Keys are only unique among spans that are still archived: once every
+/// snapshot of a span is dropped, a new span may reuse its key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SpanKey(usize);
+
+/// The distinct spans that `events` were recorded in, and their parents.
+///
+/// Spans are listed in order of first appearance, each after its parent.
+/// Each is the first snapshot seen of that span, so may not have fields
+/// recorded later; see [`Span::latest`].
+pub fn distinct_spans<'a>(events: impl IntoIterator<Item = &'a Event>) -> Vec<&'a Span> {
+    let mut seen = HashSet::with_hasher(ahash::RandomState::new());
+    let mut spans = Vec::new();
+    let mut chain = Vec::new();
+    for event in events {
+        chain.extend(
+            std::iter::successors(event.span(), |span| span.parent())
+                .take_while(|span| !seen.contains(&span.key())),
+        );
+        for span in chain.drain(..).rev() {
+            seen.insert(span.key());
+            spans.push(span);
+        }
+    }
+    spans
+}
+
+/// State shared by every archived snapshot of a single span.
+///
+/// Recording fields clones the archived [`Span`], but timing is tracked for
+/// the span itself, so it lives behind a shared pointer instead.
+#[derive(Debug)]
+pub(crate) struct SpanInstance {
+    /// The span's [`tracing::span::Id`], as a `u64`.
+    pub(crate) id: u64,
+    pub(crate) created: i64,
+    pub(crate) first_entered: OnceCell<i64>,
+    pub(crate) busy_nanos: AtomicU64,
+    pub(crate) entered: AtomicBool,
+    pub(crate) closed: OnceCell<i64>,
+    /// The generation of the newest snapshot, bumped whenever fields are recorded.
+    pub(crate) generation: AtomicU64,
+    /// The fields of the newest snapshot, if there has been more than one.
+    pub(crate) latest_fields: Mutex<FieldMap>,
+}
+
+impl SpanInstance {
+    pub(crate) fn new(id: &tracing::span::Id) -> Self {
+        SpanInstance {
+            id: id.into_u64(),
+            created: clock::now(),
+            first_entered: OnceCell::new(),
+            busy_nanos: AtomicU64::new(0),
+            entered: AtomicBool::new(false),
+            closed: OnceCell::new(),
+            generation: AtomicU64::new(0),
+            latest_fields: Mutex::default(),
+        }
+    }
+}
+
+pub(crate) type FieldMap = IndexMap<&'static str, Field, ahash::RandomState>;
 
 /// A field recorded on some tracing event/span.
+///
+/// tracing passes `%value` (`Display`) fields to the subscriber as `Debug`,
+/// so those are recorded as [`Field::Debug`] holding their `Display` output.
+/// Only the formatted `message` of an event is known to be [`Field::Display`].
+///
+/// A field recorded more than once, like a span field [recorded][tracing::Span::record]
+/// again, keeps every value in order as [`Field::Multiple`], even if they
+/// have different types. The typed accessors like [`as_u64`](Self::as_u64)
+/// return `None` for those; [`iter`](Self::iter) goes through each value.
+///
+/// An error is recorded as [`Field::Error`] if it has no
+/// [source](std::error::Error::source), and as [`Field::ErrorChain`] with the
+/// text of it and each of its sources if it does.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Field {
     I64(i64),
     U64(u64),
     Bool(bool),
     Str(SmartString),
+    Display(SmartString),
     Error(SmartString),
+    /// An error with sources: its own text, then that of each source in turn.
+    /// It shows as the texts joined by `: `, like `err: cause: root cause`.
+    ErrorChain(SVec<SmartString, 4>),
     Debug(SmartString),
     Multiple(Vec<Field>),
+    /// A text value longer than the [maximum field length](crate::Layer::max_field_len),
+    /// cut short: the start of the text, ending on a char boundary, and how
+    /// many bytes the whole text was. It shows as the text followed by
+    /// `… (+N bytes)`, with the number of bytes left out.
+    Truncated {
+        text: SmartString,
+        original_len: usize,
+    },
+}
+
+/// Shows a [`Field::Truncated`] value.
+struct Elided<'a>(&'a str, usize);
+
+impl fmt::Display for Elided<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Elided(text, original_len) = *self;
+        write!(f, "{}… (+{} bytes)", text, original_len - text.len())
+    }
+}
+
+impl fmt::Debug for Elided<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Shows a [`Field::ErrorChain`] value.
+struct Chain<'a>(&'a [SmartString]);
+
+impl fmt::Display for Chain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (ix, link) in self.0.iter().enumerate() {
+            if ix != 0 {
+                f.write_str(": ")?;
+            }
+            f.write_str(link)?;
+        }
+        Ok(())
+    }
+}
+
+/// The values of a [`Field`]; see [`Field::iter`].
+///
+/// Only [`Field::Multiple`] values need a stack to flatten them.
+enum Values<'a> {
+    One(std::slice::Iter<'a, Field>),
+    Nested(Vec<&'a [Field]>),
+}
+
+impl<'a> Iterator for Values<'a> {
+    type Item = &'a Field;
+
+    fn next(&mut self) -> Option<&'a Field> {
+        let stack = match self {
+            Values::One(value) => return value.next(),
+            Values::Nested(stack) => stack,
+        };
+        loop {
+            let values = stack.last_mut()?;
+            match values.split_first() {
+                None => {
+                    stack.pop();
+                }
+                Some((Field::Multiple(nested), rest)) => {
+                    *values = rest;
+                    stack.push(nested);
+                }
+                Some((value, rest)) => {
+                    *values = rest;
+                    return Some(value);
+                }
+            }
+        }
+    }
+}
+
+/// Whether a field was recorded on an event or span; see [`Event::field_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldState<'a> {
+    /// The field was recorded, with this value.
+    Recorded(&'a Field),
+    /// The field was declared by the callsite, but not recorded, like a field
+    /// given as [`tracing::field::Empty`] that hasn't been recorded yet.
+    Declared,
+    /// The callsite didn't declare the field.
+    Unknown,
+}
+
+impl<'a> FieldState<'a> {
+    fn of(meta: &tracing::Metadata<'_>, fields: &'a FieldMap, name: &str) -> Self {
+        match fields.get(name) {
+            Some(field) => FieldState::Recorded(field),
+            None if meta.fields().field(name).is_some() => FieldState::Declared,
+            None => FieldState::Unknown,
+        }
+    }
+}
+
+/// The names of the fields of `meta` not recorded in `fields`.
+fn unset_fields<'a>(
+    meta: &'static tracing::Metadata<'static>,
+    fields: &'a FieldMap,
+) -> impl Iterator<Item = &'static str> + 'a {
+    (meta.fields().iter())
+        .map(|field| field.name())
+        .filter(move |name| !fields.contains_key(name))
 }
 
 impl Event {
@@ -42,9 +287,154 @@ impl Event {
         self.meta
     }
 
+    /// The source file the event was fired from, if known; see also
+    /// [`effective_file`](Self::effective_file).
+    pub fn file(&self) -> Option<&'static str> {
+        self.meta.file()
+    }
+
+    /// The source line the event was fired from, if known; see also
+    /// [`effective_line`](Self::effective_line).
+    pub fn line(&self) -> Option<u32> {
+        self.meta.line()
+    }
+
     /// The time at which this event was fired.
-    pub fn timestamp(&self) -> NaiveDateTime {
-        self.timestamp
+    ///
+    /// This is the same instant as [`timestamp`](Self::timestamp), which
+    /// needs the `chrono` feature.
+    pub fn time(&self) -> SystemTime {
+        clock::system_time(self.timestamp)
+    }
+
+    /// The time at which this event was fired, in UTC.
+    ///
+    /// Convert it to local time with [`DateTime::with_timezone`] only to show
+    /// it, so that times from before and after a DST change, or from
+    /// machines in other timezones, still compare correctly.
+    #[cfg(feature = "chrono")]
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        clock::utc(self.timestamp)
+    }
+
+    /// The time at which this event was fired, in UTC.
+    #[cfg(feature = "chrono")]
+    #[deprecated(note = "use timestamp() / time()")]
+    pub fn timestamp_utc(&self) -> DateTime<Utc> {
+        self.timestamp()
+    }
+
+    /// The local time at which this event was fired, with its offset from UTC.
+    #[cfg(feature = "chrono")]
+    #[deprecated(note = "use timestamp() / time()")]
+    pub fn timestamp_local(&self) -> DateTime<Local> {
+        clock::local_offset(self.timestamp)
+    }
+
+    /// How long after [`start_time`](crate::start_time) this event was fired,
+    /// by the wall clock its [time](Self::time) is read from.
+    ///
+    /// This agrees with subtracting `start_time` from the
+    /// [timestamp](Self::timestamp), but jumps along with changes to the
+    /// system clock. To time events against each other, use
+    /// [`elapsed_since_start`](Self::elapsed_since_start) instead.
+    pub fn wall_time_since_start(&self) -> Duration {
+        Duration::from_nanos(self.timestamp.saturating_sub(clock::start()).max(0) as u64)
+    }
+
+    /// How long after [`start_time`](crate::start_time) this event was fired,
+    /// by the monotonic clock.
+    ///
+    /// Unlike timestamps, which follow changes to the wall clock, this only
+    /// counts time that actually passed, so it never decreases from one event
+    /// to the next and the difference between two events is reliable. Prefer
+    /// this unless the duration has to line up with the timestamps.
+    pub fn elapsed_since_start(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The time at which the last repeat of this event was fired.
+    ///
+    /// This is the same as [`time`](Self::time) unless the event was
+    /// [repeated](Self::repeat_count).
+    pub fn last_time(&self) -> SystemTime {
+        clock::system_time(self.repeats.last.load(Ordering::Relaxed))
+    }
+
+    /// The time at which the last repeat of this event was fired, in UTC.
+    ///
+    /// This is the same as [`timestamp`](Self::timestamp) unless the event
+    /// was [repeated](Self::repeat_count).
+    #[cfg(feature = "chrono")]
+    pub fn last_timestamp(&self) -> DateTime<Utc> {
+        clock::utc(self.repeats.last.load(Ordering::Relaxed))
+    }
+
+    /// How many times this event was fired in a row.
+    ///
+    /// This is 1 unless the event was recorded by a layer that
+    /// [coalesces repeats](crate::Layer::coalesce_repeats), in which case
+    /// identical events recorded right after this one are counted here
+    /// instead of being recorded separately.
+    pub fn repeat_count(&self) -> u32 {
+        self.repeats.count.load(Ordering::Relaxed)
+    }
+
+    /// The position of this event in the order it was recorded into its store.
+    ///
+    /// Sequence numbers start at 1 and increase by 1 for each event recorded,
+    /// so a store's events are in sequence; see
+    /// [`EventStore::with_events_since`](crate::EventStore::with_events_since).
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// The target of this event, looking through `log` records.
+    ///
+    /// With the `log-compat` feature, this is the target of the `log` record
+    /// for events bridged from `log` by tracing-log, whose [metadata](Self::meta)
+    /// target is always `log`. Otherwise, it is the metadata's target.
+    pub fn effective_target(&self) -> &str {
+        #[cfg(feature = "log-compat")]
+        if let Some(log) = &self.log {
+            return &log.target;
+        }
+        self.meta.target()
+    }
+
+    /// The module path of this event, looking through `log` records like
+    /// [`effective_target`](Self::effective_target).
+    pub fn effective_module_path(&self) -> Option<&str> {
+        #[cfg(feature = "log-compat")]
+        if let Some(log) = &self.log {
+            return log.module_path.as_deref();
+        }
+        self.meta.module_path()
+    }
+
+    /// The source file of this event, looking through `log` records like
+    /// [`effective_target`](Self::effective_target).
+    pub fn effective_file(&self) -> Option<&str> {
+        #[cfg(feature = "log-compat")]
+        if let Some(log) = &self.log {
+            return log.file.as_deref();
+        }
+        self.meta.file()
+    }
+
+    /// The source line of this event, looking through `log` records like
+    /// [`effective_target`](Self::effective_target).
+    pub fn effective_line(&self) -> Option<u32> {
+        #[cfg(feature = "log-compat")]
+        if let Some(log) = &self.log {
+            return log.line;
+        }
+        self.meta.line()
+    }
+
+    /// The thread on which this event was fired.
+    pub fn thread(&self) -> &ThreadInfo {
+        &self.thread
     }
 
     /// A recorded field on this event.
@@ -52,6 +442,69 @@ impl Event {
         self.fields.get(name)
     }
 
+    /// The event's message, if it has one: the text its macro formatted, as
+    /// in `info!("hi {}", name)`, or its field named `message`.
+    ///
+    /// The message is always text, a [`Field::Display`], or a [`Field::Str`]
+    /// if given as a string, as in `info!(message = "hi")`, or truncated as
+    /// [`Field::Truncated`]. A message given as a number, bool, or error is
+    /// recorded as its display text.
+    pub fn message(&self) -> Option<&Field> {
+        self.field("message")
+    }
+
+    /// A recorded field on this event, if it is a `u64`; see [`Field::as_u64`].
+    pub fn field_u64(&self, name: &str) -> Option<u64> {
+        self.field(name)?.as_u64()
+    }
+
+    /// A recorded field on this event, if it is an `i64`; see [`Field::as_i64`].
+    pub fn field_i64(&self, name: &str) -> Option<i64> {
+        self.field(name)?.as_i64()
+    }
+
+    /// A recorded field on this event, if it is a `bool`; see [`Field::as_bool`].
+    pub fn field_bool(&self, name: &str) -> Option<bool> {
+        self.field(name)?.as_bool()
+    }
+
+    /// A recorded field on this event, if it is text; see [`Field::as_str`].
+    pub fn field_str(&self, name: &str) -> Option<&str> {
+        self.field(name)?.as_str()
+    }
+
+    /// Whether `name` was recorded on this event, or only declared.
+    pub fn field_state(&self, name: &str) -> FieldState<'_> {
+        match self.is_log_field(name) {
+            true => FieldState::Unknown,
+            false => FieldState::of(self.meta, &self.fields, name),
+        }
+    }
+
+    /// The names of every field declared for this event, recorded or not.
+    pub fn declared_fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        (self.meta.fields().iter())
+            .map(|field| field.name())
+            .filter(move |name| !self.is_log_field(name))
+    }
+
+    /// The names of the fields declared for this event but not recorded.
+    pub fn unset_fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        unset_fields(self.meta, &self.fields).filter(move |name| !self.is_log_field(name))
+    }
+
+    /// Whether `name` is one of the `log.*` fields [moved out](Self::fold_log_fields)
+    /// of a bridged `log` record.
+    #[cfg(feature = "log-compat")]
+    fn is_log_field(&self, name: &str) -> bool {
+        self.log.is_some() && name.starts_with("log.")
+    }
+
+    #[cfg(not(feature = "log-compat"))]
+    fn is_log_field(&self, _: &str) -> bool {
+        false
+    }
+
     /// All recorded fields on this event.
     pub fn fields(&self) -> impl Iterator<Item = (&'static str, &Field)> + '_ {
         self.fields.iter().map(|(&name, field)| (name, field))
@@ -62,11 +515,86 @@ impl Event {
         self.span.as_deref()
     }
 
+    /// The [`tracing::span::Id`] of the containing span, as a `u64`.
+    ///
+    /// This is known even when the span itself wasn't archived, such as when
+    /// it was created before the layer was added. Like [`Span::id`], the id is
+    /// only meaningful while the span is open, as the subscriber may reuse it.
+    pub fn span_id(&self) -> Option<u64> {
+        self.span_id
+    }
+
+    /// Whether `other` is a repeat of this event: the same callsite, thread,
+    /// span, and fields.
+    pub(crate) fn is_repeated_by(&self, other: &Event) -> bool {
+        std::ptr::eq(self.meta, other.meta)
+            && self.thread.id == other.thread.id
+            && match (&self.span, &other.span) {
+                (Some(this), Some(other)) => Arc::ptr_eq(this, other),
+                (None, None) => true,
+                _ => false,
+            }
+            && self.fields == other.fields
+            && self.same_log(other)
+    }
+
+    #[cfg(feature = "log-compat")]
+    fn same_log(&self, other: &Event) -> bool {
+        self.log == other.log
+    }
+
+    #[cfg(not(feature = "log-compat"))]
+    fn same_log(&self, _: &Event) -> bool {
+        true
+    }
+
+    /// Move the `log.*` fields of a bridged `log` record out of the fields.
+    #[cfg(feature = "log-compat")]
+    pub(crate) fn fold_log_fields(&mut self) {
+        let target = match self.fields.get("log.target").and_then(Field::as_str) {
+            Some(target) => target.into(),
+            None => return,
+        };
+        let mut take = |name| self.fields.shift_remove(name);
+        take("log.target");
+        let module_path = take("log.module_path");
+        let file = take("log.file");
+        let line = take("log.line");
+        self.log = Some(Box::new(LogMeta {
+            target,
+            module_path: module_path.as_ref().and_then(Field::as_str).map(Into::into),
+            file: file.as_ref().and_then(Field::as_str).map(Into::into),
+            line: line
+                .as_ref()
+                .and_then(Field::as_u64)
+                .and_then(|line| u32::try_from(line).ok()),
+        }));
+    }
+
+    /// Count a repeat of this event fired at `timestamp`.
+    pub(crate) fn repeat(&self, timestamp: i64) {
+        self.repeats.count.fetch_add(1, Ordering::Relaxed);
+        self.repeats.last.fetch_max(timestamp, Ordering::Relaxed);
+    }
+
     pub(crate) fn record_field(
         &mut self,
         field: &tracing::field::Field,
         value: impl Fn() -> Field,
     ) {
+        // the message is always text; see `message`
+        let value = || match value() {
+            value @ Field::I64(_)
+            | value @ Field::U64(_)
+            | value @ Field::Bool(_)
+            | value @ Field::Error(_)
+            | value @ Field::ErrorChain(_)
+                if field.name() == "message" =>
+            {
+                Field::Display(value.to_string().into())
+            }
+            value => value,
+        };
         self.fields
             .entry(field.name())
             .and_modify(|entry| {
@@ -82,6 +610,63 @@ impl Event {
     }
 }
 
+impl Repeats {
+    pub(crate) fn new(timestamp: i64) -> Self {
+        Repeats {
+            count: AtomicU32::new(1),
+            last: AtomicI64::new(timestamp),
+        }
+    }
+}
+
+impl Clone for Repeats {
+    fn clone(&self) -> Self {
+        Repeats {
+            count: AtomicU32::new(self.count.load(Ordering::Relaxed)),
+            last: AtomicI64::new(self.last.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl ThreadInfo {
+    /// The current thread's info, shared by all events recorded on it.
+    pub(crate) fn current() -> Arc<Self> {
+        thread_local! {
+            static CURRENT: Arc<ThreadInfo> = Arc::new(ThreadInfo::new(&thread::current()));
+        }
+        // the thread local is unavailable while the thread is being torn down
+        CURRENT
+            .try_with(Arc::clone)
+            .unwrap_or_else(|_| Arc::new(ThreadInfo::new(&thread::current())))
+    }
+
+    fn new(thread: &thread::Thread) -> Self {
+        ThreadInfo {
+            id: thread.id(),
+            name: thread.name().map(Into::into),
+        }
+    }
+
+    /// The id of the thread.
+    pub fn id(&self) -> ThreadId {
+        self.id
+    }
+
+    /// The name of the thread, if it was given one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+impl fmt::Display for ThreadInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{:?}", self.id),
+        }
+    }
+}
+
 impl Span {
     /// The [`tracing::Metadata`] describing this span.
     pub fn meta(&self) -> &'static tracing::Metadata<'static> {
@@ -93,16 +678,164 @@ impl Span {
         self.fields.get(name)
     }
 
+    /// A recorded field on this span, if it is a `u64`; see [`Field::as_u64`].
+    pub fn field_u64(&self, name: &str) -> Option<u64> {
+        self.field(name)?.as_u64()
+    }
+
+    /// A recorded field on this span, if it is an `i64`; see [`Field::as_i64`].
+    pub fn field_i64(&self, name: &str) -> Option<i64> {
+        self.field(name)?.as_i64()
+    }
+
+    /// A recorded field on this span, if it is a `bool`; see [`Field::as_bool`].
+    pub fn field_bool(&self, name: &str) -> Option<bool> {
+        self.field(name)?.as_bool()
+    }
+
+    /// A recorded field on this span, if it is text; see [`Field::as_str`].
+    pub fn field_str(&self, name: &str) -> Option<&str> {
+        self.field(name)?.as_str()
+    }
+
+    /// Whether `name` was recorded on this snapshot of the span, or only
+    /// declared; it may have been [recorded later](Self::latest).
+    pub fn field_state(&self, name: &str) -> FieldState<'_> {
+        FieldState::of(self.meta, &self.fields, name)
+    }
+
+    /// The names of every field declared for this span, recorded or not.
+    pub fn declared_fields(&self) -> impl Iterator<Item = &'static str> {
+        self.meta.fields().iter().map(|field| field.name())
+    }
+
+    /// The names of the fields declared for this span but not recorded on
+    /// this snapshot.
+    pub fn unset_fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        unset_fields(self.meta, &self.fields)
+    }
+
     /// All recorded fields on this span.
     pub fn fields(&self) -> impl Iterator<Item = (&'static str, &Field)> + '_ {
         self.fields.iter().map(|(&name, field)| (name, field))
     }
 
+    /// Whether the values recorded on this span before it was archived are
+    /// missing.
+    ///
+    /// A span is archived when it is created, but one created while
+    /// [recording was paused](crate::set_recording), or before the layer was
+    /// added to the subscriber, is only archived once an event in it is
+    /// recorded. Its [creation time](Self::created) is then when that was.
+    pub fn fields_unavailable(&self) -> bool {
+        self.fields_unavailable
+    }
+
     /// The containing span, if any.
     pub fn parent(&self) -> Option<&Span> {
         self.parent.as_deref()
     }
 
+    /// Identifies the span this is a snapshot of.
+    ///
+    /// Every snapshot of a span has the same key, even after recording fields
+    /// on it, while separate spans have different keys.
+    pub fn key(&self) -> SpanKey {
+        SpanKey(Arc::as_ptr(&self.instance) as usize)
+    }
+
+    /// The span's [`tracing::span::Id`], as a `u64`.
+    ///
+    /// Ids are only unique among spans that are still open: the subscriber
+    /// may give a closed span's id to a new span. Pair it with
+    /// [`created`](Self::created) to tell those apart, or compare snapshots
+    /// with [`same_instance`](Self::same_instance).
+    pub fn id(&self) -> u64 {
+        self.instance.id
+    }
+
+    /// The time at which this span was created.
+    #[cfg(feature = "chrono")]
+    pub fn created(&self) -> DateTime<Utc> {
+        clock::utc(self.instance.created)
+    }
+
+    /// Whether `other` is a snapshot of the same span as this one, rather
+    /// than a separate span, even one with the same name, fields, or id.
+    pub fn same_instance(&self, other: &Span) -> bool {
+        Arc::ptr_eq(&self.instance, &other.instance)
+    }
+
+    /// The time at which this span was first entered, if it has been entered.
+    #[cfg(feature = "chrono")]
+    pub fn first_entered(&self) -> Option<DateTime<Utc>> {
+        self.instance.first_entered.get().copied().map(clock::utc)
+    }
+
+    /// The total time spent inside this span, if it has been entered.
+    ///
+    /// Only completed entries are counted; time spent in a still-entered span
+    /// is added when it is exited. Overlapping entries from multiple threads
+    /// are only counted once.
+    pub fn busy_time(&self) -> Option<Duration> {
+        if self.instance.entered.load(Ordering::Relaxed) {
+            Some(Duration::from_nanos(
+                self.instance.busy_nanos.load(Ordering::Relaxed),
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// The total time spent inside this span, if it has been entered.
+    #[deprecated(note = "renamed to busy_time")]
+    pub fn busy_duration(&self) -> Option<Duration> {
+        self.busy_time()
+    }
+
+    /// The total time this span has existed without being inside it, if it
+    /// has been entered.
+    ///
+    /// This is the time from creation until close (or now, if still open)
+    /// minus the [busy time](Self::busy_time), so it includes the time spent
+    /// in a still-entered span until that entry is exited.
+    pub fn idle_time(&self) -> Option<Duration> {
+        let busy = self.busy_time()?;
+        let end = match self.instance.closed.get() {
+            Some(&closed) => closed,
+            None => clock::now(),
+        };
+        let lifetime =
+            Duration::from_nanos(end.saturating_sub(self.instance.created).max(0) as u64);
+        Some(lifetime.saturating_sub(busy))
+    }
+
+    /// This span with the fields that have been recorded on it since this
+    /// snapshot was taken, or `None` if it has not changed since.
+    ///
+    /// Events hold a snapshot of their span as of when they were recorded;
+    /// this is how to see values that were [recorded][tracing::Span::record] later.
+    pub fn latest(&self) -> Option<Span> {
+        let generation = self.instance.generation.load(Ordering::Acquire);
+        if generation == self.generation {
+            return None;
+        }
+        let fields = self.instance.latest_fields.lock();
+        if *fields == self.fields {
+            return None;
+        }
+        Some(Span {
+            fields: fields.clone(),
+            generation,
+            ..self.clone()
+        })
+    }
+
+    /// Whether this span has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.instance.closed.get().is_some()
+    }
+
     pub(crate) fn record_field(
         &mut self,
         field: &tracing::field::Field,
@@ -126,48 +859,146 @@ impl Span {
 impl Field {
     /// The field, as would be presented to [`tracing::field::Visit::record_debug`].
     ///
-    /// If the field was recorded multiple times, `record_debug` is called multiple times.
+    /// If the field was recorded multiple times, `record_debug` is called for
+    /// each value in the order they were recorded, as [`iter`](Self::iter) yields them.
     pub fn with_debug<'a, R>(
         &'a self,
-        record_debug: impl 'a + FnMut(&dyn std::fmt::Debug) -> R,
+        mut record_debug: impl 'a + FnMut(&dyn std::fmt::Debug) -> R,
     ) -> impl Iterator<Item = R> + 'a {
-        struct WithDebug<'a, F>(&'a [Field], Vec<&'a [Field]>, F);
-        impl<F, R> Iterator for WithDebug<'_, F>
-        where
-            F: FnMut(&dyn std::fmt::Debug) -> R,
-        {
-            type Item = R;
-            fn next(&mut self) -> Option<Self::Item> {
-                if self.0.is_empty() {
-                    self.0 = self.1.pop().unwrap_or(&[]);
-                }
+        self.iter().map(move |value| match value {
+            Field::I64(value) => record_debug(value),
+            Field::U64(value) => record_debug(value),
+            Field::Bool(value) => record_debug(value),
+            Field::Str(value) => record_debug(&&**value as &&str),
+            Field::Display(value) => record_debug(&format_args!("{}", value)),
+            Field::Error(value) => record_debug(&format_args!("{}", value)),
+            Field::ErrorChain(links) => record_debug(&format_args!("{}", Chain(links))),
+            Field::Debug(value) => record_debug(&format_args!("{}", value)),
+            Field::Truncated { text, original_len } => record_debug(&Elided(text, *original_len)),
+            Field::Multiple(_) => unreachable!("iter flattens multiple values"),
+        })
+    }
+
+    /// The value, if it was recorded once as an `i64`, or as a `u64` that fits.
+    ///
+    /// Like the other typed accessors, this returns `None` for a field
+    /// recorded more than once, rather than picking one of the values;
+    /// use [`iter`](Self::iter) to see each of them.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Field::I64(value) => Some(value),
+            Field::U64(value) => i64::try_from(value).ok(),
+            _ => None,
+        }
+    }
+
+    /// The value, if it was recorded once as a `u64`, or as a non-negative `i64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Field::U64(value) => Some(value),
+            Field::I64(value) => u64::try_from(value).ok(),
+            _ => None,
+        }
+    }
 
-                match self.0 {
-                    [] => None,
-                    [head, tail @ ..] => {
-                        let res = match head {
-                            Field::I64(value) => self.2(value),
-                            Field::U64(value) => self.2(value),
-                            Field::Bool(value) => self.2(value),
-                            Field::Str(value) => self.2(&&**value as &&str),
-                            Field::Error(value) => self.2(&format_args!("{}", value)),
-                            Field::Debug(value) => self.2(&format_args!("{}", value)),
-                            Field::Multiple(values) => {
-                                if tail.is_empty() {
-                                    self.0 = &**values;
-                                } else if !values.is_empty() {
-                                    self.1.push(&**values);
-                                }
-                                return self.next();
-                            }
-                        };
-                        self.0 = tail;
-                        Some(res)
+    /// The value, if it was recorded once as a `bool`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Field::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The text, if the field was recorded once as text, including the
+    /// formatted text of `Display`, `Debug`, and error values, and what was
+    /// kept of a [truncated](Field::Truncated) one.
+    ///
+    /// An [error with sources](Field::ErrorChain) has no one text; see
+    /// [`error_chain`](Self::error_chain).
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Field::Str(value)
+            | Field::Display(value)
+            | Field::Error(value)
+            | Field::Debug(value)
+            | Field::Truncated { text: value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The text of the error and of each of its sources in turn, if the
+    /// field was recorded once as an error.
+    ///
+    /// An error without sources yields just its own text.
+    pub fn error_chain(&self) -> Option<impl Iterator<Item = &str>> {
+        let links = match self {
+            Field::Error(value) => std::slice::from_ref(value),
+            Field::ErrorChain(links) => &links[..],
+            _ => return None,
+        };
+        Some(links.iter().map(|link| &**link))
+    }
+
+    /// Whether the value, or any of the values if recorded more than once,
+    /// was [truncated](Field::Truncated), so isn't kept whole.
+    pub fn is_truncated(&self) -> bool {
+        self.iter()
+            .any(|value| matches!(value, Field::Truncated { .. }))
+    }
+
+    /// Each recorded value, flattening [`Multiple`](Field::Multiple) values.
+    ///
+    /// A field recorded once yields just itself.
+    pub fn iter(&self) -> impl Iterator<Item = &Field> {
+        match self {
+            Field::Multiple(values) => Values::Nested(vec![values]),
+            value => Values::One(std::slice::from_ref(value).iter()),
+        }
+    }
+
+    /// The text of the field as [`with_debug`](Self::with_debug) presents it,
+    /// which is how tracing's own formatters show it: strings are quoted, and
+    /// multiple recorded values are joined with `, `.
+    ///
+    /// [`Display`](fmt::Display) is the same, but without quoting strings.
+    pub fn to_display_string(&self) -> String {
+        let values: Vec<String> = self.with_debug(|value| format!("{:?}", value)).collect();
+        values.join(", ")
+    }
+
+    /// Whether the field was recorded as text to be shown as-is,
+    /// rather than as a value's `Debug` representation.
+    pub fn is_stringish(&self) -> bool {
+        matches!(
+            self,
+            Field::Str(_) | Field::Display(_) | Field::Error(_) | Field::ErrorChain(_)
+        )
+    }
+}
+
+/// Shows the field without `Debug` quoting of strings, joining multiple
+/// recorded values with `, `.
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::I64(value) => value.fmt(f),
+            Field::U64(value) => value.fmt(f),
+            Field::Bool(value) => value.fmt(f),
+            Field::Str(value)
+            | Field::Display(value)
+            | Field::Error(value)
+            | Field::Debug(value) => f.write_str(value),
+            Field::ErrorChain(links) => Chain(links).fmt(f),
+            Field::Truncated { text, original_len } => Elided(text, *original_len).fmt(f),
+            Field::Multiple(_) => {
+                for (ix, value) in self.iter().enumerate() {
+                    if ix != 0 {
+                        f.write_str(", ")?;
                     }
+                    value.fmt(f)?;
                 }
+                Ok(())
             }
         }
-
-        WithDebug(std::slice::from_ref(self), vec![], record_debug)
     }
 }
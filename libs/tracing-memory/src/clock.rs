@@ -0,0 +1,246 @@
+//! Cheap wall-clock timestamps.
+//!
+//! Reading the system clock and converting it to local time is a measurable
+//! part of recording small events. Instead, we read the monotonic clock and
+//! convert it to wall-clock time with a calibration offset that is refreshed
+//! every few seconds by whichever thread notices it is stale. Timestamps are
+//! in UTC; conversion to local time is left to whoever shows them.
+
+#[cfg(feature = "chrono")]
+use chrono::prelude::*;
+use once_cell::sync::Lazy;
+use std::{
+    fmt,
+    sync::atomic::{AtomicI64, AtomicU64, Ordering},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// How long a calibration is trusted before it is refreshed.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+static CLOCK: Lazy<Clock> = Lazy::new(|| Clock::new(Instant::now(), SystemTime::now()));
+
+/// The current time, as nanoseconds since the Unix epoch.
+pub(crate) fn now() -> i64 {
+    CLOCK.at(Instant::now(), SystemTime::now)
+}
+
+/// The current time from [`now`], with the time since the clock started as
+/// measured by the monotonic clock alone.
+pub(crate) fn now_and_elapsed() -> (i64, Duration) {
+    let instant = Instant::now();
+    let elapsed = instant.saturating_duration_since(CLOCK.base);
+    (CLOCK.at(instant, SystemTime::now), elapsed)
+}
+
+/// Start the clock, if it hasn't been yet.
+pub(crate) fn start_now() {
+    Lazy::force(&CLOCK);
+}
+
+/// When the clock started, as a timestamp from [`now`].
+pub(crate) fn start() -> i64 {
+    CLOCK.start
+}
+
+/// Convert a timestamp from [`now`] to a [`SystemTime`].
+pub(crate) fn system_time(timestamp: i64) -> SystemTime {
+    let since = Duration::from_nanos(timestamp.unsigned_abs());
+    if timestamp >= 0 {
+        UNIX_EPOCH + since
+    } else {
+        UNIX_EPOCH - since
+    }
+}
+
+/// Convert a timestamp from [`now`] to local time, keeping the offset from UTC.
+#[cfg(feature = "chrono")]
+pub(crate) fn local_offset(timestamp: i64) -> DateTime<Local> {
+    utc(timestamp).with_timezone(&Local)
+}
+
+/// Convert a timestamp from [`now`] to UTC.
+#[cfg(feature = "chrono")]
+pub(crate) fn utc(timestamp: i64) -> DateTime<Utc> {
+    Utc.timestamp_nanos(timestamp)
+}
+
+/// Shows a timestamp from [`now`] in RFC 3339 format, in UTC, with as many
+/// fractional digits of 3, 6, or 9 as it needs, as chrono's `%FT%T%.fZ` does.
+pub(crate) struct Rfc3339(pub(crate) i64);
+
+impl fmt::Display for Rfc3339 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NANOS_PER_DAY: i64 = 86_400_000_000_000;
+        let (days, nanos) = (
+            self.0.div_euclid(NANOS_PER_DAY),
+            self.0.rem_euclid(NANOS_PER_DAY),
+        );
+        let (year, month, day) = civil_from_days(days);
+        let secs = nanos / 1_000_000_000;
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+        )?;
+        match nanos % 1_000_000_000 {
+            0 => {}
+            frac if frac % 1_000_000 == 0 => write!(f, ".{:03}", frac / 1_000_000)?,
+            frac if frac % 1_000 == 0 => write!(f, ".{:06}", frac / 1_000)?,
+            frac => write!(f, ".{:09}", frac)?,
+        }
+        f.write_str("Z")
+    }
+}
+
+/// The proleptic Gregorian date `days` after the Unix epoch, from
+/// <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+struct Clock {
+    base: Instant,
+    /// Wall-clock nanoseconds since the Unix epoch at `base`, as first calibrated.
+    start: i64,
+    /// Wall-clock nanoseconds since the Unix epoch at `base`.
+    offset: AtomicI64,
+    /// Nanoseconds after `base` at which `offset` was last calibrated.
+    calibrated: AtomicU64,
+}
+
+impl Clock {
+    fn new(base: Instant, wall: SystemTime) -> Self {
+        Clock {
+            base,
+            start: unix_nanos(wall),
+            offset: AtomicI64::new(unix_nanos(wall)),
+            calibrated: AtomicU64::new(0),
+        }
+    }
+
+    fn at(&self, instant: Instant, wall: impl FnOnce() -> SystemTime) -> i64 {
+        let elapsed = nanos(instant.saturating_duration_since(self.base));
+        let calibrated = self.calibrated.load(Ordering::Relaxed);
+        if elapsed.saturating_sub(calibrated) >= nanos(REFRESH_INTERVAL) {
+            self.calibrate(elapsed, wall());
+        }
+        self.offset.load(Ordering::Relaxed) + elapsed as i64
+    }
+
+    fn calibrate(&self, elapsed: u64, wall: SystemTime) {
+        self.calibrated.store(elapsed, Ordering::Relaxed);
+        // Only ever move the offset forward, so that a refresh never makes
+        // converted timestamps jump backwards. If the system clock is set
+        // back, timestamps continue monotonically until it catches up.
+        let offset = unix_nanos(wall) - elapsed as i64;
+        self.offset.fetch_max(offset, Ordering::Relaxed);
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_nanos().min(u64::MAX as u128) as u64
+}
+
+fn unix_nanos(wall: SystemTime) -> i64 {
+    match wall.duration_since(UNIX_EPOCH) {
+        Ok(since) => nanos(since) as i64,
+        Err(before) => -(nanos(before.duration()) as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn converts_by_offset() {
+        let (base, wall) = (Instant::now(), SystemTime::now());
+        let clock = Clock::new(base, wall);
+        let unreachable = || unreachable!("calibrated before refresh interval");
+        assert_eq!(clock.at(base, unreachable), unix_nanos(wall));
+        assert_eq!(
+            clock.at(base + 2 * SECOND, unreachable),
+            unix_nanos(wall + 2 * SECOND),
+        );
+    }
+
+    #[test]
+    fn recalibrates_forward() {
+        let (base, wall) = (Instant::now(), SystemTime::now());
+        let clock = Clock::new(base, wall);
+        let later = base + REFRESH_INTERVAL;
+        let drifted = wall + REFRESH_INTERVAL + SECOND;
+        assert_eq!(clock.at(later, || drifted), unix_nanos(drifted));
+        assert_eq!(
+            clock.at(later + SECOND, || unreachable!()),
+            unix_nanos(drifted + SECOND),
+        );
+    }
+
+    #[test]
+    fn never_recalibrates_backward() {
+        let (base, wall) = (Instant::now(), SystemTime::now());
+        let clock = Clock::new(base, wall);
+        let before = clock.at(base + REFRESH_INTERVAL - SECOND, || unreachable!());
+        let later = base + REFRESH_INTERVAL;
+        let after = clock.at(later, || wall);
+        assert!(after > before);
+        assert_eq!(after, unix_nanos(wall + REFRESH_INTERVAL));
+    }
+
+    #[test]
+    fn system_time_round_trips() {
+        let wall = SystemTime::now();
+        assert_eq!(system_time(unix_nanos(wall)), wall);
+        let before = UNIX_EPOCH - SECOND;
+        assert_eq!(system_time(unix_nanos(before)), before);
+    }
+
+    #[test]
+    fn rfc3339() {
+        let cases = [
+            (0, "1970-01-01T00:00:00Z"),
+            (1_628_946_309_042_000_000, "2021-08-14T13:05:09.042Z"),
+            (1_628_946_309_042_100_000, "2021-08-14T13:05:09.042100Z"),
+            (1_628_946_309_042_100_001, "2021-08-14T13:05:09.042100001Z"),
+            (951_782_400_000_000_000, "2000-02-29T00:00:00Z"),
+            (-1, "1969-12-31T23:59:59.999999999Z"),
+        ];
+        for &(timestamp, expected) in &cases {
+            assert_eq!(Rfc3339(timestamp).to_string(), expected);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn rfc3339_matches_chrono() {
+        let mut timestamp = -2_000_000_000_000_000_123;
+        while timestamp < 4_000_000_000_000_000_000 {
+            let chrono = utc(timestamp).format("%FT%T%.fZ").to_string();
+            assert_eq!(Rfc3339(timestamp).to_string(), chrono);
+            timestamp += 3_333_333_333_333_337;
+        }
+    }
+}
@@ -0,0 +1,628 @@
+use crate::{
+    archive::{FieldMap, Repeats, SpanInstance},
+    snapshot::SharedEvents,
+    stats, Event, Field, Span, ThreadInfo,
+};
+use indexmap::IndexSet;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI64, AtomicU32},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// When a store compresses its old events; see [`EventStore::set_compression`](crate::EventStore::set_compression).
+///
+/// Events are compressed a chunk at a time, once every event of the chunk is
+/// older than `older_than` or followed by at least `keep_newest` events.
+/// With neither set, nothing is compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    /// Compress events recorded longer ago than this, by the monotonic clock.
+    pub older_than: Option<Duration>,
+    /// Compress all but the newest this many events.
+    pub keep_newest: Option<usize>,
+    /// How many events are compressed together. Larger chunks compress
+    /// better, but reading an event decompresses its whole chunk.
+    pub chunk_len: usize,
+    /// How many chunks are kept decompressed after being read, so that
+    /// reading recent ones again doesn't decompress them again.
+    pub cached_chunks: usize,
+    #[doc(hidden)]
+    pub _non_exhaustive_but_allow_fru: (),
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression {
+            older_than: Some(Duration::from_secs(10 * 60)),
+            keep_newest: None,
+            chunk_len: 1024,
+            cached_chunks: 4,
+            _non_exhaustive_but_allow_fru: (),
+        }
+    }
+}
+
+/// A store's oldest events, compressed, with the chunks read most recently.
+#[derive(Debug)]
+pub(crate) struct Compressed {
+    options: Option<Compression>,
+    chunks: Vec<CompressedChunk>,
+    /// Decompressed chunks by id, the most recently read last.
+    cache: Vec<(u64, Arc<[Arc<Event>]>)>,
+    next_id: u64,
+}
+
+/// Events encoded together and compressed, which are decoded as a whole.
+///
+/// What the events share and can't be encoded, like their metadata and
+/// spans' timing, is kept alongside in tables the encoding refers to.
+#[derive(Debug)]
+struct CompressedChunk {
+    id: u64,
+    len: usize,
+    /// The sequence number of the last event.
+    last_seq: u64,
+    /// Roughly how many bytes the events took uncompressed; see [`EventStats::approx_bytes`](crate::EventStats::approx_bytes).
+    approx_bytes: usize,
+    data: Box<[u8]>,
+    metas: Box<[&'static tracing::Metadata<'static>]>,
+    names: Box<[&'static str]>,
+    threads: Box<[Arc<ThreadInfo>]>,
+    instances: Box<[Arc<SpanInstance>]>,
+}
+
+impl Compressed {
+    pub(crate) const fn new() -> Self {
+        Compressed {
+            options: None,
+            chunks: Vec::new(),
+            cache: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    pub(crate) fn options(&self) -> Option<Compression> {
+        self.options
+    }
+
+    /// Compress as `options` say from now on, keeping only as many chunks
+    /// decompressed as they allow.
+    pub(crate) fn set_options(&mut self, options: Option<Compression>) {
+        self.options = options;
+        let capacity = self.cache_capacity();
+        if self.cache.len() > capacity {
+            self.cache.drain(..self.cache.len() - capacity);
+        }
+    }
+
+    fn cache_capacity(&self) -> usize {
+        self.options.map_or(0, |options| options.cached_chunks)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// The sequence number of the last compressed event, if any.
+    pub(crate) fn last_seq(&self) -> Option<u64> {
+        self.chunks.last().map(|chunk| chunk.last_seq)
+    }
+
+    /// Compress the oldest of `events`, a chunk at a time, for as long as
+    /// the options allow, except that `keep` events aren't compressed.
+    ///
+    /// Returns whether any were.
+    pub(crate) fn compress(
+        &mut self,
+        shared: &mut SharedEvents,
+        now: Duration,
+        keep: impl Fn(&Arc<Event>) -> bool,
+    ) -> bool {
+        let options = match self.options {
+            Some(options) if options.chunk_len != 0 => options,
+            _ => return false,
+        };
+        let old = |event: &Arc<Event>| {
+            let age = now.saturating_sub(event.elapsed);
+            options.older_than.is_some_and(|older| age >= older)
+        };
+        let events = shared.get();
+        let mut compressed = 0;
+        loop {
+            let start = compressed * options.chunk_len;
+            let chunk = match events.get(start..start + options.chunk_len) {
+                Some(chunk) => chunk,
+                None => break,
+            };
+            let followed = events.len() - (start + options.chunk_len);
+            let by_count = options.keep_newest.is_some_and(|keep| followed >= keep);
+            if !chunk
+                .iter()
+                .all(|event| (by_count || old(event)) && !keep(event))
+            {
+                break;
+            }
+            let id = self.next_id;
+            self.next_id += 1;
+            self.chunks.push(CompressedChunk::new(id, chunk));
+            compressed += 1;
+        }
+        if compressed == 0 {
+            return false;
+        }
+        // only now, as this copies the events if a snapshot of them is around
+        shared.get_mut().drain(..compressed * options.chunk_len);
+        true
+    }
+
+    /// The compressed events with a sequence number after `seq`, in order.
+    pub(crate) fn since(&mut self, seq: u64) -> Vec<Arc<Event>> {
+        let first = self.chunks.partition_point(|chunk| chunk.last_seq <= seq);
+        let mut events = Vec::new();
+        for chunk_ix in first..self.chunks.len() {
+            let chunk = self.decompress(chunk_ix);
+            let since = chunk.partition_point(|event| event.seq <= seq);
+            events.extend_from_slice(&chunk[since..]);
+        }
+        events
+    }
+
+    /// Decompress every event, leaving none compressed.
+    pub(crate) fn take(&mut self) -> Vec<Arc<Event>> {
+        let events = self.since(0);
+        self.clear();
+        events
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.chunks.clear();
+        self.cache.clear();
+    }
+
+    /// How many events are compressed, the bytes they take, and roughly how
+    /// many they took uncompressed.
+    pub(crate) fn sizes(&self) -> (usize, usize, usize) {
+        (self.chunks.iter()).fold((0, 0, 0), |(len, bytes, approx_bytes), chunk| {
+            (
+                len + chunk.len,
+                bytes + chunk.bytes(),
+                approx_bytes + chunk.approx_bytes,
+            )
+        })
+    }
+
+    /// The events of the chunk at `chunk_ix`, from the cache if it was read recently.
+    fn decompress(&mut self, chunk_ix: usize) -> Arc<[Arc<Event>]> {
+        let chunk = &self.chunks[chunk_ix];
+        let capacity = self.cache_capacity();
+        if let Some(cached) = self.cache.iter().position(|&(id, _)| id == chunk.id) {
+            let entry = self.cache.remove(cached);
+            let events = Arc::clone(&entry.1);
+            self.cache.push(entry);
+            return events;
+        }
+        let events: Arc<[Arc<Event>]> = chunk.decode().into();
+        if capacity != 0 {
+            if self.cache.len() >= capacity {
+                self.cache.drain(..=self.cache.len() - capacity);
+            }
+            self.cache.push((chunk.id, Arc::clone(&events)));
+        }
+        events
+    }
+}
+
+impl CompressedChunk {
+    fn new(id: u64, events: &[Arc<Event>]) -> Self {
+        let mut encoder = Encoder::default();
+        for event in events {
+            encoder.event(event);
+        }
+        let Encoder {
+            bytes,
+            metas,
+            names,
+            threads,
+            instances,
+            ..
+        } = encoder;
+        CompressedChunk {
+            id,
+            len: events.len(),
+            last_seq: events.last().map_or(0, |event| event.seq),
+            approx_bytes: events.iter().map(|event| stats::approx_bytes(event)).sum(),
+            data: compress(&bytes),
+            metas: metas.values.into(),
+            names: names.into_iter().collect(),
+            threads: threads.values.into(),
+            instances: instances.values.into(),
+        }
+    }
+
+    /// The bytes taken by the chunk, besides what its tables point to.
+    fn bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.data.len()
+            + std::mem::size_of_val(&*self.metas)
+            + std::mem::size_of_val(&*self.names)
+            + std::mem::size_of_val(&*self.threads)
+            + std::mem::size_of_val(&*self.instances)
+    }
+
+    fn decode(&self) -> Vec<Arc<Event>> {
+        let bytes = decompress(&self.data);
+        let mut decoder = Decoder {
+            chunk: self,
+            bytes: &bytes,
+            spans: Vec::new(),
+        };
+        let mut events = Vec::with_capacity(self.len);
+        while !decoder.bytes.is_empty() {
+            match decoder.uint() {
+                SPAN => {
+                    let span = decoder.span();
+                    decoder.spans.push(Arc::new(span));
+                }
+                _ => events.push(Arc::new(decoder.event())),
+            }
+        }
+        events
+    }
+}
+
+#[cfg(feature = "lz4")]
+fn compress(bytes: &[u8]) -> Box<[u8]> {
+    lz4_flex::compress_prepend_size(bytes).into()
+}
+
+#[cfg(feature = "lz4")]
+fn decompress(data: &[u8]) -> Vec<u8> {
+    lz4_flex::decompress_size_prepended(data).expect("chunks are compressed by `compress`")
+}
+
+#[cfg(not(feature = "lz4"))]
+fn compress(bytes: &[u8]) -> Box<[u8]> {
+    bytes.into()
+}
+
+#[cfg(not(feature = "lz4"))]
+fn decompress(data: &[u8]) -> Vec<u8> {
+    data.to_vec()
+}
+
+// Each record in a chunk starts with one of these.
+const EVENT: u64 = 0;
+const SPAN: u64 = 1;
+
+// Each field value starts with one of these.
+const I64: u64 = 0;
+const U64: u64 = 1;
+const BOOL: u64 = 2;
+const STR: u64 = 3;
+const DISPLAY: u64 = 4;
+const ERROR: u64 = 5;
+const ERROR_CHAIN: u64 = 6;
+const DEBUG: u64 = 7;
+const MULTIPLE: u64 = 8;
+const TRUNCATED: u64 = 9;
+
+/// Values stored once per chunk, by the address they're shared at.
+struct Table<T> {
+    values: Vec<T>,
+    by_address: HashMap<usize, usize, ahash::RandomState>,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Table {
+            values: Vec::new(),
+            by_address: HashMap::default(),
+        }
+    }
+}
+
+impl<T> Table<T> {
+    fn index(&mut self, address: usize, value: impl FnOnce() -> T) -> usize {
+        let values = &mut self.values;
+        *self.by_address.entry(address).or_insert_with(|| {
+            values.push(value());
+            values.len() - 1
+        })
+    }
+}
+
+/// Encodes events into bytes, with each span written before the first
+/// event or span in it.
+///
+/// Numbers are written as LEB128, signed ones zigzagged first, and text as
+/// its length and then its bytes.
+#[derive(Default)]
+struct Encoder {
+    bytes: Vec<u8>,
+    metas: Table<&'static tracing::Metadata<'static>>,
+    names: IndexSet<&'static str, ahash::RandomState>,
+    threads: Table<Arc<ThreadInfo>>,
+    instances: Table<Arc<SpanInstance>>,
+    /// The spans written so far, by their address.
+    spans: HashMap<usize, usize, ahash::RandomState>,
+}
+
+impl Encoder {
+    fn event(&mut self, event: &Event) {
+        let span = event.span.as_ref().map(|span| self.span(span));
+        self.uint(EVENT);
+        self.meta(event.meta);
+        self.int(event.timestamp);
+        self.uint(event.elapsed.as_secs());
+        self.uint(event.elapsed.subsec_nanos().into());
+        let thread = (self.threads).index(Arc::as_ptr(&event.thread) as usize, || {
+            Arc::clone(&event.thread)
+        });
+        self.uint(thread as u64);
+        self.option(span, |this, span| this.uint(span as u64));
+        self.option(event.span_id, Self::uint);
+        self.uint(event.repeat_count().into());
+        self.int(
+            event
+                .repeats
+                .last
+                .load(std::sync::atomic::Ordering::Relaxed),
+        );
+        self.uint(event.seq);
+        self.fields(&event.fields);
+        #[cfg(feature = "log-compat")]
+        self.option(event.log.as_deref(), |this, log| {
+            this.str(&log.target);
+            this.option(log.module_path.as_deref(), Self::str);
+            this.option(log.file.as_deref(), Self::str);
+            this.option(log.line, |this, line| this.uint(line.into()));
+        });
+    }
+
+    /// Write `span`, and its parents, unless already written, and return its index.
+    fn span(&mut self, span: &Arc<Span>) -> usize {
+        let address = Arc::as_ptr(span) as usize;
+        if let Some(&index) = self.spans.get(&address) {
+            return index;
+        }
+        let parent = span.parent.as_ref().map(|parent| self.span(parent));
+        let instance = (self.instances).index(Arc::as_ptr(&span.instance) as usize, || {
+            Arc::clone(&span.instance)
+        });
+        self.uint(SPAN);
+        self.meta(span.meta);
+        self.uint(instance as u64);
+        self.option(parent, |this, parent| this.uint(parent as u64));
+        self.uint(span.generation);
+        self.uint(span.fields_unavailable.into());
+        self.fields(&span.fields);
+        let index = self.spans.len();
+        self.spans.insert(address, index);
+        index
+    }
+
+    fn meta(&mut self, meta: &'static tracing::Metadata<'static>) {
+        let index = (self.metas).index(meta as *const _ as usize, || meta);
+        self.uint(index as u64);
+    }
+
+    fn fields(&mut self, fields: &FieldMap) {
+        self.uint(fields.len() as u64);
+        for (&name, field) in fields {
+            let (index, _) = self.names.insert_full(name);
+            self.uint(index as u64);
+            self.field(field);
+        }
+    }
+
+    fn field(&mut self, field: &Field) {
+        match field {
+            Field::I64(value) => {
+                self.uint(I64);
+                self.int(*value);
+            }
+            Field::U64(value) => {
+                self.uint(U64);
+                self.uint(*value);
+            }
+            Field::Bool(value) => {
+                self.uint(BOOL);
+                self.uint((*value).into());
+            }
+            Field::Str(text) => self.text(STR, text),
+            Field::Display(text) => self.text(DISPLAY, text),
+            Field::Error(text) => self.text(ERROR, text),
+            Field::ErrorChain(links) => {
+                self.uint(ERROR_CHAIN);
+                self.uint(links.len() as u64);
+                for link in links {
+                    self.str(link);
+                }
+            }
+            Field::Debug(text) => self.text(DEBUG, text),
+            Field::Multiple(values) => {
+                self.uint(MULTIPLE);
+                self.uint(values.len() as u64);
+                for value in values {
+                    self.field(value);
+                }
+            }
+            Field::Truncated { text, original_len } => {
+                self.text(TRUNCATED, text);
+                self.uint(*original_len as u64);
+            }
+        }
+    }
+
+    fn text(&mut self, tag: u64, text: &str) {
+        self.uint(tag);
+        self.str(text);
+    }
+
+    fn str(&mut self, text: &str) {
+        self.uint(text.len() as u64);
+        self.bytes.extend_from_slice(text.as_bytes());
+    }
+
+    fn option<T>(&mut self, value: Option<T>, write: impl FnOnce(&mut Self, T)) {
+        match value {
+            Some(value) => {
+                self.uint(1);
+                write(self, value);
+            }
+            None => self.uint(0),
+        }
+    }
+
+    fn int(&mut self, value: i64) {
+        self.uint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn uint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+}
+
+/// Decodes what an [`Encoder`] wrote.
+struct Decoder<'a> {
+    chunk: &'a CompressedChunk,
+    bytes: &'a [u8],
+    /// The spans read so far, in order.
+    spans: Vec<Arc<Span>>,
+}
+
+impl Decoder<'_> {
+    fn event(&mut self) -> Event {
+        let meta = self.meta();
+        let timestamp = self.int();
+        let elapsed = Duration::new(self.uint(), self.uint() as u32);
+        let thread = Arc::clone(&self.chunk.threads[self.uint() as usize]);
+        let span = self
+            .option(|this| this.uint() as usize)
+            .map(|span| Arc::clone(&self.spans[span]));
+        let span_id = self.option(Self::uint);
+        let repeats = Repeats {
+            count: AtomicU32::new(self.uint() as u32),
+            last: AtomicI64::new(self.int()),
+        };
+        let seq = self.uint();
+        let fields = self.fields();
+        #[cfg(feature = "log-compat")]
+        let log = self.option(|this| {
+            Box::new(crate::archive::LogMeta {
+                target: this.str().into(),
+                module_path: this.option(|this| this.str().into()),
+                file: this.option(|this| this.str().into()),
+                line: this.option(|this| this.uint() as u32),
+            })
+        });
+        Event {
+            meta,
+            timestamp,
+            elapsed,
+            thread,
+            fields,
+            span,
+            span_id,
+            repeats,
+            seq,
+            #[cfg(feature = "log-compat")]
+            log,
+        }
+    }
+
+    fn span(&mut self) -> Span {
+        let meta = self.meta();
+        let instance = Arc::clone(&self.chunk.instances[self.uint() as usize]);
+        let parent = self
+            .option(|this| this.uint() as usize)
+            .map(|parent| Arc::clone(&self.spans[parent]));
+        Span {
+            meta,
+            instance,
+            parent,
+            generation: self.uint(),
+            fields_unavailable: self.uint() != 0,
+            fields: self.fields(),
+        }
+    }
+
+    fn meta(&mut self) -> &'static tracing::Metadata<'static> {
+        self.chunk.metas[self.uint() as usize]
+    }
+
+    fn fields(&mut self) -> FieldMap {
+        let len = self.uint() as usize;
+        let mut fields = FieldMap::with_capacity_and_hasher(len, Default::default());
+        for _ in 0..len {
+            let name = self.chunk.names[self.uint() as usize];
+            let field = self.field();
+            fields.insert(name, field);
+        }
+        fields
+    }
+
+    fn field(&mut self) -> Field {
+        match self.uint() {
+            I64 => Field::I64(self.int()),
+            U64 => Field::U64(self.uint()),
+            BOOL => Field::Bool(self.uint() != 0),
+            STR => Field::Str(self.str().into()),
+            DISPLAY => Field::Display(self.str().into()),
+            ERROR => Field::Error(self.str().into()),
+            ERROR_CHAIN => {
+                let len = self.uint() as usize;
+                Field::ErrorChain((0..len).map(|_| self.str().into()).collect())
+            }
+            DEBUG => Field::Debug(self.str().into()),
+            MULTIPLE => {
+                let len = self.uint() as usize;
+                Field::Multiple((0..len).map(|_| self.field()).collect())
+            }
+            TRUNCATED => Field::Truncated {
+                text: self.str().into(),
+                original_len: self.uint() as usize,
+            },
+            tag => unreachable!("unknown field tag {}", tag),
+        }
+    }
+
+    fn str(&mut self) -> &str {
+        let len = self.uint() as usize;
+        let (text, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        std::str::from_utf8(text).expect("chunks hold text as UTF-8")
+    }
+
+    fn option<T>(&mut self, read: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        match self.uint() {
+            0 => None,
+            _ => Some(read(self)),
+        }
+    }
+
+    fn int(&mut self) -> i64 {
+        let value = self.uint();
+        (value >> 1) as i64 ^ -((value & 1) as i64)
+    }
+
+    fn uint(&mut self) -> u64 {
+        let mut value = 0;
+        for (ix, &byte) in self.bytes.iter().enumerate() {
+            value |= u64::from(byte & 0x7f) << (7 * ix);
+            if byte & 0x80 == 0 {
+                self.bytes = &self.bytes[ix + 1..];
+                return value;
+            }
+        }
+        unreachable!("chunks end with a whole number")
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{EventStore, GLOBAL_STORE};
+use std::{fmt, sync::Arc, thread, time::Duration};
+
+/// Flushes an event store when dropped, so that events recorded right before
+/// exit make it into the log.
+///
+/// Hold one in `main` (or wherever the application shuts down). On drop,
+/// including while unwinding, it records a final "session ended" event and
+/// moves all queued events into the log.
+#[derive(Debug)]
+#[must_use = "the store is only flushed when the guard is dropped"]
+pub struct FlushGuard {
+    store: Option<Arc<EventStore>>,
+    exit_status: Option<String>,
+    timeout: Duration,
+}
+
+impl FlushGuard {
+    /// A guard that flushes the global store.
+    pub fn new() -> Self {
+        FlushGuard {
+            store: None,
+            exit_status: None,
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    /// A guard that flushes `store` instead of the global store.
+    pub fn with_store(store: Arc<EventStore>) -> Self {
+        let mut guard = FlushGuard::new();
+        guard.store = Some(store);
+        guard
+    }
+
+    /// Record `status` on the "session ended" event.
+    pub fn set_exit_status(&mut self, status: impl fmt::Display) {
+        self.exit_status = Some(status.to_string());
+    }
+
+    /// How long to wait for the log before giving up on flushing it.
+    ///
+    /// The log is only held up by [`with_events`](EventStore::with_events)
+    /// callbacks, and the file sink by a stuck write; either would otherwise
+    /// block exit. Giving up is counted by [`EventStore::abandoned_flushes`]. The default is one
+    /// second.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+}
+
+impl Default for FlushGuard {
+    fn default() -> Self {
+        FlushGuard::new()
+    }
+}
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        let panicking = thread::panicking();
+        match &self.exit_status {
+            Some(status) => {
+                tracing::info!(target: "tracing_memory", exit_status = %status, panicking, "session ended")
+            }
+            None => tracing::info!(target: "tracing_memory", panicking, "session ended"),
+        }
+        let store = self.store.as_deref().unwrap_or(&GLOBAL_STORE);
+        store.flush(self.timeout);
+    }
+}
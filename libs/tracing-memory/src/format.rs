@@ -0,0 +1,235 @@
+use crate::{clock::Rfc3339, Event};
+#[cfg(feature = "chrono")]
+use std::borrow::Cow;
+use std::{
+    fmt::{self, Write},
+    time::Duration,
+};
+
+/// How [`format_event`] shows when an event was fired.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TimeFormat {
+    /// Not at all.
+    #[default]
+    Hidden,
+    /// The time in UTC, in RFC 3339 format, like `2021-08-14T13:05:09.042Z`,
+    /// as in exported JSON.
+    Rfc3339,
+    /// The time since tracing-memory started keeping time, like `+12.345s`;
+    /// see [`Event::elapsed_since_start`].
+    Elapsed,
+    /// The time since another [time since start](Event::elapsed_since_start),
+    /// e.g. that of the event shown before, like `Δ3ms`.
+    Delta(Duration),
+    /// The local time, with a [chrono format string], like `%H:%M:%S%.3f`.
+    ///
+    /// [chrono format string]: https://docs.rs/chrono/0.4/chrono/format/strftime/index.html
+    #[cfg(feature = "chrono")]
+    Local(Cow<'static, str>),
+    /// The time in UTC, with a [chrono format string], like `%F %T%.3f`.
+    ///
+    /// [chrono format string]: https://docs.rs/chrono/0.4/chrono/format/strftime/index.html
+    #[cfg(feature = "chrono")]
+    Utc(Cow<'static, str>),
+}
+
+impl TimeFormat {
+    /// When `event` was fired, in this format, or `None` if it is hidden.
+    ///
+    /// An invalid chrono format string shows as a note that it is invalid.
+    pub fn format(&self, event: &Event) -> Option<String> {
+        match self {
+            TimeFormat::Hidden => None,
+            TimeFormat::Rfc3339 => Some(Rfc3339(event.timestamp).to_string()),
+            TimeFormat::Elapsed => Some(format!("+{}", Seconds(event.elapsed_since_start()))),
+            TimeFormat::Delta(since) => {
+                let delta = event.elapsed_since_start().saturating_sub(*since);
+                Some(format!("Δ{}", Short(delta)))
+            }
+            #[cfg(feature = "chrono")]
+            TimeFormat::Local(format) => {
+                let local = crate::clock::local_offset(event.timestamp);
+                Some(format_time(local.format(format), format))
+            }
+            #[cfg(feature = "chrono")]
+            TimeFormat::Utc(format) => {
+                let utc = crate::clock::utc(event.timestamp);
+                Some(format_time(utc.format(format), format))
+            }
+        }
+    }
+}
+
+/// The time `formatted` with the chrono format string `format`, or a note
+/// that `format` is invalid.
+#[cfg(feature = "chrono")]
+fn format_time(formatted: impl fmt::Display, format: &str) -> String {
+    let mut text = String::new();
+    match write!(text, "{}", formatted) {
+        Ok(()) => text,
+        Err(_) => format!("invalid time format {:?}", format),
+    }
+}
+
+/// Shows a duration in seconds, to the millisecond.
+struct Seconds(Duration);
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:03}s", self.0.as_secs(), self.0.subsec_millis())
+    }
+}
+
+/// Shows a duration in the largest unit it has whole, up to seconds.
+struct Short(Duration);
+
+impl fmt::Display for Short {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            d if d < Duration::from_millis(1) => write!(f, "{}µs", d.as_micros()),
+            d if d < Duration::from_secs(1) => write!(f, "{}ms", d.as_millis()),
+            d => Seconds(d).fmt(f),
+        }
+    }
+}
+
+/// What [`format_event`] includes of an event.
+///
+/// The default includes everything but the time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// How the event's time is shown, to start with.
+    pub timestamp: TimeFormat,
+    /// The event's level.
+    pub level: bool,
+    /// The name or id of the thread the event was recorded on.
+    pub thread: bool,
+    /// The event's [effective target](Event::effective_target).
+    pub target: bool,
+    /// The event's fields besides its message, each on its own line.
+    pub fields: bool,
+    /// The spans the event was recorded in, innermost first, each on its own
+    /// line, followed by their fields if `fields` is set too.
+    pub spans: bool,
+    #[doc(hidden)]
+    pub _non_exhaustive_but_allow_fru: (),
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            timestamp: TimeFormat::Hidden,
+            level: true,
+            thread: true,
+            target: true,
+            fields: true,
+            spans: true,
+            _non_exhaustive_but_allow_fru: (),
+        }
+    }
+}
+
+/// Format an event as plain text, so that everything showing events as text
+/// shows them the same way.
+///
+/// The first line holds what `options` includes of the timestamp, level,
+/// thread, and target, and then the message, like
+/// `[12:00:00.000] [INFO] [main] app::http: request handled`. The event's
+/// other fields and its spans follow on indented lines. There is no newline
+/// after the last line.
+pub fn format_event(event: &Event, options: &FormatOptions) -> String {
+    let mut text = String::new();
+    write_event(&mut text, event, options).expect("formatting into a String cannot fail");
+    text
+}
+
+fn write_event(f: &mut String, event: &Event, options: &FormatOptions) -> fmt::Result {
+    let mut line = Line { f, started: false };
+    if let Some(timestamp) = options.timestamp.format(event) {
+        line.part(format_args!("[{}]", timestamp))?;
+    }
+    if options.level {
+        line.part(format_args!("[{}]", event.meta().level()))?;
+    }
+    if options.thread {
+        line.part(format_args!("[{}]", event.thread()))?;
+    }
+    if options.target {
+        line.part(format_args!("{}:", event.effective_target()))?;
+    }
+    if let Some(message) = event.message() {
+        line.part(format_args!("{}", message))?;
+    }
+
+    if options.fields {
+        for (name, value) in event.fields().filter(|&(name, _)| name != "message") {
+            write!(f, "\n    {}: {}", name, value)?;
+        }
+    }
+    if options.spans {
+        for span in std::iter::successors(event.span(), |span| span.parent()) {
+            write!(
+                f,
+                "\n    in {}::{}",
+                span.meta().target(),
+                span.meta().name()
+            )?;
+            if options.fields {
+                for (name, value) in span.fields() {
+                    write!(f, "\n        {}: {}", name, value)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The first line of a formatted event, with its parts separated by spaces.
+struct Line<'a> {
+    f: &'a mut String,
+    started: bool,
+}
+
+impl Line<'_> {
+    fn part(&mut self, part: fmt::Arguments<'_>) -> fmt::Result {
+        if self.started {
+            self.f.push(' ');
+        }
+        self.started = true;
+        self.f.write_fmt(part)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        let ms = Duration::from_millis;
+        assert_eq!(Seconds(ms(12_345)).to_string(), "12.345s");
+        assert_eq!(Seconds(ms(5)).to_string(), "0.005s");
+        assert_eq!(Short(Duration::from_micros(250)).to_string(), "250µs");
+        assert_eq!(Short(ms(3)).to_string(), "3ms");
+        assert_eq!(Short(ms(1_500)).to_string(), "1.500s");
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn times() {
+        use chrono::{FixedOffset, TimeZone, Utc};
+        let time = Utc.timestamp_nanos(1_628_946_309_042_000_000);
+        let format = |format: &str| format_time(time.format(format), format);
+        assert_eq!(format("%H:%M:%S%.3f"), "13:05:09.042");
+        assert_eq!(format("%FT%T%.3fZ"), "2021-08-14T13:05:09.042Z");
+        assert_eq!(format("%z"), "+0000");
+        assert_eq!(format("%Q"), "invalid time format \"%Q\"");
+
+        let east = time.with_timezone(&FixedOffset::east_opt(2 * 3600).unwrap());
+        assert_eq!(
+            format_time(east.format("%F %T %z"), ""),
+            "2021-08-14 15:05:09 +0200"
+        );
+    }
+}
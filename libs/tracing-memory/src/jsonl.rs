@@ -0,0 +1,136 @@
+use crate::{clock::Rfc3339, Event, EventStore, Field};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+impl EventStore {
+    /// Write the recorded events as JSON Lines, one object per event.
+    ///
+    /// Each object holds the event's `timestamp` in UTC, `level`, `target`, `name`,
+    /// the `thread` it was recorded on, the names of its containing `spans`
+    /// from innermost to outermost, and its `fields`. Unlike [`export_json`](Self::export_json), this does not
+    /// need the `serde` feature.
+    ///
+    /// The events are snapshotted first, so recording is not blocked on `w`.
+    pub fn write_jsonl<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for event in self.snapshot().iter() {
+            write_event(w, event)?;
+            w.write_all(b"\n")?;
+        }
+        w.flush()
+    }
+}
+
+/// A file each event is written to as a JSON Line as it is recorded.
+#[derive(Debug)]
+pub(crate) struct FileSink {
+    writer: BufWriter<File>,
+    /// Whether writing failed, which is only reported once.
+    failed: bool,
+}
+
+impl FileSink {
+    /// Create or truncate the file at `path`.
+    pub(crate) fn create(path: &Path) -> io::Result<Self> {
+        Ok(FileSink {
+            writer: BufWriter::new(File::create(path)?),
+            failed: false,
+        })
+    }
+
+    /// Write `event`, reporting a failure to stderr rather than to the
+    /// recording thread, which has no way to handle it.
+    pub(crate) fn write(&mut self, event: &Event) {
+        let written =
+            write_event(&mut self.writer, event).and_then(|()| self.writer.write_all(b"\n"));
+        self.report(written);
+    }
+
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn report(&mut self, result: io::Result<()>) {
+        if let Err(error) = result {
+            if !std::mem::replace(&mut self.failed, true) {
+                eprintln!(
+                    "tracing-memory: failed to write to the file sink: {}",
+                    error
+                );
+            }
+        }
+    }
+}
+
+fn write_event(w: &mut impl Write, event: &Event) -> io::Result<()> {
+    let meta = event.meta();
+    write!(w, r#"{{"timestamp":"{}""#, Rfc3339(event.timestamp))?;
+    write!(w, r#","level":"{}","target":"#, meta.level())?;
+    write_str(w, event.effective_target())?;
+    w.write_all(br#","name":"#)?;
+    write_str(w, meta.name())?;
+    w.write_all(br#","thread":"#)?;
+    write_str(w, &event.thread().to_string())?;
+
+    w.write_all(br#","spans":["#)?;
+    for (i, span) in std::iter::successors(event.span(), |span| span.parent()).enumerate() {
+        if i != 0 {
+            w.write_all(b",")?;
+        }
+        write_str(w, span.meta().name())?;
+    }
+
+    w.write_all(br#"],"fields":{"#)?;
+    for (i, (name, field)) in event.fields().enumerate() {
+        if i != 0 {
+            w.write_all(b",")?;
+        }
+        write_str(w, name)?;
+        w.write_all(b":")?;
+        write_field(w, field)?;
+    }
+    w.write_all(b"}}")
+}
+
+fn write_field(w: &mut impl Write, field: &Field) -> io::Result<()> {
+    match field {
+        Field::I64(value) => write!(w, "{}", value),
+        Field::U64(value) => write!(w, "{}", value),
+        Field::Bool(value) => write!(w, "{}", value),
+        Field::Str(value) | Field::Display(value) | Field::Error(value) | Field::Debug(value) => {
+            write_str(w, value)
+        }
+        Field::ErrorChain(_) | Field::Truncated { .. } => write_str(w, &field.to_string()),
+        Field::Multiple(values) => {
+            w.write_all(b"[")?;
+            for (i, value) in values.iter().enumerate() {
+                if i != 0 {
+                    w.write_all(b",")?;
+                }
+                write_field(w, value)?;
+            }
+            w.write_all(b"]")
+        }
+    }
+}
+
+fn write_str(w: &mut impl Write, s: &str) -> io::Result<()> {
+    w.write_all(b"\"")?;
+    let mut rest = s;
+    while let Some(i) = rest.find(|c: char| c == '"' || c == '\\' || c.is_ascii_control()) {
+        w.write_all(&rest.as_bytes()[..i])?;
+        match rest.as_bytes()[i] {
+            b'"' => w.write_all(br#"\""#)?,
+            b'\\' => w.write_all(br"\\")?,
+            b'\n' => w.write_all(br"\n")?,
+            b'\r' => w.write_all(br"\r")?,
+            b'\t' => w.write_all(br"\t")?,
+            c => write!(w, r"\u{:04x}", c)?,
+        }
+        rest = &rest[i + 1..];
+    }
+    w.write_all(rest.as_bytes())?;
+    w.write_all(b"\"")
+}
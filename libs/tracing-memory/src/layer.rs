@@ -1,7 +1,18 @@
-use crate::{Event, Field, Span, EVENT_QUEUE};
-use chrono::prelude::*;
-use std::{marker::PhantomData, sync::Arc};
-use tracing::{span, Subscriber};
+use crate::{
+    archive::{Repeats, SpanInstance},
+    clock, Event, EventStore, Field, RecordFilter, Span, ThreadInfo, GLOBAL_STORE,
+};
+use smartstring::alias::String as SmartString;
+use std::{
+    fmt::{self, Write},
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, PoisonError, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{level_filters::LevelFilter, span, Subscriber};
 use tracing_subscriber::{
     field::RecordFields,
     layer,
@@ -9,8 +20,14 @@ use tracing_subscriber::{
 };
 
 /// A tracing [layer](mod@layer) that records events and spans.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct Layer<S> {
+    store: Option<Arc<EventStore>>,
+    coalesce_repeats: bool,
+    record_filter: Option<Arc<RwLock<RecordFilter>>>,
+    /// The most bytes kept of a text field value, or `usize::MAX` for all.
+    max_field_len: usize,
+    max_level: LevelFilter,
     _inner: PhantomData<S>,
 }
 
@@ -18,12 +35,102 @@ impl<S> Layer<S> {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// A layer that records events into `store` instead of the global store.
+    pub fn with_store(store: Arc<EventStore>) -> Self {
+        clock::start_now();
+        Layer {
+            store: Some(store),
+            coalesce_repeats: false,
+            record_filter: None,
+            max_field_len: usize::MAX,
+            max_level: LevelFilter::TRACE,
+            _inner: PhantomData,
+        }
+    }
+
+    /// Count an event that repeats the last one recorded by this layer on
+    /// its [`repeat_count`](Event::repeat_count) instead of recording it again.
+    ///
+    /// An event is a repeat if it comes from the same callsite, thread, and
+    /// span, with equal fields. Repeats are not sent to
+    /// [subscribers](EventStore::subscribe) either.
+    pub fn coalesce_repeats(self, coalesce_repeats: bool) -> Self {
+        Layer {
+            coalesce_repeats,
+            ..self
+        }
+    }
+
+    /// Only record events that `filter` [enables](RecordFilter::enabled).
+    ///
+    /// The filter is checked for each event, so it can be changed while the
+    /// layer is in use. Events it rejects are counted by
+    /// [`EventStore::dropped_by_filter`].
+    pub fn record_filter(self, filter: Arc<RwLock<RecordFilter>>) -> Self {
+        Layer {
+            record_filter: Some(filter),
+            ..self
+        }
+    }
+
+    /// A handle to the [filter](Self::record_filter) on the events this layer
+    /// records, setting one which records everything if there isn't one yet.
+    ///
+    /// Take the handle before adding the layer to a subscriber, and write a
+    /// new [`RecordFilter`] to it to change what is recorded from then on.
+    pub fn register_filter_handle(&mut self) -> Arc<RwLock<RecordFilter>> {
+        Arc::clone(self.record_filter.get_or_insert_with(Default::default))
+    }
+
+    /// Keep at most `max` bytes of each string, debug, or error field value
+    /// this layer records, recording longer values as [`Field::Truncated`].
+    ///
+    /// The limit set with [`set_max_field_len`](crate::set_max_field_len)
+    /// applies too, so the lower one is used. There is no limit by default.
+    pub fn max_field_len(self, max: usize) -> Self {
+        Layer {
+            max_field_len: max,
+            ..self
+        }
+    }
+
+    /// Only record events and spans at `max_level` or more severe ones.
+    ///
+    /// Unlike filtering the subscriber this layer is in, this only applies to
+    /// what is recorded, so other layers still see the more verbose events.
+    /// Unlike a [`record_filter`](Self::record_filter), they are skipped
+    /// before anything is recorded of them, and not counted as dropped. An
+    /// event in a skipped span is recorded in the nearest of its parents
+    /// that isn't skipped. Everything is recorded by default.
+    pub fn with_max_level(self, max_level: LevelFilter) -> Self {
+        Layer { max_level, ..self }
+    }
+
+    /// The same as [`with_max_level`](Self::with_max_level).
+    pub fn max_level(self, max_level: LevelFilter) -> Self {
+        self.with_max_level(max_level)
+    }
+
+    fn store(&self) -> &EventStore {
+        self.store.as_deref().unwrap_or(&GLOBAL_STORE)
+    }
+
+    fn field_len_limit(&self) -> usize {
+        self.max_field_len
+            .min(MAX_FIELD_LEN.load(Ordering::Relaxed))
+    }
 }
 
 impl<S> Default for Layer<S> {
     fn default() -> Self {
-        smartstring::validate();
+        clock::start_now();
         Layer {
+            store: None,
+            coalesce_repeats: false,
+            record_filter: None,
+            max_field_len: usize::MAX,
+            max_level: LevelFilter::TRACE,
             _inner: PhantomData,
         }
     }
@@ -34,22 +141,117 @@ where
     S: Subscriber + for<'a> LookupSpan<'a>,
 {
     fn new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: layer::Context<'_, S>) {
+        if !is_recording() {
+            return;
+        }
         let span = ctx.span(id).expect("Span not found, this is a bug");
-        on_span(span, attrs);
+        if *span.metadata().level() <= self.max_level {
+            on_span(span, attrs, self.field_len_limit(), self.max_level);
+        }
     }
 
     fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: layer::Context<'_, S>) {
+        if !is_recording() {
+            return;
+        }
         let span = ctx.span(id).expect("Span not found; this is a bug");
-        on_span(span, values);
+        if *span.metadata().level() <= self.max_level {
+            on_span(span, values, self.field_len_limit(), self.max_level);
+        }
     }
 
     fn on_event(&self, event: &tracing::Event<'_>, ctx: layer::Context<'_, S>) {
-        let span = ctx.event_span(event);
-        on_event(event, span);
+        if !is_recording() || *event.metadata().level() > self.max_level {
+            return;
+        }
+        if let Some(filter) = &self.record_filter {
+            let filter = filter.read().unwrap_or_else(PoisonError::into_inner);
+            if !filter.enabled(event.metadata()) {
+                self.store().filtered();
+                return;
+            }
+        }
+        let span = within_level(ctx.event_span(event), self.max_level);
+        let max_field_len = self.field_len_limit();
+        on_event(
+            self.store(),
+            event,
+            span,
+            self.coalesce_repeats,
+            max_field_len,
+            self.max_level,
+        );
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: layer::Context<'_, S>) {
+        let span = ctx.span(id).expect("Span not found; this is a bug");
+        let mut ext = span.extensions_mut();
+        if let Some(archived) = ext.get_mut::<Arc<Span>>() {
+            archived.instance.first_entered.get_or_init(clock::now);
+            archived.instance.entered.store(true, Ordering::Relaxed);
+        }
+        match ext.get_mut::<Timing>() {
+            Some(timing) => timing.enter(),
+            None => ext.insert(Timing {
+                entered: 1,
+                since: Instant::now(),
+            }),
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: layer::Context<'_, S>) {
+        let span = ctx.span(id).expect("Span not found; this is a bug");
+        let mut ext = span.extensions_mut();
+        let busy = match ext.get_mut::<Timing>() {
+            Some(timing) => timing.exit(),
+            None => return,
+        };
+        if let (Some(busy), Some(archived)) = (busy, ext.get_mut::<Arc<Span>>()) {
+            let nanos = busy.as_nanos().min(u64::MAX as u128) as u64;
+            archived
+                .instance
+                .busy_nanos
+                .fetch_add(nanos, Ordering::Relaxed);
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: layer::Context<'_, S>) {
+        let span = ctx.span(&id).expect("Span not found; this is a bug");
+        let ext = span.extensions();
+        if let Some(archived) = ext.get::<Arc<Span>>() {
+            archived.instance.closed.get_or_init(clock::now);
+        }
+    }
+}
+
+/// Bookkeeping for the currently running entry of a span.
+///
+/// A span may be entered concurrently from multiple threads; the busy time
+/// only counts from the first enter until the last matching exit.
+struct Timing {
+    entered: usize,
+    since: Instant,
+}
+
+impl Timing {
+    fn enter(&mut self) {
+        if self.entered == 0 {
+            self.since = Instant::now();
+        }
+        self.entered += 1;
+    }
+
+    fn exit(&mut self) -> Option<Duration> {
+        self.entered = self.entered.checked_sub(1)?;
+        if self.entered == 0 {
+            Some(self.since.elapsed())
+        } else {
+            None
+        }
     }
 }
 
-fn on_span<'a, R, S>(span: SpanRef<'a, S>, fields: &R)
+fn on_span<'a, R, S>(span: SpanRef<'a, S>, fields: &R, max_field_len: usize, max_level: LevelFilter)
 where
     R: RecordFields,
     S: LookupSpan<'a>,
@@ -57,37 +259,252 @@ where
     let mut ext = span.extensions_mut();
     match ext.get_mut::<Arc<Span>>() {
         Some(archived) => {
-            fields.record(&mut Visitor(&mut *Arc::make_mut(archived)));
+            // a new snapshot, even if no event holds this one yet, so that
+            // what an event holds is never changed after it is recorded
+            let mut updated = Span::clone(archived);
+            fields.record(&mut Visitor(&mut updated, max_field_len));
+            *updated.instance.latest_fields.lock() = updated.fields.clone();
+            updated.generation = updated.instance.generation.fetch_add(1, Ordering::Release) + 1;
+            *archived = Arc::new(updated);
         }
         None => {
             let mut archived = Span {
                 meta: span.metadata(),
                 fields: Default::default(),
-                parent: span
-                    .parent()
-                    .and_then(|span| span.extensions().get().map(Arc::clone)),
+                parent: within_level(span.parent(), max_level)
+                    .map(|parent| archive_late(&parent, max_level)),
+                instance: Arc::new(SpanInstance::new(&span.id())),
+                generation: 0,
+                fields_unavailable: false,
             };
-            fields.record(&mut Visitor(&mut archived));
+            fields.record(&mut Visitor(&mut archived, max_field_len));
             ext.insert(Arc::new(archived));
         }
     }
 }
 
-fn on_event<'a, S>(event: &tracing::Event<'_>, span: Option<SpanRef<'a, S>>)
+/// The nearest of `span` and its parents that `max_level` enables, if any.
+fn within_level<'a, S>(
+    span: Option<SpanRef<'a, S>>,
+    max_level: LevelFilter,
+) -> Option<SpanRef<'a, S>>
 where
     S: LookupSpan<'a>,
 {
+    std::iter::successors(span, SpanRef::parent).find(|span| *span.metadata().level() <= max_level)
+}
+
+/// The archived `span`, archiving it now without its fields if it wasn't
+/// when it was created, along with its parents that `max_level` enables.
+fn archive_late<'a, S>(span: &SpanRef<'a, S>, max_level: LevelFilter) -> Arc<Span>
+where
+    S: LookupSpan<'a>,
+{
+    if let Some(archived) = span.extensions().get::<Arc<Span>>() {
+        return Arc::clone(archived);
+    }
+    let archived = Span {
+        meta: span.metadata(),
+        fields: Default::default(),
+        parent: within_level(span.parent(), max_level)
+            .map(|parent| archive_late(&parent, max_level)),
+        instance: Arc::new(SpanInstance::new(&span.id())),
+        generation: 0,
+        fields_unavailable: true,
+    };
+    let mut ext = span.extensions_mut();
+    // another thread may have archived it meanwhile
+    match ext.get_mut::<Arc<Span>>() {
+        Some(archived) => Arc::clone(archived),
+        None => {
+            let archived = Arc::new(archived);
+            ext.insert(Arc::clone(&archived));
+            archived
+        }
+    }
+}
+
+/// The newest snapshot of `span`, as [`archive_late`] gives, with the
+/// newest snapshot of each of its parents.
+///
+/// A span's snapshot keeps the parents it was taken with, so once fields are
+/// recorded on a parent, the snapshot is taken anew with the parent's newest.
+fn archive_current<'a, S>(span: &SpanRef<'a, S>, max_level: LevelFilter) -> Arc<Span>
+where
+    S: LookupSpan<'a>,
+{
+    let archived = archive_late(span, max_level);
+    let parent =
+        within_level(span.parent(), max_level).map(|parent| archive_current(&parent, max_level));
+    let current = match (&archived.parent, &parent) {
+        (Some(archived), Some(parent)) => Arc::ptr_eq(archived, parent),
+        _ => true,
+    };
+    if current {
+        return archived;
+    }
+    let updated = Arc::new(Span {
+        parent,
+        ..Span::clone(&archived)
+    });
+    let mut ext = span.extensions_mut();
+    if let Some(archived) = ext.get_mut::<Arc<Span>>() {
+        // unless fields were recorded on it meanwhile; the next event updates that one
+        if archived.generation == updated.generation {
+            *archived = Arc::clone(&updated);
+        }
+    }
+    updated
+}
+
+fn on_event<'a, S>(
+    store: &EventStore,
+    event: &tracing::Event<'_>,
+    span: Option<SpanRef<'a, S>>,
+    coalesce_repeats: bool,
+    max_field_len: usize,
+    max_level: LevelFilter,
+) where
+    S: LookupSpan<'a>,
+{
+    let (timestamp, elapsed) = clock::now_and_elapsed();
     let mut archived = Event {
         meta: event.metadata(),
-        timestamp: Local::now().naive_local(),
+        timestamp,
+        elapsed,
+        thread: ThreadInfo::current(),
         fields: Default::default(),
-        span: span.and_then(|span| span.extensions().get().map(Arc::clone)),
+        span_id: span.as_ref().map(|span| span.id().into_u64()),
+        span: span.map(|span| archive_current(&span, max_level)),
+        repeats: Repeats::new(timestamp),
+        seq: 0,
+        #[cfg(feature = "log-compat")]
+        log: None,
     };
-    event.record(&mut Visitor(&mut archived));
-    EVENT_QUEUE.push(Arc::new(archived));
+    event.record(&mut Visitor(&mut archived, max_field_len));
+    #[cfg(feature = "log-compat")]
+    archived.fold_log_fields();
+    if coalesce_repeats {
+        store.push_or_repeat(archived);
+    } else {
+        store.push(archived);
+    }
+}
+
+/// Whether layers record events and spans at all.
+static RECORDING: AtomicBool = AtomicBool::new(true);
+
+pub(crate) fn set_recording(recording: bool) {
+    RECORDING.store(recording, Ordering::Relaxed);
+}
+
+pub(crate) fn is_recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+/// The most bytes of a string, debug, or error field value that are kept,
+/// or `usize::MAX` to keep them whole.
+static MAX_FIELD_LEN: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+pub(crate) fn set_max_field_len(max: Option<usize>) {
+    MAX_FIELD_LEN.store(max.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+/// Formats a text field value, keeping up to `max` bytes of it.
+///
+/// Formatting goes on past that only to count how long the whole value is,
+/// so an oversized value is never held whole.
+struct Truncating {
+    text: SmartString,
+    max: usize,
+    len: usize,
+}
+
+impl Truncating {
+    /// The value formatted by `args` as made by `field`, or as
+    /// [`Field::Truncated`] if it is longer than `max` bytes.
+    fn format(
+        args: fmt::Arguments<'_>,
+        max: usize,
+        field: impl FnOnce(SmartString) -> Field,
+    ) -> Field {
+        let mut value = Truncating {
+            text: SmartString::new(),
+            max,
+            len: 0,
+        };
+        // an error is the value's own, and what was written before it is kept
+        let _ = value.write_fmt(args);
+        if value.len > value.text.len() {
+            Field::Truncated {
+                text: value.text,
+                original_len: value.len,
+            }
+        } else {
+            field(value.text)
+        }
+    }
+
+    /// The text of `error` and each of its sources, keeping up to `max` bytes
+    /// of them, or as [`Field::Truncated`] with the texts joined if they're
+    /// longer than that.
+    fn error(error: &(dyn std::error::Error + 'static), max: usize) -> Field {
+        if error.source().is_none() {
+            return Truncating::format(format_args!("{}", error), max, Field::Error);
+        }
+        let mut links = smallvec::SmallVec::new();
+        let mut left = max;
+        for link in std::iter::successors(Some(error), |error| error.source()) {
+            match Truncating::format(format_args!("{}", link), left, Field::Error) {
+                Field::Error(text) => {
+                    left -= text.len();
+                    links.push(text);
+                }
+                // format the whole chain again, to count how long it is
+                _ => {
+                    return Truncating::format(
+                        format_args!("{}", Sources(error)),
+                        max,
+                        Field::Error,
+                    )
+                }
+            }
+        }
+        Field::ErrorChain(links)
+    }
+}
+
+/// Shows an error followed by each of its sources, joined by `: `.
+struct Sources<'a>(&'a (dyn std::error::Error + 'static));
+
+impl fmt::Display for Sources<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)?;
+        for source in std::iter::successors(self.0.source(), |error| error.source()) {
+            write!(f, ": {}", source)?;
+        }
+        Ok(())
+    }
 }
 
-struct Visitor<'a, R>(&'a mut R);
+impl Write for Truncating {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // once a part is cut short, nothing after it is kept
+        if self.len == self.text.len() {
+            let mut end = s.len().min(self.max - self.text.len());
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            self.text.push_str(&s[..end]);
+        }
+        self.len += s.len();
+        Ok(())
+    }
+}
+
+/// Records fields into an event or span, keeping up to the given number of
+/// bytes of text values.
+struct Visitor<'a, R>(&'a mut R, usize);
 
 impl tracing::field::Visit for Visitor<'_, Span> {
     fn record_i64(&mut self, field: &tracing::field::Field, value: i64) {
@@ -103,7 +520,10 @@ impl tracing::field::Visit for Visitor<'_, Span> {
     }
 
     fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
-        self.0.record_field(field, || Field::Str(value.into()))
+        let max = self.1;
+        self.0.record_field(field, || {
+            Truncating::format(format_args!("{}", value), max, Field::Str)
+        })
     }
 
     fn record_error(
@@ -111,13 +531,21 @@ impl tracing::field::Visit for Visitor<'_, Span> {
         field: &tracing::field::Field,
         value: &(dyn std::error::Error + 'static),
     ) {
-        self.0
-            .record_field(field, || Field::Error(format!("{}", value).into()))
+        let max = self.1;
+        self.0.record_field(field, || Truncating::error(value, max))
     }
 
     fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
-        self.0
-            .record_field(field, || Field::Debug(format!("{:?}", value).into()))
+        let max = self.1;
+        self.0.record_field(field, || {
+            // the formatted message is passed as `fmt::Arguments`, which debugs as display
+            let kind = if field.name() == "message" {
+                Field::Display
+            } else {
+                Field::Debug
+            };
+            Truncating::format(format_args!("{:?}", value), max, kind)
+        })
     }
 }
 
@@ -135,7 +563,10 @@ impl tracing::field::Visit for Visitor<'_, Event> {
     }
 
     fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
-        self.0.record_field(field, || Field::Str(value.into()))
+        let max = self.1;
+        self.0.record_field(field, || {
+            Truncating::format(format_args!("{}", value), max, Field::Str)
+        })
     }
 
     fn record_error(
@@ -143,12 +574,20 @@ impl tracing::field::Visit for Visitor<'_, Event> {
         field: &tracing::field::Field,
         value: &(dyn std::error::Error + 'static),
     ) {
-        self.0
-            .record_field(field, || Field::Error(format!("{}", value).into()))
+        let max = self.1;
+        self.0.record_field(field, || Truncating::error(value, max))
     }
 
     fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
-        self.0
-            .record_field(field, || Field::Debug(format!("{:?}", value).into()))
+        let max = self.1;
+        self.0.record_field(field, || {
+            // the formatted message is passed as `fmt::Arguments`, which debugs as display
+            let kind = if field.name() == "message" {
+                Field::Display
+            } else {
+                Field::Debug
+            };
+            Truncating::format(format_args!("{:?}", value), max, kind)
+        })
     }
 }
@@ -1,14 +1,30 @@
 mod archive;
+mod clock;
+mod compress;
+mod flush;
+mod format;
+mod jsonl;
 mod layer;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod record_filter;
+#[cfg(feature = "serde")]
+mod serialize;
+mod snapshot;
+mod stats;
+mod store;
+mod subscribe;
+#[cfg(feature = "synth")]
+pub mod synth;
 
-pub use crate::{archive::*, layer::*};
+pub use crate::{
+    archive::*, compress::*, flush::*, format::*, layer::*, record_filter::*, snapshot::*,
+    stats::*, store::*, subscribe::*,
+};
 
-use crossbeam_queue::SegQueue;
-use parking_lot::Mutex;
 use std::sync::Arc;
 
-static EVENT_LOG: Mutex<Vec<Arc<Event>>> = parking_lot::const_mutex(Vec::new());
-static EVENT_QUEUE: SegQueue<Arc<Event>> = SegQueue::new();
+static GLOBAL_STORE: EventStore = EventStore::new();
 
 /// Run some callback with the recorded events.
 ///
@@ -16,10 +32,144 @@ static EVENT_QUEUE: SegQueue<Arc<Event>> = SegQueue::new();
 ///
 /// Will _not_ block the recording of new events.
 pub fn with_events<R>(cb: impl FnOnce(&mut Vec<Arc<Event>>) -> R) -> R {
-    let mut events = EVENT_LOG.lock();
-    events.reserve(EVENT_QUEUE.len());
-    events.extend(std::iter::from_fn(|| EVENT_QUEUE.pop()));
-    cb(&mut events)
+    GLOBAL_STORE.with_events(cb)
+}
+
+/// Run some callback with the recorded events for which `pred` returns true.
+///
+/// See [`EventStore::with_filtered_events`]; like [`with_events`], this is not reentrancy safe.
+pub fn with_filtered_events<R>(
+    pred: impl Fn(&Event) -> bool,
+    cb: impl FnOnce(&mut dyn Iterator<Item = &Arc<Event>>) -> R,
+) -> R {
+    GLOBAL_STORE.with_filtered_events(pred, cb)
+}
+
+/// Take a [snapshot](EventSnapshot) of the recorded events.
+///
+/// See [`EventStore::snapshot`]; unlike [`with_events`], the snapshot can be used without holding a lock.
+pub fn snapshot() -> EventSnapshot {
+    GLOBAL_STORE.snapshot()
+}
+
+/// Run some callback with the recorded events with a [sequence number](Event::seq) after `seq`.
+///
+/// See [`EventStore::with_events_since`]; like [`with_events`], this is not reentrancy safe.
+pub fn with_events_since<R>(seq: u64, cb: impl FnOnce(&[Arc<Event>]) -> R) -> R {
+    GLOBAL_STORE.with_events_since(seq, cb)
+}
+
+/// Run some callback with the recorded events in the span with the [id](Span::id) `id`.
+///
+/// See [`EventStore::events_for_span`]; span ids may be reused once a span
+/// closes, so events from each span that had the id are included.
+pub fn events_for_span<R>(id: u64, cb: impl FnOnce(&[Arc<Event>]) -> R) -> R {
+    GLOBAL_STORE.events_for_span(id, cb)
+}
+
+/// Run some callback with the recorded events with the
+/// [effective target](Event::effective_target) `target`.
+///
+/// See [`EventStore::events_for_target`]; like [`with_events`], this is not reentrancy safe.
+pub fn events_for_target<R>(target: &str, cb: impl FnOnce(&[Arc<Event>]) -> R) -> R {
+    GLOBAL_STORE.events_for_target(target, cb)
+}
+
+/// How many events are recorded, in total and by level, and more.
+///
+/// See [`EventStore::stats`]; like [`with_events`], this is not reentrancy safe.
+pub fn stats() -> EventStats {
+    GLOBAL_STORE.stats()
+}
+
+/// Keep the recorded events sorted by [time](Event::time) from now on, or not.
+///
+/// See [`EventStore::set_sorted`]; sorted events can be out of
+/// [sequence](Event::seq), which [`with_events_since`] relies on.
+pub fn set_sorted(sorted: bool) {
+    GLOBAL_STORE.set_sorted(sorted)
+}
+
+/// Compress the oldest recorded events as `compression` says from now on,
+/// or decompress them and stop if `None`.
+///
+/// See [`EventStore::set_compression`]; compressed events are decompressed
+/// as they are read, as new `Arc`s.
+pub fn set_compression(compression: Option<Compression>) {
+    GLOBAL_STORE.set_compression(compression)
+}
+
+/// Discard all recorded events, including those not yet seen by [`with_events`].
+///
+/// Like [`with_events`], this is not reentrancy safe: calling it from inside a
+/// [`with_events`] callback will deadlock. To clear from such a callback,
+/// clear the provided `Vec` instead; queued events will show up on the next call.
+pub fn clear_events() {
+    GLOBAL_STORE.clear_events()
+}
+
+/// Keep only the recorded events for which `keep` returns true.
+///
+/// See [`EventStore::retain_events`]; like [`with_events`], this is not reentrancy safe.
+pub fn retain_events(keep: impl FnMut(&Arc<Event>) -> bool) {
+    GLOBAL_STORE.retain_events(keep)
+}
+
+/// How many events layers recording into the global store didn't record
+/// because their [`RecordFilter`] rejected them.
+///
+/// See [`EventStore::dropped_by_filter`].
+pub fn dropped_by_filter() -> u64 {
+    GLOBAL_STORE.dropped_by_filter()
+}
+
+/// How many times a [`FlushGuard`] gave up on flushing the global store.
+///
+/// See [`EventStore::abandoned_flushes`].
+pub fn abandoned_flushes() -> u64 {
+    GLOBAL_STORE.abandoned_flushes()
+}
+
+/// Receive each event as it is recorded from now on.
+///
+/// Events are still recorded as usual. See [`EventStore::subscribe`] for
+/// how a slow receiver is handled. Dropping the receiver unsubscribes.
+pub fn subscribe() -> EventReceiver {
+    GLOBAL_STORE.subscribe()
+}
+
+/// Write the recorded events as newline-delimited JSON.
+///
+/// The events are snapshotted first, so recording is not blocked on `writer`.
+#[cfg(feature = "serde")]
+pub fn export_json(writer: impl std::io::Write) -> std::io::Result<()> {
+    GLOBAL_STORE.export_json(writer)
+}
+
+/// Write the recorded events as JSON Lines, one object per event.
+///
+/// See [`EventStore::write_jsonl`] for the format.
+pub fn write_jsonl<W: std::io::Write>(w: &mut W) -> std::io::Result<()> {
+    GLOBAL_STORE.write_jsonl(w)
+}
+
+/// Also write each event recorded into the global store to the file at
+/// `path` as it is recorded, as a JSON Line.
+///
+/// See [`EventStore::set_file_sink`]; a [`flush_guard`] flushes the file
+/// along with the store.
+pub fn set_file_sink(path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+    GLOBAL_STORE.set_file_sink(path)
+}
+
+/// Stop writing events to the file set by [`set_file_sink`], flushing and closing it.
+pub fn close_file_sink() -> std::io::Result<()> {
+    GLOBAL_STORE.close_file_sink()
+}
+
+/// Write out the events buffered for the file set by [`set_file_sink`], if any.
+pub fn flush_file_sink() -> std::io::Result<()> {
+    GLOBAL_STORE.flush_file_sink()
 }
 
 /// A new [recording layer](Layer) that can be [composed](mod@tracing_subscriber::layer) with other layers.
@@ -28,3 +178,52 @@ pub fn with_events<R>(cb: impl FnOnce(&mut Vec<Arc<Event>>) -> R) -> R {
 pub fn layer<S>() -> Layer<S> {
     Layer::default()
 }
+
+/// The timestamp an event recorded now would get; only for the benchmarks.
+#[doc(hidden)]
+pub fn __timestamp_now() -> i64 {
+    clock::now()
+}
+
+/// When tracing-memory started keeping time, which is when the first [`Layer`]
+/// was created, if not before.
+///
+/// This is the reference for [`Event::wall_time_since_start`] and
+/// [`Event::elapsed_since_start`].
+#[cfg(feature = "chrono")]
+pub fn start_time() -> chrono::DateTime<chrono::Utc> {
+    clock::utc(clock::start())
+}
+
+/// Pause or resume recording.
+///
+/// While paused, every [`Layer`] drops the events and spans it sees instead
+/// of recording them, whichever store it records into, so the events
+/// already recorded stay as they are. Spans created while paused are left
+/// out of the spans of events recorded after resuming. Recording is on by
+/// default.
+pub fn set_recording(recording: bool) {
+    layer::set_recording(recording)
+}
+
+/// Whether events are recorded, which they are unless [paused](set_recording).
+pub fn is_recording() -> bool {
+    layer::is_recording()
+}
+
+/// Keep at most `max` bytes of each string, debug, or error field value
+/// recorded from now on, or keep them whole if `None`, which is the default.
+///
+/// Longer values are recorded as [`Field::Truncated`]. This applies to every
+/// [`Layer`], whichever store it records into, along with the limit set by
+/// [`Layer::max_field_len`].
+pub fn set_max_field_len(max: Option<usize>) {
+    layer::set_max_field_len(max)
+}
+
+/// A [guard](FlushGuard) that flushes the global store when dropped.
+///
+/// Shorthand for the equivalent [`FlushGuard::new`].
+pub fn flush_guard() -> FlushGuard {
+    FlushGuard::new()
+}
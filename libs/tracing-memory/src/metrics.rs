@@ -0,0 +1,135 @@
+//! Publishing the health of event stores to the [`metrics`] facade.
+//!
+//! Everything is updated inline, as deltas, so that the gauges add up over
+//! all stores. Metrics are described the first time they are updated with a
+//! recorder installed, and cost one check for a recorder when there is none.
+
+use metrics::{GaugeValue, Key, Label, Recorder, Unit};
+use std::sync::Once;
+use tracing::Level;
+
+const RECORDED: &str = "tracing_memory_events_recorded_total";
+const DROPPED: &str = "tracing_memory_events_dropped_total";
+const RETAINED: &str = "tracing_memory_events_retained";
+const QUEUE_DEPTH: &str = "tracing_memory_queue_depth";
+const ABANDONED_FLUSHES: &str = "tracing_memory_flushes_abandoned_total";
+
+static LEVEL_LABELS: [[Label; 1]; 5] = [
+    [Label::from_static_parts("level", "ERROR")],
+    [Label::from_static_parts("level", "WARN")],
+    [Label::from_static_parts("level", "INFO")],
+    [Label::from_static_parts("level", "DEBUG")],
+    [Label::from_static_parts("level", "TRACE")],
+];
+
+static RECORDED_KEYS: [Key; 5] = [
+    Key::from_static_parts(RECORDED, &LEVEL_LABELS[0]),
+    Key::from_static_parts(RECORDED, &LEVEL_LABELS[1]),
+    Key::from_static_parts(RECORDED, &LEVEL_LABELS[2]),
+    Key::from_static_parts(RECORDED, &LEVEL_LABELS[3]),
+    Key::from_static_parts(RECORDED, &LEVEL_LABELS[4]),
+];
+
+static REASON_LABELS: [[Label; 1]; 2] = [
+    [Label::from_static_parts("reason", "cleared")],
+    [Label::from_static_parts("reason", "removed")],
+];
+
+static DROPPED_KEYS: [Key; 2] = [
+    Key::from_static_parts(DROPPED, &REASON_LABELS[0]),
+    Key::from_static_parts(DROPPED, &REASON_LABELS[1]),
+];
+
+static RETAINED_KEY: Key = Key::from_static_name(RETAINED);
+static QUEUE_DEPTH_KEY: Key = Key::from_static_name(QUEUE_DEPTH);
+static ABANDONED_FLUSHES_KEY: Key = Key::from_static_name(ABANDONED_FLUSHES);
+
+/// Why events were dropped from a store.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Reason {
+    /// By [`EventStore::clear_events`](crate::EventStore::clear_events).
+    Cleared = 0,
+    /// From the `Vec` given to a [`EventStore::with_events`](crate::EventStore::with_events) callback,
+    /// or by [`EventStore::retain_events`](crate::EventStore::retain_events).
+    Removed = 1,
+}
+
+fn recorder() -> Option<&'static dyn Recorder> {
+    static DESCRIBED: Once = Once::new();
+    let recorder = metrics::try_recorder()?;
+    DESCRIBED.call_once(|| {
+        for key in &RECORDED_KEYS {
+            recorder.register_counter(key, Some(Unit::Count), Some("Events recorded, by level"));
+        }
+        for key in &DROPPED_KEYS {
+            recorder.register_counter(key, Some(Unit::Count), Some("Events dropped, by reason"));
+        }
+        recorder.register_gauge(
+            &RETAINED_KEY,
+            Some(Unit::Count),
+            Some("Events currently held by event stores"),
+        );
+        recorder.register_gauge(
+            &QUEUE_DEPTH_KEY,
+            Some(Unit::Count),
+            Some("Events recorded but not yet moved into the log"),
+        );
+        recorder.register_counter(
+            &ABANDONED_FLUSHES_KEY,
+            Some(Unit::Count),
+            Some("Flushes which timed out waiting for the log"),
+        );
+    });
+    Some(recorder)
+}
+
+pub(crate) fn recorded(level: &Level) {
+    if let Some(recorder) = recorder() {
+        let key = match *level {
+            Level::ERROR => &RECORDED_KEYS[0],
+            Level::WARN => &RECORDED_KEYS[1],
+            Level::INFO => &RECORDED_KEYS[2],
+            Level::DEBUG => &RECORDED_KEYS[3],
+            Level::TRACE => &RECORDED_KEYS[4],
+        };
+        recorder.increment_counter(key, 1);
+        recorder.update_gauge(&RETAINED_KEY, GaugeValue::Increment(1.0));
+        recorder.update_gauge(&QUEUE_DEPTH_KEY, GaugeValue::Increment(1.0));
+    }
+}
+
+pub(crate) fn dequeued(count: usize) {
+    if count == 0 {
+        return;
+    }
+    if let Some(recorder) = recorder() {
+        recorder.update_gauge(&QUEUE_DEPTH_KEY, GaugeValue::Decrement(count as f64));
+    }
+}
+
+pub(crate) fn dropped(reason: Reason, count: usize) {
+    if count == 0 {
+        return;
+    }
+    if let Some(recorder) = recorder() {
+        recorder.increment_counter(&DROPPED_KEYS[reason as usize], count as u64);
+        recorder.update_gauge(&RETAINED_KEY, GaugeValue::Decrement(count as f64));
+    }
+}
+
+/// A [`FlushGuard`](crate::FlushGuard) timed out waiting for the log.
+pub(crate) fn flush_abandoned() {
+    if let Some(recorder) = recorder() {
+        recorder.increment_counter(&ABANDONED_FLUSHES_KEY, 1);
+    }
+}
+
+/// Events added to the `Vec` given to a `with_events` callback.
+pub(crate) fn added(count: usize) {
+    if count == 0 {
+        return;
+    }
+    if let Some(recorder) = recorder() {
+        recorder.update_gauge(&RETAINED_KEY, GaugeValue::Increment(count as f64));
+    }
+}
@@ -0,0 +1,119 @@
+use parse_env_filter::{eager::typed_filters, Level, ParseError};
+use smartstring::alias::String as SmartString;
+use std::fmt;
+use tracing::level_filters::LevelFilter;
+
+/// Which events a [`Layer`](crate::Layer) records, by target and level.
+///
+/// Unlike filtering what is shown, this keeps events from being recorded at
+/// all, so they take no memory. Share one between a layer and whatever
+/// changes it at runtime with [`Layer::register_filter_handle`](crate::Layer::register_filter_handle).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordFilter {
+    /// The target prefixes and their levels, longest target first.
+    /// The global level has an empty target, so comes last.
+    directives: Vec<(SmartString, LevelFilter)>,
+}
+
+impl RecordFilter {
+    /// A filter that records every event.
+    pub fn new() -> Self {
+        RecordFilter::default()
+    }
+
+    /// Parse a filter from a directive string like `info,app::db=trace`.
+    ///
+    /// Each filter gives the level to record for targets starting with its
+    /// target, or for every target if it has none or its target is a level
+    /// name. Filters with a span or `/regex` part can't be checked before the
+    /// event is recorded, so they are skipped. A filter without a level
+    /// records every level.
+    pub fn parse(directives: &str) -> Result<Self, ParseError> {
+        let mut filter = RecordFilter::new();
+        for parsed in typed_filters(directives)? {
+            if parsed.span.is_some() || parsed.regex.is_some() {
+                continue;
+            }
+            let (target, level) = match (parsed.target, parsed.level) {
+                (target, None) => match Level::parse(target) {
+                    Some(level) => ("", Some(level)),
+                    None => (target, None),
+                },
+                parsed => parsed,
+            };
+            if target.is_empty() && level.is_none() {
+                continue;
+            }
+            let target = Some(target).filter(|target| !target.is_empty());
+            filter.set(target, level.map_or(LevelFilter::TRACE, level_filter));
+        }
+        Ok(filter)
+    }
+
+    /// Record events with targets starting with `target` up to `level`, or
+    /// all events without a more specific target if `target` is `None`.
+    pub fn set(&mut self, target: Option<&str>, level: LevelFilter) {
+        let target = target.unwrap_or("");
+        match self.directives.iter_mut().find(|(t, _)| t == target) {
+            Some((_, existing)) => *existing = level,
+            None => {
+                self.directives.push((target.into(), level));
+                self.directives
+                    .sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+            }
+        }
+    }
+
+    /// Whether this filter doesn't filter out any events.
+    pub fn is_empty(&self) -> bool {
+        self.directives.is_empty()
+    }
+
+    /// Whether an event with `meta` is recorded.
+    ///
+    /// An empty filter records everything; otherwise, the directive with the
+    /// longest matching target decides, and events no directive matches
+    /// aren't recorded.
+    pub fn enabled(&self, meta: &tracing::Metadata<'_>) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let target = meta.target();
+        match self
+            .directives
+            .iter()
+            .find(|(t, _)| target.starts_with(&**t))
+        {
+            Some(&(_, level)) => *meta.level() <= level,
+            None => false,
+        }
+    }
+}
+
+/// Displays as a directive string which parses back to an equal filter.
+impl fmt::Display for RecordFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (ix, (target, level)) in self.directives.iter().enumerate() {
+            if ix != 0 {
+                f.write_str(",")?;
+            }
+            let level = level.to_string().to_lowercase();
+            match target.as_str() {
+                "" => f.write_str(&level)?,
+                target => write!(f, "{}={}", target, level)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn level_filter(level: Level) -> LevelFilter {
+    match level {
+        Level::Off => LevelFilter::OFF,
+        Level::Error => LevelFilter::ERROR,
+        Level::Warn => LevelFilter::WARN,
+        Level::Info => LevelFilter::INFO,
+        Level::Debug => LevelFilter::DEBUG,
+        Level::Trace => LevelFilter::TRACE,
+    }
+}
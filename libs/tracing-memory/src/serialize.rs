@@ -0,0 +1,115 @@
+use crate::{archive::FieldMap, clock::Rfc3339, Event, EventStore, Field, Span};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, SerializeStruct, Serializer};
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+/// Serializes as an event object, with the interesting parts of its
+/// [metadata](tracing::Metadata) flattened in, and the containing spans as an
+/// array from innermost to outermost. The target and source location are the
+/// [effective](Event::effective_target) ones.
+impl Serialize for Event {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut event = serializer.serialize_struct("Event", 10)?;
+        event.serialize_field("timestamp", &AsDisplay(Rfc3339(self.timestamp)))?;
+        // like the metadata, but looking through bridged `log` records
+        event.serialize_field("target", self.effective_target())?;
+        event.serialize_field("name", self.meta.name())?;
+        event.serialize_field("level", &AsDisplay(self.meta.level()))?;
+        event.serialize_field("module_path", &self.effective_module_path())?;
+        event.serialize_field("file", &self.effective_file())?;
+        event.serialize_field("line", &self.effective_line())?;
+        event.serialize_field("thread", &AsDisplay(self.thread()))?;
+        event.serialize_field("fields", &Fields(&self.fields))?;
+        event.serialize_field("spans", &Spans(self.span()))?;
+        event.end()
+    }
+}
+
+/// Serializes as a span object, with the interesting parts of its
+/// [metadata](tracing::Metadata) flattened in. Parent spans are not included.
+impl Serialize for Span {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut span = serializer.serialize_struct("Span", 7)?;
+        serialize_meta::<S>(&mut span, self.meta)?;
+        span.serialize_field("fields", &Fields(&self.fields))?;
+        span.end()
+    }
+}
+
+/// Serializes as the contained value; multiple values serialize as an array,
+/// and an error with sources as the text it shows as.
+impl Serialize for Field {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Field::I64(value) => serializer.serialize_i64(*value),
+            Field::U64(value) => serializer.serialize_u64(*value),
+            Field::Bool(value) => serializer.serialize_bool(*value),
+            Field::Str(value)
+            | Field::Display(value)
+            | Field::Error(value)
+            | Field::Debug(value) => serializer.serialize_str(value),
+            Field::ErrorChain(_) | Field::Truncated { .. } => serializer.collect_str(self),
+            Field::Multiple(values) => values.serialize(serializer),
+        }
+    }
+}
+
+fn serialize_meta<S: Serializer>(
+    s: &mut S::SerializeStruct,
+    meta: &tracing::Metadata<'_>,
+) -> Result<(), S::Error> {
+    s.serialize_field("target", meta.target())?;
+    s.serialize_field("name", meta.name())?;
+    s.serialize_field("level", &AsDisplay(meta.level()))?;
+    s.serialize_field("module_path", &meta.module_path())?;
+    s.serialize_field("file", &meta.file())?;
+    s.serialize_field("line", &meta.line())?;
+    Ok(())
+}
+
+struct AsDisplay<T>(T);
+
+impl<T: fmt::Display> Serialize for AsDisplay<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+struct Fields<'a>(&'a FieldMap);
+
+impl Serialize for Fields<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, field) in self.0 {
+            map.serialize_entry(name, field)?;
+        }
+        map.end()
+    }
+}
+
+struct Spans<'a>(Option<&'a Span>);
+
+impl Serialize for Spans<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(None)?;
+        for span in std::iter::successors(self.0, |span| span.parent()) {
+            seq.serialize_element(span)?;
+        }
+        seq.end()
+    }
+}
+
+impl EventStore {
+    /// Write the recorded events as newline-delimited JSON.
+    ///
+    /// The events are snapshotted first, so recording is not blocked on `writer`.
+    pub fn export_json(&self, mut writer: impl Write) -> io::Result<()> {
+        for event in self.snapshot().iter() {
+            serde_json::to_writer(&mut writer, &**event)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()
+    }
+}
@@ -0,0 +1,110 @@
+use crate::Event;
+use std::{iter::FromIterator, ops::Deref, slice, sync::Arc};
+
+/// The recorded events of a store as they were at one point in time.
+///
+/// Taken by [`EventStore::snapshot`](crate::EventStore::snapshot), or made
+/// from any events, such as a subset of another snapshot. Unlike
+/// the callbacks of [`with_events`](crate::EventStore::with_events), a
+/// snapshot holds no lock, so it can be sent to another thread and worked
+/// through as slowly as needed while events are recorded and other snapshots
+/// taken. The events are shared with the store rather than copied, so taking
+/// and cloning snapshots is cheap.
+#[derive(Debug, Clone, Default)]
+pub struct EventSnapshot {
+    events: Arc<Vec<Arc<Event>>>,
+}
+
+impl EventSnapshot {
+    /// The events, oldest first.
+    pub fn iter(&self) -> slice::Iter<'_, Arc<Event>> {
+        self.events.iter()
+    }
+
+    /// The number of events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Whether there are no events.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// The events with a [sequence number](Event::seq) after `seq`, like
+    /// [`EventStore::with_events_since`](crate::EventStore::with_events_since).
+    pub fn since(&self, seq: u64) -> &[Arc<Event>] {
+        let since = self.events.partition_point(|event| event.seq <= seq);
+        &self.events[since..]
+    }
+
+    /// The events as a slice, oldest first.
+    pub fn as_slice(&self) -> &[Arc<Event>] {
+        &self.events
+    }
+}
+
+impl Deref for EventSnapshot {
+    type Target = [Arc<Event>];
+
+    fn deref(&self) -> &[Arc<Event>] {
+        &self.events
+    }
+}
+
+/// The events should be oldest first, in order of [sequence number](Event::seq),
+/// as [`since`](EventSnapshot::since) relies on it.
+impl From<Vec<Arc<Event>>> for EventSnapshot {
+    fn from(events: Vec<Arc<Event>>) -> Self {
+        EventSnapshot {
+            events: Arc::new(events),
+        }
+    }
+}
+
+impl FromIterator<Arc<Event>> for EventSnapshot {
+    fn from_iter<I: IntoIterator<Item = Arc<Event>>>(events: I) -> Self {
+        Vec::from_iter(events).into()
+    }
+}
+
+impl<'a> IntoIterator for &'a EventSnapshot {
+    type Item = &'a Arc<Event>;
+    type IntoIter = slice::Iter<'a, Arc<Event>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A store's log of events, shared with the snapshots taken of it.
+///
+/// The log is only copied when it changes while a snapshot of it is still
+/// around, and then only the pointers to the events. It isn't allocated
+/// until the first event, so that stores can be created in a `const`.
+#[derive(Debug)]
+pub(crate) struct SharedEvents(Option<Arc<Vec<Arc<Event>>>>);
+
+impl SharedEvents {
+    pub(crate) const fn new() -> Self {
+        SharedEvents(None)
+    }
+
+    pub(crate) fn get(&self) -> &[Arc<Event>] {
+        self.0.as_deref().map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn get_mut(&mut self) -> &mut Vec<Arc<Event>> {
+        Arc::make_mut(self.0.get_or_insert_with(Default::default))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0 = None;
+    }
+
+    pub(crate) fn snapshot(&mut self) -> EventSnapshot {
+        EventSnapshot {
+            events: Arc::clone(self.0.get_or_insert_with(Default::default)),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(feature = "chrono")]
+use crate::clock;
+use crate::{Event, Field};
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Utc};
+use tracing::Level;
+
+/// How many events are held by a store, in total and by level, roughly how
+/// much memory they take, and when they were recorded.
+///
+/// See [`EventStore::stats`](crate::EventStore::stats). Only events still in
+/// the store are counted, so this goes down when events are cleared or
+/// removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventStats {
+    total: usize,
+    /// By level, from `ERROR` to `TRACE`.
+    by_level: [usize; 5],
+    approx_bytes: usize,
+    /// How many of the events are compressed, the bytes they take, and
+    /// roughly how many they took uncompressed.
+    compressed: (usize, usize, usize),
+    /// The earliest and latest event timestamps, as from [`clock::now`].
+    oldest: Option<i64>,
+    newest: Option<i64>,
+}
+
+impl EventStats {
+    /// The number of events.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// The number of events at exactly `level`.
+    pub fn count(&self, level: Level) -> usize {
+        self.by_level[level_index(level)]
+    }
+
+    /// The number of `ERROR` events.
+    pub fn errors(&self) -> usize {
+        self.count(Level::ERROR)
+    }
+
+    /// The number of `WARN` events.
+    pub fn warnings(&self) -> usize {
+        self.count(Level::WARN)
+    }
+
+    /// Roughly how many bytes the events take, from the size of an event
+    /// and the length of its field values.
+    ///
+    /// Spans are shared between events, so aren't counted. [Compressed](Self::compressed)
+    /// events are counted as they were before being compressed.
+    pub fn approx_bytes(&self) -> usize {
+        self.approx_bytes
+    }
+
+    /// The number of events [compressed](crate::EventStore::set_compression).
+    pub fn compressed(&self) -> usize {
+        self.compressed.0
+    }
+
+    /// How many bytes the [compressed](Self::compressed) events take.
+    pub fn compressed_bytes(&self) -> usize {
+        self.compressed.1
+    }
+
+    /// Roughly how many bytes the events that aren't [compressed](Self::compressed)
+    /// take, like [`approx_bytes`](Self::approx_bytes).
+    pub fn live_bytes(&self) -> usize {
+        self.approx_bytes - self.compressed.2
+    }
+
+    /// How many times fewer bytes the [compressed](Self::compressed) events
+    /// take than they did before, if any are.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        match self.compressed {
+            (0, ..) => None,
+            (_, bytes, approx_bytes) => Some(approx_bytes as f64 / bytes as f64),
+        }
+    }
+
+    /// The timestamp of the earliest event, if there are any.
+    #[cfg(feature = "chrono")]
+    pub fn oldest(&self) -> Option<DateTime<Utc>> {
+        self.oldest.map(clock::utc)
+    }
+
+    /// The timestamp of the latest event, if there are any.
+    ///
+    /// This is when a [coalesced](crate::Layer::coalesce_repeats) event was
+    /// first recorded, not when it was last repeated.
+    #[cfg(feature = "chrono")]
+    pub fn newest(&self) -> Option<DateTime<Utc>> {
+        self.newest.map(clock::utc)
+    }
+
+    pub(crate) fn set_compressed(&mut self, compressed: (usize, usize, usize)) {
+        self.compressed = compressed;
+    }
+
+    pub(crate) fn add(&mut self, event: &Event) {
+        self.total += 1;
+        self.by_level[level_index(*event.meta().level())] += 1;
+        self.approx_bytes += approx_bytes(event);
+        let timestamp = event.timestamp;
+        self.oldest = Some(
+            self.oldest
+                .map_or(timestamp, |oldest| oldest.min(timestamp)),
+        );
+        self.newest = Some(
+            self.newest
+                .map_or(timestamp, |newest| newest.max(timestamp)),
+        );
+    }
+}
+
+/// Roughly how many bytes `event` takes; see [`EventStats::approx_bytes`].
+pub(crate) fn approx_bytes(event: &Event) -> usize {
+    std::mem::size_of::<Event>()
+        + (event.fields.values())
+            .map(|field| std::mem::size_of::<(&str, Field)>() + field_bytes(field))
+            .sum::<usize>()
+}
+
+/// The bytes taken by the text of `field`, and by the values it holds.
+fn field_bytes(field: &Field) -> usize {
+    match field {
+        Field::I64(_) | Field::U64(_) | Field::Bool(_) => 0,
+        Field::Str(text)
+        | Field::Display(text)
+        | Field::Error(text)
+        | Field::Debug(text)
+        | Field::Truncated { text, .. } => text.len(),
+        Field::ErrorChain(links) => links.iter().map(|link| link.len()).sum(),
+        Field::Multiple(values) => values
+            .iter()
+            .map(|value| std::mem::size_of::<Field>() + field_bytes(value))
+            .sum(),
+    }
+}
+
+fn level_index(level: Level) -> usize {
+    match level {
+        Level::ERROR => 0,
+        Level::WARN => 1,
+        Level::INFO => 2,
+        Level::DEBUG => 3,
+        Level::TRACE => 4,
+    }
+}
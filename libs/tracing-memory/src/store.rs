@@ -0,0 +1,667 @@
+#[cfg(feature = "metrics")]
+use crate::metrics::Reason;
+use crate::{
+    clock,
+    compress::Compressed,
+    jsonl::FileSink,
+    snapshot::SharedEvents,
+    subscribe::{self, Subscriber},
+    Compression, Event, EventReceiver, EventSnapshot, EventStats, DEFAULT_SUBSCRIBER_CAPACITY,
+};
+use crossbeam_queue::SegQueue;
+use parking_lot::Mutex;
+use smartstring::alias::String as SmartString;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Storage for recorded events.
+///
+/// By default, [layers](crate::Layer) record into a global store accessed via
+/// the free functions like [`with_events`](crate::with_events). Use
+/// [`Layer::with_store`](crate::Layer::with_store) to record into a separate one.
+#[derive(Debug)]
+pub struct EventStore {
+    log: Mutex<Log>,
+    queue: SegQueue<Arc<Event>>,
+    /// The [sequence number](Event::seq) of the next event recorded.
+    next_seq: AtomicU64,
+    subscribers: Mutex<Vec<Subscriber>>,
+    subscriber_count: AtomicUsize,
+    /// The last event recorded by a layer that coalesces repeats.
+    last: Mutex<Option<Arc<Event>>>,
+    /// How many events a layer's [`RecordFilter`](crate::RecordFilter) kept out.
+    dropped_by_filter: AtomicU64,
+    /// How many flushes gave up waiting for the log.
+    abandoned_flushes: AtomicU64,
+    file_sink: Mutex<Option<FileSink>>,
+    /// Whether `file_sink` is set, to not lock it for every event when it isn't.
+    has_file_sink: AtomicBool,
+    /// Whether events are kept sorted by time rather than by sequence number.
+    sorted: AtomicBool,
+}
+
+#[derive(Debug)]
+struct Log {
+    events: SharedEvents,
+    /// The oldest events, compressed, which come before `events`.
+    compressed: Compressed,
+    /// Dequeued events waiting for an event recorded before them to be queued.
+    pending: Vec<Arc<Event>>,
+    /// The sequence number of the next event to go into `events`.
+    next_seq: u64,
+    /// The events by [effective target](Event::effective_target), built on
+    /// first use and dropped whenever `events` is handed out mutably.
+    by_target: Option<TargetIndex>,
+    /// Counts of `events`, kept like `by_target`.
+    stats: Option<EventStats>,
+}
+
+impl Log {
+    /// The events with a sequence number after `seq`, decompressing those
+    /// of them that are compressed.
+    fn since(&mut self, seq: u64) -> Cow<'_, [Arc<Event>]> {
+        let events = self.events.get();
+        let since = events.partition_point(|event| event.seq <= seq);
+        match self.compressed.last_seq() {
+            Some(last) if last > seq => {
+                let mut all = self.compressed.since(seq);
+                all.extend_from_slice(&events[since..]);
+                Cow::Owned(all)
+            }
+            _ => Cow::Borrowed(&events[since..]),
+        }
+    }
+
+    /// Decompress the compressed events back into `events`.
+    fn decompress(&mut self) {
+        if self.compressed.is_empty() {
+            return;
+        }
+        let mut all = self.compressed.take();
+        all.extend_from_slice(self.events.get());
+        *self.events.get_mut() = all;
+        self.by_target = None;
+    }
+}
+
+/// Sort the events from `start` by time, and merge them into those before,
+/// which are already sorted, keeping events with the same time in sequence.
+///
+/// This takes time in proportion to the events from the earliest of those
+/// sorted on, rather than to all of them. Returns whether any event moved.
+fn merge_by_time(events: &mut Vec<Arc<Event>>, start: usize) -> bool {
+    let time = |event: &Arc<Event>| event.timestamp;
+    let in_order =
+        (events[start.saturating_sub(1)..].windows(2)).all(|pair| time(&pair[0]) <= time(&pair[1]));
+    if in_order {
+        return false;
+    }
+    // stable, so events with the same time stay in sequence
+    events[start..].sort_by_key(time);
+    let earliest = time(&events[start]);
+    let from = events[..start].partition_point(|event| time(event) <= earliest);
+    let mut older = events.split_off(from);
+    let mut new = older.split_off(start - from).into_iter().peekable();
+    for event in older {
+        while let Some(newer) = new.next_if(|new| time(new) < time(&event)) {
+            events.push(newer);
+        }
+        events.push(event);
+    }
+    events.extend(new);
+    true
+}
+
+type TargetIndex = HashMap<SmartString, Vec<Arc<Event>>, ahash::RandomState>;
+
+fn index_target(index: &mut TargetIndex, event: &Arc<Event>) {
+    let target = event.effective_target();
+    match index.get_mut(target) {
+        Some(events) => events.push(Arc::clone(event)),
+        None => {
+            index.insert(target.into(), vec![Arc::clone(event)]);
+        }
+    }
+}
+
+impl Default for EventStore {
+    fn default() -> Self {
+        EventStore::new()
+    }
+}
+
+impl EventStore {
+    /// Create a new, empty event store.
+    pub const fn new() -> Self {
+        EventStore {
+            log: parking_lot::const_mutex(Log {
+                events: SharedEvents::new(),
+                compressed: Compressed::new(),
+                pending: Vec::new(),
+                next_seq: 1,
+                by_target: None,
+                stats: None,
+            }),
+            queue: SegQueue::new(),
+            next_seq: AtomicU64::new(1),
+            subscribers: parking_lot::const_mutex(Vec::new()),
+            subscriber_count: AtomicUsize::new(0),
+            last: parking_lot::const_mutex(None),
+            dropped_by_filter: AtomicU64::new(0),
+            abandoned_flushes: AtomicU64::new(0),
+            file_sink: parking_lot::const_mutex(None),
+            has_file_sink: AtomicBool::new(false),
+            sorted: AtomicBool::new(false),
+        }
+    }
+
+    /// Run some callback with the recorded events.
+    ///
+    /// This is not reentrancy safe, and reentrant use will deadlock.
+    ///
+    /// Will _not_ block the recording of new events.
+    pub fn with_events<R>(&self, cb: impl FnOnce(&mut Vec<Arc<Event>>) -> R) -> R {
+        let mut log = self.log.lock();
+        self.drain_queue(&mut log);
+        log.decompress();
+        // the callback may change the events in any way
+        log.by_target = None;
+        log.stats = None;
+        let events = log.events.get_mut();
+        #[cfg(feature = "metrics")]
+        let before = events.len();
+
+        let result = cb(events);
+        #[cfg(feature = "metrics")]
+        match events.len() {
+            after if after < before => crate::metrics::dropped(Reason::Removed, before - after),
+            after => crate::metrics::added(after - before),
+        }
+        result
+    }
+
+    /// Run some callback with the recorded events for which `pred` returns
+    /// true, in order, without collecting them.
+    ///
+    /// Like [`with_events`](Self::with_events), this is not reentrancy safe,
+    /// and will _not_ block the recording of new events.
+    pub fn with_filtered_events<R>(
+        &self,
+        pred: impl Fn(&Event) -> bool,
+        cb: impl FnOnce(&mut dyn Iterator<Item = &Arc<Event>>) -> R,
+    ) -> R {
+        let mut log = self.log.lock();
+        self.drain_queue(&mut log);
+        let events = log.since(0);
+        cb(&mut events.iter().filter(|event| pred(event)))
+    }
+
+    /// Take a [snapshot](EventSnapshot) of the recorded events.
+    ///
+    /// The snapshot shares the events with the store instead of copying
+    /// them, and holds no lock, so it can be iterated, filtered, or
+    /// serialized at leisure, on any thread, without getting in the way of
+    /// recording new events. Like [`with_events`](Self::with_events), this is
+    /// not reentrancy safe.
+    pub fn snapshot(&self) -> EventSnapshot {
+        let mut log = self.log.lock();
+        self.drain_queue(&mut log);
+        match log.compressed.is_empty() {
+            true => log.events.snapshot(),
+            false => log.since(0).into_owned().into(),
+        }
+    }
+
+    /// Run some callback with the recorded events with a
+    /// [sequence number](Event::seq) after `seq`.
+    ///
+    /// Passing the sequence number of the last event seen gives only the
+    /// events recorded since, or all of them for `0`. This relies on the
+    /// events being in sequence, which they are unless a [`with_events`](Self::with_events)
+    /// callback added or reordered them, or they are [sorted](Self::set_sorted) by time.
+    ///
+    /// Like `with_events`, this is not reentrancy safe, and will _not_ block
+    /// the recording of new events.
+    pub fn with_events_since<R>(&self, seq: u64, cb: impl FnOnce(&[Arc<Event>]) -> R) -> R {
+        let mut log = self.log.lock();
+        self.drain_queue(&mut log);
+        cb(&log.since(seq))
+    }
+
+    /// Run some callback with the recorded events in the span with the
+    /// [id](crate::Span::id) `id`, directly or in one of its children.
+    ///
+    /// Span ids may be reused once a span closes, so this includes events
+    /// from every span that had that id; use [`Span::same_instance`](crate::Span::same_instance) on
+    /// them to pick out one span. Like [`with_events`](Self::with_events),
+    /// this is not reentrancy safe.
+    pub fn events_for_span<R>(&self, id: u64, cb: impl FnOnce(&[Arc<Event>]) -> R) -> R {
+        let events = self.with_events_since(0, |events| {
+            let in_span = |event: &&Arc<Event>| {
+                std::iter::successors(event.span(), |span| span.parent())
+                    .any(|span| span.id() == id)
+            };
+            events.iter().filter(in_span).cloned().collect::<Vec<_>>()
+        });
+        cb(&events)
+    }
+
+    /// Run some callback with the recorded events with the
+    /// [effective target](Event::effective_target) `target`, in order.
+    ///
+    /// Only events with exactly that target are included, not those from its
+    /// submodules. The events are kept indexed by target as they are
+    /// recorded, so this doesn't scan every event, except the first time
+    /// after a [`with_events`](Self::with_events) callback, which may have
+    /// changed them, and except for [compressed](Self::set_compression)
+    /// events, which are scanned. Like `with_events`, this is not reentrancy safe.
+    pub fn events_for_target<R>(&self, target: &str, cb: impl FnOnce(&[Arc<Event>]) -> R) -> R {
+        let mut log = self.log.lock();
+        self.drain_queue(&mut log);
+        let Log {
+            events,
+            compressed,
+            by_target,
+            ..
+        } = &mut *log;
+        let index = by_target.get_or_insert_with(|| {
+            let mut index = TargetIndex::default();
+            for event in events.get() {
+                index_target(&mut index, event);
+            }
+            index
+        });
+        let indexed = index.get(target).map_or(&[][..], Vec::as_slice);
+        if compressed.is_empty() {
+            return cb(indexed);
+        }
+        let mut found = compressed.since(0);
+        found.retain(|event| event.effective_target() == target);
+        found.extend_from_slice(indexed);
+        cb(&found)
+    }
+
+    /// How many events are recorded, in total and by level, roughly how much
+    /// memory they take, and when they were recorded.
+    ///
+    /// The counts are kept up to date as events are recorded, so this is
+    /// cheap, except the first time after a [`with_events`](Self::with_events)
+    /// callback, which may have changed the events. Only events still in the
+    /// store are counted. Like `with_events`, this is not reentrancy safe.
+    pub fn stats(&self) -> EventStats {
+        let mut log = self.log.lock();
+        self.drain_queue(&mut log);
+        let mut stats = match log.stats {
+            Some(stats) => stats,
+            None => {
+                let mut stats = EventStats::default();
+                for event in log.since(0).iter() {
+                    stats.add(event);
+                }
+                log.stats = Some(stats);
+                stats
+            }
+        };
+        stats.set_compressed(log.compressed.sizes());
+        stats
+    }
+
+    /// Discard all recorded events, including those not yet seen by [`with_events`](Self::with_events).
+    ///
+    /// Like [`with_events`](Self::with_events), this is not reentrancy safe:
+    /// calling it from inside a `with_events` callback will deadlock. To clear
+    /// from such a callback, clear the provided `Vec` instead; queued events
+    /// will show up on the next call.
+    ///
+    /// Events recorded on other threads while clearing aren't lost: those
+    /// given a [sequence number](Event::seq) once clearing started are kept,
+    /// as if recorded after it.
+    pub fn clear_events(&self) {
+        let mut log = self.log.lock();
+        // events still being queued were recorded before clearing, so skip them
+        let next_seq = self.next_seq.load(Ordering::Relaxed);
+        let queued = std::iter::from_fn(|| self.queue.pop());
+        log.pending.extend(queued);
+        #[cfg(feature = "metrics")]
+        {
+            let dequeued = (log.pending.iter())
+                .filter(|event| event.seq < next_seq)
+                .count();
+            crate::metrics::dequeued(dequeued);
+            let compressed = log.compressed.sizes().0;
+            let cleared = log.events.get().len() + compressed + dequeued;
+            crate::metrics::dropped(Reason::Cleared, cleared);
+        }
+        log.events.clear();
+        log.compressed.clear();
+        log.pending.retain(|event| event.seq >= next_seq);
+        log.by_target = None;
+        log.stats = Some(EventStats::default());
+        log.next_seq = next_seq;
+        *self.last.lock() = None;
+    }
+
+    /// Keep only the recorded events for which `keep` returns true, like
+    /// [`Vec::retain`], e.g. to drop old events below some level.
+    ///
+    /// Events queued but not yet seen by [`with_events`](Self::with_events)
+    /// are included. Like `with_events`, this is not reentrancy safe, and
+    /// will _not_ block the recording of new events.
+    pub fn retain_events(&self, keep: impl FnMut(&Arc<Event>) -> bool) {
+        let mut log = self.log.lock();
+        self.drain_queue(&mut log);
+        log.decompress();
+        let removed = {
+            let events = log.events.get_mut();
+            let before = events.len();
+            events.retain(keep);
+            before - events.len()
+        };
+        if removed != 0 {
+            log.by_target = None;
+            log.stats = None;
+            #[cfg(feature = "metrics")]
+            crate::metrics::dropped(Reason::Removed, removed);
+        }
+    }
+
+    /// How many events [layers](crate::Layer) recording into this store didn't
+    /// record because their [`RecordFilter`](crate::RecordFilter) rejected them.
+    ///
+    /// This is not reset by [`clear_events`](Self::clear_events).
+    pub fn dropped_by_filter(&self) -> u64 {
+        self.dropped_by_filter.load(Ordering::Relaxed)
+    }
+
+    /// How many times a [`FlushGuard`](crate::FlushGuard) gave up on moving
+    /// queued events into the log, because a [`with_events`](Self::with_events)
+    /// callback held it for longer than the guard's timeout, or on flushing
+    /// the [file sink](Self::set_file_sink), because writing it took as long.
+    ///
+    /// The events left queued are still moved into the log by the next read.
+    pub fn abandoned_flushes(&self) -> u64 {
+        self.abandoned_flushes.load(Ordering::Relaxed)
+    }
+
+    /// Receive each event as it is recorded from now on.
+    ///
+    /// Events are still recorded into the store as usual. Up to
+    /// [`DEFAULT_SUBSCRIBER_CAPACITY`] events are buffered for the receiver,
+    /// and any more are dropped until it catches up. Dropping the receiver
+    /// unsubscribes.
+    pub fn subscribe(&self) -> EventReceiver {
+        self.subscribe_with_capacity(DEFAULT_SUBSCRIBER_CAPACITY)
+    }
+
+    /// Like [`subscribe`](Self::subscribe), buffering up to `capacity` events.
+    ///
+    /// With a capacity of zero, events are only received by a receiver
+    /// already waiting for one.
+    pub fn subscribe_with_capacity(&self, capacity: usize) -> EventReceiver {
+        let (subscriber, receiver) = subscribe::channel(capacity);
+        let mut subscribers = self.subscribers.lock();
+        subscribers.push(subscriber);
+        self.subscriber_count
+            .store(subscribers.len(), Ordering::Relaxed);
+        receiver
+    }
+
+    /// Also write each event to the file at `path` as it is recorded, as a
+    /// JSON Line in the format of [`write_jsonl`](Self::write_jsonl), so the
+    /// events outlive the process even if it crashes.
+    ///
+    /// The file is created, or truncated if it exists, and replaces any file
+    /// set before, which is flushed and closed. Writes are buffered; they are
+    /// flushed by [`flush_file_sink`](Self::flush_file_sink) and by a
+    /// [`FlushGuard`](crate::FlushGuard) for this store. Failing to write an
+    /// event doesn't stop recording, and is reported once to stderr. Repeats
+    /// [coalesced](crate::Layer::coalesce_repeats) into an event aren't written.
+    pub fn set_file_sink(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let sink = FileSink::create(path.as_ref())?;
+        let old = self.file_sink.lock().replace(sink);
+        self.has_file_sink.store(true, Ordering::Relaxed);
+        match old {
+            Some(mut old) => old.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Keep the events sorted by [time](Event::time) from now on, or not.
+    ///
+    /// Events are timestamped before their fields are recorded, so events
+    /// recorded at the same time on several threads can be stored slightly
+    /// out of order of their times. Sorted, each batch of events newly seen
+    /// by [`with_events`](Self::with_events) and the like is sorted and merged
+    /// into the events before it, going back only as far as its earliest.
+    /// Events with the same time stay in sequence.
+    ///
+    /// Sorted events can be out of [sequence](Event::seq), which
+    /// [`with_events_since`](Self::with_events_since) relies on. Events are
+    /// kept in sequence by default. Events already stored aren't sorted.
+    pub fn set_sorted(&self, sorted: bool) {
+        self.sorted.store(sorted, Ordering::Relaxed);
+    }
+
+    /// Compress the oldest events as `compression` says from now on, or
+    /// decompress them and stop if `None`, which is the default.
+    ///
+    /// Old events are rarely looked at again, but keeping every event of a
+    /// long session takes a lot of memory, mostly for their field values.
+    /// Compressed, events are encoded a chunk at a time into a compact form,
+    /// and with the `lz4` feature, that is compressed with LZ4 too. This
+    /// happens as queued events are moved into the store, by reads like
+    /// [`with_events_since`](Self::with_events_since), so recording events
+    /// never waits for it.
+    ///
+    /// Reads decompress the chunks they need, keeping those read most
+    /// recently decompressed. Reads of every event, like [`snapshot`](Self::snapshot),
+    /// decompress every chunk; [`with_events`](Self::with_events) decompresses
+    /// them for good, until they are compressed again.
+    ///
+    /// Decompressed events are new `Arc`s, so aren't [pointer-equal](Arc::ptr_eq)
+    /// to the events they were before being compressed, or to those of
+    /// another read. Their [sequence numbers](Event::seq) and their spans'
+    /// [keys](crate::Span::key) stay the same, so those still identify them.
+    /// [Sorted](Self::set_sorted) events aren't sorted into compressed ones.
+    pub fn set_compression(&self, compression: Option<Compression>) {
+        let mut log = self.log.lock();
+        log.compressed.set_options(compression);
+        if compression.is_none() {
+            log.decompress();
+        }
+    }
+
+    /// Stop writing events to the file set by [`set_file_sink`](Self::set_file_sink),
+    /// flushing and closing it.
+    pub fn close_file_sink(&self) -> io::Result<()> {
+        self.has_file_sink.store(false, Ordering::Relaxed);
+        match self.file_sink.lock().take() {
+            Some(mut sink) => sink.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Write out the events buffered for the file set by
+    /// [`set_file_sink`](Self::set_file_sink), if any.
+    pub fn flush_file_sink(&self) -> io::Result<()> {
+        match &mut *self.file_sink.lock() {
+            Some(sink) => sink.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Move queued events into the log and flush the file sink, giving up on
+    /// either after waiting until `timeout` has passed for it.
+    pub(crate) fn flush(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        match self.log.try_lock_until(deadline) {
+            Some(mut log) => self.drain_queue(&mut log),
+            None => {
+                self.abandoned_flushes.fetch_add(1, Ordering::Relaxed);
+                #[cfg(feature = "metrics")]
+                crate::metrics::flush_abandoned();
+            }
+        }
+        match self.file_sink.try_lock_until(deadline) {
+            Some(mut sink) => {
+                if let Some(sink) = &mut *sink {
+                    // there's nowhere left to report this but stderr
+                    if let Err(error) = sink.flush() {
+                        eprintln!("tracing-memory: failed to flush the file sink: {}", error);
+                    }
+                }
+            }
+            None => {
+                self.abandoned_flushes.fetch_add(1, Ordering::Relaxed);
+                #[cfg(feature = "metrics")]
+                crate::metrics::flush_abandoned();
+            }
+        }
+    }
+
+    /// Move queued events into the log, and compress old ones.
+    fn drain_queue(&self, log: &mut Log) {
+        self.dequeue(log);
+        if log.compressed.options().is_none() {
+            return;
+        }
+        // the last event may still be repeated, which compressing would lose
+        let last = self.last.lock();
+        let is_last =
+            |event: &Arc<Event>| last.as_ref().is_some_and(|last| Arc::ptr_eq(last, event));
+        let (_, now) = clock::now_and_elapsed();
+        if log.compressed.compress(&mut log.events, now, is_last) {
+            // only indexes the events left uncompressed
+            log.by_target = None;
+        }
+    }
+
+    /// Move queued events into the log, in sequence.
+    ///
+    /// Recording threads take a sequence number and then queue the event, so
+    /// events can be queued out of order. Those after a gap wait in `pending`
+    /// until the events before them are queued.
+    fn dequeue(&self, log: &mut Log) {
+        let Log {
+            events,
+            pending,
+            next_seq,
+            by_target,
+            stats,
+            ..
+        } = log;
+        pending.extend(std::iter::from_fn(|| self.queue.pop()));
+        if pending.is_empty() {
+            return;
+        }
+        pending.sort_unstable_by_key(|event| event.seq);
+
+        let skipped = pending.partition_point(|event| event.seq < *next_seq);
+        pending.drain(..skipped);
+        let ready = (pending.iter().map(|event| event.seq))
+            .zip(*next_seq..)
+            .take_while(|&(seq, next)| seq == next)
+            .count();
+        *next_seq += ready as u64;
+        // only touch the log when there's something to add, as doing so
+        // copies it if a snapshot of it is still around
+        if ready != 0 {
+            let events = events.get_mut();
+            let start = events.len();
+            events.extend(pending.drain(..ready));
+            if let Some(stats) = stats {
+                for event in &events[start..] {
+                    stats.add(event);
+                }
+            }
+            if self.sorted.load(Ordering::Relaxed) && merge_by_time(events, start) {
+                // rebuilt on first use, in the new order
+                *by_target = None;
+            } else if let Some(index) = by_target {
+                for event in &events[start..] {
+                    index_target(index, event);
+                }
+            }
+        }
+
+        #[cfg(feature = "metrics")]
+        {
+            crate::metrics::dequeued(skipped + ready);
+            crate::metrics::dropped(Reason::Cleared, skipped);
+        }
+    }
+
+    /// Count an event rejected by a layer's record filter.
+    pub(crate) fn filtered(&self) {
+        self.dropped_by_filter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Push `event`, unless it repeats the last event pushed this way.
+    ///
+    /// A repeat is still written to the file sink, so that the file doesn't
+    /// count fewer events than the log.
+    pub(crate) fn push_or_repeat(&self, event: Event) {
+        let mut last = self.last.lock();
+        let repeated = match &*last {
+            Some(last) if last.is_repeated_by(&event) => {
+                last.repeat(event.timestamp);
+                true
+            }
+            _ => false,
+        };
+        // the file is written without `last` held, so recording threads don't wait on it
+        if repeated {
+            drop(last);
+            self.write_file_sink(&event);
+        } else {
+            let event = self.enqueue(event);
+            *last = Some(Arc::clone(&event));
+            drop(last);
+            self.write_file_sink(&event);
+        }
+    }
+
+    pub(crate) fn push(&self, event: Event) -> Arc<Event> {
+        let event = self.enqueue(event);
+        self.write_file_sink(&event);
+        event
+    }
+
+    /// Queue `event` and send it to subscribers.
+    fn enqueue(&self, mut event: Event) -> Arc<Event> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::recorded(event.meta().level());
+        // queue right away, so later events don't wait in `pending` for long
+        event.seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let event = Arc::new(event);
+        self.queue.push(Arc::clone(&event));
+        if self.subscriber_count.load(Ordering::Relaxed) != 0 {
+            let mut subscribers = self.subscribers.lock();
+            subscribers.retain(|subscriber| subscriber.send(&event));
+            self.subscriber_count
+                .store(subscribers.len(), Ordering::Relaxed);
+        }
+        event
+    }
+
+    /// Write `event` to the file sink, if there is one.
+    ///
+    /// Writing the file is the slowest part of recording an event, so it is
+    /// done last, with no other lock held.
+    fn write_file_sink(&self, event: &Event) {
+        if self.has_file_sink.load(Ordering::Relaxed) {
+            if let Some(sink) = &mut *self.file_sink.lock() {
+                sink.write(event);
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::Event;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, RecvError, RecvTimeoutError, SyncSender, TryRecvError},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// How many events a subscriber buffers by default before dropping new ones.
+pub const DEFAULT_SUBSCRIBER_CAPACITY: usize = 4096;
+
+/// A subscription to events as they are recorded.
+///
+/// Each receiver has a bounded buffer, so that a slow receiver never blocks
+/// recording; events that arrive while it is full are dropped and counted.
+/// Dropping the receiver unsubscribes.
+#[derive(Debug)]
+pub struct EventReceiver {
+    receiver: Receiver<Arc<Event>>,
+    dropped: Arc<AtomicU64>,
+}
+
+/// The sending half of an [`EventReceiver`], held by the store.
+#[derive(Debug)]
+pub(crate) struct Subscriber {
+    sender: SyncSender<Arc<Event>>,
+    dropped: Arc<AtomicU64>,
+}
+
+pub(crate) fn channel(capacity: usize) -> (Subscriber, EventReceiver) {
+    let (sender, receiver) = mpsc::sync_channel(capacity);
+    let dropped = Arc::new(AtomicU64::new(0));
+    let subscriber = Subscriber {
+        sender,
+        dropped: dropped.clone(),
+    };
+    (subscriber, EventReceiver { receiver, dropped })
+}
+
+impl Subscriber {
+    /// Send `event` without blocking, returning whether the receiver is still subscribed.
+    pub(crate) fn send(&self, event: &Arc<Event>) -> bool {
+        match self.sender.try_send(Arc::clone(event)) {
+            Ok(()) => true,
+            Err(mpsc::TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+impl EventReceiver {
+    /// Wait for the next event.
+    ///
+    /// This only fails if the store is dropped, which the global one never is.
+    pub fn recv(&self) -> Result<Arc<Event>, RecvError> {
+        self.receiver.recv()
+    }
+
+    /// Take the next event if there is one, without waiting.
+    pub fn try_recv(&self) -> Result<Arc<Event>, TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    /// Wait up to `timeout` for the next event.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Arc<Event>, RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout)
+    }
+
+    /// Iterate over the events received so far, without waiting.
+    pub fn try_iter(&self) -> mpsc::TryIter<'_, Arc<Event>> {
+        self.receiver.try_iter()
+    }
+
+    /// Iterate over events as they are received, waiting for each.
+    pub fn iter(&self) -> mpsc::Iter<'_, Arc<Event>> {
+        self.receiver.iter()
+    }
+
+    /// How many events were dropped because this receiver's buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl<'a> IntoIterator for &'a EventReceiver {
+    type Item = Arc<Event>;
+    type IntoIter = mpsc::Iter<'a, Arc<Event>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
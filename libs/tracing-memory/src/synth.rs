@@ -0,0 +1,273 @@
+//! A synthetic tracing workload, for demos, benchmarks, and screenshots.
+//!
+//! The workload is a number of interleaved "requests", each a tree of spans
+//! of varying depth with events in them. Everything but timing is driven by
+//! a seeded random number generator, so the same [`Scenario`] always emits
+//! the same sequence of events with the same fields.
+
+use std::{error::Error, fmt, thread, time::Duration};
+use tracing::{field, Dispatch, Level};
+
+/// The parameters of a synthetic workload.
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    /// The seed for the random number generator.
+    pub seed: u64,
+    /// The total number of events to emit.
+    pub events: usize,
+    /// The number of requests in flight at the same time.
+    pub concurrent_requests: usize,
+    /// The maximum depth of a request's span tree, including the request span.
+    pub max_depth: usize,
+    /// The fraction of events that are errors, between 0 and 1.
+    pub error_rate: f64,
+    /// The number of events emitted back to back between quiet periods.
+    pub burst_len: usize,
+    /// How long to pause after each burst.
+    pub quiet_period: Duration,
+    #[doc(hidden)]
+    pub _non_exhaustive_but_allow_fru: (),
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario {
+            seed: 0,
+            events: 1000,
+            concurrent_requests: 8,
+            max_depth: 4,
+            error_rate: 0.02,
+            burst_len: 50,
+            quiet_period: Duration::default(),
+            _non_exhaustive_but_allow_fru: (),
+        }
+    }
+}
+
+/// Emit the events of `scenario` to `dispatch`, returning once all are emitted.
+pub fn generate_into(dispatch: &Dispatch, scenario: &Scenario) {
+    tracing::dispatcher::with_default(dispatch, || generate(scenario))
+}
+
+/// Emit the events of `scenario` to `dispatch` from a background thread.
+pub fn spawn(dispatch: Dispatch, scenario: Scenario) -> thread::JoinHandle<()> {
+    thread::Builder::new()
+        .name("tracing-memory synth".into())
+        .spawn(move || generate_into(&dispatch, &scenario))
+        .expect("failed to spawn synthetic workload thread")
+}
+
+fn generate(scenario: &Scenario) {
+    let mut rng = Rng(scenario.seed);
+    let mut next_id = 0;
+    let mut requests: Vec<Request> = (0..scenario.concurrent_requests.max(1))
+        .map(|_| Request::start(&mut rng, &mut next_id))
+        .collect();
+
+    for ix in 0..scenario.events {
+        let quiet = scenario.quiet_period > Duration::default();
+        if quiet && scenario.burst_len != 0 && ix != 0 && ix % scenario.burst_len == 0 {
+            thread::sleep(scenario.quiet_period);
+        }
+
+        let slot = rng.below(requests.len() as u64) as usize;
+        if requests[slot].step(&mut rng, scenario) {
+            requests[slot] = Request::start(&mut rng, &mut next_id);
+        }
+    }
+}
+
+/// A request in flight: its span stack, outermost first.
+struct Request {
+    spans: Vec<tracing::Span>,
+    steps: u64,
+    attempt: u64,
+}
+
+const METHODS: [&str; 4] = ["GET", "GET", "POST", "DELETE"];
+const PATHS: [&str; 5] = ["/", "/users", "/users/:id", "/orders", "/health"];
+const TABLES: [&str; 3] = ["users", "orders", "sessions"];
+const TEMPLATES: [&str; 3] = ["index.html", "user.html", "order.html"];
+const LOG_TARGETS: [&str; 3] = ["hyper::proto::h1", "rustls::conn", "mio::poll"];
+
+// Random values are drawn before the macros that use them, since field values
+// are not evaluated for disabled callsites, and the sequence of random values
+// should not depend on what the subscriber is interested in.
+
+impl Request {
+    fn start(rng: &mut Rng, next_id: &mut u64) -> Self {
+        *next_id += 1;
+        let (method, path) = (rng.pick(&METHODS), rng.pick(&PATHS));
+        let span = tracing::info_span!(
+            target: "app::http",
+            "request",
+            id = *next_id,
+            method,
+            path,
+            attempt = field::Empty,
+            status = field::Empty,
+        );
+        Request {
+            spans: vec![span],
+            steps: 0,
+            attempt: 0,
+        }
+    }
+
+    /// Emit one event, returning whether the request has completed.
+    fn step(&mut self, rng: &mut Rng, scenario: &Scenario) -> bool {
+        self.steps += 1;
+        let depth = self.spans.len();
+        let current = self.spans.last().unwrap().clone();
+        let _entered = current.enter();
+
+        if rng.chance(scenario.error_rate) {
+            let error = SynthError::chain(rng);
+            tracing::error!(
+                target: "app::http",
+                error = &error as &(dyn Error + 'static),
+                "request handling failed",
+            );
+            return false;
+        }
+
+        match rng.below(8) {
+            0 | 1 if depth < scenario.max_depth => {
+                let span = child(rng, &current);
+                tracing::trace!(target: "app::http", depth, "descending");
+                self.spans.push(span);
+            }
+            2 if depth > 1 => {
+                let elapsed_ms = rng.duration_ms();
+                tracing::debug!(target: "app::http", elapsed_ms, "finished step");
+                drop(_entered);
+                self.spans.pop();
+            }
+            3 => {
+                let (target, line, bytes) =
+                    (rng.pick(&LOG_TARGETS), rng.below(1000), rng.below(1 << 16));
+                tracing::event!(
+                    target: "log",
+                    Level::DEBUG,
+                    log.target = target,
+                    log.module_path = target,
+                    log.file = "src/lib.rs",
+                    log.line = line,
+                    "{} bytes read",
+                    bytes,
+                );
+            }
+            4 => {
+                // Recording the same field again makes it a multiple field.
+                self.attempt += 1;
+                self.spans[0].record("attempt", self.attempt);
+                tracing::warn!(target: "app::http", attempt = self.attempt, "retrying");
+            }
+            5 if depth == 1 && self.steps > 3 => {
+                let (status, latency_ms) =
+                    (rng.pick(&[200, 200, 200, 201, 204, 404]), rng.duration_ms());
+                self.spans[0].record("status", status);
+                tracing::info!(target: "app::http", status, latency_ms, "request completed");
+                return true;
+            }
+            _ => {
+                let (user_id, bytes, duration_ms) =
+                    (rng.below(1000), rng.below(1 << 20), rng.duration_ms());
+                tracing::info!(target: "app::service", user_id, bytes, duration_ms, "handled");
+            }
+        }
+        false
+    }
+}
+
+fn child(rng: &mut Rng, parent: &tracing::Span) -> tracing::Span {
+    match rng.below(4) {
+        0 => {
+            let (table, rows) = (rng.pick(&TABLES), rng.below(100));
+            tracing::info_span!(target: "app::db", parent: parent, "query", table, rows)
+        }
+        1 => {
+            let (key, hit) = (rng.below(1 << 12), rng.chance(0.8));
+            tracing::debug_span!(target: "app::cache", parent: parent, "lookup", key, hit)
+        }
+        2 => {
+            let user_id = rng.below(1000);
+            tracing::debug_span!(target: "app::auth", parent: parent, "authorize", user_id)
+        }
+        _ => {
+            let template = rng.pick(&TEMPLATES);
+            tracing::info_span!(target: "app::render", parent: parent, "render", template)
+        }
+    }
+}
+
+/// An error with a chain of sources.
+#[derive(Debug)]
+struct SynthError {
+    message: &'static str,
+    source: Option<Box<SynthError>>,
+}
+
+const ERRORS: [&str; 5] = [
+    "failed to load user",
+    "database query failed",
+    "connection reset by peer",
+    "timed out after 30s",
+    "invalid utf-8 in request body",
+];
+
+impl SynthError {
+    fn chain(rng: &mut Rng) -> Self {
+        let mut error = None;
+        for _ in 0..=rng.below(3) {
+            error = Some(SynthError {
+                message: rng.pick(&ERRORS),
+                source: error.map(Box::new),
+            });
+        }
+        error.unwrap()
+    }
+}
+
+impl fmt::Display for SynthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message)
+    }
+}
+
+impl Error for SynthError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.as_deref().map(|source| source as _)
+    }
+}
+
+/// A small deterministic random number generator (SplitMix64).
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    fn pick<T: Copy>(&mut self, choices: &[T]) -> T {
+        choices[self.below(choices.len() as u64) as usize]
+    }
+
+    /// A duration in milliseconds, mostly short with a long tail.
+    fn duration_ms(&mut self) -> u64 {
+        let scale = 1 << self.below(8);
+        1 + self.below(scale * 4)
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use tracing::Level;
+use tracing_memory::{clear_events, with_events, EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn clear_discards_queued_events() {
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("drained");
+        tracing::info!("drained");
+        assert_eq!(with_events(|events| events.len()), 2);
+
+        tracing::info!("queued");
+        clear_events();
+        assert_eq!(with_events(|events| events.len()), 0);
+
+        tracing::info!("after clear");
+        assert_eq!(with_events(|events| events.len()), 1);
+    });
+}
+
+#[test]
+fn retain_keeps_matching_events() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::warn!("warned");
+        tracing::debug!("drained");
+        store.with_events(|events| assert_eq!(events.len(), 2));
+        tracing::error!("queued");
+        tracing::trace!("queued");
+
+        store.retain_events(|event| *event.meta().level() <= Level::WARN);
+        let kept = store.with_events(|events| {
+            (events.iter())
+                .map(|event| *event.meta().level())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(kept, [Level::WARN, Level::ERROR]);
+        assert_eq!(store.stats().total(), 2);
+
+        // events recorded since are kept in sequence
+        tracing::info!("after");
+        store.retain_events(|_| true);
+        store.with_events(|events| assert_eq!(events.len(), 3));
+    });
+}
+
+#[test]
+fn events_recorded_while_clearing_are_kept() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    let dispatch = tracing::Dispatch::new(subscriber);
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let dispatch = dispatch.clone();
+            std::thread::spawn(move || {
+                tracing::dispatcher::with_default(&dispatch, || {
+                    for n in 0..2000 {
+                        tracing::info!(n, "racing");
+                    }
+                })
+            })
+        })
+        .collect();
+    for _ in 0..50 {
+        store.clear_events();
+        std::thread::yield_now();
+    }
+    store.clear_events();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    // every event given a sequence number after the last clear started is there
+    let seqs: Vec<_> = store.with_events(|events| events.iter().map(|event| event.seq()).collect());
+    let expected: Vec<_> = match seqs.first() {
+        Some(&first) => (first..first + seqs.len() as u64).collect(),
+        None => Vec::new(),
+    };
+    assert_eq!(seqs, expected);
+    tracing::dispatcher::with_default(&dispatch, || tracing::info!("last"));
+    let last = store.with_events(|events| events.last().unwrap().seq());
+    assert_eq!(last, seqs.last().map_or(last, |seq| seq + 1));
+}
@@ -0,0 +1,268 @@
+use std::{sync::Arc, thread, time::Duration};
+use tracing::Level;
+use tracing_memory::{format_event, Compression, Event, EventStore, FormatOptions, Layer};
+use tracing_subscriber::prelude::*;
+
+/// An error with an optional source, for errors with a chain of them.
+#[derive(Debug)]
+struct Nested(&'static str, Option<Box<Nested>>);
+
+impl std::fmt::Display for Nested {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for Nested {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.1.as_deref().map(|source| source as _)
+    }
+}
+
+/// Record 40 events of every kind of field, in spans and on two threads.
+fn record(store: &Arc<EventStore>) {
+    let layer = |store: &Arc<EventStore>| Layer::with_store(store.clone()).max_field_len(32);
+    let subscriber = tracing_subscriber::registry().with(layer(store));
+    tracing::subscriber::with_default(subscriber, || {
+        let request = tracing::info_span!(
+            target: "app::http",
+            "request",
+            id = 1u64,
+            user = tracing::field::Empty
+        );
+        let _entered = request.enter();
+        for ix in 0..12 {
+            tracing::info!(target: "app::http", ix, ok = ix % 2 == 0, "handled {}", ix);
+            if ix == 5 {
+                request.record("user", "alice");
+                request.record("id", 2u64);
+            }
+        }
+        let query = tracing::debug_span!(target: "app::db", "query", table = "users");
+        query.in_scope(|| {
+            for delta in -6i64..6 {
+                tracing::debug!(target: "app::db", delta, rows = ?[1, 2], "queried");
+            }
+        });
+        let error = Nested("query failed", Some(Box::new(Nested("timed out", None))));
+        tracing::warn!(target: "app::db", error = &error as &dyn std::error::Error, "slow");
+        tracing::error!(
+            target: "app::http",
+            text = "a value much longer than thirty-two bytes, cut short",
+            "failed"
+        );
+    });
+
+    let store = store.clone();
+    let worker = thread::Builder::new().name("worker".into()).spawn(move || {
+        let subscriber = tracing_subscriber::registry().with(layer(&store));
+        tracing::subscriber::with_default(subscriber, || {
+            for ix in 0..14 {
+                tracing::trace!(target: "app::worker", ix, "working");
+            }
+        });
+    });
+    worker.unwrap().join().unwrap();
+}
+
+/// Everything reading `events` tells about them, to compare events read
+/// before and after being compressed.
+fn describe<'a>(events: impl IntoIterator<Item = &'a Arc<Event>>) -> Vec<String> {
+    let describe = |event: &Event| {
+        let spans: Vec<_> = std::iter::successors(event.span(), |span| span.parent())
+            .map(|span| {
+                let fields: Vec<_> = span.fields().collect();
+                format!("{:?} {} {:?}", span.key(), span.id(), fields)
+            })
+            .collect();
+        let fields: Vec<_> = event.fields().collect();
+        format!(
+            "{} {:?} {:?} {} {:?} {} {:?} {:?}\n{}",
+            event.seq(),
+            event.time(),
+            event.elapsed_since_start(),
+            event.thread(),
+            event.span_id(),
+            event.repeat_count(),
+            fields,
+            spans,
+            format_event(event, &FormatOptions::default()),
+        )
+    };
+    events.into_iter().map(|event| describe(event)).collect()
+}
+
+fn compress_all_but(keep_newest: usize, chunk_len: usize) -> Option<Compression> {
+    Some(Compression {
+        older_than: None,
+        keep_newest: Some(keep_newest),
+        chunk_len,
+        ..Compression::default()
+    })
+}
+
+#[test]
+fn compressed_events_read_as_before() {
+    let store = Arc::new(EventStore::new());
+    record(&store);
+    let before = store.snapshot();
+    assert_eq!(before.len(), 40);
+    let approx_bytes = store.stats().approx_bytes();
+
+    store.set_compression(compress_all_but(5, 8));
+    let stats = store.stats();
+    assert_eq!((stats.total(), stats.compressed()), (40, 32));
+    assert_eq!(stats.approx_bytes(), approx_bytes);
+    assert!(stats.live_bytes() < approx_bytes);
+    assert!(stats.compression_ratio().unwrap() > 1.0);
+
+    assert_eq!(describe(&store.snapshot()), describe(&before));
+    let since = before[20].seq();
+    store.with_events_since(since, |events| {
+        assert_eq!(describe(events), describe(&before[21..]));
+    });
+    for target in ["app::db", "app::worker"] {
+        let expected = before
+            .iter()
+            .filter(|event| event.effective_target() == target);
+        store.events_for_target(target, |events| {
+            assert_eq!(describe(events), describe(expected));
+        });
+    }
+    let id = before[0].span().unwrap().id();
+    store.events_for_span(id, |events| {
+        assert_eq!(describe(events), describe(&before[..26]));
+    });
+
+    // decompressed for good, until the next read compresses them again
+    store.with_events(|events| assert_eq!(describe(&*events), describe(&before)));
+    assert_eq!(store.stats().compressed(), 32);
+}
+
+#[test]
+fn filters_and_exports_see_across_compressed_events() {
+    let store = Arc::new(EventStore::new());
+    record(&store);
+    let filtered = |store: &EventStore| {
+        let severe = |event: &Event| *event.meta().level() <= Level::WARN;
+        let every_third = |event: &Event| event.field_i64("ix").is_some_and(|ix| ix % 3 == 0);
+        let severe = store.with_filtered_events(severe, |events| describe(events));
+        let every_third = store.with_filtered_events(every_third, |events| describe(events));
+        (severe, every_third)
+    };
+    let export = |store: &EventStore| {
+        let mut jsonl = Vec::new();
+        store.write_jsonl(&mut jsonl).unwrap();
+        String::from_utf8(jsonl).unwrap()
+    };
+    let filtered_before = filtered(&store);
+    let exported_before = export(&store);
+    #[cfg(feature = "serde")]
+    let json_before = {
+        let mut json = Vec::new();
+        store.export_json(&mut json).unwrap();
+        json
+    };
+
+    store.set_compression(compress_all_but(5, 8));
+    assert_eq!(store.stats().compressed(), 32);
+    assert_eq!(filtered(&store), filtered_before);
+    #[cfg(feature = "serde")]
+    {
+        let mut json = Vec::new();
+        store.export_json(&mut json).unwrap();
+        assert_eq!(json, json_before);
+        assert_eq!(store.stats().compressed(), 32);
+    }
+    assert_eq!(export(&store), exported_before);
+}
+
+#[test]
+fn decompressed_chunks_are_cached_up_to_a_bound() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        for ix in 0..6 {
+            tracing::info!(ix);
+        }
+    });
+    let read = |seq| store.with_events_since(seq, |events| events.to_vec());
+
+    store.set_compression(Some(Compression {
+        cached_chunks: 1,
+        ..compress_all_but(0, 2).unwrap()
+    }));
+    assert_eq!(store.stats().compressed(), 6);
+    let (first, again) = (read(4), read(4));
+    assert!(Arc::ptr_eq(&first[0], &again[0]));
+    // the first chunk is left out of the cache to make room for the others
+    let (first, again) = (read(0), read(0));
+    assert_eq!(describe(&first), describe(&again));
+    assert!(!Arc::ptr_eq(&first[0], &again[0]));
+
+    store.set_compression(Some(Compression {
+        cached_chunks: 0,
+        ..compress_all_but(0, 2).unwrap()
+    }));
+    let (first, again) = (read(4), read(4));
+    assert!(!Arc::ptr_eq(&first[0], &again[0]));
+}
+
+#[test]
+fn events_are_compressed_once_old_enough() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        for ix in 0..10 {
+            tracing::info!(ix);
+        }
+    });
+    let before = store.snapshot();
+    let older_than = |age| {
+        Some(Compression {
+            older_than: Some(age),
+            keep_newest: None,
+            chunk_len: 4,
+            ..Compression::default()
+        })
+    };
+
+    store.set_compression(older_than(Duration::from_secs(60 * 60)));
+    assert_eq!(store.stats().compressed(), 0);
+    store.set_compression(older_than(Duration::ZERO));
+    assert_eq!(store.stats().compressed(), 8);
+    store.set_compression(None);
+    let stats = store.stats();
+    assert_eq!((stats.total(), stats.compressed()), (10, 0));
+    assert_eq!(stats.compression_ratio(), None);
+    assert_eq!(describe(&store.snapshot()), describe(&before));
+}
+
+#[test]
+fn recording_does_not_wait_for_compression() {
+    let store = Arc::new(EventStore::new());
+    let layer = Layer::with_store(store.clone()).coalesce_repeats(true);
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        store.set_compression(Some(Compression {
+            older_than: Some(Duration::ZERO),
+            chunk_len: 1,
+            ..Compression::default()
+        }));
+        tracing::info!("first");
+        // recording only queues events, without the lock held by reading and compressing
+        store.with_events_since(0, |_| tracing::info!("while reading"));
+        let repeat = || tracing::info!("repeated");
+        for _ in 0..3 {
+            repeat();
+        }
+        assert_eq!(store.stats().compressed(), 2);
+
+        // the last event may still be repeated, so isn't compressed
+        repeat();
+        store.with_events_since(0, |events| {
+            assert_eq!(events.len(), 3);
+            assert_eq!(events[2].repeat_count(), 4);
+        });
+    });
+}
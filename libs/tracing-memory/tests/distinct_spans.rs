@@ -0,0 +1,42 @@
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn spans_are_listed_once_parents_first() {
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("outside");
+        let root = tracing::info_span!("root", done = tracing::field::Empty);
+        root.in_scope(|| {
+            tracing::info_span!("child").in_scope(|| {
+                tracing::info_span!("grandchild").in_scope(|| tracing::info!("deep"));
+                tracing::info!("shallower");
+            });
+            root.record("done", true);
+            tracing::info!("after recording");
+        });
+        tracing::info_span!("other").in_scope(|| tracing::info!("elsewhere"));
+    });
+
+    tracing_memory::with_events(|events| {
+        let spans = tracing_memory::distinct_spans(events.iter().map(|event| &**event));
+        let names: Vec<_> = spans.iter().map(|span| span.meta().name()).collect();
+        assert_eq!(names, ["root", "child", "grandchild", "other"]);
+
+        // recording took a new snapshot of root, which is still the same span
+        let before = events[1].span().unwrap();
+        let after = events[3].span().unwrap();
+        assert_eq!(after.meta().name(), "root");
+        assert!(!std::ptr::eq(
+            before.parent().unwrap().parent().unwrap(),
+            after
+        ));
+        assert_eq!(
+            before.parent().unwrap().parent().unwrap().key(),
+            after.key()
+        );
+        assert_ne!(after.key(), events[4].span().unwrap().key());
+        assert_eq!(spans[0].key(), after.key());
+        assert_eq!(spans[0].field("done"), None);
+        assert!(spans[0].latest().unwrap().field_bool("done").unwrap());
+    });
+}
@@ -0,0 +1,48 @@
+use serde_json::Value;
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn export_shape() {
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    tracing::subscriber::with_default(subscriber, || {
+        let outer = tracing::info_span!("outer", id = 1u64);
+        let inner = tracing::debug_span!(parent: &outer, "inner", ok = true);
+        inner.in_scope(|| {
+            tracing::warn!(target: "export", a = -1i64, b = "text", "hello {}", "world");
+        });
+        tracing::error!(target: "export", error = %"oops");
+    });
+
+    let mut exported = Vec::new();
+    tracing_memory::export_json(&mut exported).unwrap();
+    let exported = String::from_utf8(exported).unwrap();
+
+    let golden = include_str!("export_json.jsonl");
+    assert_eq!(exported.lines().count(), golden.lines().count());
+    for (line, expected) in exported.lines().zip(golden.lines()) {
+        let mut line: Value = serde_json::from_str(line).unwrap();
+        let expected: Value = serde_json::from_str(expected).unwrap();
+        assert!(line["timestamp"].is_string());
+        assert!(line["thread"].is_string());
+        assert!(line["name"].as_str().unwrap().starts_with("event "));
+        line["name"] = Value::Null;
+        normalize(&mut line);
+        assert_eq!(line, expected);
+    }
+}
+
+/// Null out the values which depend on when and where the test ran.
+fn normalize(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match key.as_str() {
+                    "timestamp" | "file" | "line" | "thread" => *value = Value::Null,
+                    _ => normalize(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(normalize),
+        _ => {}
+    }
+}
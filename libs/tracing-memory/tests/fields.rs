@@ -0,0 +1,338 @@
+use tracing_memory::Field;
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn variants_with_debug_and_display() {
+    let fields = [
+        (Field::I64(-1), "-1", "-1"),
+        (Field::U64(1), "1", "1"),
+        (Field::Bool(true), "true", "true"),
+        (Field::Str("a b".into()), r#""a b""#, "a b"),
+        (Field::Display("a b".into()), "a b", "a b"),
+        (Field::Error("oops".into()), "oops", "oops"),
+        (
+            Field::Debug(r#"Some("a")"#.into()),
+            r#"Some("a")"#,
+            r#"Some("a")"#,
+        ),
+        (
+            Field::Truncated {
+                text: "abc".into(),
+                original_len: 10,
+            },
+            "abc… (+7 bytes)",
+            "abc… (+7 bytes)",
+        ),
+        (
+            Field::Multiple(vec![Field::U64(1), Field::Str("a".into())]),
+            r#"1"a""#,
+            "1, a",
+        ),
+        (
+            Field::Multiple(vec![Field::I64(1), Field::Str("x".into())]),
+            r#"1"x""#,
+            "1, x",
+        ),
+        (
+            Field::Multiple(vec![
+                Field::Bool(false),
+                Field::Multiple(vec![Field::Display("b".into()), Field::I64(-2)]),
+            ]),
+            "falseb-2",
+            "false, b, -2",
+        ),
+        (Field::Multiple(vec![]), "", ""),
+    ];
+
+    for (field, debug, display) in &fields {
+        let with_debug: String = field.with_debug(|value| format!("{:?}", value)).collect();
+        assert_eq!(with_debug, *debug);
+        assert_eq!(field.to_string(), *display);
+    }
+}
+
+#[test]
+fn with_debug_flattens_nesting_in_order() {
+    let debug = |field: &Field| -> Vec<String> {
+        field.with_debug(|value| format!("{:?}", value)).collect()
+    };
+    let nested_first = Field::Multiple(vec![
+        Field::Multiple(vec![Field::I64(1), Field::I64(2)]),
+        Field::I64(3),
+    ]);
+    assert_eq!(debug(&nested_first), ["1", "2", "3"]);
+
+    let deep = Field::Multiple(vec![
+        Field::Multiple(vec![
+            Field::Multiple(vec![]),
+            Field::Multiple(vec![Field::I64(1)]),
+            Field::I64(2),
+        ]),
+        Field::Multiple(vec![Field::I64(3), Field::Multiple(vec![Field::I64(4)])]),
+        Field::I64(5),
+    ]);
+    assert_eq!(debug(&deep), ["1", "2", "3", "4", "5"]);
+    assert_eq!(deep.to_string(), "1, 2, 3, 4, 5");
+    assert!(debug(&Field::Multiple(vec![Field::Multiple(vec![])])).is_empty());
+}
+
+#[test]
+fn display_string_matches_tracing() {
+    let multiple = Field::Multiple(vec![Field::U64(1), Field::Str("a".into())]);
+    assert_eq!(multiple.to_display_string(), r#"1, "a""#);
+    assert_eq!(Field::Str("a b".into()).to_display_string(), r#""a b""#);
+    assert_eq!(Field::Display("a b".into()).to_display_string(), "a b");
+}
+
+#[test]
+fn iter_flattens_multiple() {
+    let nested = Field::Multiple(vec![
+        Field::U64(1),
+        Field::Multiple(vec![Field::Str("a".into()), Field::Multiple(vec![])]),
+        Field::Bool(true),
+    ]);
+    let values: Vec<_> = nested.iter().collect();
+    assert_eq!(
+        values,
+        [&Field::U64(1), &Field::Str("a".into()), &Field::Bool(true)]
+    );
+
+    let single = Field::I64(-1);
+    assert_eq!(single.iter().collect::<Vec<_>>(), [&single]);
+}
+
+#[test]
+fn typed_accessors() {
+    #[rustfmt::skip]
+    let fields = [
+        (Field::I64(-1), Some(-1), None, None, None),
+        (Field::I64(5), Some(5), Some(5), None, None),
+        (Field::U64(1), Some(1), Some(1), None, None),
+        (Field::U64(u64::MAX), None, Some(u64::MAX), None, None),
+        (Field::Bool(true), None, None, Some(true), None),
+        (Field::Str("a".into()), None, None, None, Some("a")),
+        (Field::Display("b".into()), None, None, None, Some("b")),
+        (Field::Error("c".into()), None, None, None, Some("c")),
+        (Field::Debug("d".into()), None, None, None, Some("d")),
+        (Field::Multiple(vec![Field::U64(1), Field::U64(2)]), None, None, None, None),
+    ];
+
+    for (field, i64, u64, bool, str) in &fields {
+        assert_eq!(field.as_i64(), *i64, "{:?}", field);
+        assert_eq!(field.as_u64(), *u64, "{:?}", field);
+        assert_eq!(field.as_bool(), *bool, "{:?}", field);
+        assert_eq!(field.as_str(), *str, "{:?}", field);
+    }
+}
+
+#[test]
+fn message_is_display() {
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(shown = %"a b", "message {}", 1);
+    });
+
+    tracing_memory::with_events(|events| {
+        let message = events[0].field("message").unwrap();
+        assert_eq!(message, &Field::Display("message 1".into()));
+        assert!(message.is_stringish());
+        // tracing passes display values to record_debug
+        assert_eq!(events[0].field("shown"), Some(&Field::Debug("a b".into())));
+    });
+}
+
+#[test]
+fn recording_twice_keeps_both_values() {
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("span", id = "first", n = 3u64);
+        span.record("id", 2u64);
+        span.in_scope(|| tracing::info!(flag = true, "in span"));
+    });
+
+    tracing_memory::with_events(|events| {
+        let event = events.last().unwrap();
+        assert_eq!(event.field_bool("flag"), Some(true));
+        assert_eq!(event.field_str("message"), Some("in span"));
+        assert_eq!(event.field_u64("message"), None);
+
+        let span = event.span().unwrap();
+        assert_eq!(span.field_u64("n"), Some(3));
+        assert_eq!(span.field_i64("n"), Some(3));
+        // the typed accessors don't pick one of several values
+        let id = span.field("id").unwrap();
+        assert_eq!(span.field_str("id"), None);
+        assert_eq!(span.field_u64("id"), None);
+        let values: Vec<_> = id.iter().collect();
+        assert_eq!(values, [&Field::Str("first".into()), &Field::U64(2)]);
+        assert_eq!(id.to_display_string(), r#""first", 2"#);
+    });
+}
+
+#[test]
+fn declared_fields_are_known_before_recording() {
+    use tracing_memory::FieldState;
+
+    // its own store, as the other tests here look at the global one's first event
+    let store = std::sync::Arc::new(tracing_memory::EventStore::new());
+    let layer = tracing_memory::Layer::with_store(store.clone());
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("request", id = 7, user = tracing::field::Empty);
+        span.in_scope(|| tracing::info!(status = tracing::field::Empty, "before"));
+        span.record("user", "bob");
+        span.in_scope(|| tracing::info!("after"));
+    });
+
+    store.with_events(|events| {
+        let before = &events[0];
+        assert_eq!(
+            before.declared_fields().collect::<Vec<_>>(),
+            ["message", "status"]
+        );
+        assert_eq!(before.unset_fields().collect::<Vec<_>>(), ["status"]);
+        assert!(matches!(
+            before.field_state("message"),
+            FieldState::Recorded(_)
+        ));
+        assert_eq!(before.field_state("status"), FieldState::Declared);
+        assert_eq!(before.field_state("missing"), FieldState::Unknown);
+
+        let span = before.span().unwrap();
+        assert_eq!(span.declared_fields().collect::<Vec<_>>(), ["id", "user"]);
+        assert_eq!(span.field_state("id"), FieldState::Recorded(&Field::I64(7)));
+        assert_eq!(span.field_state("user"), FieldState::Declared);
+        assert_eq!(span.unset_fields().collect::<Vec<_>>(), ["user"]);
+
+        // recorded late, on the snapshot taken by recording
+        let user = Field::Str("bob".into());
+        let later = events[1].span().unwrap();
+        assert_eq!(later.field_state("user"), FieldState::Recorded(&user));
+        assert_eq!(later.unset_fields().count(), 0);
+        let latest = span.latest().unwrap();
+        assert_eq!(latest.field_state("user"), FieldState::Recorded(&user));
+    });
+}
+
+#[test]
+fn message_is_always_text() {
+    let store = std::sync::Arc::new(tracing_memory::EventStore::new());
+    let layer = tracing_memory::Layer::with_store(store.clone());
+    let subscriber = tracing_subscriber::registry().with(layer);
+    let name = "world";
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("hi");
+        tracing::info!("hi {}", name);
+        tracing::info!(message = %name);
+        tracing::info!(message = ?name);
+        tracing::info!(message = "plain");
+        tracing::info!(message = 5);
+        tracing::info!(message = false);
+        tracing::info!(name, "with a field");
+        tracing::info!(name);
+    });
+
+    store.with_events(|events| {
+        let messages: Vec<_> = events.iter().map(|event| event.message()).collect();
+        assert_eq!(
+            messages,
+            [
+                Some(&Field::Display("hi".into())),
+                Some(&Field::Display("hi world".into())),
+                Some(&Field::Display("world".into())),
+                Some(&Field::Display(r#""world""#.into())),
+                Some(&Field::Str("plain".into())),
+                Some(&Field::Display("5".into())),
+                Some(&Field::Display("false".into())),
+                Some(&Field::Display("with a field".into())),
+                None,
+            ]
+        );
+        // other fields keep their type
+        assert_eq!(events[7].field("name"), Some(&Field::Str("world".into())));
+    });
+}
+
+/// An error with an optional source, for errors with a chain of them.
+#[derive(Debug)]
+struct Nested(&'static str, Option<Box<Nested>>);
+
+impl std::fmt::Display for Nested {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for Nested {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.1.as_deref().map(|source| source as _)
+    }
+}
+
+#[test]
+fn errors_keep_their_sources() {
+    let root = Nested("connection reset", None);
+    let cause = Nested("query failed", Some(Box::new(root)));
+    let error = Nested("failed to load user", Some(Box::new(cause)));
+    let single = Nested("not found", None);
+
+    let store = std::sync::Arc::new(tracing_memory::EventStore::new());
+    let layer = tracing_memory::Layer::with_store(store.clone());
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::error!(error = &error as &dyn std::error::Error, "chained");
+        tracing::error!(error = &single as &dyn std::error::Error, "single");
+    });
+
+    store.with_events(|events| {
+        let chained = events[0].field("error").unwrap();
+        assert!(matches!(chained, Field::ErrorChain(_)));
+        let links: Vec<_> = chained.error_chain().unwrap().collect();
+        assert_eq!(
+            links,
+            ["failed to load user", "query failed", "connection reset"]
+        );
+        assert_eq!(
+            chained.to_string(),
+            "failed to load user: query failed: connection reset"
+        );
+        assert_eq!(
+            chained.to_display_string(),
+            "failed to load user: query failed: connection reset"
+        );
+        assert!(chained.is_stringish());
+        assert_eq!(chained.as_str(), None);
+
+        // an error without sources is recorded as before
+        let single = events[1].field("error").unwrap();
+        assert_eq!(single, &Field::Error("not found".into()));
+        let links: Vec<_> = single.error_chain().unwrap().collect();
+        assert_eq!(links, ["not found"]);
+        assert!(Field::Str("a".into()).error_chain().is_none());
+    });
+}
+
+#[test]
+fn long_error_chains_are_truncated_whole() {
+    let cause = Nested("query failed", None);
+    let error = Nested("failed to load user", Some(Box::new(cause)));
+
+    let store = std::sync::Arc::new(tracing_memory::EventStore::new());
+    let layer = tracing_memory::Layer::with_store(store.clone()).max_field_len(25);
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::error!(error = &error as &dyn std::error::Error);
+    });
+
+    store.with_events(|events| {
+        let field = events[0].field("error").unwrap();
+        assert_eq!(
+            field,
+            &Field::Truncated {
+                text: "failed to load user: quer".into(),
+                original_len: 33,
+            }
+        );
+        assert!(field.error_chain().is_none());
+    });
+}
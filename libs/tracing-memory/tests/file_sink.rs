@@ -0,0 +1,78 @@
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+fn read_lines(path: &std::path::Path) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn events_are_written_as_recorded() {
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("tracing-memory-sink-{}.jsonl", std::process::id()));
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("before the sink");
+        store.set_file_sink(&path).unwrap();
+        tracing::info_span!("request").in_scope(|| {
+            tracing::warn!(n = 3u64, "first");
+        });
+        tracing::error!("second");
+        store.flush_file_sink().unwrap();
+
+        let lines = read_lines(&path);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["level"], "WARN");
+        assert_eq!(lines[0]["spans"], json!(["request"]));
+        assert_eq!(lines[0]["fields"], json!({ "message": "first", "n": 3 }));
+        assert_eq!(lines[1]["fields"], json!({ "message": "second" }));
+
+        // the file is written to as events are recorded, not from the store
+        store.clear_events();
+        tracing::info!("third");
+        store.close_file_sink().unwrap();
+        tracing::info!("after closing");
+    });
+
+    let messages: Vec<_> = (read_lines(&path).iter())
+        .map(|line| line["fields"]["message"].clone())
+        .collect();
+    assert_eq!(messages, ["first", "second", "third"]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn repeats_are_each_written() {
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!(
+        "tracing-memory-repeats-{}.jsonl",
+        std::process::id()
+    ));
+    let store = Arc::new(EventStore::new());
+    let layer = Layer::with_store(store.clone()).coalesce_repeats(true);
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        store.set_file_sink(&path).unwrap();
+        for _ in 0..3 {
+            tracing::info!("repeated");
+        }
+        tracing::info!("once");
+        store.close_file_sink().unwrap();
+    });
+
+    store.with_events(|events| {
+        let counts: Vec<_> = events.iter().map(|event| event.repeat_count()).collect();
+        assert_eq!(counts, [3, 1]);
+    });
+    let messages: Vec<_> = (read_lines(&path).iter())
+        .map(|line| line["fields"]["message"].clone())
+        .collect();
+    assert_eq!(messages, ["repeated", "repeated", "repeated", "once"]);
+    std::fs::remove_file(&path).unwrap();
+}
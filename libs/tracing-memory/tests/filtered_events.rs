@@ -0,0 +1,28 @@
+use tracing::Level;
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn only_matching_events_are_passed() {
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::trace!("trace");
+        tracing::error!("first error");
+        tracing::info!("info");
+        tracing::warn!("warning");
+        tracing::debug!("debug");
+        tracing::error!("second error");
+    });
+
+    let important = |event: &tracing_memory::Event| *event.meta().level() <= Level::WARN;
+    let messages = tracing_memory::with_filtered_events(important, |events| {
+        let message = |event: &std::sync::Arc<tracing_memory::Event>| {
+            event.field("message").unwrap().to_string()
+        };
+        events.map(message).collect::<Vec<_>>()
+    });
+    assert_eq!(messages, ["first error", "warning", "second error"]);
+
+    let none = tracing_memory::with_filtered_events(|_| false, |events| events.count());
+    assert_eq!(none, 0);
+    assert_eq!(tracing_memory::with_events(|events| events.len()), 6);
+}
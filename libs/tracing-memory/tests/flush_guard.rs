@@ -0,0 +1,97 @@
+use std::{
+    panic,
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+use tracing_memory::{EventStore, Field, FlushGuard, Layer};
+use tracing_subscriber::prelude::*;
+
+fn messages(store: &EventStore) -> Vec<String> {
+    store.with_events(|events| {
+        events
+            .iter()
+            .map(|event| event.field("message").unwrap().to_string())
+            .collect()
+    })
+}
+
+#[test]
+fn records_session_end_on_return() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    let received = store.subscribe();
+    tracing::subscriber::with_default(subscriber, || {
+        let mut guard = FlushGuard::with_store(store.clone());
+        tracing::info!("before exit");
+        guard.set_exit_status(0);
+    });
+
+    assert_eq!(messages(&store), ["before exit", "session ended"]);
+    assert_eq!(received.try_iter().count(), 2);
+    store.with_events(|events| {
+        let ended = &events[1];
+        assert_eq!(ended.field("exit_status").unwrap().to_string(), "0");
+        assert_eq!(ended.field("panicking"), Some(&Field::Bool(false)));
+    });
+}
+
+#[test]
+fn records_session_end_on_panic() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            let _guard = FlushGuard::with_store(store.clone());
+            tracing::info!("before panic");
+            panic!("exiting");
+        }));
+        assert!(result.is_err());
+    });
+
+    assert_eq!(messages(&store), ["before panic", "session ended"]);
+    store.with_events(|events| {
+        let ended = &events[1];
+        assert_eq!(ended.field("exit_status"), None);
+        assert_eq!(ended.field("panicking"), Some(&Field::Bool(true)));
+    });
+}
+
+#[test]
+fn gives_up_on_a_blocked_log() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    let (held, is_held) = mpsc::channel();
+    let (release, released) = mpsc::channel::<()>();
+    let reader = {
+        let store = store.clone();
+        thread::spawn(move || {
+            store.with_events(|_| {
+                held.send(()).unwrap();
+                released.recv().unwrap();
+            })
+        })
+    };
+    is_held.recv().unwrap();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let mut guard = FlushGuard::with_store(store.clone());
+        guard.set_timeout(Duration::from_millis(10));
+        tracing::info!("before exit");
+    });
+    assert_eq!(store.abandoned_flushes(), 1);
+
+    release.send(()).unwrap();
+    reader.join().unwrap();
+    assert_eq!(messages(&store), ["before exit", "session ended"]);
+    drop(FlushGuard::with_store(store.clone()));
+    assert_eq!(store.abandoned_flushes(), 1);
+}
+
+#[test]
+fn does_nothing_without_a_subscriber() {
+    let store = Arc::new(EventStore::new());
+    drop(FlushGuard::with_store(store.clone()));
+    drop(tracing_memory::flush_guard());
+    assert!(messages(&store).is_empty());
+}
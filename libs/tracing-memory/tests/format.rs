@@ -0,0 +1,117 @@
+use std::{sync::Arc, thread};
+use tracing_memory::{format_event, EventStore, FormatOptions, Layer, TimeFormat};
+use tracing_subscriber::prelude::*;
+
+fn record(f: impl FnOnce() + Send + 'static) -> Arc<EventStore> {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    // on a named thread, so that the thread shows the same every time
+    thread::Builder::new()
+        .name("worker".into())
+        .spawn(move || tracing::subscriber::with_default(subscriber, f))
+        .unwrap()
+        .join()
+        .unwrap();
+    store
+}
+
+#[test]
+fn everything_but_the_time_by_default() {
+    let store = record(|| {
+        let request = tracing::info_span!(target: "app", "request", id = 7);
+        let _request = request.enter();
+        tracing::info_span!(target: "app::db", "query")
+            .in_scope(|| tracing::warn!(target: "app::db", rows = 0, "nothing found"));
+    });
+
+    store.with_events(|events| {
+        let options = FormatOptions::default();
+        assert_eq!(
+            format_event(&events[0], &options),
+            "[WARN] [worker] app::db: nothing found\n    \
+             rows: 0\n    \
+             in app::db::query\n    \
+             in app::request\n        \
+             id: 7"
+        );
+
+        let without_fields = FormatOptions {
+            timestamp: TimeFormat::Delta(events[0].elapsed_since_start()),
+            fields: false,
+            ..options
+        };
+        assert_eq!(
+            format_event(&events[0], &without_fields),
+            "[Δ0µs] [WARN] [worker] app::db: nothing found\n    \
+             in app::db::query\n    \
+             in app::request"
+        );
+    });
+}
+
+#[test]
+fn one_line_with_only_some_parts() {
+    let store = record(|| {
+        tracing::info!(target: "app", n = 1, "hello");
+        tracing::warn!(target: "app", n = 2);
+    });
+
+    store.with_events(|events| {
+        let summary = FormatOptions {
+            thread: false,
+            target: false,
+            fields: false,
+            spans: false,
+            ..Default::default()
+        };
+        assert_eq!(format_event(&events[0], &summary), "[INFO] hello");
+        assert_eq!(format_event(&events[1], &summary), "[WARN]");
+
+        let target_only = FormatOptions {
+            level: false,
+            target: true,
+            ..summary
+        };
+        assert_eq!(format_event(&events[0], &target_only), "app: hello");
+        assert_eq!(format_event(&events[1], &target_only), "app:");
+    });
+}
+
+#[test]
+#[cfg(feature = "chrono")]
+fn times_in_each_format() {
+    let store = record(|| tracing::info!(target: "app", "hello"));
+
+    store.with_events(|events| {
+        let event = &events[0];
+        let at = |timestamp| {
+            let options = FormatOptions {
+                timestamp,
+                level: false,
+                thread: false,
+                target: false,
+                ..Default::default()
+            };
+            format_event(event, &options)
+        };
+        let utc = event.timestamp();
+        assert_eq!(
+            at(TimeFormat::Rfc3339),
+            format!("[{}] hello", utc.format("%FT%T%.fZ"))
+        );
+        assert_eq!(
+            at(TimeFormat::Utc("%F %T%.3f".into())),
+            format!("[{}] hello", utc.format("%F %T%.3f"))
+        );
+        let local = utc.with_timezone(&chrono::Local);
+        assert_eq!(
+            at(TimeFormat::Local("%H:%M:%S%.3f".into())),
+            format!("[{}] hello", local.format("%H:%M:%S%.3f"))
+        );
+        assert_eq!(
+            at(TimeFormat::Local("%Q".into())),
+            "[invalid time format \"%Q\"] hello"
+        );
+        assert_eq!(at(TimeFormat::Hidden), "hello");
+    });
+}
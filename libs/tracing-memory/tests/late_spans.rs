@@ -0,0 +1,43 @@
+use std::sync::Arc;
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::{prelude::*, reload};
+
+#[test]
+fn spans_created_before_the_layer_was_added() {
+    let store = Arc::new(EventStore::new());
+    let (layer, handle) = reload::Layer::new(None::<Layer<_>>);
+    let subscriber = tracing_subscriber::registry().with(layer);
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let outer = tracing::info_span!("outer", id = 1);
+    let inner = outer.in_scope(|| tracing::info_span!("inner", n = 2));
+    handle
+        .reload(Some(Layer::with_store(store.clone())))
+        .unwrap();
+    inner.in_scope(|| tracing::info!("first"));
+    inner.in_scope(|| tracing::info!("second"));
+    let recorded = inner.in_scope(|| tracing::info_span!("recorded", n = 3));
+    recorded.in_scope(|| tracing::info!("in recorded"));
+
+    store.with_events(|events| {
+        let span = events[0].span().unwrap();
+        assert_eq!(span.meta().name(), "inner");
+        assert!(span.fields_unavailable());
+        assert_eq!(span.field("n"), None);
+        let parent = span.parent().unwrap();
+        assert_eq!(parent.meta().name(), "outer");
+        assert!(parent.fields_unavailable());
+        assert!(parent.parent().is_none());
+
+        // archived once, so later events share it
+        assert!(events[1].span().unwrap().same_instance(span));
+
+        let span = events[2].span().unwrap();
+        assert!(!span.fields_unavailable());
+        assert_eq!(span.field_i64("n"), Some(3));
+        assert!(span
+            .parent()
+            .unwrap()
+            .same_instance(events[0].span().unwrap()));
+    });
+}
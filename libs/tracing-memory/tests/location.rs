@@ -0,0 +1,21 @@
+use std::sync::Arc;
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn events_know_where_they_were_fired() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    let line = tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("here");
+        line!() - 1
+    });
+
+    store.with_events(|events| {
+        let event = &events[0];
+        assert_eq!(event.file(), Some(file!()));
+        assert_eq!(event.line(), Some(line));
+        assert_eq!(event.effective_file(), event.file());
+        assert_eq!(event.effective_line(), event.line());
+    });
+}
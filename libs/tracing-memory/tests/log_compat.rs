@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn log_records_show_their_target() {
+    tracing_log::LogTracer::init().unwrap();
+
+    let store = Arc::new(EventStore::new());
+    let layer = Layer::with_store(store.clone()).coalesce_repeats(true);
+    let subscriber = tracing_subscriber::registry().with(layer);
+    let line = tracing::subscriber::with_default(subscriber, || {
+        log::info!(target: "my_mod", "hello {}", "log");
+        let line = line!() - 1;
+        // the same message from another target is not a repeat
+        log::info!(target: "other_mod", "hello {}", "log");
+        tracing::info!("hello tracing");
+        line
+    });
+
+    store.with_events(|events| {
+        let [from_log, other, from_tracing] = match &events[..] {
+            [a, b, c] => [a, b, c],
+            events => panic!("unexpected events: {:?}", events),
+        };
+
+        assert_eq!(from_log.meta().target(), "log");
+        assert_eq!(from_log.effective_target(), "my_mod");
+        assert_eq!(from_log.effective_file(), Some(file!()));
+        assert_eq!(from_log.effective_line(), Some(line));
+        assert_eq!(from_log.effective_module_path(), Some(module_path!()));
+        let fields: Vec<_> = from_log.fields().map(|(name, _)| name).collect();
+        assert_eq!(fields, ["message"]);
+        assert_eq!(from_log.field("message").unwrap().to_string(), "hello log");
+
+        assert_eq!(other.effective_target(), "other_mod");
+        assert_eq!(other.repeat_count(), 1);
+
+        assert_eq!(from_tracing.effective_target(), module_path!());
+        assert_eq!(from_tracing.effective_line(), from_tracing.meta().line());
+    });
+}
+
+#[test]
+fn out_of_range_lines_are_left_out() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        let line = u64::from(u32::MAX) + 1;
+        tracing::info!(log.target = "my_mod", log.line = line, "hello");
+    });
+
+    store.with_events(|events| {
+        assert_eq!(events[0].effective_target(), "my_mod");
+        assert_eq!(events[0].effective_line(), None);
+    });
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use tracing_memory::{EventStore, Field, Layer};
+use tracing_subscriber::prelude::*;
+
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Big {
+    data: Vec<u32>,
+}
+
+fn record(max_field_len: Option<usize>, f: impl FnOnce()) -> Arc<EventStore> {
+    let store = Arc::new(EventStore::new());
+    let mut layer = Layer::with_store(store.clone());
+    if let Some(max) = max_field_len {
+        layer = layer.max_field_len(max);
+    }
+    tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), f);
+    store
+}
+
+fn truncated(field: Option<&Field>) -> (&str, usize) {
+    match field {
+        Some(Field::Truncated { text, original_len }) => (text, *original_len),
+        field => panic!("not truncated: {:?}", field),
+    }
+}
+
+#[test]
+fn oversized_field_values_are_truncated() {
+    let big = || Big {
+        data: (0..10_000).collect(),
+    };
+    tracing_memory::set_max_field_len(Some(32));
+    let store = record(
+        None,
+        || tracing::info!(big = ?big(), short = "fits", "done"),
+    );
+    tracing_memory::set_max_field_len(None);
+
+    store.with_events(|events| {
+        let field = events[0].field("big").unwrap();
+        let (text, original_len) = truncated(Some(field));
+        assert_eq!(text, "Big { data: [0, 1, 2, 3, 4, 5, 6");
+        assert_eq!(original_len, format!("{:?}", big()).len());
+        assert!(field.is_truncated());
+        let elided = format!("… (+{} bytes)", original_len - 32);
+        assert_eq!(field.to_string(), format!("{}{}", text, elided));
+        assert_eq!(events[0].field("short"), Some(&Field::Str("fits".into())));
+        assert_eq!(
+            events[0].field("message"),
+            Some(&Field::Display("done".into()))
+        );
+    });
+}
+
+// These values are shorter than the global limit set above, in case that
+// test is running at the same time.
+#[test]
+fn truncation_never_splits_a_char() {
+    let store = record(Some(4), || {
+        // 1, 2, 3, and 4 byte chars
+        tracing::info!(a = "abcde", b = "ééé", c = "日本語", d = "🦀🦀");
+        tracing::info!(
+            split = "日日",
+            error = &std::fmt::Error as &dyn std::error::Error
+        );
+        tracing::info!(message = %"a日本");
+    });
+
+    store.with_events(|events| {
+        let field = |event: usize, name| truncated(events[event].field(name));
+        assert_eq!(field(0, "a"), ("abcd", 5));
+        assert_eq!(field(0, "b"), ("éé", 6));
+        assert_eq!(field(0, "c"), ("日", 9));
+        assert_eq!(field(0, "d"), ("🦀", 8));
+        assert_eq!(field(2, "message"), ("a日", 7));
+
+        assert_eq!(field(1, "split"), ("日", 6));
+        let error = events[1].field("error").unwrap();
+        assert!(error.is_truncated());
+        let rest = std::fmt::Error.to_string().len() - 4;
+        assert_eq!(error.to_string(), format!("an e… (+{} bytes)", rest));
+    });
+}
+
+#[test]
+fn short_values_are_kept_whole() {
+    let store = record(Some(6), || {
+        tracing::info!(exact = "日日", short = "ab", debug = ?(1, 2));
+    });
+    store.with_events(|events| {
+        assert_eq!(events[0].field("exact"), Some(&Field::Str("日日".into())));
+        assert_eq!(events[0].field("short"), Some(&Field::Str("ab".into())));
+        assert_eq!(
+            events[0].field("debug"),
+            Some(&Field::Debug("(1, 2)".into()))
+        );
+        assert!(!events[0].field("debug").unwrap().is_truncated());
+    });
+}
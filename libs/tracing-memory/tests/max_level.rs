@@ -0,0 +1,50 @@
+use std::sync::Arc;
+use tracing::level_filters::LevelFilter;
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn more_verbose_events_are_not_recorded() {
+    let store = Arc::new(EventStore::new());
+    let layer = Layer::with_store(store.clone()).with_max_level(LevelFilter::INFO);
+    tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+        tracing::trace!("trace");
+        tracing::info!("info");
+    });
+
+    store.with_events(|events| {
+        let messages: Vec<_> = events
+            .iter()
+            .map(|event| event.field_str("message"))
+            .collect();
+        assert_eq!(messages, [Some("info")]);
+    });
+    assert_eq!(store.dropped_by_filter(), 0);
+}
+
+#[test]
+fn events_skip_more_verbose_spans() {
+    let store = Arc::new(EventStore::new());
+    let layer = Layer::with_store(store.clone()).max_level(LevelFilter::INFO);
+    tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+        let request = tracing::info_span!("request", id = 1);
+        let _request = request.enter();
+        let lookup = tracing::debug_span!("lookup", key = 2);
+        let _lookup = lookup.enter();
+        tracing::info_span!("cache").in_scope(|| tracing::info!("in cache"));
+        tracing::info!("in lookup");
+    });
+
+    store.with_events(|events| {
+        let spans = |event_ix: usize| {
+            let span = events[event_ix].span();
+            let spans = std::iter::successors(span, |span| span.parent());
+            spans.map(|span| span.meta().name()).collect::<Vec<_>>()
+        };
+        assert_eq!(spans(0), ["cache", "request"]);
+        assert_eq!(spans(1), ["request"]);
+        let request = events[1].span().unwrap();
+        assert_eq!(request.field_i64("id"), Some(1));
+        assert!(!request.fields_unavailable());
+    });
+}
@@ -0,0 +1,130 @@
+use metrics::{GaugeValue, Key, Recorder, Unit};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tracing_memory::{EventStore, FlushGuard, Layer};
+use tracing_subscriber::prelude::*;
+
+#[derive(Default)]
+struct TestRecorder {
+    described: Mutex<Vec<String>>,
+    values: Mutex<BTreeMap<String, f64>>,
+}
+
+fn key_string(key: &Key) -> String {
+    let labels: Vec<String> = key
+        .labels()
+        .map(|label| format!("{}={}", label.key(), label.value()))
+        .collect();
+    format!("{}{{{}}}", key.name(), labels.join(","))
+}
+
+impl Recorder for TestRecorder {
+    fn register_counter(&self, key: &Key, _unit: Option<Unit>, _description: Option<&'static str>) {
+        self.described.lock().unwrap().push(key_string(key));
+    }
+
+    fn register_gauge(&self, key: &Key, _unit: Option<Unit>, _description: Option<&'static str>) {
+        self.described.lock().unwrap().push(key_string(key));
+    }
+
+    fn register_histogram(
+        &self,
+        key: &Key,
+        _unit: Option<Unit>,
+        _description: Option<&'static str>,
+    ) {
+        self.described.lock().unwrap().push(key_string(key));
+    }
+
+    fn increment_counter(&self, key: &Key, value: u64) {
+        *self
+            .values
+            .lock()
+            .unwrap()
+            .entry(key_string(key))
+            .or_default() += value as f64;
+    }
+
+    fn update_gauge(&self, key: &Key, value: GaugeValue) {
+        let mut values = self.values.lock().unwrap();
+        let gauge = values.entry(key_string(key)).or_default();
+        *gauge = value.update_value(*gauge);
+    }
+
+    fn record_histogram(&self, _key: &Key, _value: f64) {}
+}
+
+#[test]
+fn publishes_store_health() {
+    let recorder: &'static TestRecorder = Box::leak(Box::new(TestRecorder::default()));
+    metrics::set_recorder(recorder).unwrap();
+    let value = |key: &str| {
+        recorder
+            .values
+            .lock()
+            .unwrap()
+            .get(key)
+            .copied()
+            .unwrap_or_default()
+    };
+
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("a");
+        tracing::info!("b");
+        tracing::error!("c");
+        assert_eq!(value("tracing_memory_queue_depth{}"), 3.0);
+        assert_eq!(value("tracing_memory_events_retained{}"), 3.0);
+
+        store.with_events(|events| {
+            events.pop();
+        });
+        assert_eq!(value("tracing_memory_queue_depth{}"), 0.0);
+        assert_eq!(value("tracing_memory_events_retained{}"), 2.0);
+
+        tracing::warn!("d");
+        store.clear_events();
+        tracing::info!("e");
+    });
+
+    assert_eq!(
+        value("tracing_memory_events_recorded_total{level=INFO}"),
+        3.0
+    );
+    assert_eq!(
+        value("tracing_memory_events_recorded_total{level=WARN}"),
+        1.0
+    );
+    assert_eq!(
+        value("tracing_memory_events_recorded_total{level=ERROR}"),
+        1.0
+    );
+    assert_eq!(
+        value("tracing_memory_events_dropped_total{reason=removed}"),
+        1.0
+    );
+    assert_eq!(
+        value("tracing_memory_events_dropped_total{reason=cleared}"),
+        3.0
+    );
+    assert_eq!(value("tracing_memory_events_retained{}"), 1.0);
+    assert_eq!(value("tracing_memory_queue_depth{}"), 1.0);
+
+    // the log is held by this very thread, so flushing it gives up
+    store.with_events(|_| {
+        let mut guard = FlushGuard::with_store(store.clone());
+        guard.set_timeout(Duration::ZERO);
+    });
+    assert_eq!(value("tracing_memory_flushes_abandoned_total{}"), 1.0);
+
+    let described = recorder.described.lock().unwrap();
+    assert!(described.contains(&"tracing_memory_events_recorded_total{level=TRACE}".to_string()));
+    assert!(described.contains(&"tracing_memory_events_dropped_total{reason=cleared}".to_string()));
+    assert!(described.contains(&"tracing_memory_events_retained{}".to_string()));
+    assert!(described.contains(&"tracing_memory_queue_depth{}".to_string()));
+    assert!(described.contains(&"tracing_memory_flushes_abandoned_total{}".to_string()));
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use tracing_memory::{EventStore, Layer, RecordFilter};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn filters_by_longest_target_prefix() {
+    let filter = RecordFilter::parse("warn,app=info,app::db=trace,app[conn]=off").unwrap();
+    assert_eq!(filter.to_string(), "app::db=trace,app=info,warn");
+    assert_eq!(RecordFilter::parse(&filter.to_string()).unwrap(), filter);
+
+    assert!(RecordFilter::new().is_empty());
+    assert_eq!(RecordFilter::parse("debug").unwrap().to_string(), "debug");
+    assert_eq!(
+        RecordFilter::parse("hyper").unwrap().to_string(),
+        "hyper=trace"
+    );
+    assert!(RecordFilter::parse("app=loud").is_err());
+}
+
+#[test]
+fn rejected_events_are_counted_not_recorded() {
+    let store = Arc::new(EventStore::new());
+    let mut layer = Layer::with_store(store.clone());
+    let handle = layer.register_filter_handle();
+    let subscriber = tracing_subscriber::registry().with(layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::trace!(target: "app", "everything is recorded at first");
+        *handle.write().unwrap() = RecordFilter::parse("info,app::db=debug").unwrap();
+        tracing::debug!(target: "app", "app debug");
+        tracing::info!(target: "app", "app info");
+        tracing::debug!(target: "app::db", "db debug");
+        tracing::trace!(target: "app::db", "db trace");
+    });
+
+    let messages: Vec<String> = store.with_events(|events| {
+        let message =
+            |event: &Arc<tracing_memory::Event>| event.field("message").unwrap().to_string();
+        events.iter().map(message).collect()
+    });
+    assert_eq!(
+        messages,
+        ["everything is recorded at first", "app info", "db debug"]
+    );
+    assert_eq!(store.dropped_by_filter(), 2);
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn nothing_is_recorded_while_paused() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        assert!(tracing_memory::is_recording());
+        tracing::info!("before");
+
+        tracing_memory::set_recording(false);
+        assert!(!tracing_memory::is_recording());
+        tracing::info!("while paused");
+        let paused_span = tracing::info_span!("paused");
+        paused_span.in_scope(|| tracing::info!("in paused span"));
+
+        tracing_memory::set_recording(true);
+        tracing::info!("after");
+        paused_span.in_scope(|| tracing::info!("in span created while paused"));
+        tracing::info_span!("resumed").in_scope(|| tracing::info!("in span"));
+    });
+
+    store.with_events(|events| {
+        let messages: Vec<_> = events
+            .iter()
+            .map(|event| event.field_str("message").unwrap())
+            .collect();
+        assert_eq!(
+            messages,
+            ["before", "after", "in span created while paused", "in span"]
+        );
+        // archived late, without the fields it was created with
+        let paused = events[2].span().unwrap();
+        assert_eq!(paused.meta().name(), "paused");
+        assert!(paused.fields_unavailable());
+        assert_eq!(events[3].span().unwrap().meta().name(), "resumed");
+    });
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+fn record(coalesce_repeats: bool, f: impl FnOnce()) -> Vec<(String, u32)> {
+    let store = Arc::new(EventStore::new());
+    let layer = Layer::with_store(store.clone()).coalesce_repeats(coalesce_repeats);
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, f);
+    store.with_events(|events| {
+        events
+            .iter()
+            .map(|event| {
+                let message = event.field("message").unwrap().to_string();
+                (message, event.repeat_count())
+            })
+            .collect()
+    })
+}
+
+fn spam() {
+    for _ in 0..3 {
+        tracing::info!("spam");
+    }
+    for n in 0..2 {
+        tracing::info!(n, "counting");
+    }
+    for _ in 0..2 {
+        tracing::info!(n = 1, "counting");
+    }
+    let span = tracing::info_span!("span");
+    span.in_scope(|| tracing::info!("spam"));
+    tracing::info!("spam");
+}
+
+#[test]
+fn repeats_are_coalesced() {
+    let events = record(true, spam);
+    let expected = [
+        ("spam", 3),
+        ("counting", 1),
+        ("counting", 1),
+        // the same fields from another callsite
+        ("counting", 2),
+        ("spam", 1),
+        ("spam", 1),
+    ];
+    let expected: Vec<_> = expected.iter().map(|&(m, n)| (m.into(), n)).collect();
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn repeats_are_kept_by_default() {
+    let events = record(false, spam);
+    assert_eq!(events.len(), 9);
+    assert!(events.iter().all(|&(_, count)| count == 1));
+}
+
+#[test]
+fn repeats_keep_both_timestamps() {
+    let store = Arc::new(EventStore::new());
+    let layer = Layer::with_store(store.clone()).coalesce_repeats(true);
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("once");
+        for _ in 0..2 {
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            tracing::info!("spam");
+        }
+    });
+
+    store.with_events(|events| {
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].time(), events[0].last_time());
+        assert!(events[1].time() < events[1].last_time());
+        #[cfg(feature = "chrono")]
+        {
+            assert_eq!(events[0].timestamp(), events[0].last_timestamp());
+            assert!(events[1].timestamp() < events[1].last_timestamp());
+        }
+    });
+}
@@ -0,0 +1,125 @@
+use std::{sync::Arc, thread};
+use tracing_memory::{Event, EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+fn seqs(events: &[Arc<Event>]) -> Vec<u64> {
+    events.iter().map(|event| event.seq()).collect()
+}
+
+#[test]
+fn events_since_a_sequence_number() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!("a");
+        tracing::info!("b");
+        assert_eq!(store.with_events_since(0, seqs), [1, 2]);
+        assert!(store.with_events_since(2, seqs).is_empty());
+
+        tracing::info!("c");
+        assert_eq!(store.with_events_since(2, seqs), [3]);
+
+        // removed events leave a gap, but the rest stay in sequence
+        store.with_events(|events| {
+            events.remove(1);
+        });
+        assert_eq!(store.with_events_since(1, seqs), [3]);
+
+        // numbering carries on after clearing
+        store.clear_events();
+        tracing::info!("d");
+        assert_eq!(store.with_events_since(0, seqs), [4]);
+    });
+}
+
+#[test]
+fn concurrent_events_are_in_sequence() {
+    const THREADS: usize = 8;
+    const EVENTS: usize = 2000;
+
+    let store = Arc::new(EventStore::new());
+    let reader = {
+        let store = store.clone();
+        thread::spawn(move || {
+            // read incrementally while recording, checking each batch
+            let (mut last, mut read) = (0, 0);
+            while read < THREADS * EVENTS {
+                store.with_events_since(last, |events| {
+                    for event in events {
+                        assert_eq!(event.seq(), last + 1);
+                        last = event.seq();
+                    }
+                    read += events.len();
+                });
+            }
+        })
+    };
+
+    let writers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let store = store.clone();
+            thread::spawn(move || {
+                let subscriber = tracing_subscriber::registry().with(Layer::with_store(store));
+                tracing::subscriber::with_default(subscriber, || {
+                    for i in 0..EVENTS {
+                        tracing::info!(i);
+                    }
+                });
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    reader.join().unwrap();
+
+    let expected: Vec<u64> = (1..=(THREADS * EVENTS) as u64).collect();
+    assert_eq!(store.with_events(|events| seqs(events)), expected);
+}
+
+#[test]
+fn sorted_events_are_in_order_of_time() {
+    const THREADS: usize = 8;
+    const EVENTS: usize = 1000;
+
+    let store = Arc::new(EventStore::new());
+    store.set_sorted(true);
+    let in_order = |events: &[Arc<Event>]| {
+        let times = events.iter().map(|event| event.time());
+        times.clone().zip(times.skip(1)).all(|(a, b)| a <= b)
+    };
+    let reader = {
+        let store = store.clone();
+        thread::spawn(move || {
+            // merged batch by batch while recording
+            while store.with_events(|events| events.len()) < THREADS * EVENTS {
+                assert!(store.with_events_since(0, in_order));
+            }
+        })
+    };
+
+    let writers: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let store = store.clone();
+            thread::spawn(move || {
+                let subscriber = tracing_subscriber::registry().with(Layer::with_store(store));
+                tracing::subscriber::with_default(subscriber, || {
+                    for i in 0..EVENTS {
+                        // slow to record, to widen the gap between timestamping and storing
+                        tracing::info!(i, padding = ?[i; 64]);
+                    }
+                });
+            })
+        })
+        .collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    reader.join().unwrap();
+
+    assert!(store.with_events_since(0, in_order));
+    let mut seqs = store.with_events(|events| seqs(events));
+    seqs.sort_unstable();
+    let expected: Vec<u64> = (1..=(THREADS * EVENTS) as u64).collect();
+    assert_eq!(seqs, expected);
+}
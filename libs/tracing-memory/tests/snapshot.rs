@@ -0,0 +1,72 @@
+use std::{
+    sync::{mpsc, Arc},
+    thread,
+    time::Duration,
+};
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+fn record(store: &Arc<EventStore>, count: usize) {
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        for i in 0..count {
+            tracing::info!(i);
+        }
+    });
+}
+
+#[test]
+fn snapshots_share_events_with_the_store() {
+    let store = Arc::new(EventStore::new());
+    assert!(store.snapshot().is_empty());
+    record(&store, 3);
+
+    let snapshot = store.snapshot();
+    assert_eq!(snapshot.len(), 3);
+    store.with_events(|events| {
+        for (snapshot, event) in snapshot.iter().zip(events.iter()) {
+            assert!(Arc::ptr_eq(snapshot, event));
+        }
+    });
+    let seq = snapshot[0].seq();
+    assert_eq!(snapshot.since(seq).len(), 2);
+
+    // later changes to the store don't change the snapshot
+    record(&store, 2);
+    store.with_events(|events| events.remove(0));
+    assert_eq!(snapshot.len(), 3);
+    assert_eq!(snapshot.clone().len(), 3);
+    assert_eq!(store.snapshot().len(), 4);
+    store.clear_events();
+    assert_eq!(snapshot.len(), 3);
+    assert!(store.snapshot().is_empty());
+}
+
+#[test]
+fn recording_proceeds_while_a_snapshot_is_iterated() {
+    let store = Arc::new(EventStore::new());
+    record(&store, 10);
+    let snapshot = store.snapshot();
+
+    let (done, recorded) = mpsc::channel();
+    let mut recorder = None;
+    for (ix, event) in snapshot.iter().enumerate() {
+        // while iterating, another thread records and takes snapshots
+        if ix == 5 {
+            let store = store.clone();
+            let done = done.clone();
+            recorder = Some(thread::spawn(move || {
+                record(&store, 100);
+                done.send(store.snapshot().len()).unwrap();
+            }));
+            let len = recorded
+                .recv_timeout(Duration::from_secs(10))
+                .expect("recording was blocked by the snapshot");
+            assert_eq!(len, 110);
+        }
+        thread::sleep(Duration::from_millis(1));
+        assert!(event.field("i").is_some());
+    }
+    recorder.unwrap().join().unwrap();
+    assert_eq!(snapshot.len(), 10);
+}
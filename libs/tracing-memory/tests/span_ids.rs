@@ -0,0 +1,62 @@
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn events_are_found_by_span_id() {
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    let (root_id, other_id) = tracing::subscriber::with_default(subscriber, || {
+        let root = tracing::info_span!("handler", n = tracing::field::Empty);
+        root.in_scope(|| {
+            tracing::info!("first");
+            tracing::info_span!("child").in_scope(|| tracing::info!("nested"));
+            root.record("n", 1);
+            tracing::info!("after recording");
+        });
+        let other = tracing::info_span!("handler");
+        other.in_scope(|| tracing::info!("elsewhere"));
+        (root.id().unwrap(), other.id().unwrap())
+    });
+
+    tracing_memory::with_events(|events| {
+        let first = events[0].span().unwrap();
+        let recorded = events[2].span().unwrap();
+        let other = events[3].span().unwrap();
+        assert_eq!(first.id(), root_id.into_u64());
+        assert_eq!(other.id(), other_id.into_u64());
+        assert_eq!(events[0].span_id(), Some(first.id()));
+        assert_eq!(events[1].span_id(), Some(events[1].span().unwrap().id()));
+        assert_ne!(events[1].span_id(), Some(root_id.into_u64()));
+
+        // the same span, even after recording on it, but not one with the same name
+        assert!(first.same_instance(recorded));
+        assert!(first.same_instance(events[1].span().unwrap().parent().unwrap()));
+        assert!(!first.same_instance(other));
+        #[cfg(feature = "chrono")]
+        assert_eq!(first.created(), recorded.created());
+    });
+
+    let messages = |events: &[std::sync::Arc<tracing_memory::Event>]| -> Vec<String> {
+        let message = |event: &std::sync::Arc<tracing_memory::Event>| {
+            event.field("message").unwrap().to_string()
+        };
+        events.iter().map(message).collect()
+    };
+    tracing_memory::events_for_span(root_id.into_u64(), |events| {
+        assert_eq!(messages(events), ["first", "nested", "after recording"]);
+    });
+    tracing_memory::events_for_span(other_id.into_u64(), |events| {
+        assert_eq!(messages(events), ["elsewhere"]);
+    });
+}
+
+#[test]
+fn events_outside_spans_have_no_span_id() {
+    let store = std::sync::Arc::new(tracing_memory::EventStore::new());
+    let layer = tracing_memory::Layer::with_store(store.clone());
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || tracing::info!("outside"));
+
+    store.with_events(|events| {
+        assert_eq!(events[0].span_id(), None);
+        assert!(events[0].span().is_none());
+    });
+}
@@ -0,0 +1,95 @@
+use std::{sync::Arc, thread, time::Duration};
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+#[tracing::instrument]
+fn busy(sleep: Duration) {
+    thread::sleep(sleep);
+    tracing::info!("done");
+}
+
+#[test]
+fn instrumented_busy_time() {
+    let sleep = Duration::from_millis(20);
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        busy(sleep);
+        busy(sleep);
+    });
+
+    store.with_events(|events| {
+        let span = events[0].span().unwrap();
+        assert!(span.is_closed());
+        #[cfg(feature = "chrono")]
+        assert!(span.first_entered().is_some());
+        assert!(span.busy_time().unwrap() >= sleep);
+        assert!(span.idle_time().unwrap() < span.busy_time().unwrap());
+        #[allow(deprecated)]
+        let busy_duration = span.busy_duration();
+        assert_eq!(busy_duration, span.busy_time());
+    });
+}
+
+#[test]
+fn unentered_span_has_no_times() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("unentered");
+        tracing::info!(parent: &span, "event");
+    });
+
+    store.with_events(|events| {
+        let span = events[0].span().unwrap();
+        assert_eq!(span.busy_time(), None);
+        assert_eq!(span.idle_time(), None);
+    });
+}
+
+#[test]
+fn timing_shows_through_earlier_snapshots() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("span");
+        tracing::info!(parent: &span, "before entering");
+        let event = store.with_events(|events| events[0].clone());
+        let archived = event.span().unwrap();
+        assert!(!archived.is_closed());
+        assert_eq!(archived.busy_time(), None);
+        #[cfg(feature = "chrono")]
+        assert_eq!(archived.first_entered(), None);
+
+        span.in_scope(|| thread::sleep(Duration::from_millis(5)));
+        assert!(archived.busy_time().unwrap() >= Duration::from_millis(5));
+        assert!(!archived.is_closed());
+        drop(span);
+        assert!(archived.is_closed());
+    });
+}
+
+#[test]
+fn busy_time_adds_up_every_entry() {
+    let (sleep, between) = (Duration::from_millis(5), Duration::from_millis(20));
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("span");
+        span.in_scope(|| tracing::info!("entered"));
+        let event = store.with_events(|events| events[0].clone());
+        let archived = event.span().unwrap();
+        #[cfg(feature = "chrono")]
+        let first_entered = archived.first_entered().unwrap();
+        for _ in 0..3 {
+            thread::sleep(between);
+            span.in_scope(|| thread::sleep(sleep));
+        }
+
+        assert!(archived.busy_time().unwrap() >= sleep * 3);
+        assert!(archived.idle_time().unwrap() >= between * 3);
+        // only the first entry counts
+        #[cfg(feature = "chrono")]
+        assert_eq!(archived.first_entered(), Some(first_entered));
+    });
+}
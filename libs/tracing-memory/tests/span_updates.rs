@@ -0,0 +1,88 @@
+use tracing_memory::Field;
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn fields_recorded_after_an_event() {
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("span", result = tracing::field::Empty);
+        span.in_scope(|| tracing::info!("before"));
+        span.record("result", "ok");
+        span.in_scope(|| tracing::info!("after"));
+    });
+
+    tracing_memory::with_events(|events| {
+        let before = events[0].span().unwrap();
+        let after = events[1].span().unwrap();
+        assert_eq!(before.field("result"), None);
+        assert_eq!(after.field("result"), Some(&Field::Str("ok".into())));
+
+        let latest = before.latest().unwrap();
+        assert_eq!(latest.field("result"), Some(&Field::Str("ok".into())));
+        assert!(latest.latest().is_none());
+        assert!(after.latest().is_none());
+    });
+}
+
+/// Emit an event, record a field on the span, and emit another, with
+/// `holders` extra clones of the recorded events held across the recording.
+fn record_between_events(holders: usize) {
+    let store = std::sync::Arc::new(tracing_memory::EventStore::new());
+    let layer = tracing_memory::Layer::with_store(store.clone());
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("span", state = "first");
+        span.in_scope(|| tracing::info!("a"));
+        let held: Vec<_> = (0..holders)
+            .map(|_| store.with_events(|events| events.clone()))
+            .collect();
+        span.record("state", "second");
+        drop(held);
+        span.in_scope(|| tracing::info!("b"));
+    });
+
+    store.with_events(|events| {
+        let a = events[0].span().unwrap();
+        let b = events[1].span().unwrap();
+        assert_eq!(a.field("state"), Some(&Field::Str("first".into())));
+        let both = Field::Multiple(vec![
+            Field::Str("first".into()),
+            Field::Str("second".into()),
+        ]);
+        assert_eq!(b.field("state"), Some(&both));
+        assert_eq!(a.key(), b.key());
+    });
+}
+
+#[test]
+fn events_keep_the_fields_of_when_they_were_recorded() {
+    for holders in 0..3 {
+        record_between_events(holders);
+    }
+}
+
+#[test]
+fn later_events_see_fields_recorded_on_parents() {
+    let store = std::sync::Arc::new(tracing_memory::EventStore::new());
+    let layer = tracing_memory::Layer::with_store(store.clone());
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || {
+        let parent = tracing::info_span!("parent", user = tracing::field::Empty);
+        let child = parent.in_scope(|| tracing::info_span!("child"));
+        child.in_scope(|| tracing::info!("before"));
+        parent.record("user", "bob");
+        child.in_scope(|| tracing::info!("after"));
+        child.in_scope(|| tracing::info!("after again"));
+    });
+
+    store.with_events(|events| {
+        let parent = |ix: usize| events[ix].span().unwrap().parent().unwrap();
+        assert_eq!(parent(0).field("user"), None);
+        assert_eq!(parent(1).field("user"), Some(&Field::Str("bob".into())));
+        assert_eq!(parent(0).key(), parent(1).key());
+        // the child's snapshot is only taken anew once
+        let child = |ix: usize| events[ix].span().unwrap() as *const _;
+        assert_ne!(child(0), child(1));
+        assert_eq!(child(1), child(2));
+    });
+}
@@ -0,0 +1,20 @@
+use std::time::Duration;
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn events_are_timed_from_the_first_layer() {
+    let layer = tracing_memory::layer();
+    let start = tracing_memory::start_time();
+    std::thread::sleep(Duration::from_millis(10));
+
+    let subscriber = tracing_subscriber::registry().with(layer);
+    tracing::subscriber::with_default(subscriber, || tracing::info!("later"));
+
+    assert_eq!(tracing_memory::start_time(), start);
+    tracing_memory::with_events(|events| {
+        let since_start = events[0].wall_time_since_start();
+        assert!(since_start >= Duration::from_millis(10));
+        let between = events[0].timestamp() - start;
+        assert_eq!(between.to_std().unwrap(), since_start);
+    });
+}
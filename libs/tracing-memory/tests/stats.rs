@@ -0,0 +1,75 @@
+use std::sync::Arc;
+use tracing::Level;
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn events_are_counted_by_level() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        assert_eq!(store.stats().total(), 0);
+        for _ in 0..4 {
+            tracing::error!("error");
+        }
+        for _ in 0..12 {
+            tracing::warn!("warning");
+        }
+        tracing::info!("info");
+        let stats = store.stats();
+        assert_eq!(stats.total(), 17);
+        assert_eq!(stats.errors(), 4);
+        assert_eq!(stats.warnings(), 12);
+        assert_eq!(stats.count(Level::INFO), 1);
+        assert_eq!(stats.count(Level::TRACE), 0);
+
+        // counts follow events being recorded and removed
+        tracing::debug!("debug");
+        store.with_events(|events| events.retain(|event| *event.meta().level() != Level::WARN));
+        tracing::trace!("trace");
+        let stats = store.stats();
+        assert_eq!(stats.total(), 7);
+        assert_eq!(stats.warnings(), 0);
+        assert_eq!(stats.count(Level::DEBUG), 1);
+        assert_eq!(stats.count(Level::TRACE), 1);
+
+        store.clear_events();
+        tracing::error!("error");
+        assert_eq!(store.stats().total(), 1);
+        assert_eq!(store.stats().errors(), 1);
+    });
+}
+
+#[test]
+fn stats_estimate_size_and_time_span() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        let empty = store.stats();
+        assert_eq!(empty.approx_bytes(), 0);
+        #[cfg(feature = "chrono")]
+        assert_eq!((empty.oldest(), empty.newest()), (None, None));
+
+        tracing::info!("short");
+        let short = store.stats();
+        tracing::info!(
+            text = "a much longer field value, to make some difference",
+            "short"
+        );
+        let stats = store.stats();
+        assert!(short.approx_bytes() > 0);
+        assert!(stats.approx_bytes() > 2 * short.approx_bytes());
+
+        #[cfg(feature = "chrono")]
+        {
+            let (first, last) =
+                store.with_events_since(0, |events| (events[0].timestamp(), events[1].timestamp()));
+            assert_eq!(stats.oldest(), Some(first));
+            assert_eq!(stats.newest(), Some(last));
+            assert_eq!(short.newest(), Some(first));
+        }
+
+        store.retain_events(|event| event.field("text").is_none());
+        assert_eq!(store.stats(), short);
+    });
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+use tracing_memory::{EventStore, Field, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn layers_record_into_their_own_store() {
+    let a = Arc::new(EventStore::new());
+    let b = Arc::new(EventStore::new());
+
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(a.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(store = "a");
+        tracing::info!(store = "a");
+    });
+
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(b.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(store = "b");
+    });
+
+    let stores = |store: &EventStore| {
+        store.with_events(|events| {
+            events
+                .iter()
+                .map(|event| event.field("store").cloned())
+                .collect::<Vec<_>>()
+        })
+    };
+    let a_field = Some(Field::Str("a".into()));
+    let b_field = Some(Field::Str("b".into()));
+    assert_eq!(stores(&a), [a_field.clone(), a_field]);
+    assert_eq!(stores(&b), [b_field]);
+    assert_eq!(tracing_memory::with_events(|events| events.len()), 0);
+}
+
+#[test]
+fn registries_on_other_threads_keep_apart() {
+    let stores = [Arc::new(EventStore::new()), Arc::new(EventStore::new())];
+    let threads: Vec<_> = (stores.iter().cloned().enumerate())
+        .map(|(ix, store)| {
+            std::thread::spawn(move || {
+                let subscriber =
+                    tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+                tracing::subscriber::with_default(subscriber, || {
+                    for n in 0..100 {
+                        tracing::info_span!("span", ix).in_scope(|| tracing::info!(ix, n));
+                    }
+                });
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    for (ix, store) in stores.iter().enumerate() {
+        let ix = Some(ix as u64);
+        store.with_events(|events| {
+            assert_eq!(events.len(), 100);
+            assert!(events.iter().all(|event| event.field_u64("ix") == ix));
+            assert!(events
+                .iter()
+                .all(|event| event.span().unwrap().field_u64("ix") == ix));
+        });
+    }
+    assert_eq!(tracing_memory::with_events(|events| events.len()), 0);
+}
@@ -0,0 +1,84 @@
+use std::{sync::Arc, thread};
+use tracing_memory::{EventStore, Field, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn receives_events_in_order() {
+    let events = tracing_memory::subscribe();
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info!(n = 1u64);
+        tracing::info!(n = 2u64);
+    });
+
+    assert_eq!(events.recv().unwrap().field("n"), Some(&Field::U64(1)));
+    assert_eq!(events.recv().unwrap().field("n"), Some(&Field::U64(2)));
+    assert!(events.try_recv().is_err());
+    assert_eq!(tracing_memory::with_events(|events| events.len()), 2);
+}
+
+fn recording(store: &Arc<EventStore>) -> impl tracing::Subscriber {
+    tracing_subscriber::registry().with(Layer::with_store(store.clone()))
+}
+
+#[test]
+fn every_subscriber_receives_events() {
+    let store = Arc::new(EventStore::new());
+    let (a, b) = (store.subscribe(), store.subscribe());
+    tracing::subscriber::with_default(recording(&store), || {
+        tracing::info!("one");
+        drop(b);
+        tracing::info!("two");
+    });
+
+    assert_eq!(a.try_iter().count(), 2);
+    let c = store.subscribe();
+    tracing::subscriber::with_default(recording(&store), || tracing::info!("three"));
+    assert_eq!(a.try_iter().count(), 1);
+    assert_eq!(c.try_iter().count(), 1);
+    assert_eq!(store.with_events(|events| events.len()), 3);
+}
+
+#[test]
+fn slow_subscriber_drops_events_without_blocking() {
+    let store = Arc::new(EventStore::new());
+    let slow = store.subscribe_with_capacity(2);
+    tracing::subscriber::with_default(recording(&store), || {
+        for n in 0..5u64 {
+            tracing::info!(n);
+        }
+    });
+
+    let received: Vec<_> = slow
+        .try_iter()
+        .map(|event| event.field("n").cloned())
+        .collect();
+    assert_eq!(received, [Some(Field::U64(0)), Some(Field::U64(1))]);
+    assert_eq!(slow.dropped(), 3);
+    assert_eq!(store.with_events(|events| events.len()), 5);
+}
+
+#[test]
+fn subscribing_while_recording() {
+    let store = Arc::new(EventStore::new());
+    let recorder = {
+        let store = store.clone();
+        thread::spawn(move || {
+            tracing::subscriber::with_default(recording(&store), || {
+                for _ in 0..1000 {
+                    tracing::info!("event");
+                }
+            })
+        })
+    };
+    while !recorder.is_finished() {
+        let receiver = store.subscribe();
+        let _ = receiver.try_iter().count();
+    }
+    recorder.join().unwrap();
+
+    let receiver = store.subscribe();
+    tracing::subscriber::with_default(recording(&store), || tracing::info!("last"));
+    assert_eq!(receiver.try_iter().count(), 1);
+    assert_eq!(store.with_events(|events| events.len()), 1001);
+}
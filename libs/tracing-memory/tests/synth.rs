@@ -0,0 +1,64 @@
+use std::sync::Arc;
+use tracing::{Dispatch, Level};
+use tracing_memory::{
+    synth::{generate_into, Scenario},
+    Event, EventStore, Field, Layer,
+};
+use tracing_subscriber::prelude::*;
+
+fn generate(scenario: &Scenario) -> Vec<Arc<Event>> {
+    let store = Arc::new(EventStore::new());
+    let dispatch =
+        Dispatch::new(tracing_subscriber::registry().with(Layer::with_store(store.clone())));
+    generate_into(&dispatch, scenario);
+    store.with_events(|events| events.clone())
+}
+
+fn summarize(events: &[Arc<Event>]) -> Vec<(&'static str, Vec<(&'static str, Field)>)> {
+    events
+        .iter()
+        .map(|event| {
+            let fields = event.fields().map(|(name, field)| (name, field.clone()));
+            (event.meta().name(), fields.collect())
+        })
+        .collect()
+}
+
+#[test]
+fn same_seed_same_events() {
+    let scenario = Scenario {
+        seed: 97,
+        events: 500,
+        ..Default::default()
+    };
+    let a = generate(&scenario);
+    let b = generate(&scenario);
+    assert_eq!(summarize(&a), summarize(&b));
+
+    let c = generate(&Scenario {
+        seed: 98,
+        ..scenario
+    });
+    assert_ne!(summarize(&a), summarize(&c));
+}
+
+#[test]
+fn honors_scenario() {
+    let events = generate(&Scenario {
+        events: 4000,
+        error_rate: 0.1,
+        ..Default::default()
+    });
+    assert_eq!(events.len(), 4000);
+
+    let errors = events
+        .iter()
+        .filter(|event| *event.meta().level() == Level::ERROR)
+        .count();
+    let error_rate = errors as f64 / events.len() as f64;
+    assert!(
+        (0.08..0.12).contains(&error_rate),
+        "error rate {}",
+        error_rate
+    );
+}
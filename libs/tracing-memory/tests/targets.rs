@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use tracing_memory::{Event, EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+fn messages(events: &[Arc<Event>]) -> Vec<String> {
+    let message = |event: &Arc<Event>| event.field("message").unwrap().to_string();
+    events.iter().map(message).collect()
+}
+
+#[test]
+fn events_are_found_by_target() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    let emit = |target_app: bool, message: &str| {
+        if target_app {
+            tracing::info!(target: "app", "{}", message);
+        } else {
+            tracing::info!(target: "app::db", "{}", message);
+        }
+    };
+
+    tracing::subscriber::with_default(subscriber, || {
+        emit(true, "one");
+        emit(false, "two");
+        emit(true, "three");
+        store.events_for_target("app", |events| {
+            assert_eq!(messages(events), ["one", "three"]);
+        });
+
+        // the index keeps up with newly recorded events
+        emit(false, "four");
+        emit(true, "five");
+        store.events_for_target("app", |events| {
+            assert_eq!(messages(events), ["one", "three", "five"]);
+        });
+        store.events_for_target("app::db", |events| {
+            assert_eq!(messages(events), ["two", "four"]);
+        });
+        store.events_for_target("ap", |events| assert!(events.is_empty()));
+
+        // and with events removed through with_events
+        store.with_events(|events| {
+            events.retain(|event| event.field("message").unwrap().to_string() != "three")
+        });
+        emit(true, "six");
+        store.events_for_target("app", |events| {
+            assert_eq!(messages(events), ["one", "five", "six"]);
+        });
+
+        store.clear_events();
+        emit(false, "seven");
+        store.events_for_target("app", |events| assert!(events.is_empty()));
+        store.events_for_target("app::db", |events| {
+            assert_eq!(messages(events), ["seven"]);
+        });
+    });
+}
@@ -0,0 +1,63 @@
+use std::{sync::Arc, thread};
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn events_record_their_thread() {
+    let store = Arc::new(EventStore::new());
+
+    let spawn = |name: &str| {
+        let store = store.clone();
+        thread::Builder::new()
+            .name(name.into())
+            .spawn(move || {
+                let subscriber = tracing_subscriber::registry().with(Layer::with_store(store));
+                tracing::subscriber::with_default(subscriber, || tracing::info!("hello"));
+                thread::current().id()
+            })
+            .unwrap()
+    };
+    let a = spawn("a").join().unwrap();
+    let b = spawn("b").join().unwrap();
+
+    store.with_events(|events| {
+        let threads = events
+            .iter()
+            .map(|event| (event.thread().id(), event.thread().name()))
+            .collect::<Vec<_>>();
+        assert_eq!(threads, [(a, Some("a")), (b, Some("b"))]);
+    });
+}
+
+#[test]
+fn thread_info_is_shared_and_displays_its_name() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    let unnamed = {
+        let store = store.clone();
+        thread::spawn(move || {
+            let subscriber = tracing_subscriber::registry().with(Layer::with_store(store));
+            tracing::subscriber::with_default(subscriber, || tracing::info!("unnamed"));
+            thread::current().id()
+        })
+        .join()
+        .unwrap()
+    };
+    thread::Builder::new()
+        .name("named".into())
+        .spawn(move || {
+            tracing::subscriber::with_default(subscriber, || {
+                tracing::info!("first");
+                tracing::info!("second");
+            });
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    store.with_events(|events| {
+        assert_eq!(events[0].thread().to_string(), format!("{:?}", unnamed));
+        assert_eq!(events[1].thread().to_string(), "named");
+        assert!(std::ptr::eq(events[1].thread(), events[2].thread()));
+    });
+}
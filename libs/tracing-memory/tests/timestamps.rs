@@ -0,0 +1,76 @@
+#[cfg(feature = "chrono")]
+use chrono::Local;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tracing_memory::{EventStore, Layer};
+use tracing_subscriber::prelude::*;
+
+#[test]
+#[cfg(feature = "chrono")]
+fn timestamps_agree_across_timezones() {
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    tracing::subscriber::with_default(subscriber, || tracing::info!("now"));
+
+    tracing_memory::with_events(|events| {
+        let event = events.last().unwrap();
+        let (utc, local) = (event.timestamp(), event.timestamp().with_timezone(&Local));
+        assert_eq!(utc, local);
+        assert_eq!(utc.naive_utc(), local.naive_utc());
+        #[allow(deprecated)]
+        {
+            assert_eq!(event.timestamp_utc(), utc);
+            assert_eq!(event.timestamp_local(), local);
+        }
+    });
+}
+
+#[test]
+fn elapsed_time_never_goes_backwards() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        for n in 0..100 {
+            tracing::info!(n);
+        }
+    });
+
+    store.with_events(|events| {
+        assert_eq!(events.len(), 100);
+        for pair in events.windows(2) {
+            let (earlier, later) = (&pair[0], &pair[1]);
+            assert!(earlier.elapsed_since_start() <= later.elapsed_since_start());
+            assert!(earlier.time() <= later.time());
+            #[cfg(feature = "chrono")]
+            assert!(earlier.timestamp() <= later.timestamp());
+        }
+        #[cfg(feature = "chrono")]
+        {
+            let start = tracing_memory::start_time();
+            assert!(events[0].timestamp() >= start);
+        }
+    });
+}
+
+#[test]
+fn time_is_system_time() {
+    let store = Arc::new(EventStore::new());
+    let subscriber = tracing_subscriber::registry().with(Layer::with_store(store.clone()));
+    let before = SystemTime::now();
+    tracing::subscriber::with_default(subscriber, || tracing::info!("now"));
+    let after = SystemTime::now();
+
+    store.with_events(|events| {
+        let event = &events[0];
+        // calibrated at most a few seconds ago, so close to the system clock
+        let slack = Duration::from_secs(1);
+        assert!(event.time() + slack >= before && event.time() <= after + slack);
+        assert_eq!(event.last_time(), event.time());
+        #[cfg(feature = "chrono")]
+        assert_eq!(
+            chrono::DateTime::<chrono::Utc>::from(event.time()),
+            event.timestamp()
+        );
+    });
+}
@@ -0,0 +1,35 @@
+use serde_json::{json, Value};
+use tracing_subscriber::prelude::*;
+
+#[test]
+fn lines_are_json() {
+    let subscriber = tracing_subscriber::registry().with(tracing_memory::layer());
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::info_span!("outer").in_scope(|| {
+            tracing::info_span!("inner").in_scope(|| {
+                tracing::info!(quote = "\"quoted\"\n", n = 3u64, "hello");
+            });
+        });
+        tracing::event!(target: "empty", tracing::Level::WARN, {});
+    });
+
+    let mut out = Vec::new();
+    tracing_memory::write_jsonl(&mut out).unwrap();
+    let lines: Vec<Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["level"], "INFO");
+    assert!(lines[0]["thread"].is_string());
+    assert_eq!(lines[0]["spans"], json!(["inner", "outer"]));
+    assert_eq!(
+        lines[0]["fields"],
+        json!({ "message": "hello", "quote": "\"quoted\"\n", "n": 3 }),
+    );
+    assert_eq!(lines[1]["level"], "WARN");
+    assert_eq!(lines[1]["spans"], json!([]));
+    assert_eq!(lines[1]["fields"], json!({}));
+}